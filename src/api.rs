@@ -0,0 +1,281 @@
+// Local HTTP control API for headless mode, enabled with `--api-port`.
+// Every route delegates to the shared `Backend`, the same way the UI does.
+
+use crate::backend::SharedBackend;
+use crate::backend::error::BackendResult;
+use crate::backend::types::{RuntimeStateView, TunnelEntry, TunnelId};
+use crate::errors;
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use http_body_util::{BodyExt, Empty};
+use hyper::body::Bytes;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunnelView {
+    #[serde(flatten)]
+    pub tunnel: TunnelEntry,
+    pub status: RuntimeStateView,
+}
+
+impl From<TunnelEntry> for TunnelView {
+    fn from(tunnel: TunnelEntry) -> Self {
+        let status = tunnel
+            .runtime_state
+            .as_ref()
+            .map(RuntimeStateView::from)
+            .unwrap_or(RuntimeStateView::Stopped);
+        Self { tunnel, status }
+    }
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+// Outcome for one tunnel of a start-all or stop-all request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkResult {
+    pub id: TunnelId,
+    pub error: Option<String>,
+}
+
+impl BulkResult {
+    fn new<T>(id: TunnelId, result: BackendResult<T>) -> Self {
+        Self {
+            id,
+            error: result.err().map(|e| format!("{:#}", e)),
+        }
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+pub fn router(backend: SharedBackend) -> Router {
+    Router::new()
+        .route("/tunnels", get(list_tunnels))
+        .route("/tunnels/start", post(start_all))
+        .route("/tunnels/stop", post(stop_all))
+        .route("/tunnels/{id}/start", post(start_tunnel))
+        .route("/tunnels/{id}/stop", post(stop_tunnel))
+        .route("/tunnels/{id}/status", get(tunnel_status))
+        .route("/tunnels/{id}/stderr", get(tunnel_stderr))
+        .with_state(backend)
+}
+
+// Only ever binds to loopback; the API has no authentication.
+pub async fn bind(port: u16) -> anyhow::Result<TcpListener> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpListener::bind(address)
+        .await
+        .with_context(|| errors::api::failed_to_bind(&address.to_string()))
+}
+
+pub async fn serve(listener: TcpListener, backend: SharedBackend) -> anyhow::Result<()> {
+    if let Ok(address) = listener.local_addr() {
+        tracing::info!("HTTP API listening on http://{}", address);
+    }
+    axum::serve(listener, router(backend))
+        .await
+        .context(errors::api::SERVER_FAILED)
+}
+
+fn parse_id(id: &str) -> Result<TunnelId, ApiError> {
+    id.parse()
+        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, errors::api::invalid_tunnel_id(id)))
+}
+
+async fn find_tunnel(backend: &SharedBackend, id: TunnelId) -> Result<TunnelEntry, ApiError> {
+    backend
+        .lock()
+        .await
+        .list_tunnels()
+        .into_iter()
+        .find(|tunnel| tunnel.id == id)
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                errors::tunnel::not_found(&id.to_string()),
+            )
+        })
+}
+
+async fn list_tunnels(State(backend): State<SharedBackend>) -> Json<Vec<TunnelView>> {
+    let tunnels = backend.lock().await.list_tunnels();
+    Json(tunnels.into_iter().map(TunnelView::from).collect())
+}
+
+async fn start_tunnel(
+    State(backend): State<SharedBackend>,
+    Path(id): Path<String>,
+) -> ApiResult<TunnelView> {
+    let id = parse_id(&id)?;
+    find_tunnel(&backend, id).await?;
+    backend
+        .lock()
+        .await
+        .start_tunnel(id)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, format!("{:#}", e)))?;
+    Ok(Json(find_tunnel(&backend, id).await?.into()))
+}
+
+async fn stop_tunnel(
+    State(backend): State<SharedBackend>,
+    Path(id): Path<String>,
+) -> ApiResult<TunnelView> {
+    let id = parse_id(&id)?;
+    find_tunnel(&backend, id).await?;
+    backend
+        .lock()
+        .await
+        .stop_tunnel(id)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, format!("{:#}", e)))?;
+    Ok(Json(find_tunnel(&backend, id).await?.into()))
+}
+
+async fn start_all(State(backend): State<SharedBackend>) -> Json<Vec<BulkResult>> {
+    let results = backend.lock().await.start_all().await;
+    Json(
+        results
+            .into_iter()
+            .map(|(id, r)| BulkResult::new(id, r))
+            .collect(),
+    )
+}
+
+async fn stop_all(State(backend): State<SharedBackend>) -> Json<Vec<BulkResult>> {
+    let results = backend.lock().await.stop_all().await;
+    Json(
+        results
+            .into_iter()
+            .map(|(id, r)| BulkResult::new(id, r))
+            .collect(),
+    )
+}
+
+async fn tunnel_status(
+    State(backend): State<SharedBackend>,
+    Path(id): Path<String>,
+) -> ApiResult<RuntimeStateView> {
+    let id = parse_id(&id)?;
+    let tunnel = find_tunnel(&backend, id).await?;
+    Ok(Json(TunnelView::from(tunnel).status))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StderrView {
+    pub stderr: String,
+}
+
+async fn tunnel_stderr(
+    State(backend): State<SharedBackend>,
+    Path(id): Path<String>,
+) -> ApiResult<StderrView> {
+    let id = parse_id(&id)?;
+    find_tunnel(&backend, id).await?;
+    let stderr = backend.lock().await.recent_stderr(id);
+    Ok(Json(StderrView { stderr }))
+}
+
+// Minimal client for the routes above, used by the CLI subcommands to control
+// a headless instance that is already running.
+pub struct ApiClient {
+    address: SocketAddr,
+}
+
+impl ApiClient {
+    pub fn new(port: u16) -> Self {
+        Self {
+            address: SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        }
+    }
+
+    pub async fn list_tunnels(&self) -> anyhow::Result<Vec<TunnelView>> {
+        self.request(axum::http::Method::GET, "/tunnels").await
+    }
+
+    pub async fn start_tunnel(&self, id: TunnelId) -> anyhow::Result<TunnelView> {
+        let path = format!("/tunnels/{}/start", id);
+        self.request(axum::http::Method::POST, &path).await
+    }
+
+    pub async fn stop_tunnel(&self, id: TunnelId) -> anyhow::Result<TunnelView> {
+        let path = format!("/tunnels/{}/stop", id);
+        self.request(axum::http::Method::POST, &path).await
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: axum::http::Method,
+        path: &str,
+    ) -> anyhow::Result<T> {
+        let stream = TcpStream::connect(self.address)
+            .await
+            .with_context(|| errors::api::failed_to_connect(&self.address.to_string()))?;
+        let (mut sender, connection) =
+            hyper::client::conn::http1::handshake(hyper_util::rt::TokioIo::new(stream))
+                .await
+                .context(errors::api::REQUEST_FAILED)?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!("HTTP API connection closed with error: {}", e);
+            }
+        });
+
+        let request = axum::http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header(axum::http::header::HOST, self.address.to_string())
+            .body(Empty::<Bytes>::new())
+            .context(errors::api::REQUEST_FAILED)?;
+        let response = sender
+            .send_request(request)
+            .await
+            .context(errors::api::REQUEST_FAILED)?;
+
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .context(errors::api::REQUEST_FAILED)?
+            .to_bytes();
+
+        if !status.is_success() {
+            let message = serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|value| value["error"].as_str().map(str::to_string))
+                .unwrap_or_else(|| status.to_string());
+            anyhow::bail!(message);
+        }
+
+        serde_json::from_slice(&body).context(errors::api::INVALID_RESPONSE)
+    }
+}
@@ -1,95 +1,224 @@
 use crate::backend::Backend;
-use crate::backend::process::ProcessInstance;
-use crate::backend::types::{Config, ProcessId, TunnelEntry, TunnelId, TunnelRuntimeState};
+use crate::backend::config::ConfigStore;
+use crate::backend::config_lock::ConfigLock;
+use crate::backend::error::{BackendError, BackendResult};
+use crate::backend::logs::{LogSearch, TaggedLogLine};
+use crate::backend::process::{ProcessInstance, StartCheck, StartOutcome, StartWatch};
+use crate::backend::running::{self, LastRun, RunningProcess};
+use crate::backend::types::{
+    Config, GlobalSettings, ProcessId, Timestamp, TunnelEntry, TunnelEvent, TunnelId,
+    TunnelRuntimeState, TunnelStats, TunnelSummary, WstunnelVersion, autostart_plan,
+    dependency_start_order, dependents_stop_order,
+};
 use crate::errors;
-use anyhow::{Context, Result};
-use arc_swap::ArcSwap;
-use std::collections::HashMap;
+use anyhow::Context;
+use async_trait::async_trait;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
 pub struct BackendState {
-    config: Arc<ArcSwap<Config>>,
+    config: Arc<ConfigStore>,
     processes: HashMap<TunnelId, ProcessInstance>,
     last_known_log_paths: HashMap<TunnelId, PathBuf>,
+    // Stderr of each tunnel's last run, kept after its process is gone.
+    last_stderr: HashMap<TunnelId, String>,
+    // How each tunnel's last run ended, including runs from before a restart.
+    last_runs: HashMap<TunnelId, LastRun>,
+    stats: HashMap<TunnelId, TunnelStats>,
     config_path: PathBuf,
+    // Hash of the config file as it was last loaded or saved here.
+    config_file_hash: Option<u64>,
+    // Held until shutdown so no other manager uses the same config.
+    config_lock: Option<ConfigLock>,
+    // Set by `--read-only`; `global.locked` is read from the config.
+    read_only: bool,
     wstunnel_binary_path: PathBuf,
     cancellation_token: CancellationToken,
-    runtime_handle: tokio::runtime::Handle,
     cleanup_task: Option<JoinHandle<()>>,
+    events: broadcast::Sender<TunnelEvent>,
+    wstunnel_version: Option<String>,
+    // Binary the cached version belongs to.
+    version_checked_for: Option<PathBuf>,
+    // Tunnels whose process is spawned but not ready yet.
+    starting: Arc<std::sync::Mutex<HashSet<TunnelId>>>,
+    // Why a start failed, left by its readiness wait for
+    // `cleanup_dead_processes` to record.
+    start_failures: Arc<std::sync::Mutex<HashMap<TunnelId, String>>>,
+    // Current log files of the running tunnels, which log maintenance leaves
+    // alone. Shared with the periodic cleanup task.
+    open_logs: Arc<std::sync::Mutex<HashSet<PathBuf>>>,
+}
+
+// Marks a tunnel as starting for as long as it is held. `start_one` takes it
+// before spawning and hands it to the readiness wait, so a second start is
+// refused until the first one has a result, even though the backend lock is
+// released in between.
+struct StartGuard {
+    starting: Arc<std::sync::Mutex<HashSet<TunnelId>>>,
+    id: TunnelId,
+}
+
+impl StartGuard {
+    fn acquire(starting: &Arc<std::sync::Mutex<HashSet<TunnelId>>>, id: TunnelId) -> Option<Self> {
+        let inserted = starting.lock().unwrap().insert(id);
+        inserted.then(|| Self {
+            starting: Arc::clone(starting),
+            id,
+        })
+    }
+}
+
+impl Drop for StartGuard {
+    fn drop(&mut self) {
+        self.starting.lock().unwrap().remove(&self.id);
+    }
 }
 
 impl BackendState {
+    // Applies `change` to a copy of the config, then validates and saves the
+    // copy before swapping it in. If any step fails, neither the file nor the
+    // in-memory config has changed.
+    async fn apply_config<T>(
+        &mut self,
+        validation_context: String,
+        change: impl FnOnce(&mut Config) -> BackendResult<T>,
+    ) -> BackendResult<T> {
+        let mut new_config = (*self.config.load_full()).clone();
+        let result = change(&mut new_config)?;
+        new_config.validate().context(validation_context)?;
+
+        if self.config_changed_on_disk().await {
+            return Err(BackendError::ConfigChangedOnDisk);
+        }
+        crate::backend::config::save_config(&self.config_path, &new_config)
+            .await
+            .context(errors::config::SAVE_FAILED)?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+
+        self.config.store(Arc::new(new_config));
+        Ok(result)
+    }
+
+    // Stops the tunnels `config` does not have, which would otherwise be
+    // left unmanaged once it replaces the current one.
+    async fn stop_orphaned(&mut self, config: &Config) {
+        let orphaned: Vec<TunnelId> = self
+            .processes
+            .keys()
+            .filter(|id| !config.tunnels.iter().any(|t| t.id == **id))
+            .copied()
+            .collect();
+        for id in orphaned {
+            if let Err(e) = self.stop_tunnel(id).await {
+                tracing::error!("Error stopping tunnel {:?} after reload: {}", id, e);
+            }
+        }
+    }
+
+    // Blocks on `runtime_handle`, so it must not be called from async code;
+    // use `load` there instead. Fails with `ConfigLocked` when another
+    // manager is using the config.
     pub fn new(
         runtime_handle: tokio::runtime::Handle,
         config_path: PathBuf,
         wstunnel_binary_path: PathBuf,
-    ) -> Self {
-        let config = runtime_handle
-            .block_on(async { crate::backend::config::load_config(&config_path).await })
+    ) -> BackendResult<Self> {
+        runtime_handle.clone().block_on(Self::load(
+            runtime_handle,
+            config_path,
+            wstunnel_binary_path,
+        ))
+    }
+
+    // `new` for callers already running on the runtime.
+    pub async fn load(
+        runtime_handle: tokio::runtime::Handle,
+        config_path: PathBuf,
+        wstunnel_binary_path: PathBuf,
+    ) -> BackendResult<Self> {
+        let config_lock = ConfigLock::acquire(&config_path)?;
+        let config = crate::backend::config::load_config(&config_path)
+            .await
             .unwrap_or_else(|e| {
                 tracing::error!("Failed to load config: {}, using defaults", e);
                 Config::default()
             });
 
-        let config_arc = Arc::new(ArcSwap::from_pointee(config));
+        let config_file_hash = crate::backend::config::file_hash(&config_path).await;
+        let config_arc = Arc::new(ConfigStore::new(config));
         let cancellation_token = CancellationToken::new();
+        let open_logs: Arc<std::sync::Mutex<HashSet<PathBuf>>> = Arc::default();
 
         let cleanup_task = Self::spawn_periodic_cleanup_task(
             config_arc.clone(),
+            open_logs.clone(),
             runtime_handle.clone(),
             cancellation_token.clone(),
         );
 
-        Self {
+        let mut state = Self {
             config: config_arc,
             processes: HashMap::new(),
             last_known_log_paths: HashMap::new(),
+            last_stderr: HashMap::new(),
+            last_runs: HashMap::new(),
+            stats: HashMap::new(),
             config_path,
+            config_file_hash,
+            config_lock: Some(config_lock),
+            read_only: false,
             wstunnel_binary_path,
             cancellation_token,
-            runtime_handle,
             cleanup_task: Some(cleanup_task),
-        }
+            events: broadcast::channel(crate::constants::EVENT_CHANNEL_CAPACITY).0,
+            wstunnel_version: None,
+            version_checked_for: None,
+            starting: Arc::default(),
+            start_failures: Arc::default(),
+            open_logs,
+        };
+        state.refresh_wstunnel_version().await;
+        state.adopt_running_processes().await;
+        state.load_last_runs();
+        Ok(state)
     }
 
     fn spawn_periodic_cleanup_task(
-        config: Arc<ArcSwap<Config>>,
+        config: Arc<ConfigStore>,
+        open_logs: Arc<std::sync::Mutex<HashSet<PathBuf>>>,
         runtime_handle: tokio::runtime::Handle,
         cancellation_token: CancellationToken,
     ) -> JoinHandle<()> {
         runtime_handle.spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 60 * 60));
-            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            // The GUI and headless mode clean up once at startup, so the first
+            // run here is one interval later. The interval is read again after
+            // every run, so a changed setting takes effect from the next one.
+            let mut delay = config.load().global.cleanup_interval();
 
             loop {
                 tokio::select! {
-                    _ = interval.tick() => {
+                    _ = tokio::time::sleep(delay) => {
                         let current_config = config.load();
-                        match current_config.global.log_retention_days {
-                            Some(days) => {
-                                tracing::info!(
-                                    "Running periodic log cleanup for logs older than {} days",
-                                    days
-                                );
-                                match crate::backend::config::cleanup_old_logs(
-                                    &current_config.global.log_directory,
-                                    days,
-                                )
-                                .await
-                                {
-                                    Ok(()) => {
-                                        tracing::debug!("Periodic log cleanup completed successfully");
-                                    }
-                                    Err(e) => {
-                                        tracing::warn!("Periodic log cleanup failed: {}", e);
-                                    }
-                                }
+                        delay = current_config.global.cleanup_interval();
+                        let global = &current_config.global;
+                        if global.log_retention_days.is_none() && global.log_compress_days.is_none() {
+                            tracing::debug!("Log retention not configured, skipping periodic cleanup");
+                            continue;
+                        }
+
+                        tracing::info!("Running periodic log maintenance");
+                        let in_use = open_logs.lock().unwrap().clone();
+                        match crate::backend::config::maintain_logs(&current_config, &in_use).await {
+                            Ok(()) => {
+                                tracing::debug!("Periodic log cleanup completed successfully");
                             }
-                            None => {
-                                tracing::debug!("Log retention not configured, skipping periodic cleanup");
+                            Err(e) => {
+                                tracing::warn!("Periodic log cleanup failed: {}", e);
                             }
                         }
                     }
@@ -102,153 +231,748 @@ impl BackendState {
         })
     }
 
+    async fn stop_cleanup_task(&mut self) {
+        if let Some(task) = self.cleanup_task.take() {
+            task.abort();
+            let _ = task.await;
+            tracing::info!("Periodic cleanup task stopped");
+        }
+    }
+
+    // A bare file name has an empty parent, which means the current directory.
+    fn config_dir(&self) -> PathBuf {
+        match self.config_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    fn binary_path(&self, config: &Config) -> PathBuf {
+        config
+            .global
+            .wstunnel_binary_path
+            .clone()
+            .unwrap_or_else(|| self.wstunnel_binary_path.clone())
+    }
+
+    // Runs the version check again only when the configured binary changed.
+    // It only warns on failure: the binary may be a wrapper script that
+    // doesn't answer `--version`, and a missing binary is reported when
+    // starting.
+    async fn refresh_wstunnel_version(&mut self) {
+        let binary_path = self.binary_path(&self.config.load());
+        if self.version_checked_for.as_ref() == Some(&binary_path) || !binary_path.exists() {
+            return;
+        }
+
+        self.wstunnel_version =
+            match crate::backend::process::detect_wstunnel_version(&binary_path).await {
+                Ok(version) => {
+                    tracing::info!("Using wstunnel {} at {}", version, binary_path.display());
+                    if !WstunnelVersion::parse(&version)
+                        .is_none_or(|parsed| parsed.supports_subcommands())
+                    {
+                        tracing::warn!("{}", errors::binary::too_old(&version));
+                    }
+                    Some(version)
+                }
+                Err(e) => {
+                    tracing::warn!("{:#}", e);
+                    None
+                }
+            };
+        self.version_checked_for = Some(binary_path);
+    }
+
+    // Adopts the processes an earlier run recorded that are still alive and
+    // still belong to a configured tunnel, so they show as running rather than
+    // being orphaned. Everything else is dropped from the state file.
+    async fn adopt_running_processes(&mut self) {
+        let config = self.config.load_full();
+        let binary_path = self.binary_path(&config);
+        let state_path = running::state_file_path(&self.config_path);
+        let recorded = running::load(&state_path).await;
+        let mut adopted = Vec::new();
+
+        for entry in recorded {
+            let Some(tunnel) = config.tunnels.iter().find(|t| t.id == entry.id) else {
+                tracing::info!(
+                    "Dropping process {} recorded for a tunnel that no longer exists",
+                    entry.pid
+                );
+                continue;
+            };
+            if !crate::backend::process::is_tunnel_process(entry.pid, &binary_path) {
+                tracing::info!(
+                    "Process {} recorded for tunnel '{}' is no longer running",
+                    entry.pid,
+                    tunnel.tag
+                );
+                continue;
+            }
+
+            let mut process_instance = crate::backend::process::adopt_process_instance(
+                entry.id,
+                entry.pid,
+                binary_path.clone(),
+                entry.log_path.clone(),
+                entry.started_at,
+                self.cancellation_token.child_token(),
+                self.events.clone(),
+            );
+
+            if let Some(check) = &tunnel.health_check {
+                process_instance.start_health_check(
+                    tunnel.tag.clone(),
+                    check.clone(),
+                    self.events.clone(),
+                );
+            }
+
+            tracing::info!(
+                "Adopted tunnel '{}' running with PID {}",
+                tunnel.tag,
+                entry.pid
+            );
+            self.stats
+                .entry(entry.id)
+                .or_default()
+                .record_start(process_instance.started_at);
+            self.last_known_log_paths
+                .insert(entry.id, process_instance.log_path.clone());
+            self.processes.insert(entry.id, process_instance);
+            adopted.push(entry);
+        }
+        self.sync_open_logs();
+
+        if let Err(e) = running::save(&state_path, &adopted).await {
+            tracing::warn!("{:#}", e);
+        }
+    }
+
+    // Adopted tunnels already have their log path; for the others it is the
+    // one of their last run.
+    fn load_last_runs(&mut self) {
+        let config = self.config.load_full();
+        let runs = running::load_last_runs(&running::last_run_file_path(&self.config_path));
+        for run in runs {
+            if !config.tunnels.iter().any(|t| t.id == run.id) {
+                continue;
+            }
+            self.last_known_log_paths
+                .entry(run.id)
+                .or_insert_with(|| run.log_path.clone());
+            self.last_runs.insert(run.id, run);
+        }
+    }
+
+    // Takes a running tunnel's process out of the map for `stop_process`.
+    fn take_for_stop(&mut self, id: TunnelId) -> BackendResult<ProcessInstance> {
+        let process_instance = self.processes.get(&id).ok_or(BackendError::NotRunning)?;
+        if process_instance.pid().is_none() {
+            return Err(BackendError::AlreadyStopping);
+        }
+        let process_instance = self.processes.remove(&id).unwrap();
+        self.last_known_log_paths
+            .insert(id, process_instance.log_path.clone());
+        self.start_failures.lock().unwrap().remove(&id);
+        self.sync_open_logs();
+        Ok(process_instance)
+    }
+
+    fn open_log_files(&self) -> HashSet<PathBuf> {
+        self.processes
+            .values()
+            .flat_map(|process| crate::backend::logs::current_log_files(&process.log_path))
+            .collect()
+    }
+
+    fn sync_open_logs(&self) {
+        *self.open_logs.lock().unwrap() = self.open_log_files();
+    }
+
+    async fn record_stop(&mut self, process_instance: ProcessInstance, exit_code: Option<i32>) {
+        let id = process_instance.tunnel_id;
+
+        // A non-zero code here is the process reacting to being stopped.
+        if let Some(code) = exit_code
+            && code != 0
+        {
+            tracing::info!("Tunnel {:?} stopped by user with exit code {}", id, code);
+        }
+
+        self.stats.entry(id).or_default().record_exit(
+            process_instance.started_at,
+            exit_code,
+            false,
+        );
+        self.record_last_run(&process_instance, exit_code, None);
+
+        if let Err(e) = running::remove(&running::state_file_path(&self.config_path), id).await {
+            tracing::warn!("{:#}", e);
+        }
+
+        tracing::info!("Stopped tunnel {:?}", id);
+        let _ = self.events.send(TunnelEvent::Stopped { id });
+    }
+
+    // Remembers how a run ended, here and in the last-run file.
+    fn record_last_run(
+        &mut self,
+        process: &ProcessInstance,
+        exit_code: Option<i32>,
+        error: Option<String>,
+    ) {
+        self.last_runs.insert(
+            process.tunnel_id,
+            LastRun {
+                id: process.tunnel_id,
+                pid: process.process_id,
+                log_path: process.log_path.clone(),
+                started_at: process.started_at,
+                ended_at: Timestamp::now(),
+                exit_code,
+                error,
+            },
+        );
+        let runs: Vec<LastRun> = self.last_runs.values().cloned().collect();
+        let path = running::last_run_file_path(&self.config_path);
+        if let Err(e) = running::save_last_runs(&path, &runs) {
+            tracing::warn!("{:#}", e);
+        }
+    }
+
+    // A process that exits while starting is left to its readiness wait
+    // until that has recorded why the start failed.
     fn cleanup_dead_processes(&mut self) {
+        let starting = self.starting.lock().unwrap().clone();
         let dead_tunnel_ids: Vec<TunnelId> = self
             .processes
-            .iter_mut()
-            .filter_map(|(tunnel_id, process_instance)| {
-                if let Some(ref mut child) = process_instance.child_handle {
-                    match child.try_wait() {
-                        Ok(Some(status)) => {
-                            let exit_code = status.code();
-                            tracing::info!(
-                                "Process for tunnel {:?} exited with status: {} (code: {:?})",
-                                tunnel_id,
-                                status,
-                                exit_code
-                            );
-                            Some(*tunnel_id)
-                        }
-                        Ok(None) => None,
-                        Err(e) => {
-                            tracing::error!(
-                                "Error checking process status for tunnel {:?}: {}",
-                                tunnel_id,
-                                e
-                            );
-                            Some(*tunnel_id)
-                        }
-                    }
-                } else {
-                    Some(*tunnel_id)
-                }
+            .iter()
+            .filter(|(tunnel_id, process_instance)| {
+                process_instance.has_exited() && !starting.contains(tunnel_id)
             })
+            .map(|(tunnel_id, _)| *tunnel_id)
             .collect();
 
         for tunnel_id in dead_tunnel_ids {
             if let Some(mut process) = self.processes.remove(&tunnel_id) {
                 self.last_known_log_paths
                     .insert(tunnel_id, process.log_path.clone());
+                self.last_stderr.insert(tunnel_id, process.get_stderr());
+                let exit_code = process
+                    .exit_status
+                    .borrow()
+                    .and_then(|status| status.code());
                 process.cancellation_token.cancel();
                 if let Some(monitor_task) = process.monitor_task.take() {
                     monitor_task.abort();
                 }
+                let start_failure = self.start_failures.lock().unwrap().remove(&tunnel_id);
+                if let Some(error) = start_failure {
+                    self.stats.entry(tunnel_id).or_default().record_exit(
+                        process.started_at,
+                        exit_code,
+                        false,
+                    );
+                    self.record_last_run(&process, exit_code, Some(error));
+                    continue;
+                }
+                let tag = self
+                    .config
+                    .load()
+                    .tunnels
+                    .iter()
+                    .find(|t| t.id == tunnel_id)
+                    .map(|t| t.tag.clone())
+                    .unwrap_or_else(|| tunnel_id.to_string());
+                let stats = self.stats.entry(tunnel_id).or_default();
+                let error = stats.crash_error(&tag, exit_code);
+                stats.record_exit(process.started_at, exit_code, true);
+                if stats.is_flapping() {
+                    tracing::warn!("{}", error);
+                }
+                self.record_last_run(&process, exit_code, Some(error));
                 tracing::info!("Cleaned up dead process for tunnel {:?}", tunnel_id);
             }
         }
+        self.sync_open_logs();
+    }
+
+    async fn start_one(&mut self, id: TunnelId) -> BackendResult<ProcessId> {
+        let config = self.config.load_full();
+
+        let tunnel = config
+            .tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(BackendError::TunnelNotFound(id))?;
+
+        // Taken before looking at the process, which is already there while
+        // an earlier start waits for it to be ready.
+        let guard = StartGuard::acquire(&self.starting, id)
+            .ok_or_else(|| BackendError::Transitioning(tunnel.tag.clone()))?;
+
+        if let Some(process) = self.processes.get(&id) {
+            if process.pid().is_some() {
+                return Err(BackendError::AlreadyRunning(tunnel.tag.clone()));
+            } else {
+                return Err(BackendError::Transitioning(tunnel.tag.clone()));
+            }
+        }
+
+        let binary_path = self.binary_path(&config);
+
+        if !binary_path.exists() {
+            return Err(BackendError::BinaryNotFound(errors::binary::not_found(
+                &binary_path.display().to_string(),
+            )));
+        }
+        self.refresh_wstunnel_version().await;
+
+        let cli_args = tunnel.cli_args.clone();
+        let env = tunnel.env.clone();
+        let priority = tunnel.priority;
+        let fail_on_missing_env_vars = config.global.fail_on_missing_env_vars;
+        let kill_on_drop = !config.global.detach_on_exit;
+        let working_dir = tunnel.effective_working_dir(&self.config_dir());
+        if !working_dir.is_dir() {
+            return Err(
+                anyhow::anyhow!(errors::tunnel::validation::working_dir_invalid(
+                    &working_dir.display().to_string()
+                ))
+                .into(),
+            );
+        }
+        let log_directory = tunnel.effective_log_directory(&config.global).to_path_buf();
+        if !crate::backend::logs::is_directory_writable(&log_directory) {
+            return Err(anyhow::anyhow!(errors::logs::directory_not_writable(
+                &log_directory.display().to_string()
+            ))
+            .into());
+        }
+        if let Some(available) = crate::backend::logs::available_space(&log_directory) {
+            let available_mb = available / (1024 * 1024);
+            let path = log_directory.display().to_string();
+            if available_mb < crate::constants::MIN_FREE_LOG_SPACE_MB {
+                return Err(anyhow::anyhow!(errors::disk::not_enough_log_space(
+                    &path,
+                    available_mb
+                ))
+                .into());
+            }
+            if available_mb < crate::constants::LOW_LOG_SPACE_MB {
+                tracing::warn!("{}", errors::disk::low_log_space(&path, available_mb));
+            }
+        }
+        let log_options = config.global.log_options();
+        let tunnel_id = tunnel.id;
+        let tunnel_tag = tunnel.tag.clone();
+
+        let child_token = self.cancellation_token.child_token();
+        let events = self.events.clone();
+        let output = self.events.subscribe();
+
+        let mut process_instance = async {
+            let (child, command_line) = crate::backend::process::spawn_tunnel_process(
+                &binary_path,
+                &cli_args,
+                &env,
+                fail_on_missing_env_vars,
+                kill_on_drop,
+                &working_dir,
+                priority,
+                &config.global.sensitive_flags,
+            )
+            .await?;
+            let mut instance = crate::backend::process::create_process_instance(
+                tunnel_id,
+                tunnel_tag.clone(),
+                child,
+                &log_directory,
+                log_options,
+                child_token,
+                events,
+            )
+            .await?;
+            instance.command_line = command_line;
+            anyhow::Ok(instance)
+        }
+        .await
+        // Failures the caller can act on are passed through as they are.
+        .map_err(|e| match BackendError::from(e) {
+            BackendError::Other(e) => e
+                .context(errors::tunnel::failed_to_start(&tunnel_tag))
+                .into(),
+            error => error,
+        })?;
+
+        // The process may have exited already; the readiness wait reports that.
+        let pid = process_instance.process_id;
+
+        if let Some(check) = tunnel.health_check.clone() {
+            process_instance.start_health_check(tunnel_tag.clone(), check, self.events.clone());
+        }
+
+        tracing::info!("Spawned tunnel '{}' with PID {}", tunnel_tag, pid);
+        self.start_failures.lock().unwrap().remove(&id);
+
+        self.stats
+            .entry(id)
+            .or_default()
+            .record_start(process_instance.started_at);
+        self.last_known_log_paths
+            .insert(id, process_instance.log_path.clone());
+
+        let recorded = RunningProcess {
+            id,
+            pid,
+            binary_path,
+            log_path: process_instance.log_path.clone(),
+            started_at: process_instance.started_at,
+        };
+        if let Err(e) =
+            running::record(&running::state_file_path(&self.config_path), recorded).await
+        {
+            tracing::warn!("{:#}", e);
+        }
+        let watch = process_instance.start_watch();
+        self.processes.insert(id, process_instance);
+        self.sync_open_logs();
+
+        tokio::spawn(wait_until_started(
+            watch,
+            config.global.start_check(),
+            output,
+            tunnel_tag,
+            pid,
+            self.events.clone(),
+            Arc::clone(&self.start_failures),
+            guard,
+        ));
+
+        Ok(pid)
+    }
+
+    async fn stop_one(&mut self, id: TunnelId) -> BackendResult<()> {
+        let process_instance = self.take_for_stop(id)?;
+        let (process_instance, exit_code) = stop_process(process_instance).await;
+        self.record_stop(process_instance, exit_code).await;
+        Ok(())
+    }
+
+    // Stops the running tunnels that depend on `id`, the furthest first.
+    // Failures are only logged so that `id` itself still stops.
+    async fn stop_dependents(&mut self, id: TunnelId) {
+        for dependent in dependents_stop_order(&self.config.load().tunnels, id) {
+            if !self.is_tunnel_running(dependent) {
+                continue;
+            }
+            if let Err(e) = self.stop_one(dependent).await {
+                tracing::warn!("Failed to stop dependent tunnel {:?}: {}", dependent, e);
+            }
+        }
     }
 }
 
+// Waits for a process `start_one` spawned to come up, without holding the
+// backend. A failed start is stopped and reported with `StartFailed`, and its
+// error is left in `start_failures` before `guard` is released, so the exit
+// is not mistaken for a crash. The guard goes before the event, so whoever
+// reacts to it sees the tunnel's new state.
+#[allow(clippy::too_many_arguments)]
+async fn wait_until_started(
+    watch: StartWatch,
+    check: StartCheck,
+    mut output: broadcast::Receiver<TunnelEvent>,
+    tag: String,
+    pid: ProcessId,
+    events: broadcast::Sender<TunnelEvent>,
+    start_failures: Arc<std::sync::Mutex<HashMap<TunnelId, String>>>,
+    guard: StartGuard,
+) {
+    let id = guard.id;
+    let outcome = tokio::select! {
+        outcome = watch.wait_until_ready(&check, &mut output) => outcome,
+        // Stopped, detached or shut down before it was ready.
+        _ = watch.cancellation_token.cancelled() => return,
+        _ = watch.detach_token.cancelled() => return,
+    };
+
+    let error = match outcome {
+        StartOutcome::Ready => {
+            tracing::info!("Started tunnel '{}' with PID {}", tag, pid);
+            drop(guard);
+            let _ = events.send(TunnelEvent::Started { id, pid });
+            return;
+        }
+        StartOutcome::Exited(status) => {
+            tracing::error!("Tunnel '{}' exited immediately with status {}", tag, status);
+            let stderr_tail = watch
+                .get_stderr()
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .rev()
+                .take(10)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .join("\n");
+            let detail = match stderr_tail.is_empty() {
+                true => errors::process::exited_with_status(&status.to_string()),
+                false => stderr_tail,
+            };
+            errors::tunnel::failed_to_start_with_detail(&tag, &detail)
+        }
+        StartOutcome::TimedOut => {
+            watch.cancellation_token.cancel();
+            watch.wait_for_exit(std::time::Duration::from_secs(2)).await;
+            let seconds = check.timeout.as_secs();
+            tracing::error!("Tunnel '{}' was not ready after {}s", tag, seconds);
+            errors::tunnel::failed_to_start_with_detail(
+                &tag,
+                &errors::process::start_timed_out(seconds),
+            )
+        }
+    };
+    start_failures.lock().unwrap().insert(id, error.clone());
+    drop(guard);
+    let _ = events.send(TunnelEvent::StartFailed { id, error });
+}
+
+// Asks the process to exit and waits up to `STOP_TIMEOUT` for it. Needs no
+// access to the backend, so several can run at once.
+async fn stop_process(mut process_instance: ProcessInstance) -> (ProcessInstance, Option<i32>) {
+    process_instance.cancellation_token.cancel();
+
+    let exit_code = match process_instance
+        .wait_for_exit(crate::constants::STOP_TIMEOUT)
+        .await
+    {
+        Some(status) => status.code(),
+        None if process_instance.adopted => None,
+        None => {
+            tracing::warn!(
+                "Process {} did not exit within timeout, forcing kill",
+                process_instance.process_id
+            );
+            None
+        }
+    };
+
+    // Aborting the monitor drops the child, which kills it if the signal
+    // above was not enough.
+    if let Some(monitor_task) = process_instance.monitor_task.take() {
+        monitor_task.abort();
+        let _ = monitor_task.await;
+    }
+
+    (process_instance, exit_code)
+}
+
+#[async_trait]
 impl Backend for BackendState {
-    fn load_config(&mut self, _path: &Path) -> Result<Arc<Config>> {
+    async fn load_config(&mut self, _path: &Path) -> BackendResult<Arc<Config>> {
         unimplemented!("load_config - to be implemented in Phase 3")
     }
 
-    fn save_config(&self, _config: &Config, _path: &Path) -> Result<()> {
-        unimplemented!("save_config - to be implemented in Phase 3")
+    async fn save_config(&self, config: &Config, path: &Path) -> BackendResult<()> {
+        Ok(crate::backend::config::export_config(path, config).await?)
     }
 
     fn get_config(&self) -> Arc<Config> {
         self.config.load_full()
     }
 
-    fn validate_tunnel_entry(&self, entry: &TunnelEntry) -> Result<()> {
-        entry.validate()
+    async fn import_config(&mut self, path: &Path) -> BackendResult<usize> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let imported = crate::backend::config::read_config(path).await?;
+        let imported_count = imported.tunnels.len();
+
+        self.apply_config(errors::config::validation_failed_after_add(), |config| {
+            let mut existing_ids: HashSet<TunnelId> = config.tunnels.iter().map(|t| t.id).collect();
+            for mut tunnel in imported.tunnels {
+                if !existing_ids.insert(tunnel.id) {
+                    tunnel.id = TunnelId::new();
+                    existing_ids.insert(tunnel.id);
+                }
+                tunnel.created_at.get_or_insert_with(Timestamp::now);
+                tunnel.modified_at.get_or_insert_with(Timestamp::now);
+                config.tunnels.push(tunnel);
+            }
+            Ok(())
+        })
+        .await?;
+
+        tracing::info!(
+            "{}",
+            errors::config::imported(imported_count, &path.display().to_string())
+        );
+        Ok(imported_count)
+    }
+
+    fn validate_tunnel_entry(&self, entry: &TunnelEntry) -> BackendResult<()> {
+        entry.validate()?;
+        if let Some((_, error)) = entry.path_errors(&self.config_dir()).into_iter().next() {
+            return Err(anyhow::anyhow!(error).into());
+        }
+        Ok(())
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only || self.config.load().global.locked
+    }
+
+    fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    async fn update_global_settings(&mut self, settings: GlobalSettings) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        settings.check_binary_executable()?;
+        self.apply_config(errors::config::validation_failed_after_edit(), |config| {
+            config.global = settings;
+            Ok(())
+        })
+        .await?;
+        self.refresh_wstunnel_version().await;
+        tracing::info!("Updated global settings");
+        Ok(())
+    }
+
+    async fn restore_config_backup(&mut self) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let restored = crate::backend::config::restore_backup(&self.config_path).await?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        self.stop_orphaned(&restored).await;
+
+        self.config.store(Arc::new(restored));
+        tracing::info!(
+            "{}",
+            errors::config::restored(&self.config_path.display().to_string())
+        );
+        Ok(())
+    }
+
+    async fn config_changed_on_disk(&self) -> bool {
+        crate::backend::config::file_hash(&self.config_path).await != self.config_file_hash
     }
 
-    fn add_tunnel(&mut self, mut entry: TunnelEntry) -> Result<TunnelId> {
+    async fn reload_config(&mut self) -> BackendResult<()> {
+        let reloaded = crate::backend::config::read_config(&self.config_path).await?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        self.stop_orphaned(&reloaded).await;
+
+        self.config.store(Arc::new(reloaded));
+        self.refresh_wstunnel_version().await;
+        tracing::info!(
+            "{}",
+            errors::config::reloaded(&self.config_path.display().to_string())
+        );
+        Ok(())
+    }
+
+    async fn overwrite_config(&mut self) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let config = self.config.load_full();
+        crate::backend::config::save_config(&self.config_path, &config)
+            .await
+            .context(errors::config::SAVE_FAILED)?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        tracing::info!(
+            "{}",
+            errors::config::overwritten(&self.config_path.display().to_string())
+        );
+        Ok(())
+    }
+
+    async fn add_tunnel(&mut self, mut entry: TunnelEntry) -> BackendResult<TunnelId> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        crate::backend::config::resolve_tunnel_paths(&mut entry, &self.config_path);
         self.validate_tunnel_entry(&entry)
             .context(errors::tunnel::validation::failed("tunnel entry"))?;
 
         if entry.id == TunnelId::default() {
             entry.id = TunnelId::new();
         }
+        let now = Timestamp::now();
+        entry.created_at = Some(now);
+        entry.modified_at = Some(now);
 
-        let mut new_config = (*self.config.load_full()).clone();
-        new_config.tunnels.push(entry.clone());
-        new_config
-            .validate()
-            .context(errors::config::validation_failed_after_add())?;
-
-        let config_path = self.config_path.clone();
-        self.runtime_handle
-            .block_on(async {
-                crate::backend::config::save_config(&config_path, &new_config).await
-            })
-            .context(errors::config::SAVE_FAILED)?;
-
-        self.config.store(Arc::new(new_config));
+        self.apply_config(errors::config::validation_failed_after_add(), |config| {
+            config.tunnels.push(entry.clone());
+            Ok(())
+        })
+        .await?;
         tracing::info!("Added tunnel: {}", entry.tag);
         Ok(entry.id)
     }
 
-    fn edit_tunnel(&mut self, id: TunnelId, entry: TunnelEntry) -> Result<()> {
+    async fn edit_tunnel(&mut self, id: TunnelId, mut entry: TunnelEntry) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        crate::backend::config::resolve_tunnel_paths(&mut entry, &self.config_path);
         self.validate_tunnel_entry(&entry)
             .context(errors::tunnel::validation::failed("tunnel entry"))?;
 
-        anyhow::ensure!(
-            !self.is_tunnel_running(id),
-            errors::tunnel::CANNOT_EDIT_RUNNING
-        );
+        if self.is_tunnel_running(id) {
+            return Err(anyhow::anyhow!(errors::tunnel::CANNOT_EDIT_RUNNING).into());
+        }
 
-        let mut new_config = (*self.config.load_full()).clone();
-        let tunnel_index = new_config
-            .tunnels
-            .iter()
-            .position(|t| t.id == id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&format!("{:?}", id))))?;
-
-        let old_tag = new_config.tunnels[tunnel_index].tag.clone();
-        new_config.tunnels[tunnel_index] = entry.clone();
-        new_config
-            .validate()
-            .context(errors::config::validation_failed_after_edit())?;
-
-        let config_path = self.config_path.clone();
-        self.runtime_handle
-            .block_on(async {
-                crate::backend::config::save_config(&config_path, &new_config).await
+        let new_tag = entry.tag.clone();
+        let old_tag = self
+            .apply_config(errors::config::validation_failed_after_edit(), |config| {
+                let tunnel = config
+                    .tunnels
+                    .iter_mut()
+                    .find(|t| t.id == id)
+                    .ok_or(BackendError::TunnelNotFound(id))?;
+                entry.created_at = tunnel.created_at;
+                entry.modified_at = Some(Timestamp::now());
+                Ok(std::mem::replace(tunnel, entry).tag)
             })
-            .context(errors::config::SAVE_FAILED)?;
-
-        self.config.store(Arc::new(new_config));
-        tracing::info!("Edited tunnel: {} -> {}", old_tag, entry.tag);
+            .await?;
+        tracing::info!("Edited tunnel: {} -> {}", old_tag, new_tag);
         Ok(())
     }
 
-    fn delete_tunnel(&mut self, id: TunnelId) -> Result<()> {
-        if self.is_tunnel_running(id) {
-            self.stop_tunnel(id)?;
-        }
-
-        let mut new_config = (*self.config.load_full()).clone();
-        let tunnel_index = new_config
-            .tunnels
-            .iter()
-            .position(|t| t.id == id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&format!("{:?}", id))))?;
-
-        let removed_tunnel = new_config.tunnels.remove(tunnel_index);
-
-        let config_path = self.config_path.clone();
-        self.runtime_handle.block_on(async {
-            crate::backend::config::save_config(&config_path, &new_config).await
-        })?;
+    async fn delete_tunnel(&mut self, id: TunnelId) -> BackendResult<()> {
+        self.delete_tunnels(&[id]).await
+    }
 
-        self.config.store(Arc::new(new_config));
-        self.last_known_log_paths.remove(&id);
+    async fn delete_tunnels(&mut self, ids: &[TunnelId]) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        // Checked up front so that nothing is stopped for a failing delete.
+        crate::backend::config::ensure_tunnels_exist(&self.config.load(), ids)?;
+        for &id in ids {
+            if self.is_tunnel_running(id) {
+                self.stop_tunnel(id).await?;
+            }
+        }
 
-        tracing::info!("Deleted tunnel: {}", removed_tunnel.tag);
+        let removed_tunnels = self
+            .apply_config(errors::config::validation_failed_after_edit(), |config| {
+                crate::backend::config::remove_tunnels(config, ids)
+            })
+            .await?;
+        for tunnel in removed_tunnels {
+            self.last_known_log_paths.remove(&tunnel.id);
+            self.stats.remove(&tunnel.id);
+            tracing::info!("Deleted tunnel: {}", tunnel.tag);
+        }
 
         Ok(())
     }
@@ -268,6 +992,15 @@ impl Backend for BackendState {
             .collect()
     }
 
+    fn tunnel_statuses(&mut self) -> Vec<(TunnelId, TunnelRuntimeState)> {
+        self.cleanup_dead_processes();
+        self.get_all_statuses()
+    }
+
+    fn config_generation(&self) -> u64 {
+        self.config.generation()
+    }
+
     fn get_tunnel(&mut self, id: TunnelId) -> Option<TunnelEntry> {
         self.cleanup_dead_processes();
         let config = self.config.load();
@@ -279,156 +1012,72 @@ impl Backend for BackendState {
         })
     }
 
-    fn start_tunnel(&mut self, id: TunnelId) -> Result<ProcessId> {
-        let config = self.config.load();
-
-        let tunnel = config
-            .tunnels
-            .iter()
-            .find(|t| t.id == id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&format!("{:?}", id))))?;
-
-        if let Some(process) = self.processes.get(&id) {
-            if process.pid().is_some() {
-                anyhow::bail!(errors::tunnel::already_running(&tunnel.tag));
-            } else {
-                anyhow::bail!(errors::tunnel::transitional_state(&tunnel.tag));
+    // Dependencies that aren't running yet are started first, and the first
+    // one that can't be spawned fails this start too. They are not waited on
+    // to be ready. A flapping dependency is left for the user to start
+    // directly.
+    async fn start_tunnel(&mut self, id: TunnelId) -> BackendResult<ProcessId> {
+        let config = self.config.load_full();
+        let tag_of = |id: TunnelId| {
+            config
+                .tunnels
+                .iter()
+                .find(|t| t.id == id)
+                .map_or_else(|| id.to_string(), |t| t.tag.clone())
+        };
+        for dependency in dependency_start_order(&config.tunnels, id) {
+            if self.is_tunnel_running(dependency) {
+                continue;
             }
-        }
-
-        let binary_path = config
-            .global
-            .wstunnel_binary_path
-            .clone()
-            .unwrap_or_else(|| self.wstunnel_binary_path.clone());
-
-        anyhow::ensure!(
-            binary_path.exists(),
-            errors::binary::not_found(&binary_path.display().to_string())
-        );
-
-        let cli_args = tunnel.cli_args.clone();
-        let log_directory = config.global.log_directory.clone();
-        let tunnel_id = tunnel.id;
-        let tunnel_tag = tunnel.tag.clone();
-
-        let child_token = self.cancellation_token.child_token();
-
-        let process_instance = self
-            .runtime_handle
-            .block_on(async {
-                let child =
-                    crate::backend::process::spawn_tunnel_process(&binary_path, &cli_args).await?;
-                crate::backend::process::create_process_instance(
-                    tunnel_id,
-                    tunnel_tag.clone(),
-                    child,
-                    &log_directory,
-                    child_token,
+            let result = match self.stats.get(&dependency) {
+                Some(stats) if stats.is_flapping() => Err(anyhow::anyhow!(
+                    stats.crash_error(&tag_of(dependency), stats.last_exit_code)
                 )
-                .await
-            })
-            .with_context(|| errors::tunnel::failed_to_start(&tunnel_tag))?;
-
-        let pid = process_instance
-            .pid()
-            .context(errors::process::FAILED_TO_PROCESS_PID)?;
-
-        tracing::info!("Started tunnel '{}' with PID {}", tunnel_tag, pid);
-
-        self.last_known_log_paths
-            .insert(id, process_instance.log_path.clone());
-        self.processes.insert(id, process_instance);
-
-        Ok(pid)
-    }
-
-    fn stop_tunnel(&mut self, id: TunnelId) -> Result<()> {
-        let process_instance = self
-            .processes
-            .get(&id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::NOT_RUNNING))?;
-
-        if process_instance.pid().is_none() {
-            anyhow::bail!(errors::tunnel::ALREADY_STOPPING);
-        }
-
-        let mut process_instance = self.processes.remove(&id).unwrap();
-        self.last_known_log_paths
-            .insert(id, process_instance.log_path.clone());
-
-        process_instance.cancellation_token.cancel();
-
-        let exit_code = self.runtime_handle.block_on(async {
-            let mut exit_code = None;
-            if let Some(mut child) = process_instance.child_handle.take() {
-                let pid = child.id();
-
-                match child.start_kill() {
-                    Ok(_) => {
-                        tracing::info!("Sent kill signal to process {:?}", pid);
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to send kill signal to process {:?}: {}", pid, e);
-                    }
-                }
-
-                match tokio::time::timeout(std::time::Duration::from_secs(5), child.wait()).await {
-                    Ok(Ok(status)) => {
-                        exit_code = status.code();
-                        tracing::info!(
-                            "Process {:?} exited with status: {} (code: {:?})",
-                            pid,
-                            status,
-                            exit_code
-                        );
-                    }
-                    Ok(Err(e)) => {
-                        tracing::error!("Error waiting for process {:?}: {}", pid, e);
-                    }
-                    Err(_) => {
-                        tracing::warn!(
-                            "Process {:?} did not exit within timeout, forcing kill",
-                            pid
-                        );
-                    }
-                }
-            }
-
-            if let Some(monitor_task) = process_instance.monitor_task.take() {
-                monitor_task.abort();
-                let _ = monitor_task.await;
+                .into()),
+                _ => self.start_one(dependency).await,
+            };
+            if let Err(e) = result {
+                return Err(anyhow::anyhow!(errors::tunnel::dependency_failed(
+                    &tag_of(id),
+                    &tag_of(dependency),
+                    &e.to_string()
+                ))
+                .into());
             }
-
-            exit_code
-        });
-
-        if let Some(code) = exit_code
-            && code != 0
-        {
-            tracing::warn!("Tunnel {:?} stopped with non-zero exit code: {}", id, code);
         }
+        self.start_one(id).await
+    }
 
-        tracing::info!("Stopped tunnel {:?}", id);
-
-        Ok(())
+    async fn stop_tunnel(&mut self, id: TunnelId) -> BackendResult<()> {
+        if self.config.load().global.stop_dependents {
+            self.stop_dependents(id).await;
+        }
+        self.stop_one(id).await
     }
 
-    fn start_autostart_tunnels(&mut self) -> Result<Vec<(TunnelId, Result<ProcessId>)>> {
-        let config = self.config.load();
-        let autostart_tunnels: Vec<TunnelId> = config
-            .tunnels
-            .iter()
-            .filter(|t| t.autostart)
-            .map(|t| t.id)
-            .collect();
+    async fn start_autostart_tunnels(
+        &mut self,
+    ) -> BackendResult<Vec<(TunnelId, BackendResult<ProcessId>)>> {
+        let autostart_tunnels = autostart_plan(&self.config.load().tunnels);
 
         let mut results = Vec::new();
         let mut started_count = 0;
         let mut failed_count = 0;
 
-        for tunnel_id in autostart_tunnels {
-            let result = self.start_tunnel(tunnel_id);
+        let mut remaining = autostart_tunnels.len();
+        for (tunnel_id, delay) in autostart_tunnels {
+            remaining -= 1;
+            // Already started as the dependency of an earlier tunnel.
+            if self.is_tunnel_running(tunnel_id) {
+                continue;
+            }
+            let result = self.start_tunnel(tunnel_id).await;
+            // Only a tunnel that actually started is worth waiting for.
+            if let (Ok(_), Some(delay)) = (&result, delay)
+                && remaining > 0
+            {
+                tokio::time::sleep(delay).await;
+            }
             match &result {
                 Ok(pid) => {
                     tracing::info!("Autostart: Started tunnel {:?} with PID {}", tunnel_id, pid);
@@ -451,20 +1100,91 @@ impl Backend for BackendState {
         Ok(results)
     }
 
+    // Tunnels with a process entry are skipped whether they run, are still
+    // starting or are being stopped.
+    async fn start_all(&mut self) -> Vec<(TunnelId, BackendResult<ProcessId>)> {
+        self.cleanup_dead_processes();
+        let tunnel_ids: Vec<TunnelId> = self
+            .config
+            .load()
+            .tunnels
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| !self.processes.contains_key(id))
+            .collect();
+
+        let mut results = Vec::new();
+        for tunnel_id in tunnel_ids {
+            // Started as the dependency of an earlier tunnel.
+            if self.processes.contains_key(&tunnel_id) {
+                continue;
+            }
+            let result = self.start_tunnel(tunnel_id).await;
+            results.push((tunnel_id, result));
+        }
+        results
+    }
+
+    // The tunnels are stopped concurrently, so stuck ones are force-killed
+    // after one `STOP_TIMEOUT` in total rather than one each.
+    async fn stop_all(&mut self) -> Vec<(TunnelId, BackendResult<()>)> {
+        let tunnel_ids: Vec<TunnelId> = self.processes.keys().copied().collect();
+
+        let mut results = Vec::new();
+        let mut stopping = tokio::task::JoinSet::new();
+        for tunnel_id in tunnel_ids {
+            match self.take_for_stop(tunnel_id) {
+                Ok(process_instance) => {
+                    stopping.spawn(stop_process(process_instance));
+                }
+                Err(e) => results.push((tunnel_id, Err(e))),
+            }
+        }
+        while let Some(stopped) = stopping.join_next().await {
+            match stopped {
+                Ok((process_instance, exit_code)) => {
+                    let tunnel_id = process_instance.tunnel_id;
+                    self.record_stop(process_instance, exit_code).await;
+                    results.push((tunnel_id, Ok(())));
+                }
+                Err(e) => tracing::error!("Stop task failed: {}", e),
+            }
+        }
+        results
+    }
+
     fn get_tunnel_status(&self, id: TunnelId) -> TunnelRuntimeState {
         match self.processes.get(&id) {
+            Some(_) if self.starting.lock().unwrap().contains(&id) => TunnelRuntimeState::Starting,
             Some(process_instance) => {
                 if let Some(pid) = process_instance.pid() {
                     TunnelRuntimeState::Running {
                         pid,
                         started_at: process_instance.started_at,
                         log_path: process_instance.log_path.clone(),
+                        health: process_instance.health(),
+                        command_line: crate::backend::process::redact_arg_list(
+                            process_instance.command_line.iter().cloned(),
+                            &self.config.load().global.sensitive_flags,
+                        ),
                     }
                 } else {
                     TunnelRuntimeState::Stopped
                 }
             }
-            None => TunnelRuntimeState::Stopped,
+            None => match self.last_runs.get(&id) {
+                Some(LastRun {
+                    error: Some(error),
+                    ended_at,
+                    exit_code,
+                    ..
+                }) => TunnelRuntimeState::Failed {
+                    error: error.clone(),
+                    last_attempt: *ended_at,
+                    exit_code: *exit_code,
+                },
+                _ => TunnelRuntimeState::Stopped,
+            },
         }
     }
 
@@ -477,32 +1197,176 @@ impl Backend for BackendState {
             .collect()
     }
 
+    fn summary(&self) -> TunnelSummary {
+        let config = self.config.load();
+        TunnelSummary::of(
+            config
+                .tunnels
+                .iter()
+                .map(|tunnel| self.get_tunnel_status(tunnel.id)),
+        )
+    }
+
     fn is_tunnel_running(&self, id: TunnelId) -> bool {
         self.processes.get(&id).and_then(|p| p.pid()).is_some()
     }
 
+    // A process that died since the last cleanup is counted as a crash here
+    // already, and a running one contributes its current uptime.
+    fn get_tunnel_stats(&self, id: TunnelId) -> Option<TunnelStats> {
+        if !self.config.load().tunnels.iter().any(|t| t.id == id) {
+            return None;
+        }
+
+        let mut stats = self.stats.get(&id).cloned().unwrap_or_default();
+        if let Some(process) = self.processes.get(&id) {
+            if process.has_exited() {
+                let exit_code = process
+                    .exit_status
+                    .borrow()
+                    .and_then(|status| status.code());
+                stats.record_exit(process.started_at, exit_code, true);
+            } else {
+                stats.cumulative_uptime += process.started_at.elapsed();
+            }
+        }
+        Some(stats)
+    }
+
+    fn wstunnel_version(&self) -> Option<String> {
+        self.wstunnel_version.clone()
+    }
+
+    fn config_path(&self) -> PathBuf {
+        self.config_path.clone()
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<TunnelEvent> {
+        self.events.subscribe()
+    }
+
+    fn preview_command(&self, id: TunnelId) -> BackendResult<String> {
+        let config = self.config.load_full();
+        let tunnel = config
+            .tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(BackendError::TunnelNotFound(id))?;
+
+        let working_dir = tunnel.effective_working_dir(&self.config_dir());
+        let args = crate::backend::process::resolve_cli_args(
+            &tunnel.cli_args,
+            &tunnel.env,
+            config.global.fail_on_missing_env_vars,
+            &working_dir,
+        )?;
+        Ok(crate::backend::process::format_command(
+            &self.binary_path(&config),
+            &args,
+            &tunnel.env,
+            &working_dir,
+            tunnel.priority,
+            &config.global.sensitive_flags,
+        ))
+    }
+
+    // After the last run's log is gone (or when the tunnel hasn't run since
+    // the manager started), the newest log left in its directory is used.
     fn get_log_path(&self, id: TunnelId) -> Option<PathBuf> {
-        self.processes
+        if let Some(process) = self.processes.get(&id) {
+            return Some(process.log_path.clone());
+        }
+        if let Some(path) = self
+            .last_known_log_paths
             .get(&id)
-            .map(|p| p.log_path.clone())
-            .or_else(|| self.last_known_log_paths.get(&id).cloned())
+            .and_then(|path| crate::backend::logs::resolve_log_path(path))
+        {
+            return Some(path);
+        }
+
+        let config = self.config.load();
+        let tunnel = config.tunnels.iter().find(|t| t.id == id)?;
+        crate::backend::logs::latest_log_for(
+            tunnel.effective_log_directory(&config.global),
+            &crate::backend::process::log_name(id, &tunnel.tag),
+        )
     }
 
-    fn shutdown(&mut self) -> Result<()> {
-        tracing::info!("Shutting down backend, stopping all tunnels");
+    fn recent_stderr(&self, id: TunnelId) -> String {
+        match self.processes.get(&id) {
+            Some(process) => process.get_stderr(),
+            None => self.last_stderr.get(&id).cloned().unwrap_or_default(),
+        }
+    }
 
-        self.cancellation_token.cancel();
+    fn read_recent_logs(&self, id: TunnelId, lines: usize) -> BackendResult<Vec<String>> {
+        let Some(log_path) = self.get_log_path(id) else {
+            return Ok(Vec::new());
+        };
 
-        if let Some(task) = self.cleanup_task.take() {
-            task.abort();
-            let _ = self.runtime_handle.block_on(task);
-            tracing::info!("Periodic cleanup task stopped");
+        if !log_path.exists() {
+            return Err(
+                anyhow::anyhow!(errors::logs::not_found(&log_path.display().to_string())).into(),
+            );
         }
 
-        let tunnel_ids: Vec<TunnelId> = self.processes.keys().copied().collect();
+        let lines = crate::backend::logs::tail_lines(&log_path, lines)
+            .with_context(|| errors::logs::failed_to_read(&log_path.display().to_string()))?;
+        Ok(lines)
+    }
 
-        for tunnel_id in tunnel_ids {
-            if let Err(e) = self.stop_tunnel(tunnel_id) {
+    fn grep_logs(
+        &self,
+        id: TunnelId,
+        search: &LogSearch,
+        max: usize,
+    ) -> BackendResult<Vec<String>> {
+        let config = self.config.load();
+        let tunnel = config
+            .tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(BackendError::TunnelNotFound(id))?;
+        let files = crate::backend::logs::log_history_for(
+            tunnel.effective_log_directory(&config.global),
+            &crate::backend::process::log_name(id, &tunnel.tag),
+        );
+        Ok(crate::backend::logs::grep_files(&files, search, max)?)
+    }
+
+    fn read_combined_logs(&self, lines_per_tunnel: usize) -> Vec<TaggedLogLine> {
+        let config = self.config.load();
+        let tails = config
+            .tunnels
+            .iter()
+            .map(|tunnel| {
+                let lines = self
+                    .read_recent_logs(tunnel.id, lines_per_tunnel)
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Skipping logs of tunnel '{}': {:#}", tunnel.tag, e);
+                        Vec::new()
+                    });
+                lines
+                    .iter()
+                    .map(|line| TaggedLogLine::parse(tunnel.id, &tunnel.tag, line))
+                    .collect()
+            })
+            .collect();
+        crate::backend::logs::merge_logs(tails)
+    }
+
+    async fn shutdown(&mut self) -> BackendResult<()> {
+        if self.config.load().global.detach_on_exit {
+            return self.detach().await;
+        }
+
+        tracing::info!("Shutting down backend, stopping all tunnels");
+
+        self.cancellation_token.cancel();
+        self.stop_cleanup_task().await;
+
+        for (tunnel_id, result) in self.stop_all().await {
+            if let Err(e) = result {
                 tracing::error!(
                     "Error stopping tunnel {:?} during shutdown: {}",
                     tunnel_id,
@@ -511,24 +1375,77 @@ impl Backend for BackendState {
             }
         }
 
+        self.config_lock = None;
         tracing::info!("Backend shutdown complete");
 
         Ok(())
     }
 
-    fn cleanup_old_logs_if_configured(&self) -> Result<()> {
-        let config = self.config.load();
+    // The monitor tasks are released before the backend's token is cancelled,
+    // since cancelling it would kill every child.
+    async fn detach(&mut self) -> BackendResult<()> {
+        tracing::info!("Detaching from running tunnels");
 
-        match config.global.log_retention_days {
-            Some(days) => crate::backend::config::cleanup_old_logs_sync(
-                &self.runtime_handle,
-                &config.global.log_directory,
-                days,
-            ),
-            None => {
-                tracing::debug!("Log retention not configured, skipping log cleanup");
-                Ok(())
+        for (tunnel_id, mut process) in self.processes.drain() {
+            process.detach_token.cancel();
+            if let Some(monitor_task) = process.monitor_task.take() {
+                let _ = monitor_task.await;
             }
+            self.last_known_log_paths
+                .insert(tunnel_id, process.log_path.clone());
+            tracing::info!(
+                "Left tunnel {:?} running with PID {}",
+                tunnel_id,
+                process.process_id
+            );
         }
+
+        self.cancellation_token.cancel();
+        self.stop_cleanup_task().await;
+        self.config_lock = None;
+
+        Ok(())
+    }
+
+    async fn cleanup_old_logs_if_configured(&self) -> BackendResult<()> {
+        let config = self.config.load_full();
+
+        if config.global.log_retention_days.is_none() && config.global.log_compress_days.is_none() {
+            tracing::debug!("Log retention not configured, skipping log cleanup");
+            return Ok(());
+        }
+
+        let directories = config
+            .log_directories()
+            .iter()
+            .map(|dir| dir.display())
+            .join(", ");
+
+        if let Some(days) = config.global.log_compress_days {
+            tracing::info!(
+                "Log compression enabled: compressing logs older than {} days in {}",
+                days,
+                directories
+            );
+        }
+
+        if let Some(days) = config.global.log_retention_days {
+            tracing::info!(
+                "Log retention enabled: cleaning up logs older than {} days in {}",
+                days,
+                directories
+            );
+        }
+
+        Ok(crate::backend::config::maintain_logs(&config, &self.open_log_files()).await?)
+    }
+
+    async fn cleanup_logs_now(&self, retention_days: Option<u32>) -> BackendResult<usize> {
+        let config = self.config.load_full();
+        let days = retention_days
+            .or(config.global.log_retention_days)
+            .ok_or_else(|| anyhow::anyhow!(errors::logs::NO_RETENTION_DAYS))?;
+        tracing::info!("Cleaning up logs older than {} days now", days);
+        Ok(crate::backend::config::cleanup_logs_now(&config, days, &self.open_log_files()).await?)
     }
 }
@@ -0,0 +1,309 @@
+use crate::backend::process::{args_file_path, parse_cli_args};
+use crate::backend::types::TunnelMode;
+use crate::errors;
+use std::fmt;
+
+// A small slice of wstunnel's command line, enough to catch the mistakes new
+// users make most: flags before the subcommand, client flags on a server and
+// the other way round, malformed -L/-R specs and a missing URL. Flags that
+// aren't listed here are never reported, so newer wstunnel versions keep
+// working.
+
+// Flags wstunnel accepts before the subcommand.
+const GLOBAL_FLAGS: &[&str] = &["--no-color", "--nb-worker-threads", "--log-lvl"];
+
+const CLIENT_ONLY_FLAGS: &[&str] = &[
+    "-L",
+    "--local-to-remote",
+    "-R",
+    "--remote-to-local",
+    "-c",
+    "--connection-min-idle",
+    "--reverse-tunnel-connection-retry-max-backoff",
+    "-P",
+    "--http-upgrade-path-prefix",
+    "--http-upgrade-credentials",
+    "-H",
+    "--header",
+    "--http-headers",
+    "--http-headers-file",
+    "--tls-sni-override",
+    "--tls-sni-disable",
+    "--tls-ech-enable",
+    "--tls-verify-certificate",
+];
+
+const SERVER_ONLY_FLAGS: &[&str] = &[
+    "--restrict-to",
+    "--restrict-config",
+    "-r",
+    "--restrict-http-upgrade-path-prefix",
+    "--tls-client-ca-certs",
+    "--remote-to-local-server-idle-timeout",
+];
+
+// Flags whose value is a tunnel spec such as `tcp://8080:localhost:80`.
+const TUNNEL_SPEC_FLAGS: &[&str] = &["-L", "--local-to-remote", "-R", "--remote-to-local"];
+
+// Schemes of the client's server URL and the server's bind address.
+const URL_SCHEMES: &[&str] = &["ws://", "wss://", "http://", "https://"];
+
+// Returns a message for every problem found in `cli_args`; empty when
+// nothing looks wrong. The subcommand and URL may come from an args file,
+// so they are only required when there is none.
+pub fn check_cli_args(cli_args: &str) -> Vec<String> {
+    let args = parse_cli_args(cli_args);
+    let has_args_file = args.iter().any(|arg| args_file_path(arg).is_some());
+    let mut problems = Vec::new();
+
+    let Some((position, mode)) = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.as_str() {
+            "client" => Some((i, TunnelMode::Client)),
+            "server" => Some((i, TunnelMode::Server)),
+            _ => None,
+        })
+    else {
+        if !has_args_file {
+            problems.push(errors::tunnel::validation::MISSING_SUBCOMMAND.to_string());
+        }
+        return problems;
+    };
+    let subcommand = mode.subcommand();
+
+    for arg in &args[..position] {
+        let name = flag_name(arg);
+        if name.starts_with('-') && !GLOBAL_FLAGS.contains(&name) {
+            problems.push(errors::tunnel::validation::flag_before_subcommand(
+                name, subcommand,
+            ));
+        }
+    }
+
+    let rest = &args[position + 1..];
+    let wrong_side = match mode {
+        TunnelMode::Client => SERVER_ONLY_FLAGS,
+        TunnelMode::Server => CLIENT_ONLY_FLAGS,
+    };
+    for (i, arg) in rest.iter().enumerate() {
+        let name = flag_name(arg);
+        if wrong_side.contains(&name) {
+            problems.push(errors::tunnel::validation::flag_wrong_mode(
+                name, subcommand,
+            ));
+        }
+        if TUNNEL_SPEC_FLAGS.contains(&name) {
+            let value = match arg.split_once('=') {
+                Some((_, value)) if arg.starts_with("--") => Some(value),
+                _ => rest.get(i + 1).map(String::as_str),
+            };
+            if !value.is_some_and(|value| value.contains("://")) {
+                problems.push(errors::tunnel::validation::tunnel_spec_invalid(
+                    name,
+                    value.unwrap_or_default(),
+                ));
+            }
+        }
+    }
+
+    if !has_args_file
+        && !rest
+            .iter()
+            .any(|arg| URL_SCHEMES.iter().any(|scheme| arg.starts_with(scheme)))
+    {
+        problems.push(errors::tunnel::validation::missing_url(subcommand));
+    }
+
+    problems
+}
+
+// Host of the server URL a client connects to, e.g. "example.com" for
+// `client wss://example.com:443`. None for servers and malformed URLs.
+pub fn client_server_host(cli_args: &str) -> Option<String> {
+    let args = parse_cli_args(cli_args);
+    let position = args.iter().position(|arg| arg == "client")?;
+    let url = args[position + 1..].iter().find_map(|arg| {
+        URL_SCHEMES
+            .iter()
+            .find_map(|scheme| arg.strip_prefix(scheme))
+    })?;
+
+    let authority = url.split('/').next().unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or_default();
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+// What a tunnel forwards, as shown next to it in the tunnel list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    // `-L`: listens on this machine and forwards through the server.
+    Local {
+        protocol: String,
+        listen: String,
+        target: String,
+    },
+    // `-R`: the server listens and forwards back through this machine.
+    Remote {
+        protocol: String,
+        listen: String,
+        target: String,
+    },
+    // The server URL of a client or the bind address of a server.
+    Url(String),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Local { listen, target, .. } => write!(f, "L:{} → {}", listen, target),
+            Endpoint::Remote { listen, target, .. } => write!(f, "R:{} → {}", listen, target),
+            Endpoint::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl Endpoint {
+    // `host:port` a browser can reach a local TCP or HTTP proxy forward on.
+    // Wildcard binds are reached through 127.0.0.1.
+    pub fn local_address(&self) -> Option<String> {
+        let Endpoint::Local {
+            protocol, listen, ..
+        } = self
+        else {
+            return None;
+        };
+        if protocol != "tcp" && protocol != "http" {
+            return None;
+        }
+        let (bind, port) = match listen.rsplit_once(':') {
+            Some((bind, port)) => (bind, port),
+            None => ("", listen.as_str()),
+        };
+        let port: u16 = port.parse().ok()?;
+        let host = match bind {
+            "" | "0.0.0.0" | "[::]" => "127.0.0.1",
+            bind => bind,
+        };
+        Some(format!("{}:{}", host, port))
+    }
+}
+
+// Best-effort reading of the -L/-R specs and the URL in `cli_args`. Specs
+// that don't look like wstunnel's syntax are left out rather than guessed.
+pub fn parse_endpoints(cli_args: &str) -> Vec<Endpoint> {
+    let args = parse_cli_args(cli_args);
+    let Some(position) = args
+        .iter()
+        .position(|arg| arg == "client" || arg == "server")
+    else {
+        return Vec::new();
+    };
+    let rest = &args[position + 1..];
+
+    let mut endpoints = Vec::new();
+    let mut url = None;
+    for (i, arg) in rest.iter().enumerate() {
+        let name = flag_name(arg);
+        if TUNNEL_SPEC_FLAGS.contains(&name) {
+            let value = match arg.split_once('=') {
+                Some((_, value)) if arg.starts_with("--") => Some(value),
+                _ => rest.get(i + 1).map(String::as_str),
+            };
+            let Some((protocol, listen, target)) = value.and_then(parse_tunnel_spec) else {
+                continue;
+            };
+            endpoints.push(match name {
+                "-L" | "--local-to-remote" => Endpoint::Local {
+                    protocol,
+                    listen,
+                    target,
+                },
+                _ => Endpoint::Remote {
+                    protocol,
+                    listen,
+                    target,
+                },
+            });
+        } else if url.is_none()
+            && URL_SCHEMES.iter().any(|scheme| arg.starts_with(scheme))
+            && !rest[..i]
+                .last()
+                .is_some_and(|prev| TUNNEL_SPEC_FLAGS.contains(&prev.as_str()))
+        {
+            url = Some(arg.clone());
+        }
+    }
+    endpoints.extend(url.map(Endpoint::Url));
+    endpoints
+}
+
+// Splits a spec such as `tcp://8080:localhost:80` into its protocol, what is
+// listened on and where it goes. Dynamic tunnels (socks5, http proxy) show
+// their protocol as the target.
+fn parse_tunnel_spec(spec: &str) -> Option<(String, String, String)> {
+    let (protocol, rest) = spec.split_once("://")?;
+    let rest = rest.split('?').next().unwrap_or_default();
+    let parts = split_host_ports(rest);
+    match protocol.trim_start_matches("tproxy+") {
+        "tcp" | "udp" => match parts.as_slice() {
+            [port, host, remote_port] => Some((port.clone(), format!("{}:{}", host, remote_port))),
+            [bind, port, host, remote_port] => Some((
+                format!("{}:{}", bind, port),
+                format!("{}:{}", host, remote_port),
+            )),
+            _ => None,
+        },
+        "socks5" | "http" => match parts.as_slice() {
+            [port] => Some((port.clone(), protocol.to_string())),
+            [bind, port] => Some((format!("{}:{}", bind, port), protocol.to_string())),
+            _ => None,
+        },
+        "stdio" => match parts.as_slice() {
+            [host, port] => Some(("stdio".to_string(), format!("{}:{}", host, port))),
+            _ => None,
+        },
+        "unix" => match parts.as_slice() {
+            [path @ .., host, port] if !path.is_empty() => {
+                Some((path.join(":"), format!("{}:{}", host, port)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+    .filter(|(listen, target)| !listen.is_empty() && !target.is_empty())
+    .map(|(listen, target)| (protocol.to_string(), listen, target))
+}
+
+// Splits on ':' but keeps bracketed IPv6 addresses such as `[::1]` whole.
+fn split_host_ports(value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_brackets = false;
+    for c in value.chars() {
+        match c {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            ':' if !in_brackets => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+// `--flag=value` is checked under `--flag`.
+fn flag_name(arg: &str) -> &str {
+    match arg.split_once('=') {
+        Some((name, _)) if arg.starts_with("--") => name,
+        _ => arg,
+    }
+}
@@ -1,31 +1,346 @@
-use crate::backend::types::Config;
+use crate::backend::error::BackendError;
+use crate::backend::types::{Config, TunnelEntry, TunnelId};
 use crate::errors;
 use anyhow::Context;
+use arc_swap::ArcSwap;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs;
 use tokio::sync::mpsc;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    // Unknown or missing extensions fall back to YAML, the original format.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => ConfigFormat::Json,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Yaml => "YAML",
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Toml => "TOML",
+        }
+    }
+
+    // For text without a file name, e.g. from the clipboard.
+    pub fn guess(contents: &str) -> Self {
+        if contents.trim_start().starts_with('{') {
+            ConfigFormat::Json
+        } else if serde_yaml::from_str::<serde_yaml::Value>(contents)
+            .is_ok_and(|value| value.is_mapping())
+        {
+            ConfigFormat::Yaml
+        } else {
+            ConfigFormat::Toml
+        }
+    }
+
+    pub fn parse_value(&self, contents: &str) -> anyhow::Result<serde_yaml::Value> {
+        let value = match self {
+            ConfigFormat::Yaml => serde_yaml::from_str(contents)?,
+            ConfigFormat::Json => serde_json::from_str(contents)?,
+            ConfigFormat::Toml => toml::from_str(contents)?,
+        };
+        Ok(value)
+    }
+
+    pub fn serialize(&self, config: &Config) -> anyhow::Result<String> {
+        let contents = match self {
+            ConfigFormat::Yaml => serde_yaml::to_string(config)?,
+            ConfigFormat::Json => serde_json::to_string_pretty(config)?,
+            ConfigFormat::Toml => toml::to_string_pretty(config)?,
+        };
+        Ok(contents)
+    }
+}
+
+// One tunnel as a snippet to send to someone else. The ID, timestamps and
+// dependencies, which mean nothing in another config, are left out, and the
+// values of sensitive flags and environment variables are redacted.
+pub fn tunnel_snippet(
+    tunnel: &TunnelEntry,
+    sensitive_flags: &[String],
+    format: ConfigFormat,
+) -> anyhow::Result<String> {
+    let mut tunnel = tunnel.clone();
+    tunnel.runtime_state = None;
+    tunnel.created_at = None;
+    tunnel.modified_at = None;
+    tunnel.depends_on.clear();
+    tunnel.cli_args = crate::backend::process::redact_cli_args(&tunnel.cli_args, sensitive_flags);
+    tunnel
+        .env
+        .values_mut()
+        .for_each(|value| *value = crate::constants::REDACTED.to_string());
+
+    let mut value = serde_yaml::to_value(&tunnel)?;
+    if let Some(mapping) = value.as_mapping_mut() {
+        mapping.remove("id");
+    }
+    let contents = match format {
+        ConfigFormat::Yaml => serde_yaml::to_string(&value)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(&value)?,
+        ConfigFormat::Toml => toml::to_string_pretty(&value)?,
+    };
+    Ok(contents)
+}
+
+// Whether `contents` holds a single tunnel rather than a whole config.
+pub fn is_tunnel_snippet(contents: &str, format: ConfigFormat) -> bool {
+    format
+        .parse_value(contents)
+        .is_ok_and(|value| value.get("cli_args").is_some())
+}
+
+// The tunnel in a snippet from `tunnel_snippet`, with a fresh ID so it can be
+// added even next to the tunnel it was exported from.
+pub fn parse_tunnel_snippet(contents: &str, format: ConfigFormat) -> anyhow::Result<TunnelEntry> {
+    let mut value = format
+        .parse_value(contents)
+        .context(errors::config::TUNNEL_SNIPPET_INVALID)?;
+    if let Some(mapping) = value.as_mapping_mut() {
+        mapping.insert("id".into(), serde_yaml::to_value(TunnelId::new())?);
+    }
+    let mut tunnel: TunnelEntry =
+        serde_yaml::from_value(value).context(errors::config::TUNNEL_SNIPPET_INVALID)?;
+    tunnel.runtime_state = None;
+    tunnel.created_at = None;
+    tunnel.modified_at = None;
+    tunnel.depends_on.clear();
+    tunnel
+        .validate()
+        .with_context(|| errors::tunnel::validation::failed(&tunnel.tag))?;
+    Ok(tunnel)
+}
+
+// JSON Schema of the config file, for editors that validate and complete it.
+// It describes the current version; older files are migrated on load.
+pub fn config_schema() -> serde_json::Value {
+    let mut schema = schemars::schema_for!(Config);
+    schema.insert(
+        "title".to_string(),
+        format!("{} config", crate::constants::APP_TITLE).into(),
+    );
+    schema.to_value()
+}
+
+pub fn ensure_tunnels_exist(config: &Config, ids: &[TunnelId]) -> Result<(), BackendError> {
+    match ids
+        .iter()
+        .find(|id| !config.tunnels.iter().any(|t| t.id == **id))
+    {
+        Some(&missing) => Err(BackendError::TunnelNotFound(missing)),
+        None => Ok(()),
+    }
+}
+
+// Takes the tunnels out of `config` and drops them from the dependencies of
+// the tunnels that stay. Nothing is removed when any of them is missing.
+pub fn remove_tunnels(
+    config: &mut Config,
+    ids: &[TunnelId],
+) -> Result<Vec<TunnelEntry>, BackendError> {
+    ensure_tunnels_exist(config, ids)?;
+    let (removed, kept) = std::mem::take(&mut config.tunnels)
+        .into_iter()
+        .partition(|t| ids.contains(&t.id));
+    config.tunnels = kept;
+    for tunnel in &mut config.tunnels {
+        tunnel
+            .depends_on
+            .retain(|dependency| !ids.contains(dependency));
+    }
+    Ok(removed)
+}
+
+// The in-memory config. Every `store` bumps the generation, so callers that
+// cached the tunnel list can tell whether it needs loading again.
+pub struct ConfigStore {
+    config: ArcSwap<Config>,
+    generation: AtomicU64,
+}
+
+impl ConfigStore {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: ArcSwap::from_pointee(config),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn load(&self) -> arc_swap::Guard<Arc<Config>> {
+        self.config.load()
+    }
+
+    pub fn load_full(&self) -> Arc<Config> {
+        self.config.load_full()
+    }
+
+    pub fn store(&self, config: Arc<Config>) {
+        self.config.store(config);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+}
+
+fn backup_path_for(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.as_os_str().to_os_string();
+    file_name.push(suffix);
+    PathBuf::from(file_name)
+}
+
+// Parses into a generic value first so older config versions can be migrated
+// before they are deserialized into the current `Config` shape.
+fn parse_and_migrate(path: &Path, contents: &str) -> anyhow::Result<(Config, Option<u32>)> {
+    let mut value = ConfigFormat::from_path(path).parse_value(contents)?;
+    let migrated_from = crate::backend::migrations::migrate(&mut value)?;
+    let mut config = serde_yaml::from_value(value)?;
+    resolve_paths(&mut config, path);
+    Ok((config, migrated_from))
+}
+
+// Relative paths in the global settings and in per-tunnel log directories
+// are relative to the config file, not to wherever the manager happened to be
+// started from. They are made absolute when a config is read and relative
+// again when it is written, so the file keeps the form the user wrote.
+fn config_dir(path: &Path) -> PathBuf {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+fn resolve_paths(config: &mut Config, path: &Path) {
+    let dir = config_dir(path);
+    let global = &mut config.global;
+    if global.log_directory.is_relative() {
+        global.log_directory = dir.join(&global.log_directory);
+    }
+    if let Some(binary) = &mut global.wstunnel_binary_path
+        && binary.is_relative()
+    {
+        *binary = dir.join(&*binary);
+    }
+    for tunnel in &mut config.tunnels {
+        resolve_tunnel_paths(tunnel, path);
+    }
+}
+
+// Also used for tunnels added or edited after the config was read.
+pub fn resolve_tunnel_paths(tunnel: &mut TunnelEntry, path: &Path) {
+    if let Some(log_directory) = &mut tunnel.log_directory
+        && log_directory.is_relative()
+    {
+        *log_directory = config_dir(path).join(&*log_directory);
+    }
+}
+
+fn unresolve_paths(config: &mut Config, path: &Path) {
+    let dir = config_dir(path);
+    // Compared as text rather than by components so that `./logs` comes back
+    // as `./logs` and not `logs`.
+    let relative = |resolved: &Path| -> Option<PathBuf> {
+        if !resolved.starts_with(&dir) {
+            return None;
+        }
+        let rest = resolved.to_str()?.strip_prefix(dir.to_str()?)?;
+        let rest = rest.trim_start_matches(std::path::is_separator);
+        (!rest.is_empty()).then(|| PathBuf::from(rest))
+    };
+    let global = &mut config.global;
+    if let Some(log_directory) = relative(&global.log_directory) {
+        global.log_directory = log_directory;
+    }
+    if let Some(binary) = &mut global.wstunnel_binary_path
+        && let Some(relative_binary) = relative(binary)
+    {
+        *binary = relative_binary;
+    }
+    for tunnel in &mut config.tunnels {
+        if let Some(log_directory) = &mut tunnel.log_directory
+            && let Some(relative_log_directory) = relative(log_directory)
+        {
+            *log_directory = relative_log_directory;
+        }
+    }
+}
+
+// Reads and validates a config without the side effects of `load_config`:
+// missing or corrupted files are reported instead of being replaced.
+pub async fn read_config(path: &Path) -> anyhow::Result<Config> {
+    let contents = fs::read_to_string(path)
+        .await
+        .with_context(|| errors::config::failed_to_read(&path.display().to_string()))?;
+    let (config, _) = parse_and_migrate(path, &contents)
+        .with_context(|| errors::config::failed_to_parse(&path.display().to_string()))?;
+    config
+        .validate()
+        .with_context(|| errors::config::validation_failed(&path.display().to_string()))?;
+    Ok(config)
+}
+
 #[allow(dead_code)]
 pub async fn load_config(path: &Path) -> anyhow::Result<Config> {
     match fs::read_to_string(path).await {
-        Ok(contents) => match serde_yaml::from_str::<Config>(&contents) {
-            Ok(config) => {
+        Ok(contents) => match parse_and_migrate(path, &contents) {
+            Ok((config, migrated_from)) => {
                 config.validate().with_context(|| {
                     errors::config::validation_failed(&path.display().to_string())
                 })?;
+
+                if let Some(from_version) = migrated_from {
+                    let backup_path = backup_path_for(path, &format!(".v{}.bak", from_version));
+                    fs::copy(path, &backup_path).await.with_context(|| {
+                        errors::config::failed_to_backup(&backup_path.display().to_string())
+                    })?;
+                    save_config(path, &config)
+                        .await
+                        .context(errors::config::SAVE_FAILED)?;
+                    tracing::info!(
+                        "{}",
+                        errors::config::migrated(
+                            from_version,
+                            config.version,
+                            &backup_path.display().to_string()
+                        )
+                    );
+                }
+
                 Ok(config)
             }
             Err(parse_error) => {
                 tracing::error!(
                     "{}",
-                    errors::config::corrupted_yaml(
+                    errors::config::corrupted_file(
                         &path.display().to_string(),
                         &parse_error.to_string()
                     )
                 );
 
-                let backup_path = path.with_extension("yaml.bak");
+                let backup_path = backup_path_for(path, ".bak");
                 if let Err(e) = fs::copy(path, &backup_path).await {
                     tracing::warn!("Failed to create backup of corrupted config: {}", e);
                 } else {
@@ -40,18 +355,20 @@ pub async fn load_config(path: &Path) -> anyhow::Result<Config> {
                     errors::config::failed_to_create_default(&path.display().to_string())
                 })?;
 
-                Err(anyhow::anyhow!(errors::config::corrupted(
+                Err(BackendError::ConfigCorrupted(errors::config::corrupted(
                     &path.display().to_string(),
                     &backup_path.display().to_string(),
-                    &parse_error.to_string()
-                )))
+                    &parse_error.to_string(),
+                ))
+                .into())
             }
         },
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            let default_config = Config::default();
+            let mut default_config = Config::default();
             save_config(path, &default_config).await.with_context(|| {
                 errors::config::failed_to_create_default(&path.display().to_string())
             })?;
+            resolve_paths(&mut default_config, path);
             Ok(default_config)
         }
         Err(e) => {
@@ -60,10 +377,94 @@ pub async fn load_config(path: &Path) -> anyhow::Result<Config> {
     }
 }
 
-// Atomic write with temp file
+pub fn config_backup_path(path: &Path, index: usize) -> PathBuf {
+    backup_path_for(path, &format!(".bak.{}", index))
+}
+
+// Shifts the ring of backups along and copies the current file in as the
+// newest. Files that do not parse are not worth restoring and are skipped.
+async fn rotate_backups(path: &Path) -> anyhow::Result<()> {
+    let Ok(contents) = fs::read_to_string(path).await else {
+        return Ok(());
+    };
+    if parse_and_migrate(path, &contents).is_err() {
+        return Ok(());
+    }
+
+    for index in (1..crate::constants::CONFIG_BACKUP_COUNT).rev() {
+        let from = config_backup_path(path, index);
+        if from.exists() {
+            fs::rename(&from, config_backup_path(path, index + 1)).await?;
+        }
+    }
+    fs::write(config_backup_path(path, 1), contents).await?;
+    Ok(())
+}
+
+// Writes the newest backup back over the config and drops it from the ring,
+// so restoring again goes one version further back.
+pub async fn restore_backup(path: &Path) -> anyhow::Result<Config> {
+    let newest = config_backup_path(path, 1);
+    if !newest.exists() {
+        anyhow::bail!(errors::config::NO_BACKUP);
+    }
+
+    let contents = fs::read_to_string(&newest)
+        .await
+        .with_context(|| errors::config::failed_to_read(&newest.display().to_string()))?;
+    // Parsed with the config's own path, since the format follows its extension.
+    let (config, _) = parse_and_migrate(path, &contents)
+        .with_context(|| errors::config::failed_to_parse(&newest.display().to_string()))?;
+    config
+        .validate()
+        .with_context(|| errors::config::validation_failed(&newest.display().to_string()))?;
+
+    write_config(path, &config, true).await?;
+
+    fs::remove_file(&newest).await?;
+    for index in 2..=crate::constants::CONFIG_BACKUP_COUNT {
+        let from = config_backup_path(path, index);
+        if from.exists() {
+            fs::rename(&from, config_backup_path(path, index - 1)).await?;
+        }
+    }
+    Ok(config)
+}
+
 pub async fn save_config(path: &Path, config: &Config) -> anyhow::Result<()> {
-    let yaml_content =
-        serde_yaml::to_string(config).context(errors::config::failed_to_serialize())?;
+    save(path, config, true).await
+}
+
+// Saves a copy of the config to a file the user picked, such as an export.
+// That file isn't the manager's, so comments already in it are not carried
+// over and it gets no managed-by header.
+pub async fn export_config(path: &Path, config: &Config) -> anyhow::Result<()> {
+    save(path, config, false).await
+}
+
+async fn save(path: &Path, config: &Config, managed: bool) -> anyhow::Result<()> {
+    if let Err(e) = rotate_backups(path).await {
+        tracing::warn!(
+            "{}",
+            errors::config::failed_to_back_up(&path.display().to_string(), &e.to_string())
+        );
+    }
+    write_config(path, config, managed).await
+}
+
+// Atomic write with temp file. Only the manager's own config keeps the
+// comments of the file it replaces.
+async fn write_config(path: &Path, config: &Config, managed: bool) -> anyhow::Result<()> {
+    let mut config = config.clone();
+    unresolve_paths(&mut config, path);
+    let format = ConfigFormat::from_path(path);
+    let mut content = format
+        .serialize(&config)
+        .with_context(|| errors::config::failed_to_serialize(format.name()))?;
+    if managed && format == ConfigFormat::Yaml {
+        let previous = fs::read_to_string(path).await.unwrap_or_default();
+        content = crate::backend::yaml_comments::carry_over(&previous, &content);
+    }
 
     let parent = path.parent().unwrap_or_else(|| Path::new("."));
     fs::create_dir_all(parent)
@@ -72,18 +473,21 @@ pub async fn save_config(path: &Path, config: &Config) -> anyhow::Result<()> {
 
     let tmp_path = path.with_extension("tmp");
 
-    fs::write(&tmp_path, yaml_content.as_bytes())
-        .await
-        .with_context(|| errors::config::failed_to_write_temp(&tmp_path.display().to_string()))
-        .map_err(|e| {
-            if e.to_string().contains("No space left on device")
-                || e.to_string().contains("disk full")
-            {
-                anyhow::anyhow!(errors::disk::FULL)
-            } else {
-                e
-            }
-        })?;
+    retry_transient("writing the temp file", || {
+        fs::write(&tmp_path, content.as_bytes())
+    })
+    .await
+    .map_err(|e| {
+        if e.kind() == std::io::ErrorKind::StorageFull
+            || e.to_string().contains("No space left on device")
+        {
+            BackendError::DiskFull.into()
+        } else {
+            anyhow::Error::new(e).context(errors::config::failed_to_write_temp(
+                &tmp_path.display().to_string(),
+            ))
+        }
+    })?;
 
     #[cfg(unix)]
     #[allow(unused_imports)]
@@ -99,16 +503,69 @@ pub async fn save_config(path: &Path, config: &Config) -> anyhow::Result<()> {
             .context(errors::config::FAILED_TO_FSYNC)?;
     }
 
-    fs::rename(&tmp_path, path).await.with_context(|| {
-        errors::config::failed_to_rename(
-            &tmp_path.display().to_string(),
-            &path.display().to_string(),
-        )
-    })?;
+    retry_transient("renaming the temp file", || fs::rename(&tmp_path, path))
+        .await
+        .with_context(|| {
+            errors::config::failed_to_rename(
+                &tmp_path.display().to_string(),
+                &path.display().to_string(),
+            )
+        })?;
 
     Ok(())
 }
 
+// Hash of the config file as it is on disk, to notice writes made by anything
+// but this manager. None when the file can't be read.
+pub async fn file_hash(path: &Path) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+
+    let contents = fs::read(path).await.ok()?;
+    let mut hasher = std::hash::DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+// On Windows, antivirus scanners and indexers briefly lock freshly written
+// files, which shows up as access denied or a sharing violation. Anything
+// else, such as a full disk, fails straight away.
+pub fn is_transient(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    matches!(
+        error.kind(),
+        ErrorKind::PermissionDenied
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::TimedOut
+            | ErrorKind::ResourceBusy
+    ) || (cfg!(windows) && error.raw_os_error() == Some(ERROR_SHARING_VIOLATION))
+}
+
+pub async fn retry_transient<T, F, Fut>(step: &str, mut operation: F) -> std::io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = std::io::Result<T>>,
+{
+    let mut delay = crate::constants::CONFIG_SAVE_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Err(e) if attempt < crate::constants::CONFIG_SAVE_ATTEMPTS && is_transient(&e) => {
+                tracing::warn!(
+                    "{}",
+                    errors::config::save_retry(step, attempt, &e.to_string())
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub fn watch_config_file(
     config_path: PathBuf,
@@ -132,7 +589,12 @@ pub fn watch_config_file(
     Ok(rx)
 }
 
-pub async fn cleanup_old_logs(log_directory: &Path, retention_days: u32) -> anyhow::Result<()> {
+// Returns how many files were deleted. Files in `in_use` are kept.
+pub async fn cleanup_old_logs(
+    log_directory: &Path,
+    retention_days: u32,
+    in_use: &HashSet<PathBuf>,
+) -> anyhow::Result<usize> {
     if !log_directory.exists() {
         tracing::info!(
             "Log directory does not exist, creating: {}",
@@ -141,7 +603,7 @@ pub async fn cleanup_old_logs(log_directory: &Path, retention_days: u32) -> anyh
         fs::create_dir_all(log_directory).await.with_context(|| {
             errors::config::failed_to_create_dir(&log_directory.display().to_string())
         })?;
-        return Ok(());
+        return Ok(0);
     }
 
     let cutoff_time = std::time::SystemTime::now()
@@ -155,14 +617,16 @@ pub async fn cleanup_old_logs(log_directory: &Path, retention_days: u32) -> anyh
                 log_directory.display(),
                 e
             );
-            return Ok(());
+            return Ok(0);
         }
     };
 
     let mut deleted_count = 0;
     while let Some(entry) = read_dir.next_entry().await? {
         let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("log")
+        if (crate::backend::logs::is_log_file(&path)
+            || crate::backend::logs::is_compressed_log_file(&path))
+            && !in_use.contains(&path)
             && let Ok(metadata) = entry.metadata().await
             && let Ok(modified) = metadata.modified()
             && modified < cutoff_time
@@ -184,19 +648,98 @@ pub async fn cleanup_old_logs(log_directory: &Path, retention_days: u32) -> anyh
         n => tracing::info!("Cleaned up {} old log files", n),
     }
 
-    Ok(())
+    Ok(deleted_count)
 }
 
-pub fn cleanup_old_logs_sync(
-    runtime_handle: &tokio::runtime::Handle,
-    log_directory: &Path,
+// Retention on demand: deletes logs older than `retention_days` from every
+// log directory of `config`, whatever the configured retention is.
+pub async fn cleanup_logs_now(
+    config: &Config,
     retention_days: u32,
-) -> anyhow::Result<()> {
-    tracing::info!(
-        "Log retention enabled: cleaning up logs older than {} days in {}",
-        retention_days,
-        log_directory.display()
+    in_use: &HashSet<PathBuf>,
+) -> anyhow::Result<usize> {
+    anyhow::ensure!(
+        (1..=crate::constants::MAX_LOG_AGE_DAYS).contains(&retention_days),
+        errors::logs::retention_invalid(retention_days)
     );
+    let mut deleted_count = 0;
+    for log_directory in config.log_directories() {
+        deleted_count += cleanup_old_logs(&log_directory, retention_days, in_use).await?;
+    }
+    Ok(deleted_count)
+}
+
+// Files in `in_use` are still being written to and are left as they are.
+pub async fn compress_old_logs(
+    log_directory: &Path,
+    compress_days: u32,
+    in_use: &HashSet<PathBuf>,
+) -> anyhow::Result<()> {
+    let cutoff_time = std::time::SystemTime::now()
+        - std::time::Duration::from_secs(compress_days as u64 * 24 * 60 * 60);
+
+    let mut read_dir = match fs::read_dir(log_directory).await {
+        Ok(dir) => dir,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to read log directory {}: {}, skipping compression",
+                log_directory.display(),
+                e
+            );
+            return Ok(());
+        }
+    };
+
+    let mut compressed_count = 0;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let path = entry.path();
+        if crate::backend::logs::is_log_file(&path)
+            && !in_use.contains(&path)
+            && let Ok(metadata) = entry.metadata().await
+            && let Ok(modified) = metadata.modified()
+            && modified < cutoff_time
+        {
+            let source = path.clone();
+            match tokio::task::spawn_blocking(move || {
+                crate::backend::logs::compress_log_file(&source)
+            })
+            .await?
+            {
+                Ok(compressed_path) => {
+                    tracing::info!(
+                        "Compressed old log file: {} -> {}",
+                        path.display(),
+                        compressed_path.display()
+                    );
+                    compressed_count += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to compress log file {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    match compressed_count {
+        0 => tracing::debug!("No old log files to compress"),
+        n => tracing::info!("Compressed {} old log files", n),
+    }
+
+    Ok(())
+}
 
-    runtime_handle.block_on(async { cleanup_old_logs(log_directory, retention_days).await })
+// `in_use` holds the current log files of running tunnels.
+pub async fn maintain_logs(config: &Config, in_use: &HashSet<PathBuf>) -> anyhow::Result<()> {
+    let global = &config.global;
+    for log_directory in config.log_directories() {
+        if let Some(days) = global.log_compress_days {
+            compress_old_logs(&log_directory, days, in_use).await?;
+        }
+
+        if let Some(days) = global.log_retention_days {
+            cleanup_old_logs(&log_directory, days, in_use).await?;
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,105 @@
+// Keeps a second manager away from a config that one is already using, since
+// both would save over each other and try to own the same processes. The
+// lock file sits next to the config and holds the owner's PID. A lock whose
+// process is gone was left behind by a crash and is taken over.
+
+use crate::backend::error::BackendError;
+use crate::backend::types::ProcessId;
+use crate::errors;
+use anyhow::Context;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn lock_file_path(config_path: &Path) -> PathBuf {
+    let mut file_name = config_path.as_os_str().to_os_string();
+    file_name.push(".lock");
+    PathBuf::from(file_name)
+}
+
+// Removes the lock file when dropped, unless another process has taken it
+// over in the meantime.
+#[derive(Debug)]
+pub struct ConfigLock {
+    path: PathBuf,
+}
+
+impl ConfigLock {
+    pub fn acquire(config_path: &Path) -> anyhow::Result<Self> {
+        let path = lock_file_path(config_path);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| {
+                errors::config::failed_to_create_dir(&parent.display().to_string())
+            })?;
+        }
+
+        // A second attempt follows removing a stale lock, in case another
+        // instance was quicker to take it.
+        for _ in 0..2 {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id()).with_context(|| {
+                        errors::lock::failed_to_write(&path.display().to_string())
+                    })?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        errors::lock::failed_to_write(&path.display().to_string())
+                    });
+                }
+            }
+
+            if let Some(pid) = read_owner(&path)
+                && crate::backend::process::is_manager_process(pid)
+            {
+                return Err(BackendError::ConfigLocked(errors::lock::held(
+                    &config_path.display().to_string(),
+                    pid,
+                ))
+                .into());
+            }
+            tracing::warn!(
+                "{}",
+                errors::lock::removing_stale(&path.display().to_string())
+            );
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| {
+                        errors::lock::failed_to_write(&path.display().to_string())
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Err(anyhow::anyhow!(errors::lock::failed_to_acquire(
+            &path.display().to_string()
+        )))
+    }
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        if read_owner(&self.path) != Some(ProcessId::from(std::process::id())) {
+            return;
+        }
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!(
+                "{}: {}",
+                errors::lock::failed_to_remove(&self.path.display().to_string()),
+                e
+            );
+        }
+    }
+}
+
+// The PID in the lock file, or None when it is unreadable or not a number.
+pub fn read_owner(path: &Path) -> Option<ProcessId> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    contents.trim().parse::<u32>().ok().map(ProcessId::from)
+}
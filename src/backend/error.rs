@@ -0,0 +1,63 @@
+// Errors returned by `Backend` methods. Failures a caller may want to react to
+// get their own variant; everything else is carried as `Other`. The messages
+// come from `crate::errors`, so the text shown to users is the same either way.
+
+use crate::backend::types::TunnelId;
+use crate::errors;
+
+pub type BackendResult<T> = std::result::Result<T, BackendError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackendError {
+    #[error("{}", errors::tunnel::not_found(&format!("{:?}", .0)))]
+    TunnelNotFound(TunnelId),
+    #[error("{}", errors::tunnel::already_running(.0))]
+    AlreadyRunning(String),
+    // Starting or stopping; holds the tunnel's tag.
+    #[error("{}", errors::tunnel::transitional_state(.0))]
+    Transitioning(String),
+    #[error("{}", errors::tunnel::NOT_RUNNING)]
+    NotRunning,
+    #[error("{}", errors::tunnel::ALREADY_STOPPING)]
+    AlreadyStopping,
+    // The binary variants hold the full message, which names the path.
+    #[error("{0}")]
+    BinaryNotFound(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{}", errors::process::PORT_IN_USE)]
+    PortInUse,
+    #[error("{}", errors::disk::FULL)]
+    DiskFull,
+    #[error("{0}")]
+    ConfigCorrupted(String),
+    // Another manager holds the config's lock file; the message names its PID.
+    #[error("{0}")]
+    ConfigLocked(String),
+    // The file no longer holds what was last loaded or saved, so saving
+    // would drop someone else's edits.
+    #[error("{}", errors::config::CHANGED_ON_DISK)]
+    ConfigChangedOnDisk,
+    // Started with `--read-only`, or the config sets `global.locked`.
+    #[error("{}", errors::config::READ_ONLY)]
+    ReadOnly,
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+// Helpers that return `anyhow::Result` raise a variant with
+// `BackendError::X.into()`; it is recovered here as long as no context was
+// added on top of it.
+impl From<anyhow::Error> for BackendError {
+    fn from(error: anyhow::Error) -> Self {
+        // `downcast` also looks beneath context, which would drop it.
+        let outermost = error.chain().next();
+        if !outermost.is_some_and(|e| e.is::<BackendError>()) {
+            return BackendError::Other(error);
+        }
+        match error.downcast::<BackendError>() {
+            Ok(error) => error,
+            Err(error) => BackendError::Other(error),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Health checks for running tunnels. A probe is a plain TCP connect to the
+// configured target; the result is published through a watch channel that
+// the backend reads for the tunnel's status, and transitions are broadcast as
+// `TunnelEvent::HealthChanged`.
+
+use crate::backend::types::{HealthCheck, TunnelEvent, TunnelHealth, TunnelId};
+use crate::constants::HEALTH_CHECK_TIMEOUT;
+use crate::errors;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub async fn probe(target: &str) -> TunnelHealth {
+    match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, TcpStream::connect(target)).await {
+        Ok(Ok(_)) => TunnelHealth::Healthy,
+        Ok(Err(e)) => TunnelHealth::Unhealthy {
+            error: errors::health::connect_failed(target, &e.to_string()),
+        },
+        Err(_) => TunnelHealth::Unhealthy {
+            error: errors::health::timed_out(target),
+        },
+    }
+}
+
+// The first probe runs one interval after the start, which gives the tunnel
+// time to bind its ports. The task ends when the token is cancelled.
+pub fn spawn_health_check(
+    tunnel_id: TunnelId,
+    tag: String,
+    check: HealthCheck,
+    cancellation_token: CancellationToken,
+    events: broadcast::Sender<TunnelEvent>,
+) -> (JoinHandle<()>, watch::Receiver<TunnelHealth>) {
+    let (health_tx, health_rx) = watch::channel(TunnelHealth::Unchecked);
+
+    let task = tokio::spawn(async move {
+        let period = check.interval();
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            let health = tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                health = probe(&check.target) => health,
+            };
+            if *health_tx.borrow() == health {
+                continue;
+            }
+
+            match &health {
+                TunnelHealth::Unhealthy { error } => {
+                    tracing::warn!("Tunnel '{}' is unhealthy: {}", tag, error)
+                }
+                _ => tracing::info!("Tunnel '{}' is healthy", tag),
+            }
+            health_tx.send_replace(health.clone());
+            let _ = events.send(TunnelEvent::HealthChanged {
+                id: tunnel_id,
+                health,
+            });
+        }
+    });
+
+    (task, health_rx)
+}
@@ -0,0 +1,742 @@
+use crate::backend::types::{LogMode, Timestamp, TunnelId};
+use crate::constants::LOG_WRITE_BUFFER_BYTES;
+use crate::errors;
+use anyhow::Context;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotation {
+    pub max_bytes: u64,
+    pub max_files: u32,
+}
+
+// Matches both live logs (`*.log`) and size-rotated ones (`*.log.1`, `*.log.2`, ...).
+pub fn is_log_file(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+        return false;
+    };
+
+    match file_name.rsplit_once('.') {
+        Some((stem, "log")) => !stem.is_empty(),
+        Some((stem, suffix)) => {
+            !suffix.is_empty()
+                && suffix.chars().all(|c| c.is_ascii_digit())
+                && stem.ends_with(".log")
+        }
+        None => false,
+    }
+}
+
+pub fn is_compressed_log_file(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("gz") && is_log_file(&path.with_extension(""))
+}
+
+pub fn compressed_log_path(log_path: &Path) -> PathBuf {
+    let mut file_name = log_path.as_os_str().to_os_string();
+    file_name.push(".gz");
+    PathBuf::from(file_name)
+}
+
+// What the detail screen shows about a tunnel's log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogFileInfo {
+    pub size_bytes: u64,
+    // Unset on filesystems that don't record when a file was created.
+    pub created: Option<Timestamp>,
+}
+
+pub fn log_file_info(path: &Path) -> Option<LogFileInfo> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(LogFileInfo {
+        size_bytes: metadata.len(),
+        created: metadata.created().ok().map(Timestamp::from),
+    })
+}
+
+// Bytes taken by the log files directly in `directories`, compressed ones
+// included. Directories that don't exist count as empty.
+pub fn log_directory_size(directories: &[PathBuf]) -> u64 {
+    directories
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| is_log_file(&entry.path()) || is_compressed_log_file(&entry.path()))
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+// Falls back to the `.gz` sibling when a log has been compressed by cleanup.
+pub fn resolve_log_path(log_path: &Path) -> Option<PathBuf> {
+    if log_path.exists() {
+        return Some(log_path.to_path_buf());
+    }
+    let compressed = compressed_log_path(log_path);
+    compressed.exists().then_some(compressed)
+}
+
+// The directory itself, or the nearest existing ancestor if it has not been
+// created yet.
+fn nearest_existing_dir(path: &Path) -> Option<&Path> {
+    path.ancestors()
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+        .find(|dir| dir.exists())
+}
+
+// Checks the directory by creating and removing a probe file.
+pub fn is_directory_writable(path: &Path) -> bool {
+    let Some(existing) = nearest_existing_dir(path) else {
+        return false;
+    };
+
+    if !existing.is_dir() {
+        return false;
+    }
+
+    let probe = existing.join(format!(".wstunnel_manager_probe_{}", uuid::Uuid::new_v4()));
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+// Free bytes on the disk a log directory is on, going by the longest mount
+// point that contains it. None when no disk matches.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let dir = std::fs::canonicalize(nearest_existing_dir(path)?).ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+pub fn read_log_file(path: &Path) -> std::io::Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut contents = String::new();
+    if is_compressed_log_file(path) {
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(file), &mut contents)?;
+    } else {
+        std::io::Read::read_to_string(&mut std::io::BufReader::new(file), &mut contents)?;
+    }
+    Ok(contents)
+}
+
+// Reads backwards from the end of the file in fixed-size chunks so a peek at
+// a multi-gigabyte log stays cheap. Compressed logs have to be streamed.
+pub fn tail_lines(path: &Path, max_lines: usize) -> std::io::Result<Vec<String>> {
+    use std::io::{BufRead, Read, Seek, SeekFrom};
+
+    if max_lines == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut file = std::fs::File::open(path)?;
+
+    if is_compressed_log_file(path) {
+        let reader = std::io::BufReader::new(flate2::read::GzDecoder::new(file));
+        let mut lines = std::collections::VecDeque::with_capacity(max_lines);
+        for line in reader.lines() {
+            if lines.len() == max_lines {
+                lines.pop_front();
+            }
+            lines.push_back(line?);
+        }
+        return Ok(lines.into());
+    }
+
+    const CHUNK_SIZE: u64 = 8192;
+    let mut position = file.metadata()?.len();
+    // Read back to front, counting newlines per chunk as it comes in, and
+    // joined once at the end.
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    let mut newlines = 0;
+
+    while position > 0 && newlines <= max_lines {
+        let read_size = CHUNK_SIZE.min(position);
+        position -= read_size;
+        file.seek(SeekFrom::Start(position))?;
+
+        let mut chunk = vec![0; read_size as usize];
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunks.push(chunk);
+    }
+
+    let buffer: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+    let text = String::from_utf8_lossy(&buffer);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(max_lines);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+// External viewers generally can't open `.gz` files, so expand a copy into
+// the temp directory and hand that out instead.
+pub fn decompress_to_temp(path: &Path) -> std::io::Result<PathBuf> {
+    let file_name = path
+        .with_extension("")
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| "wstunnel.log".into());
+    let temp_path = std::env::temp_dir().join(file_name);
+    std::fs::write(&temp_path, read_log_file(path)?)?;
+    Ok(temp_path)
+}
+
+// Gzips `path` into `path.gz`, keeping the original modification time so
+// retention cleanup still ages the file from when it was last written.
+pub fn compress_log_file(path: &Path) -> std::io::Result<PathBuf> {
+    let compressed_path = compressed_log_path(path);
+    let modified = std::fs::metadata(path)?.modified()?;
+
+    let mut input = std::fs::File::open(path)?;
+    let output = std::fs::File::create(&compressed_path)?;
+    let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+
+    let result = std::io::copy(&mut input, &mut encoder).and_then(|_| encoder.finish());
+    match result {
+        Ok(output) => {
+            output.set_modified(modified)?;
+            output.sync_all()?;
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&compressed_path);
+            return Err(e);
+        }
+    }
+
+    std::fs::remove_file(path)?;
+    Ok(compressed_path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl std::fmt::Display for LogStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogStream::Stdout => write!(f, "STDOUT"),
+            LogStream::Stderr => write!(f, "STDERR"),
+        }
+    }
+}
+
+pub const DEFAULT_LOG_LINE_PREFIX: &str = "[{ts}] [{stream}] ";
+
+// Placeholders a log line prefix can use, without their braces.
+pub const LOG_LINE_PLACEHOLDERS: &[&str] = &["ts", "stream", "tag"];
+
+// How each line of output is prefixed in the log file. The default, local
+// time with `DEFAULT_LOG_LINE_PREFIX`, is the format `TaggedLogLine::parse`
+// understands; lines written with a custom prefix show up unsorted in the
+// combined log view.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogLineFormat {
+    pub utc: bool,
+    pub prefix: Option<String>,
+}
+
+impl LogLineFormat {
+    pub fn format(&self, tag: &str, stream: LogStream, line: &str) -> String {
+        self.format_at(chrono::Utc::now(), tag, stream, line)
+    }
+
+    pub fn format_at(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        tag: &str,
+        stream: LogStream,
+        line: &str,
+    ) -> String {
+        let timestamp = match self.utc {
+            true => now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            false => now
+                .with_timezone(&chrono::Local)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        };
+        // The tag goes last so braces in it are left alone.
+        let prefix = self
+            .prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_LOG_LINE_PREFIX)
+            .replace("{ts}", &timestamp)
+            .replace("{stream}", &stream.to_string())
+            .replace("{tag}", tag);
+        format!("{}{}", prefix, line)
+    }
+}
+
+// How a tunnel's output is written: where its log rotates, how each line
+// is prefixed and whether each stream also gets its own file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogOptions {
+    pub rotation: Option<LogRotation>,
+    pub line_format: LogLineFormat,
+    pub mode: LogMode,
+    pub stderr_buffer_lines: usize,
+    pub max_lines_per_second: Option<u32>,
+}
+
+// Caps how many lines are logged per one-second window. Lines over the limit
+// are counted, and the count is reported once lines are let through again.
+#[derive(Debug, Clone)]
+pub struct LineRateLimiter {
+    limit: Option<u32>,
+    window_start: std::time::Instant,
+    lines_in_window: u32,
+    dropped: u64,
+}
+
+impl LineRateLimiter {
+    pub fn new(limit: Option<u32>) -> Self {
+        Self {
+            limit,
+            window_start: std::time::Instant::now(),
+            lines_in_window: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn limit(&self) -> Option<u32> {
+        self.limit
+    }
+
+    // Whether a line arriving at `now` may be logged.
+    pub fn admit(&mut self, now: std::time::Instant) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        if now.duration_since(self.window_start) >= std::time::Duration::from_secs(1) {
+            self.window_start = now;
+            self.lines_in_window = 0;
+        }
+        if self.lines_in_window < limit {
+            self.lines_in_window += 1;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+
+    // Lines dropped since the last call.
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+// The latest lines a tunnel wrote to stderr, oldest first. Older lines are
+// dropped once `capacity` is reached.
+#[derive(Debug, Clone, Default)]
+pub struct StderrBuffer {
+    lines: VecDeque<String>,
+    capacity: usize,
+}
+
+impl StderrBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    // One line per stderr line, each ending in a newline.
+    pub fn contents(&self) -> String {
+        self.lines.iter().fold(String::new(), |mut contents, line| {
+            contents.push_str(line);
+            contents.push('\n');
+            contents
+        })
+    }
+}
+
+// Files a running tunnel may still be writing to: its log and, in split mode,
+// the per-stream ones. Rotated files are closed once renamed.
+pub fn current_log_files(log_path: &Path) -> [PathBuf; 3] {
+    [
+        log_path.to_path_buf(),
+        stream_log_path(log_path, LogStream::Stdout),
+        stream_log_path(log_path, LogStream::Stderr),
+    ]
+}
+
+// `<name>-<pid>-<ts>.log` becomes `<name>-<pid>-<ts>.out.log` or `.err.log`.
+pub fn stream_log_path(log_path: &Path, stream: LogStream) -> PathBuf {
+    let suffix = match stream {
+        LogStream::Stdout => "out.log",
+        LogStream::Stderr => "err.log",
+    };
+    log_path.with_extension(suffix)
+}
+
+// The first `{name}` in `template` that isn't a known placeholder.
+pub fn unknown_placeholder(template: &str) -> Option<String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let name = &after[..end];
+        if !LOG_LINE_PLACEHOLDERS.contains(&name) {
+            return Some(name.to_string());
+        }
+        rest = &after[end + 1..];
+    }
+    None
+}
+
+// A line from one tunnel's log, as written by the monitor task:
+// `[<RFC 3339 timestamp>] [STDOUT|STDERR] <output>`. Lines that don't follow
+// the format are kept with no timestamp or stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedLogLine {
+    pub tunnel_id: TunnelId,
+    pub tag: String,
+    pub timestamp: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub stream: Option<LogStream>,
+    pub level: Option<LogLevel>,
+    pub line: String,
+}
+
+// The level wstunnel's own log lines carry, e.g.
+// `2024-05-01T10:00:00.123456Z  INFO wstunnel::protocols: ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    // Looks for a level among the first few words of `output`, which is
+    // where wstunnel puts it, so that a message merely mentioning "error"
+    // isn't mistaken for one.
+    pub fn detect(output: &str) -> Option<LogLevel> {
+        output
+            .split_whitespace()
+            .take(3)
+            .find_map(|word| match word.trim_end_matches(':') {
+                "ERROR" => Some(LogLevel::Error),
+                "WARN" | "WARNING" => Some(LogLevel::Warn),
+                "INFO" => Some(LogLevel::Info),
+                "DEBUG" => Some(LogLevel::Debug),
+                "TRACE" => Some(LogLevel::Trace),
+                _ => None,
+            })
+    }
+}
+
+impl TaggedLogLine {
+    pub fn parse(tunnel_id: TunnelId, tag: &str, line: &str) -> Self {
+        let bracketed = |text: &str| -> Option<(String, String)> {
+            let (field, rest) = text.strip_prefix('[')?.split_once("] ")?;
+            Some((field.to_string(), rest.to_string()))
+        };
+        let timestamp_and_rest = bracketed(line);
+        let timestamp = timestamp_and_rest
+            .as_ref()
+            .and_then(|(field, _)| chrono::DateTime::parse_from_rfc3339(field).ok());
+        let stream_and_output = timestamp_and_rest
+            .and_then(|(_, rest)| bracketed(&rest))
+            .and_then(|(field, output)| match field.as_str() {
+                "STDOUT" => Some((LogStream::Stdout, output)),
+                "STDERR" => Some((LogStream::Stderr, output)),
+                _ => None,
+            });
+        let level = match &stream_and_output {
+            Some((_, output)) => LogLevel::detect(output),
+            None => LogLevel::detect(line),
+        };
+
+        Self {
+            tunnel_id,
+            tag: tag.to_string(),
+            timestamp,
+            stream: stream_and_output.map(|(stream, _)| stream),
+            level,
+            line: line.to_string(),
+        }
+    }
+
+    // What "errors only" shows: lines wstunnel logged as errors, and stderr
+    // output that carries no level of its own.
+    pub fn is_error(&self) -> bool {
+        match self.level {
+            Some(level) => level == LogLevel::Error,
+            None => self.stream == Some(LogStream::Stderr),
+        }
+    }
+}
+
+// Merges the tails of several tunnels' logs into one list ordered by time.
+// A line without a timestamp stays right after the line before it in its
+// own log, and lines with the same time keep the order of `tails`.
+pub fn merge_logs(tails: Vec<Vec<TaggedLogLine>>) -> Vec<TaggedLogLine> {
+    let mut keyed = Vec::new();
+    for tail in tails {
+        let mut last_timestamp = None;
+        for line in tail {
+            last_timestamp = line.timestamp.or(last_timestamp);
+            keyed.push((last_timestamp, line));
+        }
+    }
+    keyed.sort_by_key(|(timestamp, _)| *timestamp);
+    keyed.into_iter().map(|(_, line)| line).collect()
+}
+
+// Log files are named `<name>-<pid>-<YYYYmmdd_HHMMSS>.log`, plus `.<n>` once
+// rotated and `.gz` once compressed. Yields the timestamp and rotation index
+// of the files written under `name`; the timestamp orders them and keeps "web"
+// from matching "web-api".
+fn log_files_named(log_directory: &Path, name: &str) -> Vec<(String, u32, PathBuf)> {
+    let prefix = format!("{}-", name);
+    let Ok(entries) = std::fs::read_dir(log_directory) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let rest = file_name.strip_prefix(&prefix)?;
+            let rest = rest.strip_suffix(".gz").unwrap_or(rest);
+            let (rest, rotation) = match rest.rsplit_once('.') {
+                Some((rest, index)) if index.chars().all(|c| c.is_ascii_digit()) => {
+                    (rest, index.parse().ok()?)
+                }
+                _ => (rest, 0),
+            };
+            let (pid, timestamp) = rest.strip_suffix(".log")?.split_once('-')?;
+            let is_timestamp = timestamp.len() == 15
+                && timestamp.char_indices().all(|(i, c)| match i {
+                    8 => c == '_',
+                    _ => c.is_ascii_digit(),
+                });
+            (!pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()) && is_timestamp)
+                .then(|| (timestamp.to_string(), rotation, entry.path()))
+        })
+        .collect()
+}
+
+// The newest log a tunnel wrote under `name` (see `process::log_name`),
+// compressed or not.
+pub fn latest_log_for(log_directory: &Path, name: &str) -> Option<PathBuf> {
+    log_files_named(log_directory, name)
+        .into_iter()
+        .filter(|(_, rotation, _)| *rotation == 0)
+        .max()
+        .map(|(_, _, path)| path)
+}
+
+// Every log a tunnel wrote under `name`, rotated and compressed ones included,
+// oldest first. Within a run `.2` is older than `.1`, which is older than the
+// file still being written.
+pub fn log_history_for(log_directory: &Path, name: &str) -> Vec<PathBuf> {
+    let mut files = log_files_named(log_directory, name);
+    files.sort_by(|(a_time, a_rotation, _), (b_time, b_rotation, _)| {
+        a_time.cmp(b_time).then(b_rotation.cmp(a_rotation))
+    });
+    files.into_iter().map(|(_, _, path)| path).collect()
+}
+
+// What the log viewer searches for: a plain substring unless `regex` is set,
+// ignoring case unless `case_sensitive` is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogSearch {
+    pub pattern: String,
+    pub case_sensitive: bool,
+    pub regex: bool,
+}
+
+impl LogSearch {
+    pub fn matcher(&self) -> anyhow::Result<regex::Regex> {
+        let pattern = match self.regex {
+            true => self.pattern.clone(),
+            false => regex::escape(&self.pattern),
+        };
+        regex::RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| anyhow::anyhow!(errors::logs::search_pattern_invalid(&e.to_string())))
+    }
+}
+
+// The last `max` lines of `files` that `search` matches, in file order.
+pub fn grep_files(
+    files: &[PathBuf],
+    search: &LogSearch,
+    max: usize,
+) -> anyhow::Result<Vec<String>> {
+    let matcher = search.matcher()?;
+    let mut matches = VecDeque::with_capacity(max.min(1024));
+    for path in files {
+        let contents = read_log_file(path)
+            .with_context(|| errors::logs::failed_to_read(&path.display().to_string()))?;
+        for line in contents.lines().filter(|line| matcher.is_match(line)) {
+            if matches.len() == max {
+                matches.pop_front();
+            }
+            if max > 0 {
+                matches.push_back(line.to_string());
+            }
+        }
+    }
+    Ok(matches.into())
+}
+
+pub fn rotated_log_path(log_path: &Path, index: u32) -> PathBuf {
+    let mut file_name = log_path.as_os_str().to_os_string();
+    file_name.push(format!(".{}", index));
+    PathBuf::from(file_name)
+}
+
+async fn open_log_file(path: &Path) -> std::io::Result<File> {
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
+
+// The per-stream files written next to the combined log in split mode.
+pub struct StreamLogWriters {
+    stdout: RotatingLogWriter,
+    stderr: RotatingLogWriter,
+}
+
+impl StreamLogWriters {
+    pub async fn open(log_path: &Path, rotation: Option<LogRotation>) -> std::io::Result<Self> {
+        Ok(Self {
+            stdout: RotatingLogWriter::open(stream_log_path(log_path, LogStream::Stdout), rotation)
+                .await?,
+            stderr: RotatingLogWriter::open(stream_log_path(log_path, LogStream::Stderr), rotation)
+                .await?,
+        })
+    }
+
+    pub async fn write_line(&mut self, stream: LogStream, line: &str) -> std::io::Result<()> {
+        match stream {
+            LogStream::Stdout => self.stdout.write_line(line).await,
+            LogStream::Stderr => self.stderr.write_line(line).await,
+        }
+    }
+
+    pub async fn flush(&mut self) -> std::io::Result<()> {
+        self.stdout.flush().await?;
+        self.stderr.flush().await
+    }
+}
+
+pub struct RotatingLogWriter {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    bytes_written: u64,
+    rotation: Option<LogRotation>,
+}
+
+impl RotatingLogWriter {
+    pub async fn open(path: PathBuf, rotation: Option<LogRotation>) -> std::io::Result<Self> {
+        let file = open_log_file(&path).await?;
+        let bytes_written = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self {
+            path,
+            writer: Some(BufWriter::with_capacity(LOG_WRITE_BUFFER_BYTES, file)),
+            bytes_written,
+            rotation,
+        })
+    }
+
+    pub async fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if let Some(rotation) = self.rotation
+            && self.bytes_written > 0
+            && self.bytes_written + line.len() as u64 > rotation.max_bytes
+        {
+            if let Err(e) = self.rotate(rotation.max_files).await {
+                tracing::warn!("Failed to rotate log file {}: {}", self.path.display(), e);
+            }
+            self.bytes_written = 0;
+        }
+
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| std::io::Error::other("log file is not open"))?;
+        writer.write_all(line.as_bytes()).await?;
+        self.bytes_written += line.len() as u64;
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> std::io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush().await,
+            None => Ok(()),
+        }
+    }
+
+    // The current file is closed before renaming so this also works on
+    // Windows, where open files cannot be renamed.
+    async fn rotate(&mut self, max_files: u32) -> std::io::Result<()> {
+        self.flush().await?;
+        self.writer = None;
+
+        let shift_result = self.shift_rotated_files(max_files).await;
+        self.writer = Some(BufWriter::with_capacity(
+            LOG_WRITE_BUFFER_BYTES,
+            open_log_file(&self.path).await?,
+        ));
+        shift_result?;
+
+        tracing::info!("Rotated log file: {}", self.path.display());
+
+        Ok(())
+    }
+
+    async fn shift_rotated_files(&self, max_files: u32) -> std::io::Result<()> {
+        let oldest = rotated_log_path(&self.path, max_files);
+        if tokio::fs::try_exists(&oldest).await.unwrap_or(false) {
+            tokio::fs::remove_file(&oldest).await?;
+        }
+
+        for index in (1..max_files).rev() {
+            let from = rotated_log_path(&self.path, index);
+            if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+                tokio::fs::rename(&from, rotated_log_path(&self.path, index + 1)).await?;
+            }
+        }
+
+        tokio::fs::rename(&self.path, rotated_log_path(&self.path, 1)).await
+    }
+}
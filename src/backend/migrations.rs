@@ -0,0 +1,79 @@
+use crate::backend::types::{CURRENT_CONFIG_VERSION, TunnelMode};
+use crate::errors;
+use anyhow::Context;
+use serde_yaml::Value;
+
+type Migration = fn(&mut Value) -> anyhow::Result<()>;
+
+// Each entry upgrades a config from the given version to the next one.
+const MIGRATIONS: &[(u32, Migration)] = &[(1, migrate_v1_to_v2)];
+
+// Configs written before the version field existed are treated as version 1.
+pub fn config_version(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(1)
+}
+
+// Runs every migration between the config's version and the current one.
+// Returns the original version if anything was migrated.
+pub fn migrate(value: &mut Value) -> anyhow::Result<Option<u32>> {
+    let original_version = config_version(value);
+    if original_version >= CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let mut version = original_version;
+    while version < CURRENT_CONFIG_VERSION {
+        let (_, migration) = MIGRATIONS
+            .iter()
+            .find(|(from, _)| *from == version)
+            .ok_or_else(|| anyhow::anyhow!(errors::config::no_migration(version)))?;
+
+        migration(value).with_context(|| errors::config::migration_failed(version))?;
+        version += 1;
+
+        if let Value::Mapping(map) = value {
+            map.insert(Value::from("version"), Value::from(version));
+        }
+    }
+
+    tracing::info!(
+        "Migrated config from version {} to {}",
+        original_version,
+        CURRENT_CONFIG_VERSION
+    );
+
+    Ok(Some(original_version))
+}
+
+// Version 2 keeps the version 1 layout and adds the `version` field, so for
+// files the manager wrote this only stamps the number. A hand-written tunnel
+// without `mode`, which version 1 refused to load, gets it inferred from the
+// `client`/`server` subcommand in `cli_args` instead of failing again.
+fn migrate_v1_to_v2(value: &mut Value) -> anyhow::Result<()> {
+    let Some(tunnels) = value.get_mut("tunnels").and_then(Value::as_sequence_mut) else {
+        return Ok(());
+    };
+
+    for tunnel in tunnels {
+        let Value::Mapping(map) = tunnel else {
+            continue;
+        };
+
+        if map.contains_key("mode") {
+            continue;
+        }
+
+        let mode = map
+            .get("cli_args")
+            .and_then(Value::as_str)
+            .and_then(TunnelMode::from_cli_args)
+            .unwrap_or(TunnelMode::Client);
+        map.insert(Value::from("mode"), Value::from(mode.subcommand()));
+    }
+
+    Ok(())
+}
@@ -1,41 +1,313 @@
 use crate::backend::Backend;
+use crate::backend::config::ConfigStore;
+use crate::backend::error::{BackendError, BackendResult};
+use crate::backend::logs::{LogSearch, TaggedLogLine};
 use crate::backend::types::{
-    Config, ProcessId, Timestamp, TunnelEntry, TunnelId, TunnelRuntimeState,
+    Config, GlobalSettings, ProcessId, Timestamp, TunnelEntry, TunnelEvent, TunnelHealth, TunnelId,
+    TunnelRuntimeState, TunnelStats, TunnelSummary, autostart_plan, dependency_start_order,
+    dependents_stop_order,
 };
 use crate::errors;
-use anyhow::Result;
-use arc_swap::ArcSwap;
-use std::collections::HashMap;
+use anyhow::Context;
+use async_trait::async_trait;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+const MOCK_WSTUNNEL_VERSION: &str = "10.1.8";
+
+const MOCK_CRASH_EXIT_CODE: i32 = 1;
+
+// Bounds of the random delay before a `crash` scenario tunnel exits.
+const MOCK_CRASH_MIN_SECS: u64 = 5;
+const MOCK_CRASH_MAX_SECS: u64 = 30;
+
+// How long a mock tunnel shows as starting before it runs.
+const MOCK_STARTING_DURATION: Duration = Duration::from_secs(2);
+
+// How mock tunnels behave, so the UI's status handling and error paths can
+// be exercised without a real wstunnel. The default starts tunnels at once
+// and keeps them running, which is what tests want; mock mode reads
+// WSTUNNEL_MANAGER_MOCK_SCENARIO instead, a comma-separated list of:
+//   fail-start[=tag]  starts fail, for every tunnel or only the tagged one
+//   crash[=seconds]   running tunnels exit on their own after that many
+//                     seconds, or a random 5 to 30 without a value
+//   unhealthy         running tunnels report a failing health check
+//   starting=seconds  how long a tunnel shows as starting, 2 by default
+//   seed=number       makes the random crash delays repeatable
+#[derive(Debug, Clone)]
+pub struct MockScenario {
+    pub fail_all_starts: bool,
+    pub fail_start_tags: HashSet<String>,
+    pub crash: Option<MockCrash>,
+    pub unhealthy: bool,
+    pub starting: Duration,
+    pub seed: Option<u64>,
+}
+
+impl Default for MockScenario {
+    fn default() -> Self {
+        Self {
+            fail_all_starts: false,
+            fail_start_tags: HashSet::new(),
+            crash: None,
+            unhealthy: false,
+            starting: Duration::ZERO,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockCrash {
+    Random,
+    After(Duration),
+}
+
+impl MockScenario {
+    pub fn from_env() -> Self {
+        Self::parse(&std::env::var("WSTUNNEL_MANAGER_MOCK_SCENARIO").unwrap_or_default())
+    }
+
+    pub fn parse(value: &str) -> Self {
+        let mut scenario = Self {
+            starting: MOCK_STARTING_DURATION,
+            ..Self::default()
+        };
+        for item in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, argument) = match item.split_once('=') {
+                Some((name, argument)) => (name.trim(), Some(argument.trim())),
+                None => (item, None),
+            };
+            match (name, argument) {
+                ("fail-start", None) => scenario.fail_all_starts = true,
+                ("fail-start", Some(tag)) => {
+                    scenario.fail_start_tags.insert(tag.to_string());
+                }
+                ("crash", None) => scenario.crash = Some(MockCrash::Random),
+                ("crash", Some(seconds)) => match parse_seconds(seconds) {
+                    Some(delay) => scenario.crash = Some(MockCrash::After(delay)),
+                    None => tracing::warn!(
+                        "MOCK: Ignoring scenario '{}': not a number of seconds",
+                        item
+                    ),
+                },
+                ("unhealthy", None) => scenario.unhealthy = true,
+                ("starting", Some(seconds)) => match parse_seconds(seconds) {
+                    Some(duration) => scenario.starting = duration,
+                    None => tracing::warn!(
+                        "MOCK: Ignoring scenario '{}': not a number of seconds",
+                        item
+                    ),
+                },
+                ("seed", Some(seed)) => match seed.parse() {
+                    Ok(seed) => scenario.seed = Some(seed),
+                    Err(_) => tracing::warn!("MOCK: Ignoring scenario '{}': not a number", item),
+                },
+                _ => tracing::warn!("MOCK: Ignoring unknown scenario '{}'", item),
+            }
+        }
+        scenario
+    }
+
+    fn fails_to_start(&self, tag: &str) -> bool {
+        self.fail_all_starts || self.fail_start_tags.contains(tag)
+    }
+
+    // How long each successive start runs before it crashes; empty without
+    // `crash`.
+    pub fn crash_delays(&self) -> Box<dyn Iterator<Item = Duration> + Send + Sync> {
+        match self.crash {
+            None => Box::new(std::iter::empty()),
+            Some(MockCrash::After(delay)) => Box::new(std::iter::repeat(delay)),
+            Some(MockCrash::Random) => {
+                let mut state = self
+                    .seed
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().as_u64_pair().0);
+                let span = MOCK_CRASH_MAX_SECS - MOCK_CRASH_MIN_SECS + 1;
+                Box::new(std::iter::from_fn(move || {
+                    let offset = next_random(&mut state) % span;
+                    Some(Duration::from_secs(MOCK_CRASH_MIN_SECS + offset))
+                }))
+            }
+        }
+    }
+}
+
+fn parse_seconds(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+// splitmix64: small, and the same seed always gives the same delays.
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
 
 #[derive(Debug)]
 struct MockProcess {
     pid: ProcessId,
     started_at: Timestamp,
+    starting: Duration,
+    // Counted from `started_at`, so it includes the starting phase.
+    crash_after: Option<Duration>,
+    // Cancels the task announcing the end of the starting phase and the
+    // crash, so a stopped or removed tunnel reports neither.
+    lifecycle_task: CancellationToken,
+    command_line: Vec<String>,
+}
+
+impl Drop for MockProcess {
+    fn drop(&mut self) {
+        self.lifecycle_task.cancel();
+    }
+}
+
+impl MockProcess {
+    fn is_starting(&self) -> bool {
+        self.started_at.elapsed() < self.starting
+    }
+
+    fn has_crashed(&self) -> bool {
+        self.crash_after
+            .is_some_and(|delay| self.started_at.elapsed() >= delay)
+    }
 }
 
 pub struct MockBackend {
-    config: Arc<ArcSwap<Config>>,
+    config: Arc<ConfigStore>,
     mock_processes: HashMap<TunnelId, MockProcess>,
+    // Why a tunnel last failed, until it is started again.
+    failures: HashMap<TunnelId, TunnelRuntimeState>,
+    stats: HashMap<TunnelId, TunnelStats>,
     config_path: PathBuf,
-    runtime_handle: tokio::runtime::Handle,
+    // Hash of the config file as it was last loaded or saved here.
+    config_file_hash: Option<u64>,
+    // Set by `--read-only`; `global.locked` is read from the config.
+    read_only: bool,
+    events: broadcast::Sender<TunnelEvent>,
+    scenario: MockScenario,
+    crash_delays: Box<dyn Iterator<Item = Duration> + Send + Sync>,
 }
 
 impl MockBackend {
+    // Used by the tests; mock mode picks its scenario from the environment.
+    #[allow(dead_code)]
     pub fn new(runtime_handle: tokio::runtime::Handle, config_path: PathBuf) -> Self {
-        let config = runtime_handle
-            .block_on(async { crate::backend::config::load_config(&config_path).await })
-            .unwrap_or_else(|e| {
-                tracing::warn!("MOCK: Failed to load config: {}, using defaults", e);
-                Config::default()
-            });
+        Self::with_scenario(runtime_handle, config_path, MockScenario::default())
+    }
+
+    pub fn with_scenario(
+        runtime_handle: tokio::runtime::Handle,
+        config_path: PathBuf,
+        scenario: MockScenario,
+    ) -> Self {
+        if scenario.fail_all_starts
+            || !scenario.fail_start_tags.is_empty()
+            || scenario.crash.is_some()
+            || scenario.unhealthy
+        {
+            tracing::info!("MOCK: Simulating {:?}", scenario);
+        }
+        let (config, config_file_hash) = runtime_handle.block_on(async {
+            let config = crate::backend::config::load_config(&config_path)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("MOCK: Failed to load config: {}, using defaults", e);
+                    Config::default()
+                });
+            (
+                config,
+                crate::backend::config::file_hash(&config_path).await,
+            )
+        });
 
         Self {
-            config: Arc::new(ArcSwap::from_pointee(config)),
+            config: Arc::new(ConfigStore::new(config)),
             mock_processes: HashMap::new(),
+            failures: HashMap::new(),
+            stats: HashMap::new(),
             config_path,
-            runtime_handle,
+            config_file_hash,
+            read_only: false,
+            events: broadcast::channel(crate::constants::EVENT_CHANNEL_CAPACITY).0,
+            crash_delays: scenario.crash_delays(),
+            scenario,
+        }
+    }
+
+    // Refuses to save over changes made outside the manager, like
+    // `BackendState::apply_config`.
+    async fn save(&mut self, config: &Config) -> BackendResult<()> {
+        if self.config_changed_on_disk().await {
+            return Err(BackendError::ConfigChangedOnDisk);
+        }
+        crate::backend::config::save_config(&self.config_path, config)
+            .await
+            .context(errors::config::SAVE_FAILED)?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        Ok(())
+    }
+
+    fn failed_state(
+        error: String,
+        last_attempt: Timestamp,
+        exit_code: Option<i32>,
+    ) -> TunnelRuntimeState {
+        TunnelRuntimeState::Failed {
+            error,
+            last_attempt,
+            exit_code,
+        }
+    }
+
+    fn crash_state(&self, id: TunnelId, process: &MockProcess) -> TunnelRuntimeState {
+        let config = self.config.load();
+        let tag = config
+            .tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .map_or("", |t| t.tag.as_str());
+        Self::failed_state(
+            self.stats
+                .get(&id)
+                .cloned()
+                .unwrap_or_default()
+                .crash_error(tag, Some(MOCK_CRASH_EXIT_CODE)),
+            process.started_at,
+            Some(MOCK_CRASH_EXIT_CODE),
+        )
+    }
+
+    // Moves processes whose simulated crash is due out of the running set.
+    fn reap_crashed(&mut self) {
+        let crashed: Vec<TunnelId> = self
+            .mock_processes
+            .iter()
+            .filter(|(_, process)| process.has_crashed())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in crashed {
+            let Some(process) = self.mock_processes.remove(&id) else {
+                continue;
+            };
+            let state = self.crash_state(id, &process);
+            self.stats.entry(id).or_default().record_exit(
+                process.started_at,
+                Some(MOCK_CRASH_EXIT_CODE),
+                true,
+            );
+            self.failures.insert(id, state);
         }
     }
 
@@ -47,113 +319,364 @@ impl MockBackend {
             .as_millis();
         ProcessId::from((timestamp % 100000) as u32 + 10000)
     }
+
+    async fn start_one(&mut self, id: TunnelId) -> BackendResult<ProcessId> {
+        self.reap_crashed();
+        let config = self.config.load_full();
+
+        let tunnel = config
+            .tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(BackendError::TunnelNotFound(id))?;
+
+        if self
+            .mock_processes
+            .get(&id)
+            .is_some_and(MockProcess::is_starting)
+        {
+            return Err(BackendError::Transitioning(tunnel.tag.clone()));
+        }
+        if self.is_tunnel_running(id) {
+            return Err(BackendError::AlreadyRunning(tunnel.tag.clone()));
+        }
+
+        let fake_pid = Self::generate_fake_pid();
+
+        tracing::info!(
+            "MOCK: Starting tunnel {} with fake PID {}",
+            tunnel.tag,
+            fake_pid
+        );
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        if self.scenario.fails_to_start(&tunnel.tag) {
+            let error = errors::tunnel::failed_to_start_with_detail(
+                &tunnel.tag,
+                "simulated failure (WSTUNNEL_MANAGER_MOCK_SCENARIO)",
+            );
+            tracing::warn!("MOCK: {}", error);
+            self.failures.insert(
+                id,
+                Self::failed_state(error.clone(), Timestamp::now(), None),
+            );
+            return Err(anyhow::anyhow!(error).into());
+        }
+
+        let starting = self.scenario.starting;
+        let crash_delay = self.crash_delays.next();
+        let command_line = std::iter::once("wstunnel".to_string())
+            .chain(crate::backend::process::parse_cli_args(&tunnel.cli_args))
+            .collect();
+        let mock_process = MockProcess {
+            pid: fake_pid,
+            started_at: Timestamp::now(),
+            starting,
+            crash_after: crash_delay.map(|delay| starting + delay),
+            lifecycle_task: CancellationToken::new(),
+            command_line,
+        };
+        if let Some(delay) = crash_delay {
+            tracing::info!(
+                "MOCK: Tunnel {} will crash {:?} after starting",
+                tunnel.tag,
+                delay
+            );
+        }
+        if starting.is_zero() {
+            let _ = self.events.send(TunnelEvent::Started { id, pid: fake_pid });
+        }
+        if !starting.is_zero() || crash_delay.is_some() {
+            let events = self.events.clone();
+            let cancelled = mock_process.lifecycle_task.clone();
+            tokio::spawn(async move {
+                let lifecycle = async {
+                    if !starting.is_zero() {
+                        tokio::time::sleep(starting).await;
+                        let _ = events.send(TunnelEvent::Started { id, pid: fake_pid });
+                    }
+                    if let Some(delay) = crash_delay {
+                        tokio::time::sleep(delay).await;
+                        let _ = events.send(TunnelEvent::Crashed {
+                            id,
+                            exit_code: Some(MOCK_CRASH_EXIT_CODE),
+                        });
+                    }
+                };
+                tokio::select! {
+                    _ = cancelled.cancelled() => {}
+                    _ = lifecycle => {}
+                }
+            });
+        }
+        self.failures.remove(&id);
+
+        self.stats
+            .entry(id)
+            .or_default()
+            .record_start(mock_process.started_at);
+        self.mock_processes.insert(id, mock_process);
+
+        tracing::info!(
+            "MOCK: Started tunnel {} with fake PID {}",
+            tunnel.tag,
+            fake_pid
+        );
+
+        Ok(fake_pid)
+    }
+
+    async fn stop_one(&mut self, id: TunnelId) -> BackendResult<()> {
+        self.reap_crashed();
+        let process = self
+            .mock_processes
+            .remove(&id)
+            .ok_or(BackendError::NotRunning)?;
+        self.stats
+            .entry(id)
+            .or_default()
+            .record_exit(process.started_at, Some(0), false);
+
+        tracing::info!("MOCK: Stopping tunnel {:?}", id);
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        tracing::info!("MOCK: Stopped tunnel {:?}", id);
+        let _ = self.events.send(TunnelEvent::Stopped { id });
+
+        Ok(())
+    }
+
+    // Stops the running tunnels that depend on `id`, the furthest first.
+    // Failures are only logged so that `id` itself still stops.
+    async fn stop_dependents(&mut self, id: TunnelId) {
+        for dependent in dependents_stop_order(&self.config.load().tunnels, id) {
+            if !self.is_tunnel_running(dependent) {
+                continue;
+            }
+            if let Err(e) = self.stop_one(dependent).await {
+                tracing::warn!("Failed to stop dependent tunnel {:?}: {}", dependent, e);
+            }
+        }
+    }
 }
 
+#[async_trait]
 impl Backend for MockBackend {
-    fn load_config(&mut self, path: &Path) -> Result<Arc<Config>> {
-        self.runtime_handle.block_on(async {
-            match crate::backend::config::load_config(path).await {
-                Ok(config) => {
-                    self.config.store(Arc::new(config.clone()));
-                    Ok(Arc::new(config))
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to load config, using default: {}", e);
-                    let default_config = Config::default();
-                    self.config.store(Arc::new(default_config.clone()));
-                    Ok(Arc::new(default_config))
-                }
+    async fn load_config(&mut self, path: &Path) -> BackendResult<Arc<Config>> {
+        match crate::backend::config::load_config(path).await {
+            Ok(config) => {
+                self.config_file_hash = crate::backend::config::file_hash(path).await;
+                self.config.store(Arc::new(config.clone()));
+                Ok(Arc::new(config))
             }
-        })
+            Err(e) => {
+                tracing::warn!("Failed to load config, using default: {}", e);
+                let default_config = Config::default();
+                self.config.store(Arc::new(default_config.clone()));
+                Ok(Arc::new(default_config))
+            }
+        }
     }
 
-    fn save_config(&self, config: &Config, path: &Path) -> Result<()> {
-        self.runtime_handle
-            .block_on(async { crate::backend::config::save_config(path, config).await })
+    async fn save_config(&self, config: &Config, path: &Path) -> BackendResult<()> {
+        Ok(crate::backend::config::export_config(path, config).await?)
     }
 
     fn get_config(&self) -> Arc<Config> {
         self.config.load_full()
     }
 
-    fn validate_tunnel_entry(&self, entry: &TunnelEntry) -> Result<()> {
-        entry.validate()
+    async fn import_config(&mut self, path: &Path) -> BackendResult<usize> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let imported = crate::backend::config::read_config(path).await?;
+
+        let mut new_config = (*self.config.load_full()).clone();
+        let mut existing_ids: HashSet<TunnelId> = new_config.tunnels.iter().map(|t| t.id).collect();
+
+        let imported_count = imported.tunnels.len();
+        for mut tunnel in imported.tunnels {
+            if !existing_ids.insert(tunnel.id) {
+                tunnel.id = TunnelId::new();
+                existing_ids.insert(tunnel.id);
+            }
+            tunnel.created_at.get_or_insert_with(Timestamp::now);
+            tunnel.modified_at.get_or_insert_with(Timestamp::now);
+            new_config.tunnels.push(tunnel);
+        }
+
+        new_config
+            .validate()
+            .context(errors::config::validation_failed_after_add())?;
+
+        self.save(&new_config).await?;
+
+        self.config.store(Arc::new(new_config));
+        tracing::info!(
+            "MOCK: {}",
+            errors::config::imported(imported_count, &path.display().to_string())
+        );
+        Ok(imported_count)
+    }
+
+    fn validate_tunnel_entry(&self, entry: &TunnelEntry) -> BackendResult<()> {
+        entry.validate()?;
+        let config_dir = self.config_path.parent().unwrap_or(Path::new("."));
+        if let Some((_, error)) = entry.path_errors(config_dir).into_iter().next() {
+            return Err(anyhow::anyhow!(error).into());
+        }
+        Ok(())
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only || self.config.load().global.locked
+    }
+
+    fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    async fn update_global_settings(&mut self, settings: GlobalSettings) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        settings.check_binary_executable()?;
+        let mut new_config = (*self.config.load_full()).clone();
+        new_config.global = settings;
+        new_config.validate()?;
+
+        self.save(&new_config).await?;
+
+        self.config.store(Arc::new(new_config));
+        Ok(())
+    }
+
+    async fn restore_config_backup(&mut self) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let restored = crate::backend::config::restore_backup(&self.config_path).await?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        self.mock_processes
+            .retain(|id, _| restored.tunnels.iter().any(|t| t.id == *id));
+        self.config.store(Arc::new(restored));
+        tracing::info!("MOCK: Restored config backup");
+        Ok(())
+    }
+
+    async fn config_changed_on_disk(&self) -> bool {
+        crate::backend::config::file_hash(&self.config_path).await != self.config_file_hash
     }
 
-    fn add_tunnel(&mut self, mut entry: TunnelEntry) -> Result<TunnelId> {
+    async fn reload_config(&mut self) -> BackendResult<()> {
+        let reloaded = crate::backend::config::read_config(&self.config_path).await?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        self.mock_processes
+            .retain(|id, _| reloaded.tunnels.iter().any(|t| t.id == *id));
+        self.config.store(Arc::new(reloaded));
+        tracing::info!("MOCK: Reloaded config");
+        Ok(())
+    }
+
+    async fn overwrite_config(&mut self) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let config = self.config.load_full();
+        crate::backend::config::save_config(&self.config_path, &config)
+            .await
+            .context(errors::config::SAVE_FAILED)?;
+        self.config_file_hash = crate::backend::config::file_hash(&self.config_path).await;
+        tracing::info!("MOCK: Overwrote config");
+        Ok(())
+    }
+
+    async fn add_tunnel(&mut self, mut entry: TunnelEntry) -> BackendResult<TunnelId> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        crate::backend::config::resolve_tunnel_paths(&mut entry, &self.config_path);
         self.validate_tunnel_entry(&entry)?;
 
         if entry.id == TunnelId::default() {
             entry.id = TunnelId::new();
         }
+        let now = Timestamp::now();
+        entry.created_at = Some(now);
+        entry.modified_at = Some(now);
 
         let mut new_config = (*self.config.load_full()).clone();
         new_config.tunnels.push(entry.clone());
         new_config.validate()?;
 
-        let config_path = self.config_path.clone();
-        self.runtime_handle.block_on(async {
-            crate::backend::config::save_config(&config_path, &new_config).await
-        })?;
+        self.save(&new_config).await?;
 
         self.config.store(Arc::new(new_config));
         Ok(entry.id)
     }
 
-    fn edit_tunnel(&mut self, id: TunnelId, entry: TunnelEntry) -> Result<()> {
+    async fn edit_tunnel(&mut self, id: TunnelId, mut entry: TunnelEntry) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        crate::backend::config::resolve_tunnel_paths(&mut entry, &self.config_path);
         self.validate_tunnel_entry(&entry)?;
 
-        anyhow::ensure!(
-            !self.is_tunnel_running(id),
-            errors::tunnel::CANNOT_EDIT_RUNNING
-        );
+        if self.is_tunnel_running(id) {
+            return Err(anyhow::anyhow!(errors::tunnel::CANNOT_EDIT_RUNNING).into());
+        }
 
         let mut new_config = (*self.config.load_full()).clone();
         let tunnel_index = new_config
             .tunnels
             .iter()
             .position(|t| t.id == id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&format!("{:?}", id))))?;
+            .ok_or(BackendError::TunnelNotFound(id))?;
 
+        entry.created_at = new_config.tunnels[tunnel_index].created_at;
+        entry.modified_at = Some(Timestamp::now());
         new_config.tunnels[tunnel_index] = entry;
         new_config.validate()?;
 
-        let config_path = self.config_path.clone();
-        self.runtime_handle.block_on(async {
-            crate::backend::config::save_config(&config_path, &new_config).await
-        })?;
+        self.save(&new_config).await?;
 
         self.config.store(Arc::new(new_config));
         Ok(())
     }
 
-    fn delete_tunnel(&mut self, id: TunnelId) -> Result<()> {
-        if self.is_tunnel_running(id) {
-            self.stop_tunnel(id)?;
+    async fn delete_tunnel(&mut self, id: TunnelId) -> BackendResult<()> {
+        self.delete_tunnels(&[id]).await
+    }
+
+    async fn delete_tunnels(&mut self, ids: &[TunnelId]) -> BackendResult<()> {
+        if self.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        crate::backend::config::ensure_tunnels_exist(&self.config.load(), ids)?;
+        for &id in ids {
+            if self.is_tunnel_running(id) {
+                self.stop_tunnel(id).await?;
+            }
         }
 
         let mut new_config = (*self.config.load_full()).clone();
-        let tunnel_index = new_config
-            .tunnels
-            .iter()
-            .position(|t| t.id == id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&format!("{:?}", id))))?;
-
-        let removed_tunnel = new_config.tunnels.remove(tunnel_index);
-
-        let config_path = self.config_path.clone();
-        self.runtime_handle.block_on(async {
-            crate::backend::config::save_config(&config_path, &new_config).await
-        })?;
+        let removed_tunnels = crate::backend::config::remove_tunnels(&mut new_config, ids)?;
+        self.save(&new_config).await?;
 
         self.config.store(Arc::new(new_config));
-
-        tracing::info!("MOCK: Deleted tunnel: {}", removed_tunnel.tag);
+        for tunnel in removed_tunnels {
+            self.stats.remove(&tunnel.id);
+            tracing::info!("MOCK: Deleted tunnel: {}", tunnel.tag);
+        }
 
         Ok(())
     }
 
     fn list_tunnels(&mut self) -> Vec<TunnelEntry> {
+        self.reap_crashed();
         let config = self.config.load();
         config
             .tunnels
@@ -167,6 +690,15 @@ impl Backend for MockBackend {
             .collect()
     }
 
+    fn tunnel_statuses(&mut self) -> Vec<(TunnelId, TunnelRuntimeState)> {
+        self.reap_crashed();
+        self.get_all_statuses()
+    }
+
+    fn config_generation(&self) -> u64 {
+        self.config.generation()
+    }
+
     fn get_tunnel(&mut self, id: TunnelId) -> Option<TunnelEntry> {
         let config = self.config.load();
         config.tunnels.iter().find(|t| t.id == id).map(|tunnel| {
@@ -177,76 +709,71 @@ impl Backend for MockBackend {
         })
     }
 
-    fn start_tunnel(&mut self, id: TunnelId) -> Result<ProcessId> {
-        let config = self.config.load();
-
-        let tunnel = config
-            .tunnels
-            .iter()
-            .find(|t| t.id == id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&format!("{:?}", id))))?;
-
-        anyhow::ensure!(
-            !self.is_tunnel_running(id),
-            errors::tunnel::already_running(&tunnel.tag)
-        );
-
-        let fake_pid = Self::generate_fake_pid();
-
-        tracing::info!(
-            "MOCK: Starting tunnel {} with fake PID {}",
-            tunnel.tag,
-            fake_pid
-        );
-
-        std::thread::sleep(std::time::Duration::from_millis(100));
-
-        let mock_process = MockProcess {
-            pid: fake_pid,
-            started_at: Timestamp::now(),
+    // Dependencies that aren't running yet are started first, and the first
+    // one that fails fails this start too.
+    async fn start_tunnel(&mut self, id: TunnelId) -> BackendResult<ProcessId> {
+        self.reap_crashed();
+        let config = self.config.load_full();
+        let tag_of = |id: TunnelId| {
+            config
+                .tunnels
+                .iter()
+                .find(|t| t.id == id)
+                .map_or_else(|| id.to_string(), |t| t.tag.clone())
         };
-
-        self.mock_processes.insert(id, mock_process);
-
-        tracing::info!(
-            "MOCK: Started tunnel {} with fake PID {}",
-            tunnel.tag,
-            fake_pid
-        );
-
-        Ok(fake_pid)
+        for dependency in dependency_start_order(&config.tunnels, id) {
+            if self.is_tunnel_running(dependency) {
+                continue;
+            }
+            let result = match self.stats.get(&dependency) {
+                Some(stats) if stats.is_flapping() => Err(anyhow::anyhow!(
+                    stats.crash_error(&tag_of(dependency), stats.last_exit_code)
+                )
+                .into()),
+                _ => self.start_one(dependency).await,
+            };
+            if let Err(e) = result {
+                return Err(anyhow::anyhow!(errors::tunnel::dependency_failed(
+                    &tag_of(id),
+                    &tag_of(dependency),
+                    &e.to_string()
+                ))
+                .into());
+            }
+        }
+        self.start_one(id).await
     }
 
-    fn stop_tunnel(&mut self, id: TunnelId) -> Result<()> {
-        let _process = self
-            .mock_processes
-            .remove(&id)
-            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::NOT_RUNNING))?;
-
-        tracing::info!("MOCK: Stopping tunnel {:?}", id);
-
-        std::thread::sleep(std::time::Duration::from_millis(50));
-
-        tracing::info!("MOCK: Stopped tunnel {:?}", id);
-
-        Ok(())
+    async fn stop_tunnel(&mut self, id: TunnelId) -> BackendResult<()> {
+        if self.config.load().global.stop_dependents {
+            self.stop_dependents(id).await;
+        }
+        self.stop_one(id).await
     }
 
-    fn start_autostart_tunnels(&mut self) -> Result<Vec<(TunnelId, Result<ProcessId>)>> {
-        let config = self.config.load();
-        let autostart_tunnels: Vec<TunnelId> = config
-            .tunnels
-            .iter()
-            .filter(|t| t.autostart)
-            .map(|t| t.id)
-            .collect();
+    async fn start_autostart_tunnels(
+        &mut self,
+    ) -> BackendResult<Vec<(TunnelId, BackendResult<ProcessId>)>> {
+        let autostart_tunnels = autostart_plan(&self.config.load().tunnels);
 
         let mut results = Vec::new();
         let mut started_count = 0;
         let mut failed_count = 0;
 
-        for tunnel_id in autostart_tunnels {
-            let result = self.start_tunnel(tunnel_id);
+        let mut remaining = autostart_tunnels.len();
+        for (tunnel_id, delay) in autostart_tunnels {
+            remaining -= 1;
+            // Already started as the dependency of an earlier tunnel.
+            if self.is_tunnel_running(tunnel_id) {
+                continue;
+            }
+            let result = self.start_tunnel(tunnel_id).await;
+            // Only a tunnel that actually started is worth waiting for.
+            if let (Ok(_), Some(delay)) = (&result, delay)
+                && remaining > 0
+            {
+                tokio::time::sleep(delay).await;
+            }
             match &result {
                 Ok(pid) => {
                     tracing::info!(
@@ -277,14 +804,66 @@ impl Backend for MockBackend {
         Ok(results)
     }
 
+    async fn start_all(&mut self) -> Vec<(TunnelId, BackendResult<ProcessId>)> {
+        self.reap_crashed();
+        let tunnel_ids: Vec<TunnelId> = self
+            .config
+            .load()
+            .tunnels
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| !self.mock_processes.contains_key(id))
+            .collect();
+
+        let mut results = Vec::new();
+        for tunnel_id in tunnel_ids {
+            // Started as the dependency of an earlier tunnel.
+            if self.mock_processes.contains_key(&tunnel_id) {
+                continue;
+            }
+            let result = self.start_tunnel(tunnel_id).await;
+            results.push((tunnel_id, result));
+        }
+        results
+    }
+
+    async fn stop_all(&mut self) -> Vec<(TunnelId, BackendResult<()>)> {
+        self.reap_crashed();
+        let tunnel_ids: Vec<TunnelId> = self.mock_processes.keys().copied().collect();
+
+        let mut results = Vec::new();
+        for tunnel_id in tunnel_ids {
+            let result = self.stop_tunnel(tunnel_id).await;
+            results.push((tunnel_id, result));
+        }
+        results
+    }
+
     fn get_tunnel_status(&self, id: TunnelId) -> TunnelRuntimeState {
         match self.mock_processes.get(&id) {
+            Some(mock_process) if mock_process.has_crashed() => self.crash_state(id, mock_process),
+            Some(mock_process) if mock_process.is_starting() => TunnelRuntimeState::Starting,
             Some(mock_process) => TunnelRuntimeState::Running {
                 pid: mock_process.pid,
                 started_at: mock_process.started_at,
                 log_path: PathBuf::from(format!("logs/mock-{}.log", mock_process.pid)),
+                health: if self.scenario.unhealthy {
+                    TunnelHealth::Unhealthy {
+                        error: "simulated health check failure".to_string(),
+                    }
+                } else {
+                    TunnelHealth::Unchecked
+                },
+                command_line: crate::backend::process::redact_arg_list(
+                    mock_process.command_line.iter().cloned(),
+                    &self.config.load().global.sensitive_flags,
+                ),
             },
-            None => TunnelRuntimeState::Stopped,
+            None => self
+                .failures
+                .get(&id)
+                .cloned()
+                .unwrap_or(TunnelRuntimeState::Stopped),
         }
     }
 
@@ -297,8 +876,74 @@ impl Backend for MockBackend {
             .collect()
     }
 
+    fn summary(&self) -> TunnelSummary {
+        let config = self.config.load();
+        TunnelSummary::of(
+            config
+                .tunnels
+                .iter()
+                .map(|tunnel| self.get_tunnel_status(tunnel.id)),
+        )
+    }
+
     fn is_tunnel_running(&self, id: TunnelId) -> bool {
-        self.mock_processes.contains_key(&id)
+        self.mock_processes
+            .get(&id)
+            .is_some_and(|process| !process.has_crashed())
+    }
+
+    fn get_tunnel_stats(&self, id: TunnelId) -> Option<TunnelStats> {
+        if !self.config.load().tunnels.iter().any(|t| t.id == id) {
+            return None;
+        }
+
+        let mut stats = self.stats.get(&id).cloned().unwrap_or_default();
+        if let Some(process) = self.mock_processes.get(&id) {
+            if process.has_crashed() {
+                stats.record_exit(process.started_at, Some(MOCK_CRASH_EXIT_CODE), true);
+            } else {
+                stats.cumulative_uptime += process.started_at.elapsed();
+            }
+        }
+        Some(stats)
+    }
+
+    fn wstunnel_version(&self) -> Option<String> {
+        Some(MOCK_WSTUNNEL_VERSION.to_string())
+    }
+
+    fn config_path(&self) -> PathBuf {
+        self.config_path.clone()
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<TunnelEvent> {
+        self.events.subscribe()
+    }
+
+    fn preview_command(&self, id: TunnelId) -> BackendResult<String> {
+        let config = self.config.load_full();
+        let tunnel = config
+            .tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(BackendError::TunnelNotFound(id))?;
+
+        let config_dir = self.config_path.parent().unwrap_or(Path::new("."));
+        let working_dir = tunnel.effective_working_dir(config_dir);
+        let args = crate::backend::process::resolve_cli_args(
+            &tunnel.cli_args,
+            &tunnel.env,
+            config.global.fail_on_missing_env_vars,
+            &working_dir,
+        )?;
+        Ok(crate::backend::process::format_command(
+            Path::new("wstunnel"),
+            &args,
+            &tunnel.env,
+            &working_dir,
+            tunnel.priority,
+            &config.global.sensitive_flags,
+        ))
     }
 
     fn get_log_path(&self, id: TunnelId) -> Option<PathBuf> {
@@ -307,13 +952,70 @@ impl Backend for MockBackend {
             .map(|p| PathBuf::from(format!("logs/mock-{}.log", p.pid)))
     }
 
-    fn shutdown(&mut self) -> Result<()> {
-        tracing::info!("MOCK: Shutting down backend, stopping all tunnels");
+    // Mock processes write nothing to stderr.
+    fn recent_stderr(&self, _id: TunnelId) -> String {
+        String::new()
+    }
 
-        let tunnel_ids: Vec<TunnelId> = self.mock_processes.keys().copied().collect();
+    fn read_recent_logs(&self, id: TunnelId, lines: usize) -> BackendResult<Vec<String>> {
+        let Some(mock_process) = self.mock_processes.get(&id) else {
+            return Ok(Vec::new());
+        };
 
-        for tunnel_id in tunnel_ids {
-            if let Err(e) = self.stop_tunnel(tunnel_id) {
+        let log_lines = (0..lines.min(10))
+            .map(|i| {
+                format!(
+                    "[{}] [STDOUT] MOCK: log line {} from fake PID {}",
+                    mock_process.started_at, i, mock_process.pid
+                )
+            })
+            .collect();
+
+        Ok(log_lines)
+    }
+
+    fn grep_logs(
+        &self,
+        id: TunnelId,
+        search: &LogSearch,
+        max: usize,
+    ) -> BackendResult<Vec<String>> {
+        let matcher = search.matcher()?;
+        let mut lines: Vec<String> = self
+            .read_recent_logs(id, usize::MAX)?
+            .into_iter()
+            .filter(|line| matcher.is_match(line))
+            .collect();
+        lines.drain(..lines.len().saturating_sub(max));
+        Ok(lines)
+    }
+
+    fn read_combined_logs(&self, lines_per_tunnel: usize) -> Vec<TaggedLogLine> {
+        let config = self.config.load();
+        let tails = config
+            .tunnels
+            .iter()
+            .map(|tunnel| {
+                let lines = self
+                    .read_recent_logs(tunnel.id, lines_per_tunnel)
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Skipping logs of tunnel '{}': {:#}", tunnel.tag, e);
+                        Vec::new()
+                    });
+                lines
+                    .iter()
+                    .map(|line| TaggedLogLine::parse(tunnel.id, &tunnel.tag, line))
+                    .collect()
+            })
+            .collect();
+        crate::backend::logs::merge_logs(tails)
+    }
+
+    async fn shutdown(&mut self) -> BackendResult<()> {
+        tracing::info!("MOCK: Shutting down backend, stopping all tunnels");
+
+        for (tunnel_id, result) in self.stop_all().await {
+            if let Err(e) = result {
                 tracing::error!(
                     "MOCK: Error stopping tunnel {:?} during shutdown: {}",
                     tunnel_id,
@@ -327,15 +1029,39 @@ impl Backend for MockBackend {
         Ok(())
     }
 
-    fn cleanup_old_logs_if_configured(&self) -> Result<()> {
+    async fn detach(&mut self) -> BackendResult<()> {
+        for (tunnel_id, process) in self.mock_processes.drain() {
+            tracing::info!(
+                "MOCK: Left tunnel {:?} running with fake PID {}",
+                tunnel_id,
+                process.pid
+            );
+        }
+        Ok(())
+    }
+
+    async fn cleanup_old_logs_if_configured(&self) -> BackendResult<()> {
         let config = self.config.load();
+        let directories = config
+            .log_directories()
+            .iter()
+            .map(|dir| dir.display())
+            .join(", ");
+
+        if let Some(days) = config.global.log_compress_days {
+            tracing::info!(
+                "MOCK: Would compress logs older than {} days in {}",
+                days,
+                directories
+            );
+        }
 
         match config.global.log_retention_days {
             Some(days) => {
                 tracing::info!(
                     "MOCK: Would clean up logs older than {} days in {}",
                     days,
-                    config.global.log_directory.display()
+                    directories
                 );
                 Ok(())
             }
@@ -345,4 +1071,13 @@ impl Backend for MockBackend {
             }
         }
     }
+
+    async fn cleanup_logs_now(&self, retention_days: Option<u32>) -> BackendResult<usize> {
+        let config = self.config.load();
+        let days = retention_days
+            .or(config.global.log_retention_days)
+            .ok_or_else(|| anyhow::anyhow!(errors::logs::NO_RETENTION_DAYS))?;
+        tracing::info!("MOCK: Would clean up logs older than {} days now", days);
+        Ok(0)
+    }
 }
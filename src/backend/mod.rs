@@ -1,46 +1,118 @@
 pub mod backend_impl;
+pub mod cli_schema;
 pub mod config;
+pub mod config_lock;
+pub mod error;
+pub mod health;
+pub mod logs;
+pub mod migrations;
 pub mod mock_backend;
 pub mod process;
+pub mod running;
 pub mod types;
+pub mod yaml_comments;
 
-use anyhow::Result;
+use async_trait::async_trait;
+use error::BackendResult;
+use logs::{LogSearch, TaggedLogLine};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use types::{Config, ProcessId, TunnelEntry, TunnelId, TunnelRuntimeState};
+use tokio::sync::broadcast;
+use types::{
+    Config, GlobalSettings, ProcessId, TunnelEntry, TunnelEvent, TunnelId, TunnelRuntimeState,
+    TunnelStats, TunnelSummary,
+};
 
+// Shared handle used by the UI and headless mode. The async mutex lets a
+// caller hold the backend across awaits without blocking a thread.
+pub type SharedBackend = Arc<tokio::sync::Mutex<dyn Backend>>;
+
+#[async_trait]
 pub trait Backend: Send + Sync {
     // Configuration Management
     #[allow(dead_code)]
-    fn load_config(&mut self, path: &Path) -> Result<Arc<Config>>;
-    #[allow(dead_code)]
-    fn save_config(&self, config: &Config, path: &Path) -> Result<()>;
-    #[allow(dead_code)]
+    async fn load_config(&mut self, path: &Path) -> BackendResult<Arc<Config>>;
+    // Writes `config` to a file the user picked, such as an export. The
+    // managed config is saved by the methods that change it.
+    async fn save_config(&self, config: &Config, path: &Path) -> BackendResult<()>;
     fn get_config(&self) -> Arc<Config>;
-    fn validate_tunnel_entry(&self, entry: &TunnelEntry) -> Result<()>;
+    fn config_path(&self) -> PathBuf;
+    async fn import_config(&mut self, path: &Path) -> BackendResult<usize>;
+    async fn update_global_settings(&mut self, settings: GlobalSettings) -> BackendResult<()>;
+    // Rolls the config back to the newest of the backups kept by `save_config`.
+    async fn restore_config_backup(&mut self) -> BackendResult<()>;
+    // Whether the file differs from what was last loaded or saved. Changes
+    // are refused with `ConfigChangedOnDisk` until it is reloaded or
+    // overwritten.
+    async fn config_changed_on_disk(&self) -> bool;
+    async fn reload_config(&mut self) -> BackendResult<()>;
+    async fn overwrite_config(&mut self) -> BackendResult<()>;
+    fn validate_tunnel_entry(&self, entry: &TunnelEntry) -> BackendResult<()>;
+    // Whether changes to tunnels and settings are refused with `ReadOnly`,
+    // because of `set_read_only` or the config's `global.locked`. Starting
+    // and stopping tunnels is always allowed.
+    fn is_read_only(&self) -> bool;
+    fn set_read_only(&mut self, read_only: bool);
 
     // Tunnel CRUD Operations
-    fn add_tunnel(&mut self, entry: TunnelEntry) -> Result<TunnelId>;
-    fn edit_tunnel(&mut self, id: TunnelId, entry: TunnelEntry) -> Result<()>;
-    fn delete_tunnel(&mut self, id: TunnelId) -> Result<()>;
+    async fn add_tunnel(&mut self, entry: TunnelEntry) -> BackendResult<TunnelId>;
+    async fn edit_tunnel(&mut self, id: TunnelId, entry: TunnelEntry) -> BackendResult<()>;
+    #[allow(dead_code)]
+    async fn delete_tunnel(&mut self, id: TunnelId) -> BackendResult<()>;
+    // Stops the running ones, then removes all of them with a single save.
+    // Nothing is removed when one of them does not exist.
+    async fn delete_tunnels(&mut self, ids: &[TunnelId]) -> BackendResult<()>;
     fn list_tunnels(&mut self) -> Vec<TunnelEntry>;
+    // Just the statuses, for refreshing a list whose entries are still
+    // current; `config_generation` changes whenever they are not.
+    fn tunnel_statuses(&mut self) -> Vec<(TunnelId, TunnelRuntimeState)>;
+    fn config_generation(&self) -> u64;
+    #[allow(dead_code)]
     fn get_tunnel(&mut self, id: TunnelId) -> Option<TunnelEntry>;
 
     // Process Lifecycle Management
-    fn start_tunnel(&mut self, id: TunnelId) -> Result<ProcessId>;
-    fn stop_tunnel(&mut self, id: TunnelId) -> Result<()>;
-    fn start_autostart_tunnels(&mut self) -> Result<Vec<(TunnelId, Result<ProcessId>)>>;
+    async fn start_tunnel(&mut self, id: TunnelId) -> BackendResult<ProcessId>;
+    async fn stop_tunnel(&mut self, id: TunnelId) -> BackendResult<()>;
+    async fn start_autostart_tunnels(
+        &mut self,
+    ) -> BackendResult<Vec<(TunnelId, BackendResult<ProcessId>)>>;
+    // Starts every configured tunnel that is not already running.
+    async fn start_all(&mut self) -> Vec<(TunnelId, BackendResult<ProcessId>)>;
+    // Stops every running tunnel.
+    async fn stop_all(&mut self) -> Vec<(TunnelId, BackendResult<()>)>;
 
     // State Queries
     fn get_tunnel_status(&self, id: TunnelId) -> TunnelRuntimeState;
-    #[allow(dead_code)]
     fn get_all_statuses(&self) -> Vec<(TunnelId, TunnelRuntimeState)>;
+    fn summary(&self) -> TunnelSummary;
     fn is_tunnel_running(&self, id: TunnelId) -> bool;
+    fn get_tunnel_stats(&self, id: TunnelId) -> Option<TunnelStats>;
+    // Version reported by the wstunnel binary, if it looked like wstunnel.
+    fn wstunnel_version(&self) -> Option<String>;
+    fn subscribe(&self) -> broadcast::Receiver<TunnelEvent>;
     fn get_log_path(&self, id: TunnelId) -> Option<PathBuf>;
+    // The latest stderr lines of the current run, or of the last one once the
+    // tunnel has stopped. Empty for tunnels that haven't run.
+    fn recent_stderr(&self, id: TunnelId) -> String;
+    // The command `start_tunnel` would run, without running it.
+    fn preview_command(&self, id: TunnelId) -> BackendResult<String>;
+    fn read_recent_logs(&self, id: TunnelId, lines: usize) -> BackendResult<Vec<String>>;
+    // The last `lines_per_tunnel` lines of every tunnel's log, merged by time.
+    fn read_combined_logs(&self, lines_per_tunnel: usize) -> Vec<TaggedLogLine>;
+    // The last `max` lines matching `search` across all of a tunnel's logs,
+    // not just the tail the log viewer has loaded.
+    fn grep_logs(&self, id: TunnelId, search: &LogSearch, max: usize)
+    -> BackendResult<Vec<String>>;
 
     // Lifecycle
-    fn shutdown(&mut self) -> Result<()>;
+    async fn shutdown(&mut self) -> BackendResult<()>;
+    // Stops supervising the running tunnels but leaves their processes up.
+    async fn detach(&mut self) -> BackendResult<()>;
 
     // Maintenance
-    fn cleanup_old_logs_if_configured(&self) -> Result<()>;
+    async fn cleanup_old_logs_if_configured(&self) -> BackendResult<()>;
+    // Deletes logs older than `retention_days`, or than
+    // `global.log_retention_days` when that is None, right away. Returns how
+    // many files were deleted.
+    async fn cleanup_logs_now(&self, retention_days: Option<u32>) -> BackendResult<usize>;
 }
@@ -1,10 +1,22 @@
-use crate::backend::types::{ProcessId, Timestamp, TunnelId};
+use crate::backend::error::BackendError;
+use crate::backend::logs::{
+    LineRateLimiter, LogOptions, LogStream, RotatingLogWriter, StderrBuffer, StreamLogWriters,
+};
+use crate::backend::types::{
+    HealthCheck, LogMode, ProcessId, ProcessPriority, Timestamp, TunnelEvent, TunnelHealth,
+    TunnelId,
+};
+use crate::constants::{LOG_FLUSH_INTERVAL, REDACTED};
 use crate::errors;
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
+use tokio::sync::{broadcast, watch};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
@@ -21,116 +33,670 @@ fn sanitize_filename(name: &str) -> String {
 }
 
 pub struct ProcessInstance {
-    #[allow(dead_code)]
     pub tunnel_id: TunnelId,
-    pub child_handle: Option<Child>,
+    pub process_id: ProcessId,
     pub monitor_task: Option<JoinHandle<()>>,
     pub log_path: PathBuf,
     pub started_at: Timestamp,
+    // Cancelling this asks the monitor task, which owns the child, to kill it.
     pub cancellation_token: CancellationToken,
-    #[allow(dead_code)]
-    pub exit_code: Option<i32>,
-    pub stderr_buffer: Arc<tokio::sync::Mutex<String>>,
+    // Cancelling this makes the monitor task let go of the child without
+    // killing it, so the process outlives the manager.
+    pub detach_token: CancellationToken,
+    pub exit_status: watch::Receiver<Option<ExitStatus>>,
+    pub stderr_buffer: Arc<std::sync::Mutex<StderrBuffer>>,
+    // Adopted processes were started by an earlier run of the manager, so
+    // their exit status is never known.
+    pub adopted: bool,
+    pub health: Option<watch::Receiver<TunnelHealth>>,
+    health_task: Option<JoinHandle<()>>,
+    // The program and arguments the process was started with, after env
+    // expansion and `@file` arguments. Unredacted, since it is what ran.
+    pub command_line: Vec<String>,
+}
+
+// The health check only lives as long as the process is tracked, including
+// when it is dropped without being stopped, e.g. after a crash or detaching.
+impl Drop for ProcessInstance {
+    fn drop(&mut self) {
+        if let Some(task) = self.health_task.take() {
+            task.abort();
+        }
+    }
 }
 
 impl ProcessInstance {
     pub fn new(
         tunnel_id: TunnelId,
-        child_handle: Child,
+        process_id: ProcessId,
         monitor_task: JoinHandle<()>,
         log_path: PathBuf,
         cancellation_token: CancellationToken,
+        exit_status: watch::Receiver<Option<ExitStatus>>,
     ) -> Self {
         Self {
             tunnel_id,
-            child_handle: Some(child_handle),
+            process_id,
             monitor_task: Some(monitor_task),
             log_path,
             started_at: Timestamp::now(),
             cancellation_token,
-            exit_code: None,
-            stderr_buffer: Arc::new(tokio::sync::Mutex::new(String::new())),
+            detach_token: CancellationToken::new(),
+            exit_status,
+            stderr_buffer: Arc::default(),
+            adopted: false,
+            health: None,
+            health_task: None,
+            command_line: Vec::new(),
         }
     }
 
-    pub fn pid(&self) -> Option<ProcessId> {
-        self.child_handle
+    pub fn start_health_check(
+        &mut self,
+        tag: String,
+        check: HealthCheck,
+        events: broadcast::Sender<TunnelEvent>,
+    ) {
+        let (task, health) = crate::backend::health::spawn_health_check(
+            self.tunnel_id,
+            tag,
+            check,
+            self.cancellation_token.clone(),
+            events,
+        );
+        self.health = Some(health);
+        self.health_task = Some(task);
+    }
+
+    pub fn health(&self) -> TunnelHealth {
+        self.health
             .as_ref()
-            .and_then(|child| child.id().map(ProcessId::from))
+            .map(|health| health.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    pub fn pid(&self) -> Option<ProcessId> {
+        (!self.has_exited()).then_some(self.process_id)
+    }
+
+    // A monitor task that finished without reporting a status lost track of
+    // the child, so treat it as gone too.
+    pub fn has_exited(&self) -> bool {
+        self.exit_status.borrow().is_some()
+            || self
+                .monitor_task
+                .as_ref()
+                .is_none_or(|task| task.is_finished())
+    }
+
+    pub fn get_stderr(&self) -> String {
+        self.stderr_buffer
+            .lock()
+            .map(|buffer| buffer.contents())
+            .unwrap_or_default()
+    }
+
+    pub async fn wait_for_exit(&self, timeout: std::time::Duration) -> Option<ExitStatus> {
+        let mut exit_status = self.exit_status.clone();
+        let status = tokio::time::timeout(timeout, exit_status.wait_for(Option::is_some))
+            .await
+            .ok()?
+            .ok()?;
+        *status
+    }
+
+    // What waiting for the process to come up needs, so the wait can run
+    // after the process is handed to the backend.
+    pub fn start_watch(&self) -> StartWatch {
+        StartWatch {
+            tunnel_id: self.tunnel_id,
+            exit_status: self.exit_status.clone(),
+            stderr_buffer: Arc::clone(&self.stderr_buffer),
+            cancellation_token: self.cancellation_token.clone(),
+            detach_token: self.detach_token.clone(),
+        }
+    }
+}
+
+// A freshly spawned process on its way to being ready.
+pub struct StartWatch {
+    tunnel_id: TunnelId,
+    exit_status: watch::Receiver<Option<ExitStatus>>,
+    stderr_buffer: Arc<std::sync::Mutex<StderrBuffer>>,
+    pub cancellation_token: CancellationToken,
+    pub detach_token: CancellationToken,
+}
+
+impl StartWatch {
+    pub fn get_stderr(&self) -> String {
+        self.stderr_buffer
+            .lock()
+            .map(|buffer| buffer.contents())
+            .unwrap_or_default()
+    }
+
+    pub async fn wait_for_exit(&self, timeout: std::time::Duration) -> Option<ExitStatus> {
+        let mut exit_status = self.exit_status.clone();
+        let status = tokio::time::timeout(timeout, exit_status.wait_for(Option::is_some))
+            .await
+            .ok()?
+            .ok()?;
+        *status
     }
 
-    #[allow(dead_code)]
-    pub async fn get_stderr(&self) -> String {
-        self.stderr_buffer.lock().await.clone()
+    // Gives the process time to fail on bad arguments or, with a ready
+    // pattern, to say that it is up. `output` must be subscribed before the
+    // spawn so no early line is missed. The monitor only reports an exit once
+    // the output has been drained, so the stderr buffer is complete when this
+    // returns `Exited`.
+    pub async fn wait_until_ready(
+        &self,
+        check: &StartCheck,
+        output: &mut broadcast::Receiver<TunnelEvent>,
+    ) -> StartOutcome {
+        let Some(pattern) = &check.ready_pattern else {
+            return match self.wait_for_exit(check.timeout).await {
+                Some(status) => StartOutcome::Exited(status),
+                None => StartOutcome::Ready,
+            };
+        };
+
+        let mut exit_status = self.exit_status.clone();
+        let exited = async move {
+            match exit_status.wait_for(Option::is_some).await {
+                Ok(status) => *status,
+                Err(_) => None,
+            }
+        };
+        tokio::pin!(exited);
+        let ready = async {
+            loop {
+                tokio::select! {
+                    status = &mut exited => {
+                        return match status {
+                            Some(status) => StartOutcome::Exited(status),
+                            // The monitor is gone without a status; leave it
+                            // to the timeout.
+                            None => std::future::pending().await,
+                        };
+                    }
+                    event = output.recv() => match event {
+                        Ok(TunnelEvent::LogLine { id, line })
+                            if id == self.tunnel_id && pattern.is_match(&line) =>
+                        {
+                            return StartOutcome::Ready;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+                        _ => {}
+                    },
+                }
+            }
+        };
+        tokio::time::timeout(check.timeout, ready)
+            .await
+            .unwrap_or(StartOutcome::TimedOut)
     }
 }
 
-fn parse_cli_args(cli_args: &str) -> Vec<String> {
+// How a freshly spawned tunnel shows that it started.
+#[derive(Debug, Clone)]
+pub struct StartCheck {
+    pub timeout: std::time::Duration,
+    // Matched against each output line as it is written to the log.
+    pub ready_pattern: Option<regex::Regex>,
+}
+
+#[derive(Debug)]
+pub enum StartOutcome {
+    Ready,
+    Exited(ExitStatus),
+    TimedOut,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QuoteState {
+    None,
+    Single,
+    Double,
+}
+
+// Shell-style word splitting: single quotes are fully literal, double quotes
+// allow `\"` and `\\` escapes, and quoted/unquoted segments that touch
+// are joined into one argument. Outside quotes a backslash only escapes
+// whitespace, quotes and itself so unquoted Windows paths keep working.
+pub fn parse_cli_args(cli_args: &str) -> Vec<String> {
+    tokenize_cli_args(cli_args).0
+}
+
+// An unterminated quote swallows the rest of the line into one argument,
+// which is rarely what was meant, so the edit form rejects it before saving.
+pub fn check_cli_args_quotes(cli_args: &str) -> Result<()> {
+    match tokenize_cli_args(cli_args).1 {
+        QuoteState::None => Ok(()),
+        QuoteState::Single => anyhow::bail!(errors::tunnel::validation::unterminated_quote('\'')),
+        QuoteState::Double => anyhow::bail!(errors::tunnel::validation::unterminated_quote('"')),
+    }
+}
+
+fn tokenize_cli_args(cli_args: &str) -> (Vec<String>, QuoteState) {
     let mut args = Vec::new();
     let mut current_arg = String::new();
-    let mut in_quotes = false;
-    let chars = cli_args.chars().peekable();
+    let mut in_word = false;
+    let mut quote = QuoteState::None;
+    let mut chars = cli_args.chars().peekable();
 
-    for c in chars {
-        match c {
-            '"' => {
-                in_quotes = !in_quotes;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (QuoteState::None, c) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current_arg));
+                    in_word = false;
+                }
+            }
+            (QuoteState::None, '\'') => {
+                quote = QuoteState::Single;
+                in_word = true;
+            }
+            (QuoteState::None, '"') => {
+                quote = QuoteState::Double;
+                in_word = true;
             }
-            ' ' if !in_quotes => {
-                if !current_arg.is_empty() {
-                    args.push(current_arg.clone());
-                    current_arg.clear();
+            (QuoteState::None, '\\') => {
+                in_word = true;
+                match chars.peek() {
+                    Some(&next) if next.is_whitespace() || matches!(next, '"' | '\'' | '\\') => {
+                        current_arg.push(next);
+                        chars.next();
+                    }
+                    _ => current_arg.push('\\'),
                 }
             }
-            _ => {
+            (QuoteState::Single, '\'') | (QuoteState::Double, '"') => {
+                quote = QuoteState::None;
+            }
+            (QuoteState::Double, '\\') => match chars.peek() {
+                Some(&next) if matches!(next, '"' | '\\') => {
+                    current_arg.push(next);
+                    chars.next();
+                }
+                _ => current_arg.push('\\'),
+            },
+            (_, c) => {
+                in_word = true;
                 current_arg.push(c);
             }
         }
     }
 
-    if !current_arg.is_empty() {
+    if in_word {
         args.push(current_arg);
     }
 
-    args
+    (args, quote)
+}
+
+// Expands `${VAR}` references in a single argument. Anything that is not a
+// well-formed `${NAME}` reference is left untouched.
+pub fn expand_env_vars<F>(arg: &str, lookup: F, fail_on_missing: bool) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut expanded = String::with_capacity(arg.len());
+    let mut rest = arg;
+
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+
+        let Some(end) = after_open.find('}') else {
+            expanded.push_str(&rest[start..]);
+            return Ok(expanded);
+        };
+
+        let name = &after_open[..end];
+        let is_valid_name = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        match (is_valid_name, lookup(name)) {
+            (true, Some(value)) => expanded.push_str(&value),
+            (true, None) if fail_on_missing => {
+                anyhow::bail!(errors::process::env_var_not_set(name))
+            }
+            _ => expanded.push_str(&rest[start..start + 2 + end + 1]),
+        }
+
+        rest = &after_open[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn is_plain_arg(arg: &str) -> bool {
+    !arg.is_empty()
+        && !arg
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+}
+
+// Quotes an argument so that `parse_cli_args` reads it back unchanged.
+fn quote_arg(arg: &str) -> String {
+    match is_plain_arg(arg) {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+// Masks the values of sensitive flags, given as either `--flag value` or
+// `--flag=value`, so the arguments can be logged or shown. The result is for
+// display only: quoting is normalised and the real values are what run.
+pub fn redact_cli_args(cli_args: &str, sensitive_flags: &[String]) -> String {
+    redact_args(parse_cli_args(cli_args), sensitive_flags)
+}
+
+fn redact_args(args: impl IntoIterator<Item = String>, sensitive_flags: &[String]) -> String {
+    redact_each(args, sensitive_flags, quote_arg).join(" ")
+}
+
+// Like `redact_cli_args`, but keeps the arguments apart and unquoted.
+pub fn redact_arg_list(
+    args: impl IntoIterator<Item = String>,
+    sensitive_flags: &[String],
+) -> Vec<String> {
+    redact_each(args, sensitive_flags, str::to_string)
+}
+
+fn redact_each(
+    args: impl IntoIterator<Item = String>,
+    sensitive_flags: &[String],
+    show: fn(&str) -> String,
+) -> Vec<String> {
+    let is_sensitive = |flag: &str| sensitive_flags.iter().any(|sensitive| sensitive == flag);
+    let mut redacted = Vec::new();
+    let mut mask_next = false;
+
+    for arg in args {
+        if std::mem::take(&mut mask_next) {
+            redacted.push(REDACTED.to_string());
+            continue;
+        }
+        match arg.split_once('=') {
+            Some((flag, _)) if flag.starts_with('-') && is_sensitive(flag) => {
+                redacted.push(format!("{}={}", flag, REDACTED));
+            }
+            _ => {
+                mask_next = is_sensitive(&arg);
+                redacted.push(show(&arg));
+            }
+        }
+    }
+
+    redacted
+}
+
+// The first wstunnel on PATH, for installs through a package manager. On
+// Windows this also finds `wstunnel.exe`, since the lookup follows PATHEXT.
+pub fn detect_wstunnel_binary() -> Option<PathBuf> {
+    which::which("wstunnel").ok()
+}
+
+// Runs `<binary> --version` and returns the version when the output looks
+// like wstunnel's, e.g. "wstunnel 10.1.8".
+pub async fn detect_wstunnel_version(binary_path: &Path) -> Result<String> {
+    let path = binary_path.display().to_string();
+    let output = Command::new(binary_path)
+        .arg("--version")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(crate::constants::VERSION_CHECK_TIMEOUT, output)
+        .await
+        .map_err(|_| anyhow::anyhow!(errors::binary::version_check_timed_out(&path)))?
+        .with_context(|| errors::binary::version_check_failed(&path))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_line = stdout.lines().next().unwrap_or_default().trim();
+    match first_line.split_once(' ') {
+        Some(("wstunnel", version)) if output.status.success() => Ok(version.trim().to_string()),
+        _ => anyhow::bail!(errors::binary::not_wstunnel(&path, first_line)),
+    }
+}
+
+// The arguments exactly as the process receives them: split like a shell
+// would, with environment variables expanded and `@file` arguments replaced
+// by the file's arguments. Relative args files are looked up in `working_dir`.
+pub fn resolve_cli_args(
+    cli_args: &str,
+    env: &HashMap<String, String>,
+    fail_on_missing_env_vars: bool,
+    working_dir: &Path,
+) -> Result<Vec<String>> {
+    let mut resolved = Vec::new();
+    resolve_args_into(
+        parse_cli_args(cli_args),
+        &|arg: &str| {
+            expand_env_vars(
+                arg,
+                |name| env.get(name).cloned().or_else(|| std::env::var(name).ok()),
+                fail_on_missing_env_vars,
+            )
+        },
+        working_dir,
+        &mut Vec::new(),
+        &mut resolved,
+    )?;
+    Ok(resolved)
+}
+
+// The path of an `@path` argument.
+pub fn args_file_path(arg: &str) -> Option<&Path> {
+    arg.strip_prefix('@')
+        .filter(|path| !path.is_empty())
+        .map(Path::new)
+}
+
+// `open_files` holds the args files being expanded, so a file that ends up
+// including itself is reported instead of recursing forever.
+fn resolve_args_into(
+    args: Vec<String>,
+    expand: &dyn Fn(&str) -> Result<String>,
+    working_dir: &Path,
+    open_files: &mut Vec<PathBuf>,
+    resolved: &mut Vec<String>,
+) -> Result<()> {
+    for arg in args {
+        let arg = expand(&arg)?;
+        let Some(path) = args_file_path(&arg) else {
+            resolved.push(arg);
+            continue;
+        };
+        let path = working_dir.join(path);
+        let canonical = std::fs::canonicalize(&path).map_err(|_| {
+            anyhow::anyhow!(errors::tunnel::args_file_not_found(
+                &path.display().to_string(),
+            ))
+        })?;
+        anyhow::ensure!(
+            !open_files.contains(&canonical),
+            errors::tunnel::args_file_recursive(&path.display().to_string())
+        );
+        let contents = std::fs::read_to_string(&canonical)
+            .with_context(|| errors::tunnel::args_file_unreadable(&path.display().to_string()))?;
+        open_files.push(canonical);
+        resolve_args_into(
+            parse_args_file(&contents),
+            expand,
+            working_dir,
+            open_files,
+            resolved,
+        )?;
+        open_files.pop();
+    }
+    Ok(())
+}
+
+// Args files are split like `cli_args`, one or more arguments per line.
+// Lines starting with `#` are comments.
+fn parse_args_file(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(parse_cli_args)
+        .collect()
+}
+
+// A relative binary path would otherwise be looked up from the working
+// directory the child is started in.
+fn program_path(binary_path: &Path) -> PathBuf {
+    std::path::absolute(binary_path).unwrap_or_else(|_| binary_path.to_path_buf())
+}
+
+// The command `spawn_tunnel_process` runs, written as one shell line that
+// can be pasted into a terminal. Values of sensitive flags and of the
+// environment variables are masked, like in exported snippets. A priority
+// shows as `nice` on Unix; Windows has no shell equivalent.
+pub fn format_command(
+    binary_path: &Path,
+    args: &[String],
+    env: &HashMap<String, String>,
+    working_dir: &Path,
+    priority: Option<ProcessPriority>,
+    sensitive_flags: &[String],
+) -> String {
+    let mut parts = vec![
+        "cd".to_string(),
+        quote_arg(&working_dir.display().to_string()),
+        "&&".to_string(),
+    ];
+    parts.extend(
+        env.iter()
+            .sorted()
+            .map(|(name, _)| format!("{}={}", name, REDACTED)),
+    );
+    if let Some(priority) = priority.filter(|_| cfg!(unix)) {
+        parts.push(format!("nice -n {}", priority));
+    }
+    parts.push(quote_arg(&program_path(binary_path).display().to_string()));
+    if !args.is_empty() {
+        parts.push(redact_args(args.iter().cloned(), sensitive_flags));
+    }
+    parts.join(" ")
 }
 
-pub async fn spawn_tunnel_process(binary_path: &PathBuf, cli_args: &str) -> Result<Child> {
-    let args = parse_cli_args(cli_args);
+// Returns the child together with the command line it was started with.
+#[allow(clippy::too_many_arguments)]
+pub async fn spawn_tunnel_process(
+    binary_path: &Path,
+    cli_args: &str,
+    env: &HashMap<String, String>,
+    fail_on_missing_env_vars: bool,
+    kill_on_drop: bool,
+    working_dir: &Path,
+    priority: Option<ProcessPriority>,
+    sensitive_flags: &[String],
+) -> Result<(Child, Vec<String>)> {
+    let args = resolve_cli_args(cli_args, env, fail_on_missing_env_vars, working_dir)?;
+    let program = program_path(binary_path);
 
     tracing::info!(
         "Spawning wstunnel process: {} {}",
         binary_path.display(),
-        cli_args
+        redact_cli_args(cli_args, sensitive_flags)
     );
 
-    let mut command = Command::new(binary_path);
+    let mut command = Command::new(&program);
     command
         .args(&args)
+        .envs(env)
+        .current_dir(working_dir)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
-        .kill_on_drop(true);
+        .kill_on_drop(kill_on_drop);
+    if let Some(priority) = priority {
+        set_priority(&mut command, priority);
+    }
 
     let child = command.spawn().map_err(|e| {
         let error_msg = e.to_string();
         if error_msg.contains("No such file or directory")
             || error_msg.contains("cannot find the path")
         {
-            anyhow::anyhow!(errors::binary::not_found_simple(
-                &binary_path.display().to_string()
+            BackendError::BinaryNotFound(errors::binary::not_found_simple(
+                &binary_path.display().to_string(),
             ))
+            .into()
         } else if error_msg.contains("Permission denied") {
-            anyhow::anyhow!(errors::binary::permission_denied(
-                &binary_path.display().to_string()
+            BackendError::PermissionDenied(errors::binary::permission_denied(
+                &binary_path.display().to_string(),
             ))
+            .into()
         } else if error_msg.contains("Address already in use") {
-            anyhow::anyhow!(errors::process::PORT_IN_USE)
+            BackendError::PortInUse.into()
         } else {
             anyhow::anyhow!(errors::process::spawn_failed(&error_msg))
         }
     })?;
 
-    Ok(child)
+    #[cfg(unix)]
+    if let (Some(priority), Some(pid)) = (priority, child.id()) {
+        check_priority(pid, priority);
+    }
+
+    let command_line = std::iter::once(program.display().to_string())
+        .chain(args)
+        .collect();
+    Ok((child, command_line))
+}
+
+// Runs between fork and exec in the child, where nothing may allocate or
+// lock, so a refusal is only noticed by `check_priority` afterwards.
+#[cfg(unix)]
+fn set_priority(command: &mut Command, priority: ProcessPriority) {
+    let nice = priority.nice();
+    // SAFETY: setpriority is a plain system call and async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(windows)]
+fn set_priority(command: &mut Command, priority: ProcessPriority) {
+    if let Some(class) = priority.windows_priority_class() {
+        command.creation_flags(class);
+    }
+}
+
+// Raising the priority above the default usually needs root. The tunnel
+// still runs when the OS refuses, at whatever priority it got.
+#[cfg(unix)]
+fn check_priority(pid: u32, priority: ProcessPriority) {
+    // SAFETY: getpriority only reads the scheduling priority of `pid`.
+    let actual = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid) };
+    if actual != priority.nice() {
+        tracing::warn!(
+            "{}",
+            errors::process::priority_not_applied(priority.nice(), actual)
+        );
+    }
+}
+
+// How long to keep reading output after the process exits. A child that
+// inherited the pipes can hold them open long after wstunnel itself is gone.
+const EXIT_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+// The part of a tunnel's log file names that identifies the tunnel.
+pub fn log_name(tunnel_id: TunnelId, tag: &str) -> String {
+    if tag.is_empty() {
+        format!("{:?}", tunnel_id)
+    } else {
+        sanitize_filename(tag)
+    }
 }
 
 pub async fn create_process_instance(
@@ -138,30 +704,36 @@ pub async fn create_process_instance(
     tunnel_name: String,
     mut child: Child,
     log_directory: &PathBuf,
+    log_options: LogOptions,
     cancellation_token: CancellationToken,
+    events: broadcast::Sender<TunnelEvent>,
 ) -> Result<ProcessInstance> {
     let pid = child.id().context(errors::process::FAILED_TO_GET_PID)?;
     let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
 
-    let sanitized_name = if tunnel_name.is_empty() {
-        format!("{:?}", tunnel_id)
-    } else {
-        sanitize_filename(&tunnel_name)
-    };
-
-    let log_filename = format!("{}-{}-{}.log", sanitized_name, pid, timestamp);
+    let log_filename = format!(
+        "{}-{}-{}.log",
+        log_name(tunnel_id, &tunnel_name),
+        pid,
+        timestamp
+    );
     let log_path = log_directory.join(log_filename);
 
     tokio::fs::create_dir_all(log_directory)
         .await
         .context(errors::logs::FAILED_TO_CREATE_DIR)?;
 
-    let log_file = tokio::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_path)
+    let mut log_writer = RotatingLogWriter::open(log_path.clone(), log_options.rotation)
         .await
         .context(errors::logs::FAILED_TO_CREATE_FILE)?;
+    let mut stream_writers = match log_options.mode {
+        LogMode::Combined => None,
+        LogMode::Split => Some(
+            StreamLogWriters::open(&log_path, log_options.rotation)
+                .await
+                .context(errors::logs::FAILED_TO_CREATE_FILE)?,
+        ),
+    };
 
     let stdout = child
         .stdout
@@ -174,40 +746,72 @@ pub async fn create_process_instance(
 
     let log_path_clone = log_path.clone();
     let monitor_token = cancellation_token.clone();
-    let stderr_buffer = Arc::new(tokio::sync::Mutex::new(String::new()));
+    let detach_token = CancellationToken::new();
+    let monitor_detach_token = detach_token.clone();
+    let stderr_buffer = Arc::new(std::sync::Mutex::new(StderrBuffer::new(
+        log_options.stderr_buffer_lines,
+    )));
     let stderr_buffer_clone = stderr_buffer.clone();
+    let (exit_tx, exit_rx) = watch::channel(None);
+
+    let log_tag = tunnel_name.clone();
+    let log_format = log_options.line_format;
+    let mut rate_limiter = LineRateLimiter::new(log_options.max_lines_per_second);
 
     let monitor_task = tokio::spawn(async move {
-        let mut log_writer = tokio::io::BufWriter::new(log_file);
         let stdout_reader = BufReader::new(stdout);
         let stderr_reader = BufReader::new(stderr);
 
         let mut stdout_lines = stdout_reader.lines();
         let mut stderr_lines = stderr_reader.lines();
+        let mut stdout_open = true;
+        let mut stderr_open = true;
+        let mut kill_sent = false;
+        let mut exit_result = None;
+        let mut drain_deadline = None;
+        let mut detached = false;
 
-        loop {
-            tokio::select! {
-                _ = monitor_token.cancelled() => {
-                    tracing::info!("Monitor task cancelled for log: {}", log_path_clone.display());
+        // Lines are written to the buffered writers as they arrive and
+        // flushed on this tick, so quiet tunnels still reach the file promptly.
+        let mut flush_interval = tokio::time::interval(LOG_FLUSH_INTERVAL);
+        flush_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        'monitor: while stdout_open || stderr_open {
+            let received = tokio::select! {
+                _ = monitor_detach_token.cancelled(), if exit_result.is_none() => {
+                    detached = true;
                     break;
                 }
-                result = stdout_lines.next_line() => {
+                _ = monitor_token.cancelled(), if !kill_sent => {
+                    kill_sent = true;
+                    kill_child(&mut child);
+                    None
+                }
+                result = child.wait(), if exit_result.is_none() => {
+                    exit_result = Some(result);
+                    drain_deadline = Some(tokio::time::Instant::now() + EXIT_DRAIN_TIMEOUT);
+                    None
+                }
+                _ = tokio::time::sleep_until(drain_deadline.unwrap_or_else(tokio::time::Instant::now)), if drain_deadline.is_some() => {
+                    tracing::debug!("Output still open after process exit for log: {}", log_path_clone.display());
+                    break;
+                }
+                _ = flush_interval.tick() => {
+                    if let Err(e) = log_writer.flush().await {
+                        tracing::error!("{}", errors::logs::failed_to_flush(&e.to_string()));
+                    }
+                    if let Some(writers) = &mut stream_writers && let Err(e) = writers.flush().await {
+                        tracing::error!("{}", errors::logs::failed_to_flush(&e.to_string()));
+                    }
+                    None
+                }
+                result = stdout_lines.next_line(), if stdout_open => {
                     match result {
-                        Ok(Some(line)) => {
-                            let timestamp = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-                            let log_line = format!("[{}] [STDOUT] {}\n", timestamp, line);
-                            if let Err(e) = tokio::io::AsyncWriteExt::write_all(&mut log_writer, log_line.as_bytes()).await {
-                                if e.to_string().contains("No space left on device") || e.to_string().contains("disk full") {
-                                    tracing::error!("{}", errors::disk::full_log_write(&log_path_clone.display().to_string()));
-                                } else {
-                                    tracing::error!("{}", errors::logs::failed_to_write_stdout(&e.to_string()));
-                                }
-                                break;
-                            }
-                        }
+                        Ok(Some(line)) => Some((LogStream::Stdout, line)),
                         Ok(None) => {
-                            tracing::info!("Stdout stream closed for log: {}", log_path_clone.display());
-                            break;
+                            tracing::debug!("Stdout stream closed for log: {}", log_path_clone.display());
+                            stdout_open = false;
+                            None
                         }
                         Err(e) => {
                             tracing::error!("Error reading stdout: {}", e);
@@ -215,32 +819,13 @@ pub async fn create_process_instance(
                         }
                     }
                 }
-                result = stderr_lines.next_line() => {
+                result = stderr_lines.next_line(), if stderr_open => {
                     match result {
-                        Ok(Some(line)) => {
-                            let timestamp = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-                            let log_line = format!("[{}] [STDERR] {}\n", timestamp, line);
-
-                            let mut buffer = stderr_buffer_clone.lock().await;
-                            buffer.push_str(&line);
-                            buffer.push('\n');
-                            if buffer.len() > 4096 {
-                                *buffer = buffer.chars().rev().take(4096).collect::<String>().chars().rev().collect();
-                            }
-                            drop(buffer);
-
-                            if let Err(e) = tokio::io::AsyncWriteExt::write_all(&mut log_writer, log_line.as_bytes()).await {
-                                if e.to_string().contains("No space left on device") || e.to_string().contains("disk full") {
-                                    tracing::error!("{}", errors::disk::full_log_write(&log_path_clone.display().to_string()));
-                                } else {
-                                    tracing::error!("{}", errors::logs::failed_to_write_stderr(&e.to_string()));
-                                }
-                                break;
-                            }
-                        }
+                        Ok(Some(line)) => Some((LogStream::Stderr, line)),
                         Ok(None) => {
-                            tracing::info!("Stderr stream closed for log: {}", log_path_clone.display());
-                            break;
+                            tracing::debug!("Stderr stream closed for log: {}", log_path_clone.display());
+                            stderr_open = false;
+                            None
                         }
                         Err(e) => {
                             tracing::error!("Error reading stderr: {}", e);
@@ -248,17 +833,294 @@ pub async fn create_process_instance(
                         }
                     }
                 }
+            };
+            let Some((stream, line)) = received else {
+                continue;
+            };
+
+            if stream == LogStream::Stderr
+                && let Ok(mut buffer) = stderr_buffer_clone.lock()
+            {
+                buffer.push(line.clone());
+            }
+            // Dropped lines are not formatted, logged or published.
+            if !rate_limiter.admit(std::time::Instant::now()) {
+                continue;
+            }
+
+            let dropped = rate_limiter.take_dropped();
+            let notice = (dropped > 0).then(|| {
+                let limit = rate_limiter.limit().unwrap_or_default();
+                (
+                    LogStream::Stderr,
+                    errors::logs::lines_dropped(dropped, limit),
+                )
+            });
+            for (stream, line) in notice.into_iter().chain(std::iter::once((stream, line))) {
+                let log_line = format!("{}\n", log_format.format(&log_tag, stream, &line));
+                let _ = events.send(TunnelEvent::LogLine {
+                    id: tunnel_id,
+                    line: log_line.trim_end().to_string(),
+                });
+                if let Err(e) = log_writer.write_line(&log_line).await {
+                    if e.to_string().contains("No space left on device")
+                        || e.to_string().contains("disk full")
+                    {
+                        tracing::error!(
+                            "{}",
+                            errors::disk::full_log_write(&log_path_clone.display().to_string())
+                        );
+                    } else if stream == LogStream::Stderr {
+                        tracing::error!("{}", errors::logs::failed_to_write_stderr(&e.to_string()));
+                    } else {
+                        tracing::error!("{}", errors::logs::failed_to_write_stdout(&e.to_string()));
+                    }
+                    break 'monitor;
+                }
+                if let Some(writers) = &mut stream_writers
+                    && let Err(e) = writers.write_line(stream, &log_line).await
+                {
+                    tracing::error!("{}", errors::logs::failed_to_write_stream(&e.to_string()));
+                    stream_writers = None;
+                }
             }
         }
 
-        if let Err(e) = tokio::io::AsyncWriteExt::flush(&mut log_writer).await {
+        let dropped = rate_limiter.take_dropped();
+        if dropped > 0 {
+            let limit = rate_limiter.limit().unwrap_or_default();
+            let notice = errors::logs::lines_dropped(dropped, limit);
+            let log_line = format!(
+                "{}\n",
+                log_format.format(&log_tag, LogStream::Stderr, &notice)
+            );
+            if let Err(e) = log_writer.write_line(&log_line).await {
+                tracing::error!("{}", errors::logs::failed_to_write_stderr(&e.to_string()));
+            }
+        }
+
+        if let Err(e) = log_writer.flush().await {
+            tracing::error!("{}", errors::logs::failed_to_flush(&e.to_string()));
+        }
+        if let Some(writers) = &mut stream_writers
+            && let Err(e) = writers.flush().await
+        {
             tracing::error!("{}", errors::logs::failed_to_flush(&e.to_string()));
         }
+
+        // The child may have been spawned with kill_on_drop, so it must never
+        // be dropped once detached.
+        if detached {
+            tracing::info!("Detached from process {}", pid);
+            std::mem::forget(child);
+            return;
+        }
+
+        let exit_result = match exit_result {
+            Some(result) => result,
+            None => loop {
+                tokio::select! {
+                    result = child.wait() => break result,
+                    _ = monitor_token.cancelled(), if !kill_sent => {
+                        kill_sent = true;
+                        kill_child(&mut child);
+                    }
+                }
+            },
+        };
+
+        // The token is only cancelled by `stop_tunnel` and shutdown, so an
+        // exit without it was not asked for.
+        match exit_result {
+            Ok(status) if monitor_token.is_cancelled() => {
+                tracing::info!("Process {} stopped by user (status: {})", pid, status);
+                let _ = exit_tx.send(Some(status));
+            }
+            Ok(status) => {
+                tracing::warn!(
+                    "Process {} exited on its own with status: {} (code: {:?})",
+                    pid,
+                    status,
+                    status.code()
+                );
+                let _ = exit_tx.send(Some(status));
+                let _ = events.send(TunnelEvent::Crashed {
+                    id: tunnel_id,
+                    exit_code: status.code(),
+                });
+            }
+            Err(e) => {
+                tracing::error!("Error waiting for process {}: {}", pid, e);
+            }
+        }
     });
 
-    let mut instance =
-        ProcessInstance::new(tunnel_id, child, monitor_task, log_path, cancellation_token);
+    let mut instance = ProcessInstance::new(
+        tunnel_id,
+        ProcessId::from(pid),
+        monitor_task,
+        log_path,
+        cancellation_token,
+        exit_rx,
+    );
     instance.stderr_buffer = stderr_buffer;
+    instance.detach_token = detach_token;
 
     Ok(instance)
 }
+
+// How often an adopted process is checked for having exited.
+const ADOPTED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+fn find_process(system: &mut sysinfo::System, pid: ProcessId) -> Option<&sysinfo::Process> {
+    let pid = sysinfo::Pid::from_u32(pid.into());
+    system.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid]),
+        true,
+        sysinfo::ProcessRefreshKind::nothing()
+            .with_exe(sysinfo::UpdateKind::Always)
+            .with_cmd(sysinfo::UpdateKind::Always),
+    );
+    system
+        .process(pid)
+        .filter(|process| process.status() != sysinfo::ProcessStatus::Zombie)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b
+        || matches!(
+            (std::fs::canonicalize(a), std::fs::canonicalize(b)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+// Whether `pid` is alive and was started from `binary_path`, so that a PID
+// reused by an unrelated process is never adopted. A script runs under its
+// interpreter, so the command line is checked as well as the executable.
+pub fn is_tunnel_process(pid: ProcessId, binary_path: &Path) -> bool {
+    let mut system = sysinfo::System::new();
+    let Some(process) = find_process(&mut system, pid) else {
+        return false;
+    };
+    process.exe().is_some_and(|exe| same_path(exe, binary_path))
+        || process
+            .cmd()
+            .iter()
+            .any(|arg| same_path(Path::new(arg), binary_path))
+}
+
+// What an adopted process was started with, as far as the OS reports it.
+fn command_line_of(pid: ProcessId) -> Vec<String> {
+    let mut system = sysinfo::System::new();
+    find_process(&mut system, pid)
+        .map(|process| {
+            process
+                .cmd()
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Whether `pid` is alive and runs the same executable as this process, so
+// that a lock left by a crashed manager isn't kept by a reused PID.
+pub fn is_manager_process(pid: ProcessId) -> bool {
+    let mut system = sysinfo::System::new();
+    let Some(process) = find_process(&mut system, pid) else {
+        return false;
+    };
+    let own_name = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_name().map(|name| name.to_os_string()));
+    match (process.exe().and_then(|exe| exe.file_name()), own_name) {
+        (Some(name), Some(own_name)) => name == own_name,
+        // Without a path to compare, only the PID being alive is known.
+        _ => true,
+    }
+}
+
+fn kill_process(pid: ProcessId) {
+    let mut system = sysinfo::System::new();
+    match find_process(&mut system, pid).map(|process| process.kill()) {
+        Some(true) => tracing::info!("Sent kill signal to process {}", pid),
+        _ => tracing::warn!("Failed to send kill signal to process {}", pid),
+    }
+}
+
+// Tracks a process left running by an earlier run of the manager. Without a
+// child handle there is no output to log, so the monitor only polls for the
+// process and kills it by PID when cancelled.
+pub fn adopt_process_instance(
+    tunnel_id: TunnelId,
+    pid: ProcessId,
+    binary_path: PathBuf,
+    log_path: PathBuf,
+    started_at: Timestamp,
+    cancellation_token: CancellationToken,
+    events: broadcast::Sender<TunnelEvent>,
+) -> ProcessInstance {
+    let monitor_token = cancellation_token.clone();
+    let detach_token = CancellationToken::new();
+    let monitor_detach_token = detach_token.clone();
+    let (exit_tx, exit_rx) = watch::channel(None);
+
+    let monitor_task = tokio::spawn(async move {
+        // Dropping the sender when the process is gone ends wait_for_exit.
+        let _exit_tx = exit_tx;
+        let mut interval = tokio::time::interval(ADOPTED_POLL_INTERVAL);
+        let mut kill_sent = false;
+
+        loop {
+            tokio::select! {
+                _ = monitor_detach_token.cancelled() => {
+                    tracing::info!("Detached from process {}", pid);
+                    return;
+                }
+                _ = monitor_token.cancelled(), if !kill_sent => {
+                    kill_sent = true;
+                    kill_process(pid);
+                }
+                _ = interval.tick() => {}
+            }
+
+            if !is_tunnel_process(pid, &binary_path) {
+                break;
+            }
+        }
+
+        tracing::info!("Adopted process {} exited", pid);
+        if !monitor_token.is_cancelled() {
+            let _ = events.send(TunnelEvent::Crashed {
+                id: tunnel_id,
+                exit_code: None,
+            });
+        }
+    });
+
+    let mut instance = ProcessInstance::new(
+        tunnel_id,
+        pid,
+        monitor_task,
+        log_path,
+        cancellation_token,
+        exit_rx,
+    );
+    instance.started_at = started_at;
+    instance.detach_token = detach_token;
+    instance.adopted = true;
+    instance.command_line = command_line_of(pid);
+    instance
+}
+
+fn kill_child(child: &mut Child) {
+    let pid = child.id();
+    match child.start_kill() {
+        Ok(_) => {
+            tracing::info!("Sent kill signal to process {:?}", pid);
+        }
+        Err(e) => {
+            tracing::warn!("Failed to send kill signal to process {:?}: {}", pid, e);
+        }
+    }
+}
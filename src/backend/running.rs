@@ -0,0 +1,132 @@
+// Tunnel processes started by the manager, recorded next to the config file
+// when they are spawned and dropped when they are stopped. A later run adopts
+// the ones that are still alive, whether they were left running on purpose or
+// the manager crashed, instead of showing them as stopped.
+
+use crate::backend::types::{ProcessId, Timestamp, TunnelId};
+use crate::errors;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunningProcess {
+    pub id: TunnelId,
+    pub pid: ProcessId,
+    pub binary_path: PathBuf,
+    pub log_path: PathBuf,
+    pub started_at: Timestamp,
+}
+
+pub fn state_file_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("state.json")
+}
+
+// How a tunnel's last run ended, kept in a file of its own so the status and
+// log shown before a restart are still there after it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastRun {
+    pub id: TunnelId,
+    pub pid: ProcessId,
+    pub log_path: PathBuf,
+    pub started_at: Timestamp,
+    pub ended_at: Timestamp,
+    pub exit_code: Option<i32>,
+    // Why the run failed or crashed; none when it was stopped.
+    pub error: Option<String>,
+}
+
+pub fn last_run_file_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("last_run.json")
+}
+
+// Read and written synchronously: it is small, and runs are also recorded
+// from places that can't await.
+pub fn load_last_runs(path: &Path) -> Vec<LastRun> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            tracing::warn!(
+                "{}: {}",
+                errors::running::failed_to_read(&path.display().to_string()),
+                e
+            );
+            return Vec::new();
+        }
+    };
+
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        tracing::warn!(
+            "{}: {}",
+            errors::running::failed_to_read(&path.display().to_string()),
+            e
+        );
+        Vec::new()
+    })
+}
+
+pub fn save_last_runs(path: &Path, runs: &[LastRun]) -> anyhow::Result<()> {
+    let contents = serde_json::to_string_pretty(runs)?;
+    std::fs::write(path, contents)
+        .with_context(|| errors::running::failed_to_write(&path.display().to_string()))
+}
+
+// A missing file just means nothing was left running. An unreadable one is
+// logged and ignored, since it only costs the chance to adopt.
+pub async fn load(path: &Path) -> Vec<RunningProcess> {
+    let contents = match fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            tracing::warn!(
+                "{}: {}",
+                errors::running::failed_to_read(&path.display().to_string()),
+                e
+            );
+            return Vec::new();
+        }
+    };
+
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        tracing::warn!(
+            "{}: {}",
+            errors::running::failed_to_read(&path.display().to_string()),
+            e
+        );
+        Vec::new()
+    })
+}
+
+pub async fn record(path: &Path, process: RunningProcess) -> anyhow::Result<()> {
+    let mut processes = load(path).await;
+    processes.retain(|entry| entry.id != process.id);
+    processes.push(process);
+    save(path, &processes).await
+}
+
+pub async fn remove(path: &Path, id: TunnelId) -> anyhow::Result<()> {
+    let mut processes = load(path).await;
+    let count = processes.len();
+    processes.retain(|entry| entry.id != id);
+    match processes.len() == count {
+        true => Ok(()),
+        false => save(path, &processes).await,
+    }
+}
+
+pub async fn save(path: &Path, processes: &[RunningProcess]) -> anyhow::Result<()> {
+    if processes.is_empty() {
+        return match fs::remove_file(path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e)
+                .with_context(|| errors::running::failed_to_write(&path.display().to_string())),
+            _ => Ok(()),
+        };
+    }
+
+    let contents = serde_json::to_string_pretty(processes)?;
+    fs::write(path, contents)
+        .await
+        .with_context(|| errors::running::failed_to_write(&path.display().to_string()))
+}
@@ -1,13 +1,16 @@
+use crate::backend::logs::{LogLineFormat, LogOptions, LogRotation};
+use crate::backend::process::StartCheck;
 use crate::errors;
 use anyhow::{Context, ensure};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct TunnelId(Uuid);
 
@@ -23,7 +26,51 @@ impl Default for TunnelId {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum::EnumIter)]
+impl fmt::Display for TunnelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for TunnelId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+// Whether a tunnel's output also goes to one file per stream. The combined
+// log is always written, since the GUI and the start error read it.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, strum::EnumIter,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogMode {
+    #[default]
+    Combined,
+    Split,
+}
+
+impl LogMode {
+    pub fn all() -> impl Iterator<Item = Self> {
+        use strum::IntoEnumIterator;
+        Self::iter()
+    }
+}
+
+impl fmt::Display for LogMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogMode::Combined => write!(f, "Combined"),
+            LogMode::Split => write!(f, "Split"),
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, strum::EnumIter,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum TunnelMode {
     Client,
@@ -31,11 +78,29 @@ pub enum TunnelMode {
 }
 
 impl TunnelMode {
-    #[allow(dead_code)]
     pub fn all() -> impl Iterator<Item = Self> {
         use strum::IntoEnumIterator;
         Self::iter()
     }
+
+    pub fn subcommand(&self) -> &'static str {
+        match self {
+            TunnelMode::Client => "client",
+            TunnelMode::Server => "server",
+        }
+    }
+
+    // wstunnel accepts global flags before the subcommand, so look for the
+    // first token that names one rather than assuming it comes first.
+    pub fn from_cli_args(cli_args: &str) -> Option<Self> {
+        crate::backend::process::parse_cli_args(cli_args)
+            .iter()
+            .find_map(|arg| match arg.as_str() {
+                "client" => Some(TunnelMode::Client),
+                "server" => Some(TunnelMode::Server),
+                _ => None,
+            })
+    }
 }
 
 impl fmt::Display for TunnelMode {
@@ -57,12 +122,52 @@ impl From<u32> for ProcessId {
     }
 }
 
+impl From<ProcessId> for u32 {
+    fn from(pid: ProcessId) -> Self {
+        pid.0
+    }
+}
+
 impl fmt::Display for ProcessId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+// Version reported by `wstunnel --version`, used to tell which features the
+// binary supports. Pre-release suffixes are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WstunnelVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl WstunnelVersion {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let core = version.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(str::parse::<u64>);
+        Some(Self {
+            major: parts.next()?.ok()?,
+            minor: parts.next().transpose().ok()?.unwrap_or(0),
+            patch: parts.next().transpose().ok()?.unwrap_or(0),
+        })
+    }
+
+    // The Rust rewrite in 7.0 introduced the `client`/`server` subcommands.
+    pub fn supports_subcommands(&self) -> bool {
+        self.major >= 7
+    }
+}
+
+impl fmt::Display for WstunnelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+// Serialized as an RFC 3339 string so it stays readable in the config file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Timestamp(SystemTime);
 
@@ -76,12 +181,102 @@ impl Timestamp {
     }
 }
 
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        Self(time)
+    }
+}
+
 impl fmt::Display for Timestamp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", humantime::format_rfc3339(self.0))
     }
 }
 
+impl Serialize for Timestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl JsonSchema for Timestamp {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Timestamp".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "format": "date-time",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        humantime::parse_rfc3339_weak(&s)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// File sizes for display, in binary units with one decimal above bytes,
+// e.g. "512 B" or "3.4 MB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// Compact uptime for display: the two largest units, e.g. "2h 32m" or
+// "3d 4h". Anything under ten seconds is "just now".
+pub fn format_uptime(duration: std::time::Duration) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    let secs = duration.as_secs();
+    match secs {
+        0..10 => "just now".to_string(),
+        10..MINUTE => format!("{}s", secs),
+        MINUTE..HOUR => format!("{}m", secs / MINUTE),
+        HOUR..DAY => format!("{}h {}m", secs / HOUR, secs % HOUR / MINUTE),
+        _ => format!("{}d {}h", secs / DAY, secs % DAY / HOUR),
+    }
+}
+
+// Result of the tunnel's health check. Tunnels without one stay `Unchecked`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelHealth {
+    #[default]
+    Unchecked,
+    Healthy,
+    Unhealthy {
+        error: String,
+    },
+}
+
+impl fmt::Display for TunnelHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunnelHealth::Unchecked => write!(f, "not checked"),
+            TunnelHealth::Healthy => write!(f, "healthy"),
+            TunnelHealth::Unhealthy { error } => write!(f, "unhealthy: {}", error),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum TunnelRuntimeState {
@@ -91,6 +286,10 @@ pub enum TunnelRuntimeState {
         pid: ProcessId,
         started_at: Timestamp,
         log_path: PathBuf,
+        health: TunnelHealth,
+        // What was actually run, with sensitive values masked. Empty when
+        // the OS did not report it for an adopted process.
+        command_line: Vec<String>,
     },
     Failed {
         error: String,
@@ -99,7 +298,266 @@ pub enum TunnelRuntimeState {
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// How many tunnels are configured, running and failed, for the window title
+// and tray without building the full tunnel list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TunnelSummary {
+    pub total: usize,
+    pub running: usize,
+    pub failed: usize,
+}
+
+impl TunnelSummary {
+    pub fn of(states: impl IntoIterator<Item = TunnelRuntimeState>) -> Self {
+        states
+            .into_iter()
+            .fold(Self::default(), |mut summary, state| {
+                summary.total += 1;
+                match &state {
+                    TunnelRuntimeState::Running { .. } => summary.running += 1,
+                    TunnelRuntimeState::Failed { .. } => summary.failed += 1,
+                    TunnelRuntimeState::Stopped | TunnelRuntimeState::Starting => {}
+                }
+                summary
+            })
+    }
+}
+
+// Serializable form of `TunnelRuntimeState` for JSON output. Timestamps are
+// RFC 3339 strings and running tunnels also report their uptime.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum RuntimeStateView {
+    Stopped,
+    Starting,
+    Running {
+        pid: ProcessId,
+        started_at: String,
+        uptime_secs: u64,
+        log_path: PathBuf,
+        health: TunnelHealth,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        command_line: Vec<String>,
+    },
+    Failed {
+        error: String,
+        last_attempt: String,
+        exit_code: Option<i32>,
+    },
+}
+
+impl From<&TunnelRuntimeState> for RuntimeStateView {
+    fn from(state: &TunnelRuntimeState) -> Self {
+        match state {
+            TunnelRuntimeState::Stopped => RuntimeStateView::Stopped,
+            TunnelRuntimeState::Starting => RuntimeStateView::Starting,
+            TunnelRuntimeState::Running {
+                pid,
+                started_at,
+                log_path,
+                health,
+                command_line,
+            } => RuntimeStateView::Running {
+                pid: *pid,
+                started_at: started_at.to_string(),
+                uptime_secs: started_at.elapsed().as_secs(),
+                log_path: log_path.clone(),
+                health: health.clone(),
+                command_line: command_line.clone(),
+            },
+            TunnelRuntimeState::Failed {
+                error,
+                last_attempt,
+                exit_code,
+            } => RuntimeStateView::Failed {
+                error: error.clone(),
+                last_attempt: last_attempt.to_string(),
+                exit_code: *exit_code,
+            },
+        }
+    }
+}
+
+// One line of the headless status snapshot, built from the config and
+// `Backend::get_all_statuses`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TunnelSnapshot {
+    pub id: TunnelId,
+    pub tag: String,
+    pub mode: TunnelMode,
+    pub status: String,
+    pub pid: Option<ProcessId>,
+    pub uptime_secs: Option<u64>,
+}
+
+pub fn status_snapshot(
+    config: &Config,
+    statuses: &[(TunnelId, TunnelRuntimeState)],
+) -> Vec<TunnelSnapshot> {
+    config
+        .tunnels
+        .iter()
+        .map(|tunnel| {
+            let state = statuses
+                .iter()
+                .find(|(id, _)| *id == tunnel.id)
+                .map(|(_, state)| RuntimeStateView::from(state))
+                .unwrap_or(RuntimeStateView::Stopped);
+            let (status, pid, uptime_secs) = match state {
+                RuntimeStateView::Stopped => ("stopped", None, None),
+                RuntimeStateView::Starting => ("starting", None, None),
+                RuntimeStateView::Running {
+                    pid,
+                    uptime_secs,
+                    health: TunnelHealth::Unhealthy { .. },
+                    ..
+                } => ("unhealthy", Some(pid), Some(uptime_secs)),
+                RuntimeStateView::Running {
+                    pid, uptime_secs, ..
+                } => ("running", Some(pid), Some(uptime_secs)),
+                RuntimeStateView::Failed { .. } => ("failed", None, None),
+            };
+            TunnelSnapshot {
+                id: tunnel.id,
+                tag: tunnel.tag.clone(),
+                mode: tunnel.mode,
+                status: status.to_string(),
+                pid,
+                uptime_secs,
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for RuntimeStateView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeStateView::Stopped => write!(f, "stopped"),
+            RuntimeStateView::Starting => write!(f, "starting"),
+            RuntimeStateView::Running {
+                pid,
+                health: health @ TunnelHealth::Unhealthy { .. },
+                ..
+            } => write!(f, "running (PID {}), {}", pid, health),
+            RuntimeStateView::Running { pid, .. } => write!(f, "running (PID {})", pid),
+            RuntimeStateView::Failed { error, .. } => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub enum TunnelEvent {
+    Started {
+        id: TunnelId,
+        pid: ProcessId,
+    },
+    Stopped {
+        id: TunnelId,
+    },
+    Crashed {
+        id: TunnelId,
+        exit_code: Option<i32>,
+    },
+    // The process was spawned but exited or timed out before it was ready.
+    StartFailed {
+        id: TunnelId,
+        error: String,
+    },
+    LogLine {
+        id: TunnelId,
+        line: String,
+    },
+    HealthChanged {
+        id: TunnelId,
+        health: TunnelHealth,
+    },
+}
+
+// Lifetime statistics for one tunnel. They outlive the process, so restarts and
+// crashes accumulate until the tunnel is deleted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TunnelStats {
+    pub total_starts: u64,
+    pub total_crashes: u64,
+    pub cumulative_uptime: std::time::Duration,
+    pub last_exit_code: Option<i32>,
+    pub last_started_at: Option<Timestamp>,
+    pub last_crashed_at: Option<Timestamp>,
+    // When the restarts within `FLAP_WINDOW` happened, oldest first.
+    pub recent_restarts: VecDeque<Timestamp>,
+}
+
+impl TunnelStats {
+    pub fn record_start(&mut self, started_at: Timestamp) {
+        self.total_starts += 1;
+        self.last_started_at = Some(started_at);
+        if self.total_starts > 1 {
+            self.recent_restarts.push_back(started_at);
+        }
+        while self
+            .recent_restarts
+            .front()
+            .is_some_and(|at| at.elapsed() >= crate::constants::FLAP_WINDOW)
+        {
+            self.recent_restarts.pop_front();
+        }
+    }
+
+    pub fn record_exit(&mut self, started_at: Timestamp, exit_code: Option<i32>, crashed: bool) {
+        self.cumulative_uptime += started_at.elapsed();
+        self.last_exit_code = exit_code;
+        if crashed {
+            self.total_crashes += 1;
+            self.last_crashed_at = Some(Timestamp::now());
+        }
+    }
+
+    // Every start after the first one counts as a restart.
+    pub fn restarts(&self) -> u64 {
+        self.total_starts.saturating_sub(1)
+    }
+
+    // Restarts within `FLAP_WINDOW` of now.
+    pub fn restarts_in_window(&self) -> usize {
+        self.recent_restarts
+            .iter()
+            .filter(|at| at.elapsed() < crate::constants::FLAP_WINDOW)
+            .count()
+    }
+
+    pub fn is_flaky(&self) -> bool {
+        self.restarts_in_window() >= crate::constants::FLAP_WARN_RESTARTS
+    }
+
+    pub fn is_flapping(&self) -> bool {
+        self.restarts_in_window() >= crate::constants::FLAP_RESTART_LIMIT
+    }
+
+    // The "restarted N times" warning for a flaky tunnel, None otherwise.
+    pub fn flap_warning(&self) -> Option<String> {
+        self.is_flaky().then(|| {
+            errors::tunnel::flapping(
+                self.restarts_in_window(),
+                &format_uptime(crate::constants::FLAP_WINDOW),
+            )
+        })
+    }
+
+    // Why a crash failed the tunnel: the exit itself, or flapping.
+    pub fn crash_error(&self, tag: &str, exit_code: Option<i32>) -> String {
+        match self.is_flapping() {
+            true => errors::tunnel::too_many_restarts(
+                tag,
+                self.restarts_in_window(),
+                &format_uptime(crate::constants::FLAP_WINDOW),
+            ),
+            false => errors::tunnel::exited_unexpectedly(tag, exit_code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TunnelEntry {
     pub id: TunnelId,
     pub tag: String,
@@ -107,29 +565,437 @@ pub struct TunnelEntry {
     pub cli_args: String,
     pub autostart: bool,
 
+    // Autostart starts tunnels in ascending priority, keeping config order for
+    // ties, and waits `autostart_delay_ms` after starting this one before
+    // starting the next.
+    #[serde(default, skip_serializing_if = "is_default_priority")]
+    pub autostart_priority: i32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autostart_delay_ms: Option<u64>,
+
+    // Tunnels that have to be running before this one; starting it starts
+    // them first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<TunnelId>,
+
+    // Tunnels without a group are listed under `DEFAULT_GROUP`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_directory: Option<PathBuf>,
+
+    // Relative paths are resolved against the config file's directory, which
+    // is also the default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+
+    // Scheduling priority the process is started with; the OS default when
+    // unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<ProcessPriority>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+
+    // Set by the backend when the tunnel is added and edited; starting and
+    // stopping leave them alone. Tunnels from older configs have neither.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<Timestamp>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<Timestamp>,
+
     #[serde(skip)]
     pub runtime_state: Option<TunnelRuntimeState>,
 }
 
-impl TunnelEntry {
+// Periodic TCP connect to an address the tunnel should be serving: the local
+// listen port of a client or the bind port of a server. It catches tunnels
+// whose process is up but no longer accepts connections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HealthCheck {
+    pub target: String,
+
+    #[serde(default = "default_health_check_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl HealthCheck {
     pub fn validate(&self) -> anyhow::Result<()> {
+        let port = self
+            .target
+            .rsplit_once(':')
+            .filter(|(host, _)| !host.trim().is_empty())
+            .and_then(|(_, port)| port.parse::<u16>().ok());
         ensure!(
-            !self.tag.trim().is_empty(),
-            errors::tunnel::validation::TAG_EMPTY
+            port.is_some_and(|port| port != 0),
+            errors::tunnel::validation::health_check_target_invalid(&self.target)
         );
         ensure!(
-            self.tag.len() <= 100,
-            errors::tunnel::validation::tag_too_long(&self.tag)
+            self.interval_secs > 0,
+            errors::tunnel::validation::health_check_interval_invalid(
+                &self.interval_secs.to_string()
+            )
         );
+        Ok(())
+    }
+
+    pub fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.interval_secs)
+    }
+}
+
+// A Unix nice value, from -20 (most favoured) to 19 (least). Windows has
+// priority classes instead, and uses the closest one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct ProcessPriority(#[schemars(range(min = -20, max = 19))] i32);
+
+impl ProcessPriority {
+    pub const HIGHEST: i32 = -20;
+    pub const LOWEST: i32 = 19;
+
+    pub fn nice(self) -> i32 {
+        self.0
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
         ensure!(
-            !self.cli_args.trim().is_empty(),
-            errors::tunnel::validation::CLI_ARGS_EMPTY
+            (Self::HIGHEST..=Self::LOWEST).contains(&self.0),
+            errors::tunnel::validation::priority_out_of_range(self.0)
         );
         Ok(())
     }
+
+    // `None` for the normal class, which needs no creation flag.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn windows_priority_class(self) -> Option<u32> {
+        const IDLE_PRIORITY_CLASS: u32 = 0x0000_0040;
+        const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+        const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+        const HIGH_PRIORITY_CLASS: u32 = 0x0000_0080;
+        match self.0 {
+            15.. => Some(IDLE_PRIORITY_CLASS),
+            5..=14 => Some(BELOW_NORMAL_PRIORITY_CLASS),
+            -4..=4 => None,
+            -14..=-5 => Some(ABOVE_NORMAL_PRIORITY_CLASS),
+            _ => Some(HIGH_PRIORITY_CLASS),
+        }
+    }
+}
+
+impl From<i32> for ProcessPriority {
+    fn from(nice: i32) -> Self {
+        Self(nice)
+    }
+}
+
+impl fmt::Display for ProcessPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    crate::constants::DEFAULT_HEALTH_CHECK_INTERVAL_SECS
+}
+
+fn is_default_priority(priority: &i32) -> bool {
+    *priority == 0
+}
+
+// The part of a tunnel entry a validation error is about, so the edit form
+// can show it next to the right input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TunnelField {
+    Tag,
+    Mode,
+    CliArgs,
+    Autostart,
+    DependsOn,
+    Group,
+    Env,
+    LogDirectory,
+    WorkingDir,
+    Priority,
+    HealthCheck,
+}
+
+impl TunnelEntry {
+    // A tunnel with a fresh ID and every optional setting left unset.
+    pub fn new(tag: impl Into<String>, mode: TunnelMode, cli_args: impl Into<String>) -> Self {
+        Self {
+            id: TunnelId::new(),
+            tag: tag.into(),
+            mode,
+            cli_args: cli_args.into(),
+            autostart: false,
+            autostart_priority: 0,
+            autostart_delay_ms: None,
+            depends_on: Vec::new(),
+            group: None,
+            env: HashMap::new(),
+            log_directory: None,
+            working_dir: None,
+            priority: None,
+            health_check: None,
+            created_at: None,
+            modified_at: None,
+            runtime_state: None,
+        }
+    }
+
+    // Fails with the first of `field_errors`.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some((_, error)) = self.field_errors().into_iter().next() {
+            anyhow::bail!(error);
+        }
+        for problem in self.cli_args_problems() {
+            tracing::warn!("Tunnel '{}': {}", self.tag, problem);
+        }
+        Ok(())
+    }
+
+    // Every validation error, with the field it concerns, in the order
+    // `validate` checks them.
+    pub fn field_errors(&self) -> Vec<(TunnelField, String)> {
+        let mut errors = Vec::new();
+        let mut check = |field: TunnelField, ok: bool, error: &dyn Fn() -> String| {
+            if !ok {
+                errors.push((field, error()));
+            }
+        };
+
+        check(TunnelField::Tag, !self.tag.trim().is_empty(), &|| {
+            errors::tunnel::validation::TAG_EMPTY.to_string()
+        });
+        check(TunnelField::Tag, self.tag.len() <= 100, &|| {
+            errors::tunnel::validation::tag_too_long(&self.tag)
+        });
+        check(
+            TunnelField::CliArgs,
+            !self.cli_args.trim().is_empty(),
+            &|| errors::tunnel::validation::CLI_ARGS_EMPTY.to_string(),
+        );
+        if let Some(args_mode) = TunnelMode::from_cli_args(&self.cli_args) {
+            check(TunnelField::Mode, args_mode == self.mode, &|| {
+                errors::tunnel::validation::mode_mismatch(
+                    self.mode.subcommand(),
+                    args_mode.subcommand(),
+                )
+            });
+        }
+        check(
+            TunnelField::DependsOn,
+            !self.depends_on.contains(&self.id),
+            &|| errors::tunnel::validation::depends_on_itself(&self.tag),
+        );
+        if let Some(ref group) = self.group {
+            check(TunnelField::Group, !group.trim().is_empty(), &|| {
+                errors::tunnel::validation::GROUP_EMPTY.to_string()
+            });
+            check(TunnelField::Group, group.len() <= 100, &|| {
+                errors::tunnel::validation::group_too_long(group)
+            });
+        }
+        for key in self.env.keys() {
+            check(
+                TunnelField::Env,
+                !key.trim().is_empty() && !key.contains('=') && !key.contains('\0'),
+                &|| errors::tunnel::validation::env_key_invalid(key),
+            );
+        }
+        if let Some(Err(e)) = self.priority.map(|priority| priority.validate()) {
+            errors.push((TunnelField::Priority, e.to_string()));
+        }
+        if let Some(Err(e)) = self.health_check.as_ref().map(HealthCheck::validate) {
+            errors.push((TunnelField::HealthCheck, e.to_string()));
+        }
+        errors
+    }
+
+    // Checks against the filesystem, run when a tunnel is added or edited
+    // rather than from `validate`: a directory that is gone for a while must
+    // not make the whole config fail to load.
+    pub fn path_errors(&self, config_dir: &Path) -> Vec<(TunnelField, String)> {
+        let mut errors = Vec::new();
+        // Relative args files may depend on environment variables and are
+        // checked when the tunnel starts.
+        for path in crate::backend::process::parse_cli_args(&self.cli_args)
+            .iter()
+            .filter_map(|arg| crate::backend::process::args_file_path(arg))
+            .filter(|path| path.is_absolute() && !path.is_file())
+        {
+            errors.push((
+                TunnelField::CliArgs,
+                errors::tunnel::args_file_not_found(&path.display().to_string()),
+            ));
+        }
+        if let Some(ref dir) = self.log_directory {
+            let dir = config_dir.join(dir);
+            if !crate::backend::logs::is_directory_writable(&dir) {
+                errors.push((
+                    TunnelField::LogDirectory,
+                    errors::logs::directory_not_writable(&dir.display().to_string()),
+                ));
+            }
+        }
+        if self.working_dir.is_some() {
+            let dir = self.effective_working_dir(config_dir);
+            if !dir.is_dir() {
+                errors.push((
+                    TunnelField::WorkingDir,
+                    errors::tunnel::validation::working_dir_invalid(&dir.display().to_string()),
+                ));
+            }
+        }
+        errors
+    }
+
+    // Likely mistakes in `cli_args`. They are only logged unless
+    // `global.strict_validation` is set, see `Config::validate`.
+    pub fn cli_args_problems(&self) -> Vec<String> {
+        crate::backend::cli_schema::check_cli_args(&self.cli_args)
+    }
+
+    pub fn parsed_endpoints(&self) -> Vec<crate::backend::cli_schema::Endpoint> {
+        crate::backend::cli_schema::parse_endpoints(&self.cli_args)
+    }
+
+    // `host:port` of the first local TCP or HTTP proxy forward of a client.
+    pub fn local_address(&self) -> Option<String> {
+        if self.mode != TunnelMode::Client {
+            return None;
+        }
+        self.parsed_endpoints()
+            .iter()
+            .find_map(|endpoint| endpoint.local_address())
+    }
+
+    pub fn group_name(&self) -> &str {
+        self.group
+            .as_deref()
+            .unwrap_or(crate::constants::DEFAULT_GROUP)
+    }
+
+    pub fn effective_working_dir(&self, config_dir: &Path) -> PathBuf {
+        match &self.working_dir {
+            Some(dir) => config_dir.join(dir),
+            None => config_dir.to_path_buf(),
+        }
+    }
+
+    pub fn effective_log_directory<'a>(&'a self, global: &'a GlobalSettings) -> &'a Path {
+        self.log_directory
+            .as_deref()
+            .unwrap_or(&global.log_directory)
+    }
+}
+
+// Autostart tunnels in the order they are started, each with the delay to wait
+// before starting the next one. The sort is stable, so equal priorities keep
+// their config order.
+pub fn autostart_plan(tunnels: &[TunnelEntry]) -> Vec<(TunnelId, Option<Duration>)> {
+    let mut autostart: Vec<&TunnelEntry> = tunnels.iter().filter(|t| t.autostart).collect();
+    autostart.sort_by_key(|t| t.autostart_priority);
+    autostart
+        .into_iter()
+        .map(|t| (t.id, t.autostart_delay_ms.map(Duration::from_millis)))
+        .collect()
+}
+
+// Dependencies of `id`, direct and indirect, in the order they have to be
+// started. Unknown IDs are skipped, and a cycle, which `Config::validate`
+// refuses anyway, doesn't recurse forever.
+pub fn dependency_start_order(tunnels: &[TunnelEntry], id: TunnelId) -> Vec<TunnelId> {
+    fn visit(
+        tunnels: &[TunnelEntry],
+        id: TunnelId,
+        visited: &mut HashSet<TunnelId>,
+        order: &mut Vec<TunnelId>,
+    ) {
+        let Some(tunnel) = tunnels.iter().find(|t| t.id == id) else {
+            return;
+        };
+        for dependency in &tunnel.depends_on {
+            if visited.insert(*dependency) {
+                visit(tunnels, *dependency, visited, order);
+            }
+        }
+        order.push(id);
+    }
+
+    let mut order = Vec::new();
+    visit(tunnels, id, &mut HashSet::from([id]), &mut order);
+    order.retain(|dependency| *dependency != id);
+    order
+}
+
+// Tunnels that depend on `id`, direct and indirect, in the order they have to
+// be stopped: every tunnel comes before the ones it depends on.
+pub fn dependents_stop_order(tunnels: &[TunnelEntry], id: TunnelId) -> Vec<TunnelId> {
+    fn visit(
+        tunnels: &[TunnelEntry],
+        id: TunnelId,
+        visited: &mut HashSet<TunnelId>,
+        order: &mut Vec<TunnelId>,
+    ) {
+        for dependent in tunnels.iter().filter(|t| t.depends_on.contains(&id)) {
+            if visited.insert(dependent.id) {
+                visit(tunnels, dependent.id, visited, order);
+                order.push(dependent.id);
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    visit(tunnels, id, &mut HashSet::from([id]), &mut order);
+    order
+}
+
+// Resolves a user-supplied tunnel reference, as given on the command line.
+// An exact ID match wins; otherwise the tag must match exactly one tunnel.
+pub fn find_tunnel_by_tag<'a>(
+    tunnels: &'a [TunnelEntry],
+    tag_or_id: &str,
+) -> anyhow::Result<&'a TunnelEntry> {
+    if let Ok(id) = tag_or_id.parse::<TunnelId>()
+        && let Some(tunnel) = tunnels.iter().find(|t| t.id == id)
+    {
+        return Ok(tunnel);
+    }
+
+    let mut matches = tunnels.iter().filter(|t| t.tag == tag_or_id);
+    match (matches.next(), matches.next()) {
+        (Some(tunnel), None) => Ok(tunnel),
+        (Some(_), Some(_)) => Err(anyhow::anyhow!(errors::tunnel::ambiguous_tag(tag_or_id))),
+        (None, _) => Err(anyhow::anyhow!(errors::tunnel::not_found(tag_or_id))),
+    }
+}
+
+// Tag for a tunnel saved without one: the host a client connects to, or
+// "tunnel-" and the start of its ID otherwise. A numeric suffix keeps it
+// unique among the other tunnels.
+pub fn generate_tag(id: TunnelId, cli_args: &str, tunnels: &[TunnelEntry]) -> String {
+    let base = crate::backend::cli_schema::client_server_host(cli_args)
+        .unwrap_or_else(|| format!("tunnel-{}", &id.to_string()[..8]));
+    let taken = |tag: &str| tunnels.iter().any(|t| t.id != id && t.tag == tag);
+
+    let mut tag = base.clone();
+    let mut suffix = 1;
+    while taken(&tag) {
+        suffix += 1;
+        tag = format!("{}-{}", base, suffix);
+    }
+    tag
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GlobalSettings {
     #[serde(default)]
     pub wstunnel_binary_path: Option<PathBuf>,
@@ -139,6 +1005,90 @@ pub struct GlobalSettings {
 
     #[serde(default)]
     pub log_retention_days: Option<u32>,
+
+    #[serde(default)]
+    pub fail_on_missing_env_vars: bool,
+
+    #[serde(default)]
+    pub log_compress_days: Option<u32>,
+
+    #[serde(default)]
+    pub max_log_size_mb: Option<u64>,
+
+    #[serde(default = "default_max_log_files")]
+    pub max_log_files: u32,
+
+    // Leave tunnels running when the manager exits; the next run adopts them.
+    #[serde(default)]
+    pub detach_on_exit: bool,
+
+    #[serde(default = "default_sensitive_flags")]
+    pub sensitive_flags: Vec<String>,
+
+    // Refuse tunnels whose CLI arguments fail the flag checks instead of
+    // only logging a warning.
+    #[serde(default)]
+    pub strict_validation: bool,
+
+    // Name of one of iced's built-in GUI themes, e.g. "Dark". Unset follows
+    // the OS dark-mode preference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+
+    // Write log timestamps in UTC instead of local time.
+    #[serde(default)]
+    pub log_timestamp_utc: bool,
+
+    // Replaces the `[{ts}] [{stream}] ` prefix of each log line; `{tag}` is
+    // also available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_line_prefix: Option<String>,
+
+    // `split` additionally writes `.out.log` and `.err.log` files.
+    #[serde(default)]
+    pub log_mode: LogMode,
+
+    // How long a starting tunnel has to print a line matching
+    // `ready_pattern`, or without a pattern, to stay up. A tunnel that does
+    // not make it is stopped and its start fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_timeout_seconds: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_pattern: Option<String>,
+
+    // Scheme the GUI's "Open" button uses for a tunnel's local port; wstunnel
+    // can't tell whether what it forwards speaks http or https.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_port_scheme: Option<String>,
+
+    // How many of a tunnel's latest stderr lines are kept in memory for the
+    // detail screen and the API.
+    #[serde(default = "default_stderr_buffer_lines")]
+    pub stderr_buffer_lines: usize,
+
+    // Lines a tunnel may log per second; the rest are dropped and counted so
+    // a chatty tunnel can't keep a core busy. Unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_max_lines_per_second: Option<u32>,
+
+    // How often log retention and compression run while the manager is up.
+    #[serde(default = "default_cleanup_interval_hours")]
+    pub cleanup_interval_hours: u64,
+
+    // Stopping a tunnel also stops the running tunnels that depend on it.
+    #[serde(default)]
+    pub stop_dependents: bool,
+
+    // How often the GUI reloads tunnel statuses on its own. Unset or 0 only
+    // refreshes after an action or a tunnel event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui_refresh_seconds: Option<u32>,
+
+    // Refuses adding, editing and deleting tunnels and changing settings,
+    // like `--read-only`. Only editing the file by hand turns it off again.
+    #[serde(default)]
+    pub locked: bool,
 }
 
 impl Default for GlobalSettings {
@@ -147,6 +1097,26 @@ impl Default for GlobalSettings {
             wstunnel_binary_path: None,
             log_directory: default_log_directory(),
             log_retention_days: None,
+            fail_on_missing_env_vars: false,
+            log_compress_days: None,
+            max_log_size_mb: None,
+            max_log_files: default_max_log_files(),
+            detach_on_exit: false,
+            sensitive_flags: default_sensitive_flags(),
+            strict_validation: false,
+            theme: None,
+            log_timestamp_utc: false,
+            log_line_prefix: None,
+            log_mode: LogMode::Combined,
+            start_timeout_seconds: None,
+            ready_pattern: None,
+            local_port_scheme: None,
+            stderr_buffer_lines: default_stderr_buffer_lines(),
+            log_max_lines_per_second: None,
+            cleanup_interval_hours: default_cleanup_interval_hours(),
+            stop_dependents: false,
+            ui_refresh_seconds: None,
+            locked: false,
         }
     }
 }
@@ -155,7 +1125,42 @@ fn default_log_directory() -> PathBuf {
     crate::constants::default_log_directory()
 }
 
+fn default_max_log_files() -> u32 {
+    5
+}
+
+fn default_cleanup_interval_hours() -> u64 {
+    crate::constants::DEFAULT_CLEANUP_INTERVAL_HOURS
+}
+
+fn default_stderr_buffer_lines() -> usize {
+    crate::constants::DEFAULT_STDERR_BUFFER_LINES
+}
+
+fn default_sensitive_flags() -> Vec<String> {
+    crate::constants::DEFAULT_SENSITIVE_FLAGS
+        .iter()
+        .map(|flag| flag.to_string())
+        .collect()
+}
+
 impl GlobalSettings {
+    // Only checked when the settings are saved: a binary that lost its
+    // executable bit must not make the whole config fail to load.
+    pub fn check_binary_executable(&self) -> anyhow::Result<()> {
+        #[cfg(unix)]
+        if let Some(ref path) = self.wstunnel_binary_path {
+            use std::os::unix::fs::PermissionsExt;
+            let executable = std::fs::metadata(path)
+                .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0);
+            ensure!(
+                executable,
+                errors::binary::permission_denied(&path.display().to_string())
+            );
+        }
+        Ok(())
+    }
+
     pub fn validate(&self) -> anyhow::Result<()> {
         if let Some(ref path) = self.wstunnel_binary_path {
             ensure!(
@@ -166,16 +1171,140 @@ impl GlobalSettings {
 
         if let Some(days) = self.log_retention_days {
             ensure!(
-                (1..=3650).contains(&days),
+                (1..=crate::constants::MAX_LOG_AGE_DAYS).contains(&days),
                 errors::logs::retention_invalid(days)
             );
         }
 
+        if let Some(days) = self.log_compress_days {
+            ensure!(
+                (1..=crate::constants::MAX_LOG_AGE_DAYS).contains(&days),
+                errors::logs::compress_days_invalid(days)
+            );
+            if let Some(retention_days) = self.log_retention_days {
+                ensure!(
+                    days < retention_days,
+                    errors::logs::compress_after_retention(days, retention_days)
+                );
+            }
+        }
+
+        if let Some(size_mb) = self.max_log_size_mb {
+            ensure!(
+                (1..=10240).contains(&size_mb),
+                errors::logs::max_size_invalid(size_mb)
+            );
+        }
+
+        ensure!(
+            (1..=100).contains(&self.max_log_files),
+            errors::logs::max_files_invalid(self.max_log_files)
+        );
+
+        ensure!(
+            (1..=crate::constants::MAX_STDERR_BUFFER_LINES).contains(&self.stderr_buffer_lines),
+            errors::logs::stderr_buffer_invalid(self.stderr_buffer_lines)
+        );
+
+        ensure!(
+            (1..=crate::constants::MAX_CLEANUP_INTERVAL_HOURS)
+                .contains(&self.cleanup_interval_hours),
+            errors::logs::cleanup_interval_invalid(self.cleanup_interval_hours)
+        );
+
+        if let Some(limit) = self.log_max_lines_per_second {
+            ensure!(limit > 0, errors::logs::LINE_RATE_INVALID);
+        }
+
+        if let Some(prefix) = &self.log_line_prefix
+            && let Some(name) = crate::backend::logs::unknown_placeholder(prefix)
+        {
+            anyhow::bail!(errors::logs::unknown_placeholder(&name));
+        }
+
+        if let Some(seconds) = self.start_timeout_seconds {
+            ensure!(
+                (1..=crate::constants::MAX_START_TIMEOUT_SECS).contains(&seconds),
+                errors::process::start_timeout_invalid(seconds)
+            );
+        }
+
+        if let Some(pattern) = &self.ready_pattern
+            && let Err(e) = regex::Regex::new(pattern)
+        {
+            anyhow::bail!(errors::process::ready_pattern_invalid(&e.to_string()));
+        }
+
+        if let Some(scheme) = &self.local_port_scheme {
+            ensure!(
+                scheme == "http" || scheme == "https",
+                errors::tunnel::local_port_scheme_invalid(scheme)
+            );
+        }
+
         Ok(())
     }
+
+    pub fn start_check(&self) -> StartCheck {
+        let ready_pattern = self
+            .ready_pattern
+            .as_deref()
+            .and_then(|p| regex::Regex::new(p).ok());
+        let seconds = match ready_pattern {
+            Some(_) => Some(
+                self.start_timeout_seconds
+                    .unwrap_or(crate::constants::DEFAULT_START_TIMEOUT_SECS),
+            ),
+            None => self.start_timeout_seconds,
+        };
+        StartCheck {
+            timeout: seconds.map_or(
+                crate::constants::EARLY_EXIT_GRACE,
+                std::time::Duration::from_secs,
+            ),
+            ready_pattern,
+        }
+    }
+
+    pub fn cleanup_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.cleanup_interval_hours * 60 * 60)
+    }
+
+    // None when the GUI should not poll. Values below the minimum are raised
+    // to it rather than rejected.
+    pub fn ui_refresh_interval(&self) -> Option<std::time::Duration> {
+        self.ui_refresh_seconds
+            .filter(|&seconds| seconds > 0)
+            .map(|seconds| seconds.max(crate::constants::MIN_UI_REFRESH_SECS))
+            .map(|seconds| std::time::Duration::from_secs(seconds.into()))
+    }
+
+    pub fn log_options(&self) -> LogOptions {
+        LogOptions {
+            rotation: self.log_rotation(),
+            line_format: self.log_line_format(),
+            mode: self.log_mode,
+            stderr_buffer_lines: self.stderr_buffer_lines,
+            max_lines_per_second: self.log_max_lines_per_second,
+        }
+    }
+
+    pub fn log_line_format(&self) -> LogLineFormat {
+        LogLineFormat {
+            utc: self.log_timestamp_utc,
+            prefix: self.log_line_prefix.clone(),
+        }
+    }
+
+    pub fn log_rotation(&self) -> Option<LogRotation> {
+        self.max_log_size_mb.map(|size_mb| LogRotation {
+            max_bytes: size_mb * 1024 * 1024,
+            max_files: self.max_log_files,
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     #[serde(default = "default_version")]
     pub version: u32,
@@ -187,8 +1316,10 @@ pub struct Config {
     pub tunnels: Vec<TunnelEntry>,
 }
 
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
 fn default_version() -> u32 {
-    1
+    CURRENT_CONFIG_VERSION
 }
 
 impl Default for Config {
@@ -204,7 +1335,7 @@ impl Default for Config {
 impl Config {
     pub fn validate(&self) -> anyhow::Result<()> {
         ensure!(
-            self.version == 1,
+            self.version == CURRENT_CONFIG_VERSION,
             errors::config::unsupported_version(self.version)
         );
 
@@ -217,12 +1348,89 @@ impl Config {
             tunnel
                 .validate()
                 .with_context(|| errors::tunnel::validation::failed(&tunnel.tag))?;
+            if self.global.strict_validation
+                && let Some(problem) = tunnel.cli_args_problems().first()
+            {
+                return Err(anyhow::anyhow!(
+                    errors::tunnel::validation::strict_check_failed(problem)
+                ))
+                .with_context(|| errors::tunnel::validation::failed(&tunnel.tag));
+            }
         }
 
+        self.validate_dependencies()?;
+
         self.global
             .validate()
             .context(errors::config::GLOBAL_VALIDATION_FAILED)?;
 
         Ok(())
     }
+
+    // Every dependency has to name a tunnel in the config, and following them
+    // must never lead back to where it started.
+    fn validate_dependencies(&self) -> anyhow::Result<()> {
+        for tunnel in &self.tunnels {
+            for dependency in &tunnel.depends_on {
+                ensure!(
+                    self.tunnels.iter().any(|t| t.id == *dependency),
+                    errors::tunnel::validation::dependency_not_found(
+                        &tunnel.tag,
+                        &dependency.to_string()
+                    )
+                );
+            }
+        }
+
+        let mut checked = HashSet::new();
+        for tunnel in &self.tunnels {
+            self.check_dependency_cycle(&mut vec![tunnel], &mut checked)?;
+        }
+        Ok(())
+    }
+
+    // Depth-first walk keeping the current chain of dependencies on `path`;
+    // reaching a tunnel that is already on it closes a cycle.
+    fn check_dependency_cycle<'a>(
+        &'a self,
+        path: &mut Vec<&'a TunnelEntry>,
+        checked: &mut HashSet<TunnelId>,
+    ) -> anyhow::Result<()> {
+        let Some(tunnel) = path.last().copied() else {
+            return Ok(());
+        };
+        if checked.contains(&tunnel.id) {
+            return Ok(());
+        }
+        for dependency in &tunnel.depends_on {
+            let Some(dependency) = self.tunnels.iter().find(|t| t.id == *dependency) else {
+                continue;
+            };
+            if let Some(start) = path.iter().position(|t| t.id == dependency.id) {
+                let mut cycle: Vec<&str> = path[start..].iter().map(|t| t.tag.as_str()).collect();
+                cycle.push(&dependency.tag);
+                anyhow::bail!(errors::tunnel::validation::dependency_cycle(
+                    &cycle.join(" -> ")
+                ));
+            }
+            path.push(dependency);
+            self.check_dependency_cycle(path, checked)?;
+            path.pop();
+        }
+        checked.insert(tunnel.id);
+        Ok(())
+    }
+
+    // The global log directory followed by any distinct per-tunnel overrides.
+    pub fn log_directories(&self) -> Vec<PathBuf> {
+        let mut directories = vec![self.global.log_directory.clone()];
+        for tunnel in &self.tunnels {
+            if let Some(ref dir) = tunnel.log_directory
+                && !directories.contains(dir)
+            {
+                directories.push(dir.clone());
+            }
+        }
+        directories
+    }
 }
@@ -0,0 +1,317 @@
+// Carries the comments of a hand-edited YAML config over to the file that
+// replaces it. Saving serializes the config from scratch, which drops them, so
+// they are collected from the old file by the path of the key or list item
+// they sit above or beside, and put back wherever that path appears in the
+// new one. Tunnels are matched by `id`, other list items by position.
+// Comments whose setting or tunnel is gone are dropped with it.
+//
+// This only follows the block style `serde_yaml` writes and people usually
+// hand-write. Lines it can't place, such as multi-line plain scalars, are
+// left alone and comments next to them are kept with the following key.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+struct Comments {
+    header: Vec<String>,
+    // Comment and blank lines above a key or item, without their indentation.
+    above: HashMap<String, Vec<String>>,
+    // The comment after a value, with the whitespace before it.
+    beside: HashMap<String, String>,
+    footer: Vec<String>,
+}
+
+enum Line<'a> {
+    // A comment or blank line.
+    Filler,
+    // A key, or the start of a list item.
+    Node {
+        path: String,
+        comment: Option<&'a str>,
+    },
+    // Part of a block scalar, or anything not understood.
+    Content,
+}
+
+struct Frame {
+    indent: usize,
+    path: String,
+    // List items seen directly under this key so far.
+    items: usize,
+    is_item: bool,
+}
+
+// The old file's comments on `serialized`, with `CONFIG_HEADER` on top.
+pub fn carry_over(previous: &str, serialized: &str) -> String {
+    let comments = Comments::collect(previous);
+    let mut output: Vec<String> = Vec::new();
+
+    let header = crate::constants::CONFIG_HEADER;
+    if comments.header.first().map(String::as_str) != header.lines().next() {
+        output.extend(header.lines().map(str::to_string));
+    }
+    output.extend(comments.header.iter().cloned());
+
+    let lines: Vec<&str> = serialized.lines().collect();
+    for (line, kind) in lines.iter().zip(classify(&lines)) {
+        let Line::Node { path, .. } = kind else {
+            output.push(line.to_string());
+            continue;
+        };
+        let indent = &line[..indent_of(line)];
+        if let Some(above) = comments.above.get(&path) {
+            output.extend(above.iter().map(|comment| match comment.is_empty() {
+                true => String::new(),
+                false => format!("{}{}", indent, comment),
+            }));
+        }
+        match comments.beside.get(&path) {
+            Some(comment) => output.push(format!("{}{}", line, comment)),
+            None => output.push(line.to_string()),
+        }
+    }
+
+    output.extend(comments.footer.iter().cloned());
+    output.join("\n") + "\n"
+}
+
+impl Comments {
+    fn collect(contents: &str) -> Self {
+        let lines: Vec<&str> = contents.lines().collect();
+        let mut comments = Comments::default();
+        let mut pending: Vec<String> = Vec::new();
+        let mut in_body = false;
+
+        for (line, kind) in lines.iter().zip(classify(&lines)) {
+            match kind {
+                Line::Filler => pending.push(line.trim().to_string()),
+                Line::Node { path, comment } => {
+                    if !in_body {
+                        comments.header = std::mem::take(&mut pending);
+                        in_body = true;
+                    } else if !pending.is_empty() {
+                        comments
+                            .above
+                            .insert(path.clone(), std::mem::take(&mut pending));
+                    }
+                    if let Some(comment) = comment {
+                        comments.beside.insert(path, comment.to_string());
+                    }
+                }
+                Line::Content => {}
+            }
+        }
+
+        while pending.last().is_some_and(String::is_empty) {
+            pending.pop();
+        }
+        match in_body {
+            true => comments.footer = pending,
+            false => comments.header = pending,
+        }
+        comments
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn classify<'a>(lines: &[&'a str]) -> Vec<Line<'a>> {
+    let mut stack = vec![Frame {
+        indent: 0,
+        path: String::new(),
+        items: 0,
+        is_item: false,
+    }];
+    // Lines indented deeper than this belong to a block scalar.
+    let mut block_scalar: Option<usize> = None;
+    let mut classified = Vec::with_capacity(lines.len());
+
+    for (index, line) in lines.iter().enumerate() {
+        let content = line.trim();
+        let indent = indent_of(line);
+
+        if let Some(column) = block_scalar {
+            if content.is_empty() || indent > column {
+                classified.push(Line::Content);
+                continue;
+            }
+            block_scalar = None;
+        }
+        if content.is_empty() || content.starts_with('#') {
+            classified.push(Line::Filler);
+            continue;
+        }
+
+        if content == "-" || content.starts_with("- ") {
+            // A list sits at its key's indentation or deeper, so a key at the
+            // same column is the list's parent rather than a sibling.
+            while stack.len() > 1 {
+                let top = stack.last().unwrap();
+                if top.indent > indent || (top.is_item && top.indent == indent) {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+            let rest = content[1..].trim_start();
+            let key_column = indent + content.len() - rest.len();
+            let parent = stack.last_mut().unwrap();
+            let segment = match item_id(lines, index, indent, key_column, rest) {
+                Some(id) => format!("[id={}]", id),
+                None => format!("[{}]", parent.items),
+            };
+            parent.items += 1;
+            let path = format!("{}{}", parent.path, segment);
+            stack.push(Frame {
+                indent,
+                path: path.clone(),
+                items: 0,
+                is_item: true,
+            });
+
+            // The item's first key can share its line.
+            let (value, comment, column) = match split_key(rest) {
+                Some((key, value)) => {
+                    stack.push(Frame {
+                        indent: key_column,
+                        path: format!("{}.{}", path, key),
+                        items: 0,
+                        is_item: false,
+                    });
+                    let (value, comment) = split_comment(value);
+                    (value, comment, key_column)
+                }
+                None => {
+                    let (value, comment) = split_comment(rest);
+                    (value, comment, indent)
+                }
+            };
+            if is_block_scalar(value) {
+                block_scalar = Some(column);
+            }
+            classified.push(Line::Node { path, comment });
+            continue;
+        }
+
+        let Some((key, value)) = split_key(content) else {
+            classified.push(Line::Content);
+            continue;
+        };
+        while stack.len() > 1 && stack.last().unwrap().indent >= indent {
+            stack.pop();
+        }
+        let parent = stack.last().unwrap();
+        let path = match parent.path.is_empty() {
+            true => key,
+            false => format!("{}.{}", parent.path, key),
+        };
+        stack.push(Frame {
+            indent,
+            path: path.clone(),
+            items: 0,
+            is_item: false,
+        });
+        let (value, comment) = split_comment(value);
+        if is_block_scalar(value) {
+            block_scalar = Some(indent);
+        }
+        classified.push(Line::Node { path, comment });
+    }
+    classified
+}
+
+// The `id` of the list item starting on `lines[start]`, from its first line or
+// the ones below it.
+fn item_id(
+    lines: &[&str],
+    start: usize,
+    dash_column: usize,
+    key_column: usize,
+    first: &str,
+) -> Option<String> {
+    let id_of = |content: &str| match split_key(content) {
+        Some((key, value)) if key == "id" => {
+            let id = split_comment(value).0.trim_matches(['"', '\'']);
+            (!id.is_empty()).then(|| id.to_string())
+        }
+        _ => None,
+    };
+    if let Some(id) = id_of(first) {
+        return Some(id);
+    }
+    for line in &lines[start + 1..] {
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = indent_of(line);
+        if indent <= dash_column {
+            break;
+        }
+        if indent == key_column
+            && let Some(id) = id_of(content)
+        {
+            return Some(id);
+        }
+    }
+    None
+}
+
+// Splits `key: value` at the first colon outside quotes that is followed by a
+// space or ends the line. Flow collections and comments have no key.
+fn split_key(content: &str) -> Option<(String, &str)> {
+    if content.starts_with(['{', '[', '#']) {
+        return None;
+    }
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (index, c) in content.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if index == 0 => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => return None,
+            (None, ':') => {
+                let rest = &content[index + 1..];
+                if rest.is_empty() || rest.starts_with([' ', '\t']) {
+                    let key = content[..index].trim().trim_matches(['"', '\'']);
+                    return (!key.is_empty()).then(|| (key.to_string(), rest));
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+// Splits a value from the comment after it. The comment keeps the whitespace
+// in front of it so it goes back exactly where it was.
+fn split_comment(value: &str) -> (&str, Option<&str>) {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    let leading = value.len() - value.trim_start().len();
+    for (index, c) in value.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if index == leading => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => {
+                let start = value[..index].trim_end().len();
+                return (value[..start].trim(), Some(&value[start..]));
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    (value.trim(), None)
+}
+
+// `|`, `>-`, `|2` and the like, which put the value on the following lines.
+fn is_block_scalar(value: &str) -> bool {
+    let mut chars = value.chars();
+    matches!(chars.next(), Some('|' | '>'))
+        && chars.all(|c| matches!(c, '-' | '+') || c.is_ascii_digit())
+}
@@ -0,0 +1,284 @@
+// One-shot subcommands for scripting. They act on the local config, or on a
+// running headless instance when `--api-port` points at its HTTP API.
+
+use crate::api::{ApiClient, TunnelView};
+use crate::backend::SharedBackend;
+use crate::backend::types::{
+    RuntimeStateView, TunnelEntry, TunnelEvent, TunnelId, find_tunnel_by_tag, format_uptime,
+};
+use crate::errors;
+use clap::Subcommand;
+
+#[derive(clap::Args, Debug, Clone, Copy, Default)]
+pub struct CommandOptions {
+    #[arg(long, help = "Print JSON instead of text")]
+    pub json: bool,
+
+    #[arg(
+        long,
+        help = "Send the command to the HTTP API of a headless instance on this port"
+    )]
+    pub api_port: Option<u16>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    #[command(about = "List configured tunnels and their status")]
+    List {
+        #[command(flatten)]
+        options: CommandOptions,
+    },
+
+    #[command(about = "Start a tunnel by tag or ID; without --api-port it runs until Ctrl+C")]
+    Start {
+        tunnel: String,
+        #[command(flatten)]
+        options: CommandOptions,
+    },
+
+    #[command(about = "Stop a tunnel by tag or ID on a running headless instance")]
+    Stop {
+        tunnel: String,
+        #[command(flatten)]
+        options: CommandOptions,
+    },
+
+    #[command(about = "Print the command a tunnel would run, without starting it")]
+    Preview { tunnel: String },
+
+    #[command(about = "Show the status of one tunnel, or of all tunnels")]
+    Status {
+        tunnel: Option<String>,
+        #[command(flatten)]
+        options: CommandOptions,
+    },
+
+    #[command(about = "Print the JSON Schema of the config file")]
+    Schema,
+}
+
+impl Command {
+    pub fn options(&self) -> CommandOptions {
+        match self {
+            Command::List { options }
+            | Command::Start { options, .. }
+            | Command::Stop { options, .. }
+            | Command::Status { options, .. } => *options,
+            // Reads the local config only, so it never goes through the API.
+            Command::Preview { .. } => CommandOptions::default(),
+            Command::Schema => CommandOptions::default(),
+        }
+    }
+}
+
+// Where a command is carried out: this process's own backend, or the HTTP
+// API of another instance.
+pub enum Target {
+    Local(SharedBackend),
+    Api(ApiClient),
+}
+
+impl Target {
+    async fn list_tunnels(&self) -> anyhow::Result<Vec<TunnelView>> {
+        match self {
+            Target::Local(backend) => {
+                let tunnels = backend.lock().await.list_tunnels();
+                Ok(tunnels.into_iter().map(TunnelView::from).collect())
+            }
+            Target::Api(client) => client.list_tunnels().await,
+        }
+    }
+
+    async fn resolve(&self, tag_or_id: &str) -> anyhow::Result<TunnelView> {
+        let views = self.list_tunnels().await?;
+        let tunnels: Vec<TunnelEntry> = views.iter().map(|view| view.tunnel.clone()).collect();
+        let id = find_tunnel_by_tag(&tunnels, tag_or_id)?.id;
+        views
+            .into_iter()
+            .find(|view| view.tunnel.id == id)
+            .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(tag_or_id)))
+    }
+}
+
+pub async fn run(command: Command, target: Target) -> anyhow::Result<()> {
+    let json = command.options().json;
+
+    match command {
+        Command::List { .. } => {
+            let tunnels = target.list_tunnels().await?;
+            print_tunnels(&tunnels, json)
+        }
+        Command::Status { tunnel: None, .. } => {
+            let tunnels = target.list_tunnels().await?;
+            print_tunnels(&tunnels, json)
+        }
+        Command::Status {
+            tunnel: Some(tunnel),
+            ..
+        } => {
+            let view = target.resolve(&tunnel).await?;
+            print_status(&view.status, json)
+        }
+        Command::Start { tunnel, .. } => {
+            let id = target.resolve(&tunnel).await?.tunnel.id;
+            match target {
+                Target::Api(client) => {
+                    let view = client.start_tunnel(id).await?;
+                    print_tunnels(&[view], json)
+                }
+                Target::Local(backend) => run_in_foreground(backend, id, json).await,
+            }
+        }
+        Command::Preview { tunnel } => {
+            let Target::Local(backend) = &target else {
+                anyhow::bail!(errors::cli::PREVIEW_IS_LOCAL);
+            };
+            let id = target.resolve(&tunnel).await?.tunnel.id;
+            println!("{}", backend.lock().await.preview_command(id)?);
+            Ok(())
+        }
+        Command::Stop { tunnel, .. } => {
+            let Target::Api(client) = &target else {
+                anyhow::bail!(errors::cli::STOP_REQUIRES_API);
+            };
+            let id = target.resolve(&tunnel).await?.tunnel.id;
+            let view = client.stop_tunnel(id).await?;
+            print_tunnels(&[view], json)
+        }
+        Command::Schema => print_schema(),
+    }
+}
+
+// Needs neither a config nor a backend, so `main` runs it before setting
+// either up.
+pub fn print_schema() -> anyhow::Result<()> {
+    let schema = crate::backend::config::config_schema();
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+// Without a daemon to hand the process to, the tunnel lives as long as this
+// invocation: it is stopped again on Ctrl+C or reported if it exits.
+async fn run_in_foreground(backend: SharedBackend, id: TunnelId, json: bool) -> anyhow::Result<()> {
+    let exit = run_until_exit(&backend, id, json).await;
+
+    // Stopped explicitly on every way out: `shutdown` alone would leave the
+    // tunnel running when `global.detach_on_exit` is set.
+    for (tunnel_id, result) in backend.lock().await.stop_all().await {
+        if let Err(e) = result {
+            tracing::warn!("Failed to stop tunnel {:?}: {}", tunnel_id, e);
+        }
+    }
+    backend.lock().await.shutdown().await?;
+    exit
+}
+
+async fn run_until_exit(backend: &SharedBackend, id: TunnelId, json: bool) -> anyhow::Result<()> {
+    let mut events = backend.lock().await.subscribe();
+    backend.lock().await.start_tunnel(id).await?;
+    wait_until_started(&mut events, id).await?;
+
+    let view = local_view(backend, id).await?;
+    print_tunnels(&[view], json)?;
+
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            Ok(())
+        }
+        exit_code = wait_for_crash(&mut events, id) => {
+            let tag = local_view(backend, id).await?.tunnel.tag;
+            Err(anyhow::anyhow!(errors::tunnel::exited_unexpectedly(&tag, exit_code)))
+        }
+    }
+}
+
+async fn local_view(backend: &SharedBackend, id: TunnelId) -> anyhow::Result<TunnelView> {
+    backend
+        .lock()
+        .await
+        .list_tunnels()
+        .into_iter()
+        .find(|tunnel| tunnel.id == id)
+        .map(TunnelView::from)
+        .ok_or_else(|| anyhow::anyhow!(errors::tunnel::not_found(&id.to_string())))
+}
+
+async fn wait_until_started(
+    events: &mut tokio::sync::broadcast::Receiver<TunnelEvent>,
+    id: TunnelId,
+) -> anyhow::Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        match events.recv().await {
+            Ok(TunnelEvent::Started { id: started, .. }) if started == id => return Ok(()),
+            Ok(TunnelEvent::StartFailed { id: failed, error }) if failed == id => {
+                return Err(anyhow::anyhow!(error));
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+async fn wait_for_crash(
+    events: &mut tokio::sync::broadcast::Receiver<TunnelEvent>,
+    id: TunnelId,
+) -> Option<i32> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        match events.recv().await {
+            Ok(TunnelEvent::Crashed {
+                id: crashed,
+                exit_code,
+            }) if crashed == id => return exit_code,
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+fn print_tunnels(tunnels: &[TunnelView], json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(tunnels)?);
+        return Ok(());
+    }
+
+    if tunnels.is_empty() {
+        println!("No tunnels configured");
+        return Ok(());
+    }
+
+    for view in tunnels {
+        println!(
+            "{:<24} {:<8} {:<36} {}",
+            view.tunnel.tag,
+            view.tunnel.mode.to_string(),
+            view.tunnel.id.to_string(),
+            describe(&view.status)
+        );
+    }
+    Ok(())
+}
+
+fn print_status(status: &RuntimeStateView, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(status)?);
+    } else {
+        println!("{}", describe(status));
+    }
+    Ok(())
+}
+
+fn describe(status: &RuntimeStateView) -> String {
+    match status {
+        RuntimeStateView::Running { uptime_secs, .. } => format!(
+            "{}, up {}",
+            status,
+            format_uptime(std::time::Duration::from_secs(*uptime_secs))
+        ),
+        _ => status.to_string(),
+    }
+}
@@ -1,6 +1,113 @@
 use std::path::PathBuf;
 
 pub const APP_TITLE: &str = "wstunnel Manager";
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Logical pixels, width by height.
+pub const DEFAULT_WINDOW_SIZE: (f32, f32) = (1200.0, 800.0);
+pub const MIN_WINDOW_SIZE: (f32, f32) = (400.0, 300.0);
+
+// How long a toast notification stays up, and how many are shown at once.
+pub const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(4);
+pub const MAX_TOASTS: usize = 5;
+
+// Every log line is published, so leave room for bursts of output before a
+// slow subscriber starts lagging.
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+pub const DEFAULT_GROUP: &str = "Default";
+
+// Written at the top of YAML config files. Other comments in the file are kept
+// when the manager saves it.
+pub const CONFIG_HEADER: &str = "# Managed by wstunnel Manager, which rewrites this file when settings or\n# tunnels are changed in the app. Comments are kept with the entry below them.";
+
+// wstunnel flags whose values are masked when arguments are logged or shown.
+// They carry path prefixes, credentials and headers that act as secrets.
+pub const DEFAULT_SENSITIVE_FLAGS: &[&str] = &[
+    "-P",
+    "--http-upgrade-path-prefix",
+    "--http-upgrade-credentials",
+    "-H",
+    "--http-headers",
+    "--restrict-http-upgrade-path-prefix",
+];
+
+pub const REDACTED: &str = "***";
+
+// How much of each tunnel's log the combined log view reads.
+pub const COMBINED_LOG_LINES_PER_TUNNEL: usize = 200;
+
+// Most matching lines a search of a tunnel's whole log history returns; the
+// newest are kept.
+pub const LOG_SEARCH_MAX_RESULTS: usize = 1000;
+
+// Entries the command palette lists at once, best match first.
+pub const PALETTE_MAX_RESULTS: usize = 8;
+
+// A tunnel is not started when its log directory has less free space than
+// the minimum. Below the low mark it starts with a warning, and the GUI
+// shows a banner while tunnels run.
+pub const MIN_FREE_LOG_SPACE_MB: u64 = 50;
+pub const LOW_LOG_SPACE_MB: u64 = 500;
+pub const DISK_SPACE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// How often the GUI looks for edits made to the config file by something
+// else, such as a text editor or another instance.
+pub const CONFIG_CHANGE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+
+// The GUI's periodic status refresh never runs more often than this, however
+// low `ui_refresh_seconds` is set.
+pub const MIN_UI_REFRESH_SECS: u32 = 1;
+
+// Recent stderr lines kept in memory per tunnel.
+pub const DEFAULT_STDERR_BUFFER_LINES: usize = 50;
+pub const MAX_STDERR_BUFFER_LINES: usize = 10_000;
+
+// Log output is buffered and written out once this much has accumulated, or
+// at least this often, rather than line by line.
+pub const LOG_WRITE_BUFFER_BYTES: usize = 64 * 1024;
+pub const LOG_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+// How long a stopped tunnel has to exit before it is killed. Shutdown stops
+// all tunnels at once, so this also bounds how long quitting takes.
+pub const STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// A tunnel restarted this many times within the window is shown as flaky. At
+// the limit it is flapping: its next crash marks it failed with "too many
+// restarts", and it is no longer started as another tunnel's dependency
+// until the restarts age out of the window.
+pub const FLAP_WINDOW: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+pub const FLAP_WARN_RESTARTS: usize = 3;
+pub const FLAP_RESTART_LIMIT: usize = 5;
+
+// Log retention and compression run at startup and then every
+// `cleanup_interval_hours` while the manager is up.
+pub const DEFAULT_CLEANUP_INTERVAL_HOURS: u64 = 24;
+pub const MAX_CLEANUP_INTERVAL_HOURS: u64 = 24 * 30;
+
+// Upper bound for log retention and compression ages, ten years in days.
+pub const MAX_LOG_AGE_DAYS: u32 = 3650;
+
+// Config writes that hit a transient error are retried, doubling the delay
+// after each attempt.
+pub const CONFIG_SAVE_ATTEMPTS: u32 = 3;
+pub const CONFIG_SAVE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+// Previous versions of the config kept as `<config>.bak.1` (newest) to `.bak.N`.
+pub const CONFIG_BACKUP_COUNT: usize = 3;
+
+// Without a start timeout, a new process only has to survive this long.
+pub const EARLY_EXIT_GRACE: std::time::Duration = std::time::Duration::from_millis(500);
+// Used when a ready pattern is set without a start timeout.
+pub const DEFAULT_START_TIMEOUT_SECS: u64 = 30;
+pub const MAX_START_TIMEOUT_SECS: u64 = 600;
+
+pub const VERSION_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub const DEFAULT_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+// A probe that cannot connect within this time counts as failed.
+pub const HEALTH_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 pub fn default_log_directory() -> PathBuf {
     PathBuf::from(".").join("logs")
@@ -22,11 +22,90 @@ pub mod tunnel {
     pub const NOT_RUNNING: &str = "Tunnel is not running";
     pub const ALREADY_STOPPING: &str = "Tunnel is already stopping or has stopped";
     pub const NO_LOGS: &str = "Tunnel is not running or has no logs";
+    pub const CANNOT_DELETE_RUNNING: &str = "Stop the tunnel before deleting it.";
+    pub const STILL_STARTING: &str = "Tunnel is still starting. Please wait.";
+    pub const NO_LOCAL_PORT: &str = "Tunnel does not forward a local TCP or HTTP port";
+
+    pub fn failed_to_open_url(url: &str, error: &str) -> String {
+        format!("Failed to open {}: {}", url, error)
+    }
+
+    pub fn args_file_not_found(path: &str) -> String {
+        format!("Args file not found: {}", path)
+    }
+
+    pub fn args_file_unreadable(path: &str) -> String {
+        format!("Failed to read args file: {}", path)
+    }
+
+    pub fn dependency_failed(tag: &str, dependency: &str, error: &str) -> String {
+        format!(
+            "Cannot start '{}': its dependency '{}' failed to start: {}",
+            tag, dependency, error
+        )
+    }
+
+    pub fn args_file_recursive(path: &str) -> String {
+        format!("Args file {} includes itself", path)
+    }
+
+    pub fn local_port_scheme_invalid(scheme: &str) -> String {
+        format!(
+            "Invalid local_port_scheme '{}'. Use 'http' or 'https'.",
+            scheme
+        )
+    }
 
     pub fn failed_to_start(tag: &str) -> String {
         format!("Failed to start tunnel '{}'", tag)
     }
 
+    pub fn failed_to_start_with_detail(tag: &str, detail: &str) -> String {
+        format!("Failed to start tunnel '{}': {}", tag, detail)
+    }
+
+    pub fn ambiguous_tag(tag: &str) -> String {
+        format!(
+            "More than one tunnel is tagged '{}'. Use the tunnel ID instead.",
+            tag
+        )
+    }
+
+    pub fn exited_unexpectedly(tag: &str, exit_code: Option<i32>) -> String {
+        match exit_code {
+            Some(code) => format!("Tunnel '{}' exited unexpectedly with code {}", tag, code),
+            None => format!("Tunnel '{}' exited unexpectedly", tag),
+        }
+    }
+
+    pub fn too_many_restarts(tag: &str, restarts: usize, window: &str) -> String {
+        format!(
+            "Tunnel '{}' failed: too many restarts ({} in the last {}), start it by hand once fixed",
+            tag, restarts, window
+        )
+    }
+
+    pub fn flapping(restarts: usize, window: &str) -> String {
+        format!("Restarted {} times in the last {}", restarts, window)
+    }
+
+    // Outcome of a bulk start or stop, e.g. "4 started, 1 failed (web: ...)".
+    pub fn bulk_summary(action: &str, succeeded: usize, failures: &[(String, String)]) -> String {
+        if succeeded == 0 && failures.is_empty() {
+            return format!("No tunnels needed to be {}", action);
+        }
+        let summary = format!("{} {}, {} failed", succeeded, action, failures.len());
+        if failures.is_empty() {
+            return summary;
+        }
+        let details = failures
+            .iter()
+            .map(|(tag, error)| format!("{}: {}", tag, error))
+            .collect::<Vec<_>>()
+            .join("; ");
+        format!("{} ({})", summary, details)
+    }
+
     pub mod validation {
         pub const TAG_EMPTY: &str = "Tunnel tag cannot be empty or whitespace-only";
 
@@ -36,6 +115,69 @@ pub mod tunnel {
 
         pub const CLI_ARGS_EMPTY: &str = "CLI arguments cannot be empty";
 
+        pub fn unterminated_quote(quote: char) -> String {
+            format!("CLI arguments have an unterminated {} quote", quote)
+        }
+
+        pub const GROUP_EMPTY: &str =
+            "Tunnel group cannot be empty or whitespace-only; leave it unset instead";
+
+        pub fn group_too_long(group: &str) -> String {
+            format!("Tunnel group too long (max 100 characters): {}", group)
+        }
+
+        pub fn mode_mismatch(mode: &str, subcommand: &str) -> String {
+            format!(
+                "Tunnel mode is '{}' but CLI arguments run the '{}' subcommand",
+                mode, subcommand
+            )
+        }
+
+        pub const MISSING_SUBCOMMAND: &str =
+            "CLI arguments do not contain a 'client' or 'server' subcommand";
+
+        pub fn flag_before_subcommand(flag: &str, subcommand: &str) -> String {
+            format!("'{}' must come after the '{}' subcommand", flag, subcommand)
+        }
+
+        pub fn flag_wrong_mode(flag: &str, subcommand: &str) -> String {
+            format!(
+                "'{}' is not a '{}' flag; check the subcommand",
+                flag, subcommand
+            )
+        }
+
+        pub fn tunnel_spec_invalid(flag: &str, value: &str) -> String {
+            format!(
+                "'{}' expects a tunnel like tcp://8080:localhost:80, got '{}'",
+                flag, value
+            )
+        }
+
+        pub fn missing_url(subcommand: &str) -> String {
+            match subcommand {
+                "server" => {
+                    "No bind address such as wss://0.0.0.0:443 after the 'server' subcommand"
+                        .to_string()
+                }
+                _ => format!(
+                    "No ws:// or wss:// server URL after the '{}' subcommand",
+                    subcommand
+                ),
+            }
+        }
+
+        pub fn strict_check_failed(problem: &str) -> String {
+            format!("{} (global.strict_validation is enabled)", problem)
+        }
+
+        pub fn env_key_invalid(key: &str) -> String {
+            format!(
+                "Invalid environment variable name '{}': must be non-empty and cannot contain '=' or NUL",
+                key
+            )
+        }
+
         pub fn failed(context: &str) -> String {
             format!("Failed to validate tunnel entry: {}", context)
         }
@@ -43,10 +185,83 @@ pub mod tunnel {
         pub fn duplicate_id(id: &str) -> String {
             format!("Duplicate tunnel ID found: {}", id)
         }
+
+        pub fn working_dir_invalid(path: &str) -> String {
+            format!(
+                "Working directory does not exist or is not a directory: {}",
+                path
+            )
+        }
+
+        pub fn health_check_target_invalid(target: &str) -> String {
+            format!(
+                "Invalid health check target '{}': expected host:port, e.g. 127.0.0.1:1080",
+                target
+            )
+        }
+
+        pub fn health_check_interval_invalid(interval: &str) -> String {
+            format!(
+                "Invalid health check interval '{}': must be a whole number of seconds greater than zero",
+                interval
+            )
+        }
+
+        pub fn depends_on_itself(tag: &str) -> String {
+            format!("Tunnel '{}' cannot depend on itself", tag)
+        }
+
+        pub fn dependency_not_found(tag: &str, dependency: &str) -> String {
+            format!("Tunnel '{}' depends on unknown tunnel {}", tag, dependency)
+        }
+
+        pub fn dependency_cycle(cycle: &str) -> String {
+            format!("Tunnel dependencies form a cycle: {}", cycle)
+        }
+
+        pub fn autostart_priority_invalid(priority: &str) -> String {
+            format!(
+                "Invalid autostart priority '{}': must be a whole number, lower starts first",
+                priority
+            )
+        }
+
+        pub fn priority_out_of_range(nice: i32) -> String {
+            format!(
+                "Process priority {} is out of range: must be from -20 to 19",
+                nice
+            )
+        }
+
+        pub fn priority_invalid(priority: &str) -> String {
+            format!(
+                "Invalid process priority '{}': must be a whole number from -20 to 19",
+                priority
+            )
+        }
+
+        pub fn autostart_delay_invalid(delay: &str) -> String {
+            format!(
+                "Invalid autostart delay '{}': must be a whole number of milliseconds",
+                delay
+            )
+        }
+    }
+}
+
+pub mod health {
+    pub fn connect_failed(target: &str, error: &str) -> String {
+        format!("Cannot connect to {}: {}", target, error)
+    }
+
+    pub fn timed_out(target: &str) -> String {
+        format!("Connecting to {} timed out", target)
     }
 }
 
 pub mod binary {
+    pub const NOT_ON_PATH: &str = "No wstunnel binary found on PATH";
+
     pub fn not_found(path: &str) -> String {
         format!(
             "wstunnel binary not found at path: {}. Please check the binary path configuration or use --wstunnel-path flag.",
@@ -67,6 +282,28 @@ pub mod binary {
             path
         )
     }
+
+    pub fn version_check_failed(path: &str) -> String {
+        format!("Failed to run '{} --version'", path)
+    }
+
+    pub fn version_check_timed_out(path: &str) -> String {
+        format!("'{} --version' did not finish in time", path)
+    }
+
+    pub fn too_old(version: &str) -> String {
+        format!(
+            "wstunnel {} predates the client/server subcommands tunnels are run with. Please upgrade to 7.0 or newer.",
+            version
+        )
+    }
+
+    pub fn not_wstunnel(path: &str, output: &str) -> String {
+        format!(
+            "{} does not look like wstunnel, '--version' printed: {}",
+            path, output
+        )
+    }
 }
 
 pub mod config {
@@ -81,8 +318,8 @@ pub mod config {
         )
     }
 
-    pub fn corrupted_yaml(path: &str, error: &str) -> String {
-        format!("Corrupted YAML config at {}: {}", path, error)
+    pub fn corrupted_file(path: &str, error: &str) -> String {
+        format!("Corrupted config at {}: {}", path, error)
     }
 
     pub fn backup_created(path: &str) -> String {
@@ -98,12 +335,50 @@ pub mod config {
     }
 
     pub const SAVE_FAILED: &str = "Failed to save configuration to disk";
+
+    pub const READ_ONLY: &str =
+        "The manager is read-only here: tunnels and settings can't be added, changed or deleted";
+
+    pub const CHANGED_ON_DISK: &str = "The config file was changed outside the manager. Reload it or overwrite it before making changes";
+
+    pub fn reloaded(path: &str) -> String {
+        format!("Reloaded the configuration from {}", path)
+    }
+
+    pub fn overwritten(path: &str) -> String {
+        format!("Overwrote {} with the manager's configuration", path)
+    }
+
+    pub fn setting_not_a_number(setting: &str, value: &str) -> String {
+        format!("{} must be a whole number, got: {}", setting, value)
+    }
+    pub const NO_BACKUP: &str = "There is no earlier configuration to restore";
+
+    pub fn restored(path: &str) -> String {
+        format!("Restored the previous configuration from {}", path)
+    }
     pub const GLOBAL_VALIDATION_FAILED: &str = "Global settings validation failed";
 
     pub fn unsupported_version(version: u32) -> String {
         format!(
-            "Unsupported config version: {}. Expected version 1",
-            version
+            "Unsupported config version: {}. Expected version {}",
+            version,
+            crate::backend::types::CURRENT_CONFIG_VERSION
+        )
+    }
+
+    pub fn no_migration(version: u32) -> String {
+        format!("No migration registered for config version {}", version)
+    }
+
+    pub fn migration_failed(version: u32) -> String {
+        format!("Failed to migrate config from version {}", version)
+    }
+
+    pub fn migrated(from: u32, to: u32, backup_path: &str) -> String {
+        format!(
+            "Migrated config from version {} to {}. Previous version saved to {}",
+            from, to, backup_path
         )
     }
 
@@ -115,8 +390,41 @@ pub mod config {
         format!("Failed to read config from {}", path)
     }
 
-    pub fn failed_to_serialize() -> String {
-        "Failed to serialize config to YAML".to_string()
+    pub fn failed_to_parse(path: &str) -> String {
+        format!("Failed to parse config from {}", path)
+    }
+
+    pub fn imported(count: usize, path: &str) -> String {
+        format!("Imported {} tunnel(s) from {}", count, path)
+    }
+
+    pub fn exported(path: &str) -> String {
+        format!("Exported configuration to {}", path)
+    }
+
+    pub fn failed_to_serialize(format: &str) -> String {
+        format!("Failed to serialize config to {}", format)
+    }
+
+    pub const TUNNEL_SNIPPET_INVALID: &str = "Not a tunnel snippet";
+
+    pub fn tunnel_exported(tag: &str, destination: &str) -> String {
+        format!("Exported tunnel '{}' to {}", tag, destination)
+    }
+
+    pub fn tunnel_export_failed(path: &str, error: &str) -> String {
+        format!("Failed to export tunnel to {}: {}", path, error)
+    }
+
+    pub fn tunnel_imported(tag: &str) -> String {
+        format!("Imported tunnel '{}'", tag)
+    }
+
+    pub fn tunnel_imported_with_redactions(tag: &str) -> String {
+        format!(
+            "Imported tunnel '{}'; edit it to fill in the redacted values",
+            tag
+        )
     }
 
     pub fn failed_to_create_dir(error: &str) -> String {
@@ -131,6 +439,21 @@ pub mod config {
         format!("Failed to rename {} to {}", from, to)
     }
 
+    pub fn failed_to_backup(backup_path: &str) -> String {
+        format!("Failed to back up the config to {}", backup_path)
+    }
+
+    pub fn failed_to_back_up(path: &str, error: &str) -> String {
+        format!("Failed to keep a backup of {}: {}", path, error)
+    }
+
+    pub fn save_retry(step: &str, attempt: u32, error: &str) -> String {
+        format!(
+            "Config save: {} failed on attempt {} ({}), retrying",
+            step, attempt, error
+        )
+    }
+
     #[cfg(unix)]
     pub const FAILED_TO_OPEN_TEMP: &str = "Failed to open temp file for fsync";
     #[cfg(unix)]
@@ -142,6 +465,54 @@ pub mod config {
     }
 }
 
+pub mod running {
+    pub fn failed_to_read(path: &str) -> String {
+        format!("Failed to read running tunnel state from: {}", path)
+    }
+
+    pub fn failed_to_write(path: &str) -> String {
+        format!("Failed to write running tunnel state to: {}", path)
+    }
+}
+
+pub mod lock {
+    pub fn held(config_path: &str, pid: crate::backend::types::ProcessId) -> String {
+        format!(
+            "Another wstunnel Manager (PID {}) is already using {}. Close it first, or pass --api-port to send commands to a headless instance",
+            pid, config_path
+        )
+    }
+
+    pub fn removing_stale(path: &str) -> String {
+        format!(
+            "Removing stale lock file left by a manager that is no longer running: {}",
+            path
+        )
+    }
+
+    pub fn failed_to_acquire(path: &str) -> String {
+        format!("Failed to acquire the config lock: {}", path)
+    }
+
+    pub fn failed_to_write(path: &str) -> String {
+        format!("Failed to write config lock file: {}", path)
+    }
+
+    pub fn failed_to_remove(path: &str) -> String {
+        format!("Failed to remove config lock file: {}", path)
+    }
+}
+
+pub mod window {
+    pub fn failed_to_read(path: &str) -> String {
+        format!("Failed to read window state from: {}", path)
+    }
+
+    pub fn failed_to_write(path: &str) -> String {
+        format!("Failed to write window state to: {}", path)
+    }
+}
+
 pub mod disk {
     pub const FULL: &str =
         "Disk space exhausted. Cannot save configuration. Free up disk space and try again.";
@@ -149,21 +520,70 @@ pub mod disk {
     pub fn full_log_write(error: &str) -> String {
         format!("Disk full - cannot write to log file: {}", error)
     }
+
+    pub fn not_enough_log_space(path: &str, available_mb: u64) -> String {
+        format!(
+            "Only {} MB free for logs in {}. Free up disk space and try again.",
+            available_mb, path
+        )
+    }
+
+    pub fn low_log_space(path: &str, available_mb: u64) -> String {
+        format!(
+            "Low disk space: only {} MB free for logs in {}",
+            available_mb, path
+        )
+    }
 }
 
 pub mod logs {
+    use crate::constants::{MAX_CLEANUP_INTERVAL_HOURS, MAX_LOG_AGE_DAYS, MAX_STDERR_BUFFER_LINES};
+
     pub const FAILED_TO_CREATE_DIR: &str = "Failed to create log directory";
 
     pub const FAILED_TO_CREATE_FILE: &str = "Failed to create log file";
 
+    pub const NO_RETENTION_DAYS: &str =
+        "Enter how many days of logs to keep, or set a log retention period";
+
+    pub fn cleaned_up(deleted: usize, freed: &str) -> String {
+        match deleted {
+            1 => format!("Deleted 1 old log file, freeing {}", freed),
+            n => format!("Deleted {} old log files, freeing {}", n, freed),
+        }
+    }
+
     pub fn not_found(path: &str) -> String {
         format!("Log file not found at: {}", path)
     }
 
+    pub fn search_pattern_invalid(error: &str) -> String {
+        format!("Invalid search pattern: {}", error)
+    }
+
+    pub fn directory_not_found(path: &str) -> String {
+        format!("Log directory not found at: {}", path)
+    }
+
+    pub fn failed_to_read(path: &str) -> String {
+        format!("Failed to read log file: {}", path)
+    }
+
     pub fn failed_to_open(error: &str) -> String {
         format!("Failed to open log file: {}", error)
     }
 
+    pub fn unknown_placeholder(name: &str) -> String {
+        format!(
+            "Unknown placeholder {{{}}} in log_line_prefix. Use {{ts}}, {{stream}} or {{tag}}.",
+            name
+        )
+    }
+
+    pub fn opened_directory(path: &str) -> String {
+        format!("No log file left for this tunnel, opened {} instead", path)
+    }
+
     pub fn failed_to_write_stdout(error: &str) -> String {
         format!("Failed to write stdout to log: {}", error)
     }
@@ -172,16 +592,83 @@ pub mod logs {
         format!("Failed to write stderr to log: {}", error)
     }
 
+    pub fn failed_to_write_stream(error: &str) -> String {
+        format!(
+            "Failed to write per-stream log file, continuing with the combined log only: {}",
+            error
+        )
+    }
+
     pub fn failed_to_flush(error: &str) -> String {
         format!("Failed to flush log file: {}", error)
     }
 
+    pub fn cleanup_interval_invalid(hours: u64) -> String {
+        format!(
+            "Log cleanup interval must be between 1 and {} hours, got: {}",
+            MAX_CLEANUP_INTERVAL_HOURS, hours
+        )
+    }
+
     pub fn retention_invalid(days: u32) -> String {
         format!(
-            "Log retention days must be between 1 and 3650 (10 years), got: {}",
-            days
+            "Log retention days must be between 1 and {} (10 years), got: {}",
+            MAX_LOG_AGE_DAYS, days
+        )
+    }
+
+    pub fn compress_days_invalid(days: u32) -> String {
+        format!(
+            "Log compression days must be between 1 and {} (10 years), got: {}",
+            MAX_LOG_AGE_DAYS, days
+        )
+    }
+
+    pub fn compress_after_retention(compress_days: u32, retention_days: u32) -> String {
+        format!(
+            "Log compression days ({}) must be less than log retention days ({})",
+            compress_days, retention_days
         )
     }
+
+    pub fn failed_to_decompress(error: &str) -> String {
+        format!("Failed to decompress log file: {}", error)
+    }
+
+    pub fn max_size_invalid(size_mb: u64) -> String {
+        format!(
+            "Maximum log size must be between 1 and 10240 MB, got: {}",
+            size_mb
+        )
+    }
+
+    pub fn max_files_invalid(count: u32) -> String {
+        format!(
+            "Maximum number of rotated log files must be between 1 and 100, got: {}",
+            count
+        )
+    }
+
+    pub const LINE_RATE_INVALID: &str =
+        "Log line rate limit must be at least 1 line per second; leave it unset for no limit";
+
+    pub fn lines_dropped(count: u64, limit: u32) -> String {
+        format!(
+            "{} lines dropped (more than {} lines per second)",
+            count, limit
+        )
+    }
+
+    pub fn stderr_buffer_invalid(lines: usize) -> String {
+        format!(
+            "Stderr buffer size must be between 1 and {} lines, got: {}",
+            MAX_STDERR_BUFFER_LINES, lines
+        )
+    }
+
+    pub fn directory_not_writable(path: &str) -> String {
+        format!("Log directory is not writable: {}", path)
+    }
 }
 
 pub mod process {
@@ -192,8 +679,79 @@ pub mod process {
         format!("Failed to spawn wstunnel process: {}", error)
     }
 
+    pub fn priority_not_applied(requested: i32, actual: i32) -> String {
+        format!(
+            "Could not set process priority to {} (running at {}); raising the priority usually needs administrator rights",
+            requested, actual
+        )
+    }
+
+    pub fn start_timed_out(seconds: u64) -> String {
+        format!(
+            "not ready after {} seconds; the process was stopped",
+            seconds
+        )
+    }
+
+    pub fn start_timeout_invalid(seconds: u64) -> String {
+        format!(
+            "Start timeout must be between 1 and {} seconds, got {}",
+            crate::constants::MAX_START_TIMEOUT_SECS,
+            seconds
+        )
+    }
+
+    pub fn ready_pattern_invalid(error: &str) -> String {
+        format!("Ready pattern is not a valid regular expression: {}", error)
+    }
+
+    pub fn exited_with_status(status: &str) -> String {
+        format!("process exited immediately with {}", status)
+    }
+
+    pub fn env_var_not_set(name: &str) -> String {
+        format!(
+            "Environment variable '{}' referenced in CLI arguments is not set",
+            name
+        )
+    }
+
     pub const FAILED_TO_GET_PID: &str = "Failed to get process ID";
-    pub const FAILED_TO_PROCESS_PID: &str = "Failed to process ID after spawning tunnel";
     pub const FAILED_TO_CAPTURE_STDOUT: &str = "Failed to capture stdout";
     pub const FAILED_TO_CAPTURE_STDERR: &str = "Failed to capture stderr";
 }
+
+pub mod api {
+    pub fn failed_to_bind(address: &str) -> String {
+        format!("Failed to bind HTTP API to {}", address)
+    }
+
+    pub fn invalid_tunnel_id(id: &str) -> String {
+        format!("Invalid tunnel ID: {}", id)
+    }
+
+    pub fn failed_to_connect(address: &str) -> String {
+        format!(
+            "Failed to connect to the HTTP API at {}. Is a headless instance running with --api-port?",
+            address
+        )
+    }
+
+    pub const SERVER_FAILED: &str = "HTTP API server stopped unexpectedly";
+    pub const REQUEST_FAILED: &str = "HTTP API request failed";
+    pub const INVALID_RESPONSE: &str = "HTTP API returned an invalid response";
+}
+
+pub mod metrics {
+    pub fn failed_to_bind(address: &str) -> String {
+        format!("Failed to bind metrics endpoint to {}", address)
+    }
+
+    pub const SERVER_FAILED: &str = "Metrics server stopped unexpectedly";
+}
+
+pub mod cli {
+    pub const PREVIEW_IS_LOCAL: &str =
+        "The command preview reads the local config file and doesn't go through the HTTP API.";
+    pub const STOP_REQUIRES_API: &str = "Tunnels started by another instance can only be stopped through its HTTP API. Pass --api-port to reach a running headless instance.";
+}
@@ -1,4 +1,7 @@
+pub mod api;
 pub mod backend;
+pub mod cli;
 pub mod constants;
 pub mod errors;
+pub mod metrics;
 pub mod ui;
@@ -1,13 +1,16 @@
 // wstunnel Manager
 // Entry point for the application
 
+mod api;
 mod backend;
+mod cli;
 mod constants;
 mod errors;
+mod metrics;
 mod ui;
 
 use anyhow::{Context, Result};
-use backend::Backend;
+use backend::SharedBackend;
 use backend::backend_impl::BackendState;
 use clap::Parser;
 use std::path::PathBuf;
@@ -18,6 +21,9 @@ use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberI
 #[command(name = "wstunnel_manager")]
 #[command(about = "wstunnel Manager - GUI and headless mode for managing wstunnel instances")]
 struct Args {
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
     #[arg(long, help = "Run in headless mode without GUI")]
     headless: bool,
 
@@ -26,9 +32,45 @@ struct Args {
 
     #[arg(long, help = "Path to wstunnel binary")]
     wstunnel_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Allow starting and stopping tunnels but not adding, editing or deleting them, or changing settings"
+    )]
+    read_only: bool,
+
+    #[arg(
+        long,
+        requires = "headless",
+        help = "Serve the HTTP control API on 127.0.0.1:<PORT> (headless mode only)"
+    )]
+    api_port: Option<u16>,
+
+    #[arg(
+        long,
+        requires = "headless",
+        value_name = "SECONDS",
+        help = "Print a JSON status snapshot to stdout every SECONDS (headless mode only)"
+    )]
+    status_interval: Option<u64>,
+
+    #[arg(
+        long,
+        requires = "headless",
+        help = "Serve Prometheus metrics on 127.0.0.1:<PORT>/metrics (headless mode only)"
+    )]
+    metrics_port: Option<u16>,
+
+    #[cfg(feature = "tray")]
+    #[arg(
+        long,
+        help = "Show a system tray icon; closing the window minimizes to the tray"
+    )]
+    tray: bool,
 }
 
-fn setup_tracing(headless: bool) -> Result<()> {
+// Subcommands print their results to stdout, so they only log to the file.
+fn setup_tracing(headless: bool, file_only: bool) -> Result<()> {
     let log_directory = constants::default_log_directory();
     std::fs::create_dir_all(&log_directory).context(errors::logs::FAILED_TO_CREATE_DIR)?;
 
@@ -37,7 +79,12 @@ fn setup_tracing(headless: bool) -> Result<()> {
 
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
-    if headless {
+    if file_only {
+        tracing_subscriber::registry()
+            .with(env_filter)
+            .with(fmt::layer().with_writer(non_blocking).json())
+            .init();
+    } else if headless {
         tracing_subscriber::registry()
             .with(env_filter)
             .with(fmt::layer().with_writer(non_blocking).json())
@@ -56,33 +103,102 @@ fn setup_tracing(headless: bool) -> Result<()> {
     Ok(())
 }
 
+fn print_status_snapshot(backend: &dyn backend::Backend) {
+    let snapshot =
+        backend::types::status_snapshot(&backend.get_config(), &backend.get_all_statuses());
+    match serde_json::to_string(&snapshot) {
+        Ok(line) => println!("{}", line),
+        Err(e) => tracing::error!("Failed to serialize status snapshot: {}", e),
+    }
+}
+
+// Prints one JSON array per line, every `interval` and, on Unix, whenever the
+// process receives SIGUSR1.
+async fn emit_status_snapshots(backend: SharedBackend, interval: Option<std::time::Duration>) {
+    #[cfg(unix)]
+    let mut usr1 =
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1()) {
+            Ok(signal) => Some(signal),
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGUSR1: {}", e);
+                None
+            }
+        };
+
+    let mut ticker = interval
+        .filter(|interval| !interval.is_zero())
+        .map(tokio::time::interval);
+
+    loop {
+        let tick = async {
+            match ticker.as_mut() {
+                Some(ticker) => {
+                    ticker.tick().await;
+                }
+                None => std::future::pending().await,
+            }
+        };
+
+        #[cfg(unix)]
+        let signal = async {
+            let received = match usr1.as_mut() {
+                Some(usr1) => usr1.recv().await.is_some(),
+                None => false,
+            };
+            if !received {
+                std::future::pending::<()>().await;
+            }
+        };
+        #[cfg(not(unix))]
+        let signal = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = tick => {}
+            _ = signal => {}
+        }
+
+        print_status_snapshot(&*backend.lock().await);
+    }
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    setup_tracing(args.headless).context("Failed to initialize tracing")?;
+    setup_tracing(args.headless, args.command.is_some()).context("Failed to initialize tracing")?;
+
+    if let Some(cli::Command::Schema) = args.command {
+        return cli::print_schema();
+    }
+
+    // Create tokio runtime
+    let runtime = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
+    let runtime_handle = runtime.handle().clone();
 
-    type BackendHandle = Arc<Mutex<Option<Arc<Mutex<dyn Backend>>>>>;
+    type BackendHandle = Arc<Mutex<Option<SharedBackend>>>;
     let backend_for_panic: BackendHandle = Arc::new(Mutex::new(None));
     let backend_for_panic_clone = backend_for_panic.clone();
+    let panic_runtime_handle = runtime_handle.clone();
 
     std::panic::set_hook(Box::new(move |panic_info| {
         tracing::error!("Application panic: {:?}", panic_info);
 
+        // The panic may have happened on a runtime worker, where block_on is
+        // not allowed, so drive the shutdown from a separate thread.
         if let Ok(backend_guard) = backend_for_panic_clone.lock()
             && let Some(backend) = backend_guard.as_ref()
-            && let Ok(mut backend_lock) = backend.lock()
+            && let Ok(mut backend_lock) = backend.try_lock()
         {
             tracing::info!("Shutting down tunnels due to panic");
-            let _ = backend_lock.shutdown();
+            std::thread::scope(|scope| {
+                let _ = scope
+                    .spawn(|| panic_runtime_handle.block_on(backend_lock.shutdown()))
+                    .join();
+            });
         }
     }));
 
     tracing::info!("wstunnel Manager starting - Phase 10 complete");
 
-    // Create tokio runtime
-    let runtime = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
-    let runtime_handle = runtime.handle().clone();
-
     // Get executable directory for relative path resolution
     let exe_dir = std::env::current_exe()
         .ok()
@@ -99,18 +215,42 @@ fn main() -> Result<()> {
         } else {
             "wstunnel"
         };
-        match &exe_dir {
+        let default_path = match &exe_dir {
             Some(dir) => dir.join(binary_name),
             None => PathBuf::from(binary_name),
+        };
+        if default_path.exists() {
+            return default_path;
+        }
+        match backend::process::detect_wstunnel_binary() {
+            Some(found) => {
+                tracing::info!("Found wstunnel on PATH at {}", found.display());
+                found
+            }
+            None => default_path,
         }
     });
 
     tracing::info!("Config path: {}", config_path.display());
     tracing::info!("Binary path: {}", wstunnel_binary_path.display());
 
+    // Commands aimed at another instance's HTTP API need no local backend.
+    if let Some(command) = args.command.clone()
+        && let Some(port) = command.options().api_port
+    {
+        return runtime.block_on(cli::run(
+            command,
+            cli::Target::Api(api::ApiClient::new(port)),
+        ));
+    }
+
     let use_mock = std::env::var("WSTUNNEL_MANAGER_MOCK").is_ok();
 
-    if !use_mock && !wstunnel_binary_path.exists() {
+    // The GUI asks for the binary on its setup screen instead.
+    let gui = !args.headless && args.command.is_none();
+    let first_run = !config_path.exists();
+
+    if !use_mock && !gui && !wstunnel_binary_path.exists() {
         let error_msg = errors::binary::not_found(&wstunnel_binary_path.display().to_string());
         tracing::error!("{}", error_msg);
         return Err(anyhow::anyhow!(error_msg));
@@ -120,32 +260,73 @@ fn main() -> Result<()> {
         tracing::info!("Running in MOCK mode - no real processes will be spawned");
     }
 
-    let backend: Arc<Mutex<dyn Backend>> = if use_mock {
-        Arc::new(Mutex::new(backend::mock_backend::MockBackend::new(
-            runtime_handle.clone(),
-            config_path.clone(),
-        )))
+    let window_state_path = ui::window_state::state_file_path(&config_path);
+
+    let setup_binary_path = wstunnel_binary_path.clone();
+    let backend: SharedBackend = if use_mock {
+        Arc::new(tokio::sync::Mutex::new(
+            backend::mock_backend::MockBackend::with_scenario(
+                runtime_handle.clone(),
+                config_path.clone(),
+                backend::mock_backend::MockScenario::from_env(),
+            ),
+        ))
     } else {
         let backend_state =
-            BackendState::new(runtime_handle.clone(), config_path, wstunnel_binary_path);
-        Arc::new(Mutex::new(backend_state))
+            match BackendState::new(runtime_handle.clone(), config_path, wstunnel_binary_path) {
+                Ok(backend_state) => backend_state,
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    // Started from a desktop shortcut, there is no terminal
+                    // to show the error in.
+                    if gui {
+                        rfd::MessageDialog::new()
+                            .set_level(rfd::MessageLevel::Error)
+                            .set_title(constants::APP_TITLE)
+                            .set_description(e.to_string())
+                            .show();
+                    }
+                    return Err(e.into());
+                }
+            };
+        Arc::new(tokio::sync::Mutex::new(backend_state))
     };
 
+    if args.read_only {
+        tracing::info!("Running read-only");
+        runtime.block_on(backend.lock()).set_read_only(true);
+    }
+
     *backend_for_panic.lock().unwrap() = Some(backend.clone());
 
     tracing::info!("Backend initialized");
 
+    if let Some(command) = args.command {
+        return runtime.block_on(cli::run(command, cli::Target::Local(backend)));
+    }
+
     if args.headless {
         tracing::info!("Running in headless mode");
 
-        {
-            let mut backend_lock = backend.lock().unwrap();
+        if let Some(port) = args.metrics_port {
+            let listener = runtime.block_on(metrics::bind(port))?;
+            let metrics = Arc::new(metrics::Metrics::new()?);
+            let metrics_backend = backend.clone();
+            runtime.spawn(async move {
+                if let Err(e) = metrics::serve(listener, metrics_backend, metrics).await {
+                    tracing::error!("{:#}", e);
+                }
+            });
+        }
+
+        runtime.block_on(async {
+            let mut backend_lock = backend.lock().await;
 
-            if let Err(e) = backend_lock.cleanup_old_logs_if_configured() {
+            if let Err(e) = backend_lock.cleanup_old_logs_if_configured().await {
                 tracing::warn!("Log cleanup failed: {}", e);
             }
 
-            match backend_lock.start_autostart_tunnels() {
+            match backend_lock.start_autostart_tunnels().await {
                 Ok(results) => {
                     for (tunnel_id, result) in results {
                         match result {
@@ -170,8 +351,23 @@ fn main() -> Result<()> {
                     tracing::error!("Headless: Failed to start autostart tunnels: {}", e);
                 }
             }
+        });
+
+        if let Some(port) = args.api_port {
+            let listener = runtime.block_on(api::bind(port))?;
+            let api_backend = backend.clone();
+            runtime.spawn(async move {
+                if let Err(e) = api::serve(listener, api_backend).await {
+                    tracing::error!("{:#}", e);
+                }
+            });
         }
 
+        runtime.spawn(emit_status_snapshots(
+            backend.clone(),
+            args.status_interval.map(std::time::Duration::from_secs),
+        ));
+
         tracing::info!("Headless mode running. Press Ctrl+C to exit.");
 
         runtime.block_on(async {
@@ -186,12 +382,11 @@ fn main() -> Result<()> {
         });
 
         tracing::info!("Shutting down backend");
-        {
-            let mut backend_lock = backend.lock().unwrap();
-            if let Err(e) = backend_lock.shutdown() {
+        runtime.block_on(async {
+            if let Err(e) = backend.lock().await.shutdown().await {
                 tracing::error!("Error during shutdown: {}", e);
             }
-        }
+        });
 
         return Ok(());
     }
@@ -199,6 +394,24 @@ fn main() -> Result<()> {
     // Launch iced application (GUI mode)
     tracing::info!("Launching UI");
 
+    #[cfg(feature = "tray")]
+    let tray_enabled = args.tray;
+
+    let window_geometry = ui::window_state::load(&window_state_path);
+
+    let setup_binary_path = if use_mock {
+        None
+    } else {
+        let configured = runtime
+            .block_on(backend.lock())
+            .get_config()
+            .global
+            .wstunnel_binary_path
+            .clone();
+        let binary_path = configured.unwrap_or(setup_binary_path);
+        (first_run || !binary_path.exists()).then_some(binary_path)
+    };
+
     let backend_clone = backend.clone();
     let result = iced::application(
         ui::WstunnelManagerApp::title,
@@ -207,20 +420,39 @@ fn main() -> Result<()> {
     )
     .subscription(ui::WstunnelManagerApp::subscription)
     .theme(ui::WstunnelManagerApp::theme)
-    .window_size((1200.0, 800.0))
+    .window(iced::window::Settings {
+        size: window_geometry.size(),
+        position: window_geometry.position(),
+        min_size: Some(iced::Size::new(
+            constants::MIN_WINDOW_SIZE.0,
+            constants::MIN_WINDOW_SIZE.1,
+        )),
+        ..Default::default()
+    })
+    // The app handles close requests itself so it can ask before stopping
+    // running tunnels, or hide to the tray.
+    .exit_on_close_request(false)
     .run_with(move || {
-        let app = ui::WstunnelManagerApp::new(backend_clone.clone());
-        (app, iced::Task::none())
+        let (mut app, task) =
+            ui::WstunnelManagerApp::new(backend_clone.clone(), runtime_handle.clone());
+        app.remember_window(window_state_path.clone(), window_geometry);
+        if let Some(binary_path) = &setup_binary_path {
+            app.show_setup(binary_path.clone());
+        }
+        #[cfg(feature = "tray")]
+        if tray_enabled {
+            app.enable_tray();
+        }
+        (app, task)
     })
     .map_err(|e| anyhow::anyhow!("UI error: {:?}", e));
 
     tracing::info!("UI closed, shutting down backend");
-    {
-        let mut backend_lock = backend.lock().unwrap();
-        if let Err(e) = backend_lock.shutdown() {
+    runtime.block_on(async {
+        if let Err(e) = backend.lock().await.shutdown().await {
             tracing::error!("Error during shutdown: {}", e);
         }
-    }
+    });
 
     result?;
 
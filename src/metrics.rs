@@ -0,0 +1,170 @@
+// Prometheus metrics for headless mode, served on `/metrics` when
+// `--metrics-port` is given. Every scrape refreshes the series from the
+// backend's statuses and per-tunnel stats.
+
+use crate::backend::SharedBackend;
+use crate::backend::types::{Config, TunnelId, TunnelRuntimeState, TunnelStats};
+use crate::errors;
+use anyhow::Context;
+use axum::Router;
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use prometheus::{Encoder, GaugeVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::collections::{HashMap, HashSet};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+const LABELS: &[&str] = &["id", "tag"];
+
+pub struct Metrics {
+    registry: Registry,
+    up: IntGaugeVec,
+    restarts: IntCounterVec,
+    uptime: GaugeVec,
+    labelled: Mutex<HashSet<(String, String)>>,
+}
+
+impl Metrics {
+    pub fn new() -> anyhow::Result<Self> {
+        let up = IntGaugeVec::new(
+            Opts::new(
+                "wstunnel_tunnel_up",
+                "Whether the tunnel is running (1) or not (0)",
+            ),
+            LABELS,
+        )?;
+        let restarts = IntCounterVec::new(
+            Opts::new(
+                "wstunnel_tunnel_restarts_total",
+                "Number of times the tunnel was started again after its first start",
+            ),
+            LABELS,
+        )?;
+        let uptime = GaugeVec::new(
+            Opts::new(
+                "wstunnel_tunnel_uptime_seconds",
+                "Seconds since the running tunnel was started, 0 when it is not running",
+            ),
+            LABELS,
+        )?;
+
+        let registry = Registry::new();
+        registry.register(Box::new(up.clone()))?;
+        registry.register(Box::new(restarts.clone()))?;
+        registry.register(Box::new(uptime.clone()))?;
+
+        Ok(Self {
+            registry,
+            up,
+            restarts,
+            uptime,
+            labelled: Mutex::new(HashSet::new()),
+        })
+    }
+
+    // Sets the series from the current statuses and stats and drops those of
+    // tunnels that are no longer configured.
+    pub fn update(
+        &self,
+        config: &Config,
+        statuses: &[(TunnelId, TunnelRuntimeState)],
+        stats: &HashMap<TunnelId, TunnelStats>,
+    ) {
+        let mut labelled = self.labelled.lock().unwrap();
+        let mut current = HashSet::new();
+
+        for tunnel in &config.tunnels {
+            let labels = (tunnel.id.to_string(), tunnel.tag.clone());
+            let values = [labels.0.as_str(), labels.1.as_str()];
+            let uptime = match statuses.iter().find(|(id, _)| *id == tunnel.id) {
+                Some((_, TunnelRuntimeState::Running { started_at, .. })) => {
+                    Some(started_at.elapsed().as_secs_f64())
+                }
+                _ => None,
+            };
+
+            self.up
+                .with_label_values(&values)
+                .set(i64::from(uptime.is_some()));
+            self.uptime
+                .with_label_values(&values)
+                .set(uptime.unwrap_or(0.0));
+            // Counters only go up, so add whatever the backend counted since
+            // the last scrape.
+            let restarts = self.restarts.with_label_values(&values);
+            let total = stats.get(&tunnel.id).map_or(0, TunnelStats::restarts);
+            restarts.inc_by(total.saturating_sub(restarts.get()));
+            current.insert(labels);
+        }
+
+        for (id, tag) in labelled.difference(&current) {
+            let values = [id.as_str(), tag.as_str()];
+            let _ = self.up.remove_label_values(&values);
+            let _ = self.uptime.remove_label_values(&values);
+            let _ = self.restarts.remove_label_values(&values);
+        }
+        *labelled = current;
+    }
+
+    pub fn render(&self) -> anyhow::Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+#[derive(Clone)]
+struct MetricsState {
+    backend: SharedBackend,
+    metrics: Arc<Metrics>,
+}
+
+pub fn router(backend: SharedBackend, metrics: Arc<Metrics>) -> Router {
+    Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(MetricsState { backend, metrics })
+}
+
+async fn scrape(State(state): State<MetricsState>) -> Response {
+    {
+        let backend = state.backend.lock().await;
+        let config = backend.get_config();
+        let stats = config
+            .tunnels
+            .iter()
+            .filter_map(|tunnel| Some((tunnel.id, backend.get_tunnel_stats(tunnel.id)?)))
+            .collect();
+        state
+            .metrics
+            .update(&config, &backend.get_all_statuses(), &stats);
+    }
+
+    match state.metrics.render() {
+        Ok(body) => ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    }
+}
+
+// Like the control API, metrics are only served on loopback.
+pub async fn bind(port: u16) -> anyhow::Result<TcpListener> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpListener::bind(address)
+        .await
+        .with_context(|| errors::metrics::failed_to_bind(&address.to_string()))
+}
+
+pub async fn serve(
+    listener: TcpListener,
+    backend: SharedBackend,
+    metrics: Arc<Metrics>,
+) -> anyhow::Result<()> {
+    if let Ok(address) = listener.local_addr() {
+        tracing::info!("Metrics available on http://{}/metrics", address);
+    }
+    axum::serve(listener, router(backend, metrics))
+        .await
+        .context(errors::metrics::SERVER_FAILED)
+}
@@ -1,44 +1,243 @@
-use crate::backend::types::{Config, TunnelId, TunnelRuntimeState};
+use crate::backend::logs::{LogFileInfo, TaggedLogLine};
+use crate::backend::types::{
+    Config, GlobalSettings, LogMode, TunnelEntry, TunnelEvent, TunnelId, TunnelMode,
+    TunnelRuntimeState, TunnelStats, TunnelSummary,
+};
+use crate::ui::state::{
+    AboutState, QuickFilter, SettingsField, SettingsToggle, SortOrder, StreamFilter, ThemeChoice,
+    Toast, TunnelChoice,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum TunnelListMessage {
     AddTunnel,
+    ShowDetails(TunnelId),
     EditTunnel(TunnelId),
     DeleteTunnel(TunnelId),
+    SelectTunnel(TunnelId, bool),
+    ClearSelection,
+    DeleteSelected,
     StartTunnel(TunnelId),
     StopTunnel(TunnelId),
+    StartAll,
+    StopAll,
+    StartGroup(String),
+    StopGroup(String),
+    ToggleGroup(String),
+    BulkCompleted(String),
     OpenLogs(TunnelId),
+    OpenLogsFolder(TunnelId),
+    CopyLogsPath(TunnelId),
+    CopyPid(TunnelId),
+    CopyCliArgs(TunnelId),
+    OpenLocalPort(TunnelId),
+    ImportConfig,
+    ImportFileSelected(Option<PathBuf>),
+    ImportTunnel,
+    ImportTunnelSnippet(Option<String>),
+    ExportTunnel(TunnelId),
+    ExportTunnelToFile(TunnelId),
+    ExportTunnelFileSelected(TunnelId, Option<PathBuf>),
+    ExportConfig,
+    ExportFileSelected(Option<PathBuf>),
+    RestoreBackup,
+    RestoreBackupConfirmed(bool),
+    ReloadConfig,
+    OverwriteConfig,
     Refresh,
     DismissError,
+    FilterChanged(String),
+    QuickFilterSelected(QuickFilter),
+    SortOrderSelected(SortOrder),
+    ShowCombinedLogs,
+    ShowSettings,
+    ShowAbout,
+}
+
+#[derive(Debug, Clone)]
+pub enum TunnelDetailMessage {
+    Back,
+    Tick,
+    DetailsLoaded {
+        stats: Option<TunnelStats>,
+        log_path: Option<PathBuf>,
+        log_file: Option<LogFileInfo>,
+        sensitive_flags: Vec<String>,
+        recent_stderr: String,
+    },
+    PreviewCommand,
+    CommandPreviewLoaded(String),
+    CopyCommandPreview,
+    HideCommandPreview,
+    DismissError,
 }
 
 #[derive(Debug, Clone)]
 pub enum EditTunnelMessage {
     TagChanged(String),
+    ModeSelected(TunnelMode),
     CliArgsChanged(String),
     AutostartToggled(bool),
+    AutostartPriorityChanged(String),
+    AutostartDelayChanged(String),
+    DependencyToggled(TunnelId, bool),
+    GroupChanged(String),
+    EnvKeyChanged(usize, String),
+    EnvValueChanged(usize, String),
+    AddEnvVar,
+    RemoveEnvVar(usize),
+    LogDirectoryChanged(String),
+    BrowseLogDirectory,
+    LogDirectorySelected(Option<PathBuf>),
+    WorkingDirChanged(String),
+    BrowseWorkingDir,
+    WorkingDirSelected(Option<PathBuf>),
+    PriorityChanged(String),
+    HealthCheckTargetChanged(String),
+    HealthCheckIntervalChanged(String),
     Save,
     Cancel,
+    DiscardChanges,
+    KeepEditing,
     SaveCompleted(Result<TunnelId, String>),
 }
 
+#[derive(Debug, Clone)]
+pub enum SetupMessage {
+    BinaryPathChanged(String),
+    BrowseBinary,
+    BinarySelected(Option<PathBuf>),
+    DetectBinary,
+    LogDirectoryChanged(String),
+    BrowseLogDirectory,
+    LogDirectorySelected(Option<PathBuf>),
+    Save,
+    Saved(Result<(), String>),
+    Skip,
+}
+
+#[derive(Debug, Clone)]
+pub enum AboutMessage {
+    Loaded(AboutState),
+    CopyDiagnostics,
+    OpenLogDirectory,
+    Back,
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    Loaded(GlobalSettings),
+    FieldChanged(SettingsField, String),
+    Toggled(SettingsToggle, bool),
+    LogModeSelected(LogMode),
+    ThemeSelected(ThemeChoice),
+    BrowseBinary,
+    BinarySelected(Option<PathBuf>),
+    DetectBinary,
+    BrowseLogDirectory,
+    LogDirectorySelected(Option<PathBuf>),
+    // Bytes in the saved log directories.
+    LogUsageLoaded(u64),
+    CleanupDaysChanged(String),
+    CleanUpLogs,
+    // Files deleted and the bytes left.
+    LogsCleanedUp(Result<(usize, u64), String>),
+    Save,
+    Saved(Result<GlobalSettings, String>),
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum CombinedLogsMessage {
+    Back,
+    Tick,
+    Loaded(Vec<TaggedLogLine>),
+    TunnelSelected(TunnelChoice),
+    StreamSelected(StreamFilter),
+    SearchChanged(String),
+    MatchCaseToggled(bool),
+    RegexToggled(bool),
+    NextMatch,
+    PreviousMatch,
+    SearchHistory,
+    HistoryLoaded(Vec<TaggedLogLine>),
+    ClearHistory,
+}
+
+#[derive(Debug, Clone)]
+pub enum CommandPaletteMessage {
+    Toggle,
+    Close,
+    QueryChanged(String),
+    SelectNext,
+    SelectPrevious,
+    Run,
+    RunEntry(usize),
+}
+
 #[derive(Debug, Clone)]
 pub enum ConfirmDeleteMessage {
     Confirm,
     Cancel,
 }
 
+#[derive(Debug, Clone)]
+pub enum ConfirmQuitMessage {
+    StopAndQuit,
+    LeaveRunningAndQuit,
+    Cancel,
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     TunnelList(TunnelListMessage),
+    TunnelDetail(TunnelDetailMessage),
     EditTunnel(EditTunnelMessage),
     ConfirmDelete(ConfirmDeleteMessage),
+    ConfirmQuit(ConfirmQuitMessage),
+    CombinedLogs(CombinedLogsMessage),
+    Setup(SetupMessage),
+    Settings(SettingsMessage),
+    About(AboutMessage),
+    CommandPalette(CommandPaletteMessage),
     ProcessStatusChanged {
         id: TunnelId,
         status: TunnelRuntimeState,
     },
     #[allow(dead_code)]
     ConfigReloaded(Arc<Config>),
+    TunnelsLoaded {
+        tunnels: Vec<TunnelEntry>,
+        wstunnel_version: Option<String>,
+        config_generation: u64,
+        summary: TunnelSummary,
+        flap_warnings: HashMap<TunnelId, String>,
+    },
+    StatusesLoaded {
+        statuses: Vec<(TunnelId, TunnelRuntimeState)>,
+        wstunnel_version: Option<String>,
+        summary: TunnelSummary,
+        flap_warnings: HashMap<TunnelId, String>,
+    },
+    TunnelEvent(TunnelEvent),
+    WindowCloseRequested(iced::window::Id),
+    ThemeSelected(iced::Theme),
+    Notify(Toast),
+    DismissToast(usize),
+    ExpireToasts,
+    CheckDiskSpace,
+    DiskSpaceChecked(Option<String>),
+    CheckConfigOnDisk,
+    ConfigOnDiskChecked(bool),
+    RefreshTick,
+    WindowMoved(iced::Point),
+    WindowResized(iced::Size),
+    #[cfg(feature = "tray")]
+    ShowWindow,
+    #[cfg(feature = "tray")]
+    Quit,
     Error(String),
 }
@@ -2,31 +2,179 @@ pub mod messages;
 pub mod screens;
 pub mod state;
 pub mod theme;
+#[cfg(feature = "tray")]
+pub mod tray;
+pub mod window_state;
 
-use crate::backend::Backend;
-use crate::backend::types::{TunnelEntry, TunnelId, TunnelMode};
+use crate::backend::config::{
+    ConfigFormat, is_tunnel_snippet, parse_tunnel_snippet, tunnel_snippet,
+};
+use crate::backend::error::{BackendError, BackendResult};
+use crate::backend::logs::{LogSearch, TaggedLogLine, decompress_to_temp, is_compressed_log_file};
+use crate::backend::types::{
+    TunnelEntry, TunnelEvent, TunnelId, TunnelMode, TunnelRuntimeState, TunnelSummary, format_size,
+};
+use crate::backend::{Backend, SharedBackend};
+use crate::constants::{COMBINED_LOG_LINES_PER_TUNNEL, LOG_SEARCH_MAX_RESULTS, MAX_TOASTS};
 use crate::errors;
-use messages::{ConfirmDeleteMessage, EditTunnelMessage, Message, TunnelListMessage};
-use state::{ConfirmDeleteState, EditTunnelState, Screen};
-use std::sync::{Arc, Mutex};
+use messages::{
+    AboutMessage, CombinedLogsMessage, CommandPaletteMessage, ConfirmDeleteMessage,
+    ConfirmQuitMessage, EditTunnelMessage, Message, SettingsMessage, SetupMessage,
+    TunnelDetailMessage, TunnelListMessage,
+};
+use state::{
+    AboutState, CombinedLogsState, CommandPaletteState, ConfirmDeleteState, ConfirmQuitState,
+    EditTunnelState, LeaveEdit, Screen, SettingsState, SetupState, Toast, TunnelChoice,
+    TunnelDetailState,
+};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 
 pub struct WstunnelManagerApp {
     screen: Screen,
-    backend: Arc<Mutex<dyn Backend>>,
+    backend: SharedBackend,
+    runtime_handle: tokio::runtime::Handle,
+    events: broadcast::Receiver<TunnelEvent>,
     tunnels: Vec<TunnelEntry>,
+    wstunnel_version: Option<String>,
     theme: theme::WstunnelTheme,
+    window_geometry: window_state::WindowGeometry,
+    window_state_path: Option<PathBuf>,
+    toasts: Vec<Toast>,
+    // Open while the Ctrl+P overlay is showing, over whatever screen is.
+    command_palette: Option<CommandPaletteState>,
+    // Set while a running tunnel's log directory is low on space.
+    disk_space_warning: Option<String>,
+    // "Restarted N times" for the tunnels that keep restarting.
+    flap_warnings: std::collections::HashMap<TunnelId, String>,
+    // Set while the config file holds edits made outside the manager.
+    config_changed_on_disk: bool,
+    // Started with `--read-only` or with a locked config. Only read at
+    // startup; the backend refuses changes either way.
+    read_only: bool,
+    // From `global.ui_refresh_seconds`; None when statuses aren't polled.
+    refresh_interval: Option<std::time::Duration>,
+    summary: TunnelSummary,
+    // Generation of the config `tunnels` was loaded from.
+    config_generation: Option<u64>,
+    #[cfg(feature = "tray")]
+    tray: Option<tray::Tray>,
+}
+
+// Backend futures run on the tokio runtime that owns the tunnel processes.
+// iced's executor only awaits the join handle, so process spawns and config
+// writes never block the update loop.
+fn on_runtime<T: Send + 'static>(
+    runtime_handle: &tokio::runtime::Handle,
+    future: impl Future<Output = Result<T, String>> + Send + 'static,
+) -> impl Future<Output = Result<T, String>> + 'static {
+    let join_handle = runtime_handle.spawn(future);
+    async move {
+        match join_handle.await {
+            Ok(result) => result,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+fn tunnels_loaded(
+    result: Result<(Vec<TunnelEntry>, Option<String>, u64, TunnelSummary), String>,
+) -> Message {
+    match result {
+        Ok((tunnels, wstunnel_version, config_generation, summary)) => Message::TunnelsLoaded {
+            tunnels,
+            wstunnel_version,
+            config_generation,
+            summary,
+            flap_warnings: std::collections::HashMap::new(),
+        },
+        Err(error) => Message::Error(error),
+    }
+}
+
+// Warnings for the tunnels that have restarted often lately.
+fn flap_warnings(backend: &dyn Backend) -> std::collections::HashMap<TunnelId, String> {
+    backend
+        .get_config()
+        .tunnels
+        .iter()
+        .filter_map(|tunnel| {
+            let warning = backend.get_tunnel_stats(tunnel.id)?.flap_warning()?;
+            Some((tunnel.id, warning))
+        })
+        .collect()
+}
+
+// The tunnel's current log file, falling back to the directory its logs are
+// written to when it has never run.
+fn logs_location(backend: &dyn Backend, id: TunnelId) -> Result<PathBuf, String> {
+    if let Some(path) = backend.get_log_path(id) {
+        return Ok(path);
+    }
+    let config = backend.get_config();
+    config
+        .tunnels
+        .iter()
+        .find(|t| t.id == id)
+        .map(|t| t.effective_log_directory(&config.global).to_path_buf())
+        .ok_or_else(|| errors::tunnel::NO_LOGS.to_string())
+}
+
+// Adds the tunnel in a snippet exported from this or another manager. The
+// summary asks for the redacted values to be filled in when there are any.
+async fn import_tunnel_snippet(
+    backend: &mut dyn Backend,
+    contents: &str,
+    format: ConfigFormat,
+) -> Result<String, String> {
+    let tunnel = parse_tunnel_snippet(contents, format).map_err(|e| format!("{:#}", e))?;
+    let tag = tunnel.tag.clone();
+    let redacted = contents.contains(crate::constants::REDACTED);
+    backend
+        .add_tunnel(tunnel)
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+    Ok(match redacted {
+        true => errors::config::tunnel_imported_with_redactions(&tag),
+        false => errors::config::tunnel_imported(&tag),
+    })
+}
+
+// Delivers `message` and then shows `toast`.
+fn with_toast(message: Message, toast: Toast) -> iced::Task<Message> {
+    iced::Task::batch([
+        iced::Task::done(message),
+        iced::Task::done(Message::Notify(toast)),
+    ])
 }
 
 impl WstunnelManagerApp {
-    pub fn new(backend: Arc<Mutex<dyn Backend>>) -> Self {
-        let tunnels = {
-            let mut backend_lock = backend.lock().unwrap();
+    pub fn new(
+        backend: SharedBackend,
+        runtime_handle: tokio::runtime::Handle,
+    ) -> (Self, iced::Task<Message>) {
+        let (events, wstunnel_version, theme, refresh_interval, read_only) = {
+            let backend_lock = backend.blocking_lock();
+            let config = backend_lock.get_config();
+            (
+                backend_lock.subscribe(),
+                backend_lock.wstunnel_version(),
+                theme::WstunnelTheme::from_setting(config.global.theme.as_deref()),
+                config.global.ui_refresh_interval(),
+                backend_lock.is_read_only(),
+            )
+        };
+        let startup_backend = Arc::clone(&backend);
+        let startup = on_runtime(&runtime_handle, async move {
+            let mut backend_lock = startup_backend.lock().await;
 
-            if let Err(e) = backend_lock.cleanup_old_logs_if_configured() {
+            if let Err(e) = backend_lock.cleanup_old_logs_if_configured().await {
                 tracing::warn!("Log cleanup failed: {}", e);
             }
 
-            match backend_lock.start_autostart_tunnels() {
+            match backend_lock.start_autostart_tunnels().await {
                 Ok(results) => {
                     for (tunnel_id, result) in results {
                         match result {
@@ -52,30 +200,166 @@ impl WstunnelManagerApp {
                 }
             }
 
-            backend_lock.list_tunnels()
-        };
+            Ok((
+                backend_lock.list_tunnels(),
+                backend_lock.wstunnel_version(),
+                backend_lock.config_generation(),
+                backend_lock.summary(),
+            ))
+        });
 
-        Self {
+        let app = Self {
             screen: Screen::default(),
             backend,
-            tunnels,
-            theme: theme::WstunnelTheme::new(),
+            runtime_handle,
+            events,
+            tunnels: Vec::new(),
+            wstunnel_version,
+            theme,
+            window_geometry: window_state::WindowGeometry::default(),
+            window_state_path: None,
+            toasts: Vec::new(),
+            command_palette: None,
+            disk_space_warning: None,
+            flap_warnings: std::collections::HashMap::new(),
+            config_changed_on_disk: false,
+            read_only,
+            refresh_interval,
+            config_generation: None,
+            summary: TunnelSummary::default(),
+            #[cfg(feature = "tray")]
+            tray: None,
+        };
+
+        (app, iced::Task::perform(startup, tunnels_loaded))
+    }
+
+    // The window opens with `geometry`; whatever it is when the app quits is
+    // written back to `path`.
+    pub fn remember_window(&mut self, path: PathBuf, geometry: window_state::WindowGeometry) {
+        self.window_state_path = Some(path);
+        self.window_geometry = geometry;
+    }
+
+    fn save_window_state(&self) {
+        if let Some(path) = &self.window_state_path
+            && let Err(e) = window_state::save(path, &self.window_geometry)
+        {
+            tracing::warn!("{:#}", e);
         }
     }
 
+    // Opens on the setup screen, prefilled with `binary_path` when it exists
+    // and with the first wstunnel on PATH otherwise.
+    pub fn show_setup(&mut self, binary_path: PathBuf) {
+        let log_directory = self
+            .backend
+            .blocking_lock()
+            .get_config()
+            .global
+            .log_directory
+            .clone();
+        let binary_path = Some(binary_path)
+            .filter(|path| path.exists())
+            .or_else(crate::backend::process::detect_wstunnel_binary);
+        self.screen = Screen::Setup(SetupState::new(binary_path, log_directory));
+    }
+
+    // With the tray enabled, closing the window hides it instead of exiting.
+    #[cfg(feature = "tray")]
+    pub fn enable_tray(&mut self) {
+        self.tray = Some(tray::Tray::new());
+    }
+
+    // Shows the running count in the OS window list, e.g.
+    // "wstunnel Manager (3/5 running, wstunnel 10.1.8)".
     pub fn title(&self) -> String {
-        crate::constants::APP_TITLE.to_string()
+        let mut details = Vec::new();
+        if self.summary.total > 0 {
+            details.push(format!(
+                "{}/{} running",
+                self.summary.running, self.summary.total
+            ));
+        }
+        if self.summary.failed > 0 {
+            details.push(format!("{} failed", self.summary.failed));
+        }
+        if let Some(version) = &self.wstunnel_version {
+            details.push(format!("wstunnel {}", version));
+        }
+        match details.is_empty() {
+            true => crate::constants::APP_TITLE.to_string(),
+            false => format!("{} ({})", crate::constants::APP_TITLE, details.join(", ")),
+        }
     }
 
     pub fn view(&self) -> iced::Element<'_, Message> {
+        let mut content = self.screen_view();
+        if let Some(palette) = &self.command_palette {
+            content = iced::widget::stack![
+                content,
+                screens::command_palette::command_palette_view(palette, &self.tunnels)
+            ]
+            .into();
+        }
+        if self.toasts.is_empty() {
+            return content;
+        }
+        iced::widget::stack![content, screens::toast::toast_overlay(&self.toasts)].into()
+    }
+
+    fn screen_view(&self) -> iced::Element<'_, Message> {
         match &self.screen {
-            Screen::TunnelList(state) => {
-                screens::tunnel_list::tunnel_list_view(state.clone(), self.tunnels.clone())
+            Screen::TunnelList(state) => screens::tunnel_list::tunnel_list_view(
+                state.clone(),
+                self.tunnels.clone(),
+                self.wstunnel_version.clone(),
+                self.disk_space_warning.clone(),
+                self.flap_warnings.clone(),
+                self.config_changed_on_disk,
+                self.read_only,
+                self.theme.to_iced_theme(),
+            ),
+            Screen::TunnelDetail(state) => {
+                match self.tunnels.iter().find(|t| t.id == state.tunnel_id) {
+                    Some(tunnel) => {
+                        let dependencies = tunnel
+                            .depends_on
+                            .iter()
+                            .filter_map(|id| self.tunnels.iter().find(|t| t.id == *id))
+                            .cloned()
+                            .collect();
+                        screens::tunnel_detail::tunnel_detail_view(
+                            state.clone(),
+                            tunnel.clone(),
+                            dependencies,
+                            self.read_only,
+                        )
+                    }
+                    None => screens::tunnel_list::tunnel_list_view(
+                        state::TunnelListState::default(),
+                        self.tunnels.clone(),
+                        self.wstunnel_version.clone(),
+                        self.disk_space_warning.clone(),
+                        self.flap_warnings.clone(),
+                        self.config_changed_on_disk,
+                        self.read_only,
+                        self.theme.to_iced_theme(),
+                    ),
+                }
             }
             Screen::EditTunnel(state) => screens::edit_tunnel::edit_tunnel_view(state.clone()),
             Screen::ConfirmDelete(state) => {
                 screens::tunnel_list::confirm_delete_view(state.clone())
             }
+            Screen::ConfirmQuit(state) => screens::tunnel_list::confirm_quit_view(state.clone()),
+            Screen::CombinedLogs(state) => screens::combined_logs::combined_logs_view(
+                state.clone(),
+                state::TunnelChoice::options(&self.tunnels),
+            ),
+            Screen::Setup(state) => screens::setup::setup_view(state.clone()),
+            Screen::Settings(state) => screens::settings::settings_view(state.clone()),
+            Screen::About(state) => screens::about::about_view(state.clone()),
         }
     }
 
@@ -84,146 +368,1427 @@ impl WstunnelManagerApp {
             Message::TunnelList(tunnel_list_msg) => {
                 self.handle_tunnel_list_message(tunnel_list_msg)
             }
+            Message::TunnelDetail(tunnel_detail_msg) => {
+                self.handle_tunnel_detail_message(tunnel_detail_msg)
+            }
             Message::EditTunnel(edit_tunnel_msg) => {
                 self.handle_edit_tunnel_message(edit_tunnel_msg)
             }
             Message::ConfirmDelete(confirm_delete_msg) => {
                 self.handle_confirm_delete_message(confirm_delete_msg)
             }
+            Message::ConfirmQuit(confirm_quit_msg) => {
+                self.handle_confirm_quit_message(confirm_quit_msg)
+            }
+            Message::CombinedLogs(combined_logs_msg) => {
+                self.handle_combined_logs_message(combined_logs_msg)
+            }
+            Message::Setup(setup_msg) => self.handle_setup_message(setup_msg),
+            Message::Settings(settings_msg) => self.handle_settings_message(settings_msg),
+            Message::About(about_msg) => self.handle_about_message(about_msg),
+            Message::CommandPalette(palette_msg) => {
+                self.handle_command_palette_message(palette_msg)
+            }
             Message::ProcessStatusChanged { id, status } => {
                 self.handle_process_status_changed(id, status)
             }
             Message::ConfigReloaded(config) => self.handle_config_reloaded(config),
+            Message::TunnelsLoaded {
+                tunnels,
+                wstunnel_version,
+                config_generation,
+                summary,
+                flap_warnings,
+            } => {
+                self.tunnels = tunnels;
+                self.wstunnel_version = wstunnel_version;
+                self.config_generation = Some(config_generation);
+                self.summary = summary;
+                self.flap_warnings = flap_warnings;
+                self.tunnels_updated()
+            }
+            Message::StatusesLoaded {
+                statuses,
+                wstunnel_version,
+                summary,
+                flap_warnings,
+            } => {
+                let mut statuses: std::collections::HashMap<TunnelId, TunnelRuntimeState> =
+                    statuses.into_iter().collect();
+                for tunnel in &mut self.tunnels {
+                    tunnel.runtime_state = statuses.remove(&tunnel.id);
+                }
+                self.wstunnel_version = wstunnel_version;
+                self.summary = summary;
+                self.flap_warnings = flap_warnings;
+                self.tunnels_updated()
+            }
+            Message::TunnelEvent(event) => self.handle_tunnel_event(event),
+            Message::WindowCloseRequested(id) => {
+                #[cfg(feature = "tray")]
+                if self.tray.is_some() {
+                    return iced::window::change_mode(id, iced::window::Mode::Hidden);
+                }
+                let _ = id;
+                self.request_quit()
+            }
+            #[cfg(feature = "tray")]
+            Message::ShowWindow => iced::window::get_oldest().and_then(|id| {
+                iced::Task::batch([
+                    iced::window::change_mode(id, iced::window::Mode::Windowed),
+                    iced::window::gain_focus(id),
+                ])
+            }),
+            #[cfg(feature = "tray")]
+            Message::Quit => self.request_quit(),
+            Message::ThemeSelected(theme) => self.select_theme(theme),
+            Message::Notify(toast) => {
+                self.toasts.push(toast);
+                if self.toasts.len() > MAX_TOASTS {
+                    self.toasts.remove(0);
+                }
+                iced::Task::none()
+            }
+            Message::DismissToast(index) => {
+                if index < self.toasts.len() {
+                    self.toasts.remove(index);
+                }
+                iced::Task::none()
+            }
+            Message::ExpireToasts => {
+                self.toasts.retain(|toast| !toast.expired());
+                iced::Task::none()
+            }
+            Message::CheckDiskSpace => self.check_disk_space(),
+            Message::DiskSpaceChecked(warning) => {
+                self.disk_space_warning = warning;
+                iced::Task::none()
+            }
+            Message::RefreshTick => self.refresh_tunnels(),
+            Message::CheckConfigOnDisk => {
+                let backend = Arc::clone(&self.backend);
+                iced::Task::perform(
+                    on_runtime(&self.runtime_handle, async move {
+                        Ok(backend.lock().await.config_changed_on_disk().await)
+                    }),
+                    |result| match result {
+                        Ok(changed) => Message::ConfigOnDiskChecked(changed),
+                        Err(error) => Message::Error(error),
+                    },
+                )
+            }
+            Message::ConfigOnDiskChecked(changed) => {
+                self.config_changed_on_disk = changed;
+                iced::Task::none()
+            }
+            Message::WindowMoved(position) => {
+                self.window_geometry.move_to(position);
+                iced::Task::none()
+            }
+            Message::WindowResized(size) => {
+                self.window_geometry.resize(size);
+                iced::Task::none()
+            }
             Message::Error(error) => self.handle_error(error),
         }
     }
 
+    // Forms with unsaved input and the confirmation dialogs keep the palette
+    // closed, since its actions switch screens.
+    fn handle_command_palette_message(
+        &mut self,
+        message: CommandPaletteMessage,
+    ) -> iced::Task<Message> {
+        let Some(palette) = &mut self.command_palette else {
+            return match message {
+                CommandPaletteMessage::Toggle
+                    if matches!(
+                        self.screen,
+                        Screen::TunnelList(_)
+                            | Screen::TunnelDetail(_)
+                            | Screen::CombinedLogs(_)
+                            | Screen::About(_)
+                    ) =>
+                {
+                    self.command_palette = Some(CommandPaletteState::default());
+                    iced::widget::text_input::focus(screens::command_palette::palette_input_id())
+                }
+                _ => iced::Task::none(),
+            };
+        };
+
+        let entries = palette.entries(&self.tunnels);
+        let chosen = match message {
+            CommandPaletteMessage::Toggle | CommandPaletteMessage::Close => None,
+            CommandPaletteMessage::QueryChanged(query) => {
+                palette.set_query(query);
+                return iced::Task::none();
+            }
+            CommandPaletteMessage::SelectNext | CommandPaletteMessage::SelectPrevious => {
+                let forward = matches!(message, CommandPaletteMessage::SelectNext);
+                palette.step(forward, entries.len());
+                return iced::Task::none();
+            }
+            CommandPaletteMessage::Run => entries.get(palette.selected).cloned(),
+            CommandPaletteMessage::RunEntry(index) => entries.get(index).cloned(),
+        };
+
+        self.command_palette = None;
+        match chosen {
+            Some(entry) => self.handle_tunnel_list_message(entry.action.message(entry.tunnel_id)),
+            None => iced::Task::none(),
+        }
+    }
+
     fn handle_tunnel_list_message(&mut self, message: TunnelListMessage) -> iced::Task<Message> {
+        // The buttons are hidden when read-only, but the command palette
+        // still offers editing.
+        if self.read_only
+            && matches!(
+                message,
+                TunnelListMessage::AddTunnel
+                    | TunnelListMessage::EditTunnel(_)
+                    | TunnelListMessage::DeleteTunnel(_)
+                    | TunnelListMessage::DeleteSelected
+                    | TunnelListMessage::ImportConfig
+                    | TunnelListMessage::ImportTunnel
+                    | TunnelListMessage::RestoreBackup
+                    | TunnelListMessage::OverwriteConfig
+                    | TunnelListMessage::ShowSettings
+            )
+        {
+            return self.handle_error(errors::config::READ_ONLY.to_string());
+        }
+
+        // Start and stop also arrive from the tray menu, and the per-tunnel
+        // actions from the detail screen, so they are handled whichever
+        // screen is showing.
+        let message = match message {
+            TunnelListMessage::StartTunnel(id) => return self.start_tunnel(id),
+            TunnelListMessage::StopTunnel(id) => return self.stop_tunnel(id),
+            TunnelListMessage::ShowDetails(id) => return self.show_details(id),
+            TunnelListMessage::EditTunnel(id) => return self.edit_tunnel(id),
+            TunnelListMessage::DeleteTunnel(id) => return self.confirm_delete(id),
+            TunnelListMessage::OpenLogs(id) => return self.open_logs(id),
+            TunnelListMessage::OpenLogsFolder(id) => return self.open_logs_folder(id),
+            TunnelListMessage::CopyLogsPath(id) => return self.copy_logs_path(id),
+            TunnelListMessage::OpenLocalPort(id) => return self.open_local_port(id),
+            TunnelListMessage::CopyPid(id) => {
+                return match self.tunnels.iter().find(|t| t.id == id) {
+                    Some(TunnelEntry {
+                        runtime_state: Some(TunnelRuntimeState::Running { pid, .. }),
+                        ..
+                    }) => iced::clipboard::write(pid.to_string()),
+                    _ => iced::Task::none(),
+                };
+            }
+            TunnelListMessage::CopyCliArgs(id) => {
+                return match self.tunnels.iter().find(|t| t.id == id) {
+                    Some(tunnel) => iced::clipboard::write(tunnel.cli_args.clone()),
+                    None => iced::Task::none(),
+                };
+            }
+            TunnelListMessage::ExportTunnel(id) => return self.export_tunnel(id, None),
+            TunnelListMessage::ExportTunnelToFile(id) => {
+                let file_name = match self.tunnels.iter().find(|t| t.id == id) {
+                    Some(tunnel) => format!(
+                        "{}.yaml",
+                        crate::backend::process::log_name(id, &tunnel.tag)
+                    ),
+                    None => return iced::Task::none(),
+                };
+                return iced::Task::perform(
+                    async move {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Export tunnel")
+                            .add_filter("YAML", &["yaml", "yml"])
+                            .add_filter("JSON", &["json"])
+                            .add_filter("TOML", &["toml"])
+                            .set_file_name(file_name)
+                            .save_file()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    move |path| {
+                        Message::TunnelList(TunnelListMessage::ExportTunnelFileSelected(id, path))
+                    },
+                );
+            }
+            TunnelListMessage::ExportTunnelFileSelected(id, Some(path)) => {
+                return self.export_tunnel(id, Some(path));
+            }
+            TunnelListMessage::ExportTunnelFileSelected(_, None) => return iced::Task::none(),
+            TunnelListMessage::BulkCompleted(summary) => {
+                if let Screen::TunnelList(state) = &mut self.screen {
+                    state.error_message = Some(summary);
+                }
+                return self.refresh_tunnels();
+            }
+            message => message,
+        };
+
         match &mut self.screen {
             Screen::TunnelList(state) => match message {
                 TunnelListMessage::AddTunnel => {
-                    self.screen = Screen::EditTunnel(EditTunnelState::new_create());
-                    iced::Task::none()
-                }
-                TunnelListMessage::EditTunnel(id) => {
-                    let mut backend = self.backend.lock().unwrap();
-                    match backend.get_tunnel(id) {
-                        Some(tunnel) => {
-                            self.screen = Screen::EditTunnel(EditTunnelState::new_edit(
-                                tunnel.id,
-                                tunnel.tag,
-                                tunnel.cli_args,
-                                tunnel.autostart,
-                            ));
-                        }
-                        None => {
-                            state.error_message =
-                                Some(errors::tunnel::not_found(&format!("{:?}", id)));
-                        }
+                    self.screen = Screen::EditTunnel(EditTunnelState::new_create(&self.tunnels));
+                    iced::Task::none()
+                }
+                TunnelListMessage::StartAll => self.bulk_start_stop(true, None),
+                TunnelListMessage::StopAll => self.bulk_start_stop(false, None),
+                TunnelListMessage::StartGroup(group) => self.bulk_start_stop(true, Some(group)),
+                TunnelListMessage::StopGroup(group) => self.bulk_start_stop(false, Some(group)),
+                TunnelListMessage::SelectTunnel(id, selected) => {
+                    match selected {
+                        true => state.selected.insert(id),
+                        false => state.selected.remove(&id),
+                    };
+                    iced::Task::none()
+                }
+                TunnelListMessage::ClearSelection => {
+                    state.selected.clear();
+                    iced::Task::none()
+                }
+                TunnelListMessage::DeleteSelected => {
+                    let selected = std::mem::take(&mut state.selected);
+                    let targets: Vec<&TunnelEntry> = self
+                        .tunnels
+                        .iter()
+                        .filter(|t| selected.contains(&t.id))
+                        .collect();
+                    if !targets.is_empty() {
+                        self.screen =
+                            Screen::ConfirmDelete(ConfirmDeleteState::for_tunnels(targets));
                     }
                     iced::Task::none()
                 }
-                TunnelListMessage::DeleteTunnel(id) => {
-                    let mut backend = self.backend.lock().unwrap();
-                    match backend.get_tunnel(id) {
-                        Some(tunnel) => {
-                            self.screen = Screen::ConfirmDelete(ConfirmDeleteState::new(
-                                tunnel.id, tunnel.tag,
-                            ));
-                        }
-                        None => {
-                            state.error_message =
-                                Some(errors::tunnel::not_found(&format!("{:?}", id)));
-                        }
+                TunnelListMessage::ToggleGroup(group) => {
+                    if !state.collapsed_groups.remove(&group) {
+                        state.collapsed_groups.insert(group);
                     }
                     iced::Task::none()
                 }
-                TunnelListMessage::StartTunnel(id) => {
+                TunnelListMessage::StartTunnel(_)
+                | TunnelListMessage::StopTunnel(_)
+                | TunnelListMessage::BulkCompleted(_)
+                | TunnelListMessage::ShowDetails(_)
+                | TunnelListMessage::EditTunnel(_)
+                | TunnelListMessage::DeleteTunnel(_)
+                | TunnelListMessage::OpenLogs(_)
+                | TunnelListMessage::OpenLogsFolder(_)
+                | TunnelListMessage::CopyLogsPath(_)
+                | TunnelListMessage::CopyPid(_)
+                | TunnelListMessage::CopyCliArgs(_)
+                | TunnelListMessage::ExportTunnel(_)
+                | TunnelListMessage::ExportTunnelToFile(_)
+                | TunnelListMessage::ExportTunnelFileSelected(..)
+                | TunnelListMessage::OpenLocalPort(_) => iced::Task::none(),
+                TunnelListMessage::ImportConfig => iced::Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Import configuration")
+                            .add_filter("Config", &["yaml", "yml", "json", "toml"])
+                            .pick_file()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    |path| Message::TunnelList(TunnelListMessage::ImportFileSelected(path)),
+                ),
+                TunnelListMessage::ImportFileSelected(path) => {
+                    let Some(path) = path else {
+                        return iced::Task::none();
+                    };
                     let backend = Arc::clone(&self.backend);
-                    iced::Task::perform(
-                        async move {
-                            let mut backend_lock = backend.lock().unwrap();
-                            match backend_lock.start_tunnel(id) {
-                                Ok(pid) => {
-                                    let status = backend_lock.get_tunnel_status(id);
-                                    Ok((id, status, pid))
-                                }
-                                Err(e) => Err(e.to_string()),
-                            }
-                        },
-                        |result| match result {
-                            Ok((id, status, _pid)) => Message::ProcessStatusChanged { id, status },
-                            Err(error) => Message::Error(error),
-                        },
-                    )
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        // A file holding a single tunnel, as written by the
+                        // tunnel export, adds just that tunnel.
+                        let format = ConfigFormat::from_path(&path);
+                        let snippet = tokio::fs::read_to_string(&path)
+                            .await
+                            .ok()
+                            .filter(|contents| is_tunnel_snippet(contents, format));
+                        let mut backend_lock = backend.lock().await;
+                        if let Some(contents) = snippet {
+                            return import_tunnel_snippet(&mut *backend_lock, &contents, format)
+                                .await;
+                        }
+                        backend_lock
+                            .import_config(&path)
+                            .await
+                            .map(|count| {
+                                errors::config::imported(count, &path.display().to_string())
+                            })
+                            .map_err(|e| format!("{:#}", e))
+                    }))
+                    .then(|result| match result {
+                        Ok(summary) => with_toast(
+                            Message::TunnelList(TunnelListMessage::Refresh),
+                            Toast::success(summary),
+                        ),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
+                }
+                TunnelListMessage::ImportTunnel => iced::clipboard::read().map(|contents| {
+                    Message::TunnelList(TunnelListMessage::ImportTunnelSnippet(contents))
+                }),
+                TunnelListMessage::ImportTunnelSnippet(None) => iced::Task::done(Message::Error(
+                    errors::config::TUNNEL_SNIPPET_INVALID.to_string(),
+                )),
+                TunnelListMessage::ImportTunnelSnippet(Some(contents)) => {
+                    let backend = Arc::clone(&self.backend);
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        let format = ConfigFormat::guess(&contents);
+                        import_tunnel_snippet(&mut *backend.lock().await, &contents, format).await
+                    }))
+                    .then(|result| match result {
+                        Ok(summary) => with_toast(
+                            Message::TunnelList(TunnelListMessage::Refresh),
+                            Toast::success(summary),
+                        ),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
+                }
+                TunnelListMessage::RestoreBackup => iced::Task::perform(
+                    async {
+                        rfd::AsyncMessageDialog::new()
+                            .set_title("Restore configuration")
+                            .set_description(
+                                "Roll the configuration back to the version before the last save? Tunnels that are not in that version will be stopped.",
+                            )
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show()
+                            .await
+                            == rfd::MessageDialogResult::Yes
+                    },
+                    |confirmed| {
+                        Message::TunnelList(TunnelListMessage::RestoreBackupConfirmed(confirmed))
+                    },
+                ),
+                TunnelListMessage::ReloadConfig | TunnelListMessage::OverwriteConfig => {
+                    let reload = matches!(message, TunnelListMessage::ReloadConfig);
+                    let backend = Arc::clone(&self.backend);
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        let mut backend_lock = backend.lock().await;
+                        let result = match reload {
+                            true => backend_lock.reload_config().await,
+                            false => backend_lock.overwrite_config().await,
+                        };
+                        let path = backend_lock.config_path().display().to_string();
+                        result.map(|()| path).map_err(|e| format!("{:#}", e))
+                    }))
+                    .then(move |result| match result {
+                        Ok(path) => iced::Task::batch([
+                            iced::Task::done(Message::ConfigOnDiskChecked(false)),
+                            with_toast(
+                                Message::TunnelList(TunnelListMessage::Refresh),
+                                Toast::success(match reload {
+                                    true => errors::config::reloaded(&path),
+                                    false => errors::config::overwritten(&path),
+                                }),
+                            ),
+                        ]),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
+                }
+                TunnelListMessage::RestoreBackupConfirmed(false) => iced::Task::none(),
+                TunnelListMessage::RestoreBackupConfirmed(true) => {
+                    let backend = Arc::clone(&self.backend);
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        let mut backend_lock = backend.lock().await;
+                        backend_lock
+                            .restore_config_backup()
+                            .await
+                            .map_err(|e| format!("{:#}", e))
+                    }))
+                    .then(|result| match result {
+                        Ok(()) => with_toast(
+                            Message::TunnelList(TunnelListMessage::Refresh),
+                            Toast::success("Restored the previous configuration"),
+                        ),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
+                }
+                TunnelListMessage::ExportConfig => iced::Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Export configuration")
+                            .add_filter("YAML", &["yaml", "yml"])
+                            .add_filter("JSON", &["json"])
+                            .add_filter("TOML", &["toml"])
+                            .set_file_name("wstunnel_config.yaml")
+                            .save_file()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    |path| Message::TunnelList(TunnelListMessage::ExportFileSelected(path)),
+                ),
+                TunnelListMessage::ExportFileSelected(path) => {
+                    let Some(path) = path else {
+                        return iced::Task::none();
+                    };
+                    let backend = Arc::clone(&self.backend);
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        let backend_lock = backend.lock().await;
+                        let mut config = (*backend_lock.get_config()).clone();
+                        config
+                            .tunnels
+                            .iter_mut()
+                            .for_each(|t| t.runtime_state = None);
+                        backend_lock
+                            .save_config(&config, &path)
+                            .await
+                            .map(|()| errors::config::exported(&path.display().to_string()))
+                            .map_err(|e| format!("{:#}", e))
+                    }))
+                    .then(|result| match result {
+                        Ok(summary) => with_toast(
+                            Message::TunnelList(TunnelListMessage::Refresh),
+                            Toast::success(summary),
+                        ),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
+                }
+                TunnelListMessage::Refresh => self.refresh_tunnels(),
+                TunnelListMessage::DismissError => {
+                    state.error_message = None;
+                    iced::Task::none()
+                }
+                TunnelListMessage::FilterChanged(filter) => {
+                    state.filter = filter;
+                    iced::Task::none()
                 }
-                TunnelListMessage::StopTunnel(id) => {
+                TunnelListMessage::QuickFilterSelected(quick_filter) => {
+                    state.quick_filter = quick_filter;
+                    iced::Task::none()
+                }
+                TunnelListMessage::SortOrderSelected(sort_order) => {
+                    state.sort_order = sort_order;
+                    iced::Task::none()
+                }
+                TunnelListMessage::ShowCombinedLogs => {
+                    self.screen = Screen::CombinedLogs(CombinedLogsState::default());
+                    self.load_combined_logs()
+                }
+                TunnelListMessage::ShowSettings => {
                     let backend = Arc::clone(&self.backend);
                     iced::Task::perform(
-                        async move {
-                            let mut backend_lock = backend.lock().unwrap();
-                            match backend_lock.stop_tunnel(id) {
-                                Ok(_) => {
-                                    let status = backend_lock.get_tunnel_status(id);
-                                    Ok((id, status))
-                                }
-                                Err(e) => Err(e.to_string()),
-                            }
-                        },
+                        on_runtime(&self.runtime_handle, async move {
+                            Ok(backend.lock().await.get_config().global.clone())
+                        }),
                         |result| match result {
-                            Ok((id, status)) => Message::ProcessStatusChanged { id, status },
+                            Ok(settings) => Message::Settings(SettingsMessage::Loaded(settings)),
                             Err(error) => Message::Error(error),
                         },
                     )
                 }
-                TunnelListMessage::OpenLogs(id) => {
+                TunnelListMessage::ShowAbout => {
                     let backend = Arc::clone(&self.backend);
                     iced::Task::perform(
-                        async move {
-                            let backend_lock = backend.lock().unwrap();
-                            match backend_lock.get_log_path(id) {
-                                Some(path) => {
-                                    if path.exists() {
-                                        match open::that(&path) {
-                                            Ok(_) => Ok(()),
-                                            Err(e) => {
-                                                Err(errors::logs::failed_to_open(&e.to_string()))
-                                            }
-                                        }
-                                    } else {
-                                        Err(errors::logs::not_found(&path.display().to_string()))
-                                    }
-                                }
-                                None => Err(errors::tunnel::NO_LOGS.to_string()),
-                            }
-                        },
+                        on_runtime(&self.runtime_handle, async move {
+                            Ok(AboutState::from_backend(&*backend.lock().await))
+                        }),
                         |result| match result {
-                            Ok(_) => Message::TunnelList(TunnelListMessage::Refresh),
+                            Ok(state) => Message::About(AboutMessage::Loaded(state)),
                             Err(error) => Message::Error(error),
                         },
                     )
                 }
-                TunnelListMessage::Refresh => {
-                    self.refresh_tunnels();
+            },
+            Screen::TunnelDetail(_)
+            | Screen::EditTunnel(_)
+            | Screen::ConfirmDelete(_)
+            | Screen::ConfirmQuit(_)
+            | Screen::CombinedLogs(_)
+            | Screen::Setup(_)
+            | Screen::Settings(_)
+            | Screen::About(_) => iced::Task::none(),
+        }
+    }
+
+    fn show_details(&mut self, id: TunnelId) -> iced::Task<Message> {
+        if !self.tunnels.iter().any(|t| t.id == id) {
+            return self.handle_error(errors::tunnel::not_found(&format!("{:?}", id)));
+        }
+        self.screen = Screen::TunnelDetail(TunnelDetailState::new(id));
+        self.load_details(id)
+    }
+
+    fn edit_tunnel(&mut self, id: TunnelId) -> iced::Task<Message> {
+        match self.tunnels.iter().find(|t| t.id == id).cloned() {
+            Some(tunnel) => {
+                self.screen = Screen::EditTunnel(EditTunnelState::new_edit(tunnel, &self.tunnels));
+                iced::Task::none()
+            }
+            None => self.handle_error(errors::tunnel::not_found(&format!("{:?}", id))),
+        }
+    }
+
+    fn confirm_delete(&mut self, id: TunnelId) -> iced::Task<Message> {
+        match self.tunnels.iter().find(|t| t.id == id) {
+            Some(tunnel) => {
+                self.screen = Screen::ConfirmDelete(ConfirmDeleteState::new(tunnel));
+                iced::Task::none()
+            }
+            None => self.handle_error(errors::tunnel::not_found(&format!("{:?}", id))),
+        }
+    }
+
+    fn open_logs(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let backend_lock = backend.lock().await;
+                match backend_lock.get_log_path(id) {
+                    Some(path) if path.exists() => {
+                        let path = if is_compressed_log_file(&path) {
+                            decompress_to_temp(&path)
+                                .map_err(|e| errors::logs::failed_to_decompress(&e.to_string()))?
+                        } else {
+                            path
+                        };
+                        open::that(&path)
+                            .map(|_| None)
+                            .map_err(|e| errors::logs::failed_to_open(&e.to_string()))
+                    }
+                    // The log was cleaned up or never written; the directory
+                    // may still hold other runs' logs.
+                    _ => {
+                        let config = backend_lock.get_config();
+                        let directory = config
+                            .tunnels
+                            .iter()
+                            .find(|t| t.id == id)
+                            .map(|t| t.effective_log_directory(&config.global).to_path_buf())
+                            .filter(|directory| directory.is_dir())
+                            .ok_or_else(|| errors::tunnel::NO_LOGS.to_string())?;
+                        open::that(&directory)
+                            .map(|_| {
+                                Some(errors::logs::opened_directory(
+                                    &directory.display().to_string(),
+                                ))
+                            })
+                            .map_err(|e| errors::logs::failed_to_open(&e.to_string()))
+                    }
+                }
+            }),
+            // Usually just a tunnel that hasn't written logs yet.
+            |result| match result {
+                Ok(None) => Message::TunnelList(TunnelListMessage::Refresh),
+                Ok(Some(note)) => Message::Notify(Toast::success(note)),
+                Err(error) => Message::Notify(Toast::error(error)),
+            },
+        )
+    }
+
+    // Copies the tunnel's snippet to the clipboard, or writes it to `path` in
+    // the format its extension names.
+    fn export_tunnel(&self, id: TunnelId, path: Option<PathBuf>) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::future(on_runtime(&self.runtime_handle, async move {
+            let config = backend.lock().await.get_config();
+            let tunnel = config
+                .tunnels
+                .iter()
+                .find(|t| t.id == id)
+                .ok_or_else(|| errors::tunnel::not_found(&format!("{:?}", id)))?;
+            let format = path
+                .as_deref()
+                .map_or(ConfigFormat::Yaml, ConfigFormat::from_path);
+            let snippet = tunnel_snippet(tunnel, &config.global.sensitive_flags, format)
+                .map_err(|e| format!("{:#}", e))?;
+            match path {
+                Some(path) => {
+                    tokio::fs::write(&path, snippet).await.map_err(|e| {
+                        errors::config::tunnel_export_failed(
+                            &path.display().to_string(),
+                            &e.to_string(),
+                        )
+                    })?;
+                    let summary =
+                        errors::config::tunnel_exported(&tunnel.tag, &path.display().to_string());
+                    Ok((None, summary))
+                }
+                None => Ok((
+                    Some(snippet),
+                    errors::config::tunnel_exported(&tunnel.tag, "the clipboard"),
+                )),
+            }
+        }))
+        .then(|result| match result {
+            Ok((snippet, summary)) => iced::Task::batch([
+                snippet.map_or_else(iced::Task::none, iced::clipboard::write),
+                iced::Task::done(Message::Notify(Toast::success(summary))),
+            ]),
+            Err(error) => iced::Task::done(Message::Notify(Toast::error(error))),
+        })
+    }
+
+    fn open_logs_folder(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let location = logs_location(&*backend.lock().await, id)?;
+                // Rotated and compressed logs sit next to the current file.
+                let directory = if location.is_file() {
+                    location.parent().map(PathBuf::from).unwrap_or(location)
+                } else {
+                    location
+                };
+                if !directory.is_dir() {
+                    return Err(errors::logs::directory_not_found(
+                        &directory.display().to_string(),
+                    ));
+                }
+                open::that(&directory).map_err(|e| errors::logs::failed_to_open(&e.to_string()))
+            }),
+            |result| match result {
+                Ok(()) => Message::TunnelList(TunnelListMessage::Refresh),
+                Err(error) => Message::Notify(Toast::error(error)),
+            },
+        )
+    }
+
+    fn copy_logs_path(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                logs_location(&*backend.lock().await, id)
+            }),
+            std::convert::identity,
+        )
+        .then(|result| match result {
+            Ok(path) => iced::clipboard::write(path.display().to_string()),
+            Err(error) => iced::Task::done(Message::Notify(Toast::error(error))),
+        })
+    }
+
+    fn open_local_port(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let backend_lock = backend.lock().await;
+                let config = backend_lock.get_config();
+                let address = config
+                    .tunnels
+                    .iter()
+                    .find(|t| t.id == id)
+                    .and_then(TunnelEntry::local_address)
+                    .ok_or_else(|| errors::tunnel::NO_LOCAL_PORT.to_string())?;
+                let scheme = config.global.local_port_scheme.as_deref().unwrap_or("http");
+                let url = format!("{}://{}", scheme, address);
+                open::that(&url)
+                    .map_err(|e| errors::tunnel::failed_to_open_url(&url, &e.to_string()))
+            }),
+            |result| match result {
+                Ok(()) => Message::TunnelList(TunnelListMessage::Refresh),
+                Err(error) => Message::Notify(Toast::error(error)),
+            },
+        )
+    }
+
+    // Applies the theme right away and saves it as `global.theme`.
+    fn select_theme(&mut self, theme: iced::Theme) -> iced::Task<Message> {
+        let name = theme.to_string();
+        self.theme.set(theme);
+        // Applies to this session only.
+        if self.read_only {
+            return iced::Task::none();
+        }
+
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let mut backend_lock = backend.lock().await;
+                let mut settings = backend_lock.get_config().global.clone();
+                settings.theme = Some(name);
+                backend_lock
+                    .update_global_settings(settings)
+                    .await
+                    .map_err(|e| e.to_string())
+            }),
+            |result| match result {
+                Ok(()) => Message::TunnelList(TunnelListMessage::Refresh),
+                Err(error) => Message::Notify(Toast::error(error)),
+            },
+        )
+    }
+
+    fn tunnel_tag(&self, id: TunnelId) -> String {
+        self.tunnels
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.tag.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    fn start_tunnel(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        let tag = self.tunnel_tag(id);
+        iced::Task::future(on_runtime(&self.runtime_handle, async move {
+            let mut backend_lock = backend.lock().await;
+            match backend_lock.start_tunnel(id).await {
+                Ok(_) => Ok((id, backend_lock.get_tunnel_status(id), true)),
+                // The list was stale; show the state the tunnel is really in.
+                Err(BackendError::AlreadyRunning(_) | BackendError::Transitioning(_)) => {
+                    Ok((id, backend_lock.get_tunnel_status(id), false))
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        }))
+        .then(move |result| match result {
+            Ok((id, status, true)) => with_toast(
+                Message::ProcessStatusChanged { id, status },
+                Toast::success(format!("Started {}", tag)),
+            ),
+            Ok((id, status, false)) => {
+                iced::Task::done(Message::ProcessStatusChanged { id, status })
+            }
+            Err(error) => iced::Task::done(Message::Error(error)),
+        })
+    }
+
+    fn stop_tunnel(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        let tag = self.tunnel_tag(id);
+        iced::Task::future(on_runtime(&self.runtime_handle, async move {
+            let mut backend_lock = backend.lock().await;
+            match backend_lock.stop_tunnel(id).await {
+                // Already exited on its own; either way it is stopped now.
+                Ok(_) | Err(BackendError::NotRunning | BackendError::AlreadyStopping) => {
+                    Ok((id, backend_lock.get_tunnel_status(id)))
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        }))
+        .then(move |result| match result {
+            Ok((id, status)) => with_toast(
+                Message::ProcessStatusChanged { id, status },
+                Toast::success(format!("Stopped {}", tag)),
+            ),
+            Err(error) => iced::Task::done(Message::Error(error)),
+        })
+    }
+
+    fn handle_tunnel_detail_message(
+        &mut self,
+        message: TunnelDetailMessage,
+    ) -> iced::Task<Message> {
+        match &mut self.screen {
+            Screen::TunnelDetail(state) => match message {
+                TunnelDetailMessage::Back => {
+                    self.screen = Screen::default();
+                    iced::Task::none()
+                }
+                TunnelDetailMessage::Tick => {
+                    let tunnel_id = state.tunnel_id;
+                    self.load_details(tunnel_id)
+                }
+                TunnelDetailMessage::DetailsLoaded {
+                    stats,
+                    log_path,
+                    log_file,
+                    sensitive_flags,
+                    recent_stderr,
+                } => {
+                    state.stats = stats;
+                    state.log_path = log_path;
+                    state.log_file = log_file;
+                    state.sensitive_flags = sensitive_flags;
+                    state.recent_stderr = recent_stderr;
                     iced::Task::none()
                 }
-                TunnelListMessage::DismissError => {
+                TunnelDetailMessage::PreviewCommand => {
+                    let tunnel_id = state.tunnel_id;
+                    self.preview_command(tunnel_id)
+                }
+                TunnelDetailMessage::CommandPreviewLoaded(command) => {
+                    state.command_preview = Some(command);
+                    iced::Task::none()
+                }
+                TunnelDetailMessage::CopyCommandPreview => match &state.command_preview {
+                    Some(command) => iced::clipboard::write(command.clone()),
+                    None => iced::Task::none(),
+                },
+                TunnelDetailMessage::HideCommandPreview => {
+                    state.command_preview = None;
+                    iced::Task::none()
+                }
+                TunnelDetailMessage::DismissError => {
                     state.error_message = None;
                     iced::Task::none()
                 }
             },
-            Screen::EditTunnel(_) | Screen::ConfirmDelete(_) => iced::Task::none(),
+            Screen::TunnelList(_)
+            | Screen::EditTunnel(_)
+            | Screen::ConfirmDelete(_)
+            | Screen::ConfirmQuit(_)
+            | Screen::CombinedLogs(_)
+            | Screen::Setup(_)
+            | Screen::Settings(_)
+            | Screen::About(_) => iced::Task::none(),
         }
     }
 
+    fn handle_setup_message(&mut self, message: SetupMessage) -> iced::Task<Message> {
+        let Screen::Setup(state) = &mut self.screen else {
+            return iced::Task::none();
+        };
+        match message {
+            SetupMessage::BinaryPathChanged(path) => {
+                state.binary_path_input = path;
+                iced::Task::none()
+            }
+            SetupMessage::BrowseBinary => iced::Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select the wstunnel binary")
+                        .pick_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path| Message::Setup(SetupMessage::BinarySelected(path)),
+            ),
+            SetupMessage::BinarySelected(path) => {
+                if let Some(path) = path {
+                    state.binary_path_input = path.display().to_string();
+                }
+                iced::Task::none()
+            }
+            SetupMessage::DetectBinary => {
+                match crate::backend::process::detect_wstunnel_binary() {
+                    Some(path) => {
+                        state.binary_path_input = path.display().to_string();
+                        state.error_message = None;
+                    }
+                    None => state.error_message = Some(errors::binary::NOT_ON_PATH.to_string()),
+                }
+                iced::Task::none()
+            }
+            SetupMessage::LogDirectoryChanged(path) => {
+                state.log_directory_input = path;
+                iced::Task::none()
+            }
+            SetupMessage::BrowseLogDirectory => iced::Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select log directory")
+                        .pick_folder()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path| Message::Setup(SetupMessage::LogDirectorySelected(path)),
+            ),
+            SetupMessage::LogDirectorySelected(path) => {
+                if let Some(path) = path {
+                    state.log_directory_input = path.display().to_string();
+                }
+                iced::Task::none()
+            }
+            SetupMessage::Save => {
+                let backend = Arc::clone(&self.backend);
+                let state = state.clone();
+                iced::Task::perform(
+                    on_runtime(&self.runtime_handle, async move {
+                        let mut backend_lock = backend.lock().await;
+                        let mut settings = backend_lock.get_config().global.clone();
+                        state.apply_to(&mut settings);
+                        backend_lock
+                            .update_global_settings(settings)
+                            .await
+                            .map_err(|e| format!("{:#}", e))
+                    }),
+                    |result| Message::Setup(SetupMessage::Saved(result)),
+                )
+            }
+            SetupMessage::Saved(Ok(())) => {
+                self.screen = Screen::default();
+                with_toast(
+                    Message::TunnelList(TunnelListMessage::Refresh),
+                    Toast::success("Settings saved"),
+                )
+            }
+            SetupMessage::Saved(Err(error)) => {
+                state.error_message = Some(error);
+                iced::Task::none()
+            }
+            SetupMessage::Skip => {
+                self.screen = Screen::default();
+                iced::Task::none()
+            }
+        }
+    }
+
+    fn handle_settings_message(&mut self, message: SettingsMessage) -> iced::Task<Message> {
+        if let SettingsMessage::Loaded(settings) = message {
+            self.screen = Screen::Settings(SettingsState::new(&settings));
+            return self.measure_logs();
+        }
+        let Screen::Settings(state) = &mut self.screen else {
+            return iced::Task::none();
+        };
+        match message {
+            SettingsMessage::Loaded(_) => iced::Task::none(),
+            SettingsMessage::LogUsageLoaded(bytes) => {
+                state.log_usage = Some(bytes);
+                iced::Task::none()
+            }
+            SettingsMessage::CleanupDaysChanged(days) => {
+                state.cleanup_days_input = days;
+                iced::Task::none()
+            }
+            SettingsMessage::CleanUpLogs => {
+                let days = match state.cleanup_days() {
+                    Ok(days) => days,
+                    Err(error) => {
+                        state.error_message = Some(error);
+                        return iced::Task::none();
+                    }
+                };
+                state.cleaning_logs = true;
+                let backend = Arc::clone(&self.backend);
+                iced::Task::perform(
+                    on_runtime(&self.runtime_handle, async move {
+                        let backend_lock = backend.lock().await;
+                        let deleted = backend_lock
+                            .cleanup_logs_now(days)
+                            .await
+                            .map_err(|e| format!("{:#}", e))?;
+                        let directories = backend_lock.get_config().log_directories();
+                        Ok((
+                            deleted,
+                            crate::backend::logs::log_directory_size(&directories),
+                        ))
+                    }),
+                    |result| Message::Settings(SettingsMessage::LogsCleanedUp(result)),
+                )
+            }
+            SettingsMessage::LogsCleanedUp(Ok((deleted, bytes))) => {
+                let freed = state.log_usage.unwrap_or(bytes).saturating_sub(bytes);
+                state.log_usage = Some(bytes);
+                state.cleaning_logs = false;
+                state.error_message = None;
+                iced::Task::done(Message::Notify(Toast::success(errors::logs::cleaned_up(
+                    deleted,
+                    &format_size(freed),
+                ))))
+            }
+            SettingsMessage::LogsCleanedUp(Err(error)) => {
+                state.cleaning_logs = false;
+                state.error_message = Some(error);
+                iced::Task::none()
+            }
+            SettingsMessage::FieldChanged(field, value) => {
+                *state.input_mut(field) = value;
+                iced::Task::none()
+            }
+            SettingsMessage::Toggled(toggle, checked) => {
+                *state.toggle_mut(toggle) = checked;
+                iced::Task::none()
+            }
+            SettingsMessage::LogModeSelected(mode) => {
+                state.log_mode = mode;
+                iced::Task::none()
+            }
+            SettingsMessage::ThemeSelected(theme) => {
+                state.theme = theme;
+                iced::Task::none()
+            }
+            SettingsMessage::BrowseBinary => iced::Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select the wstunnel binary")
+                        .pick_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path| Message::Settings(SettingsMessage::BinarySelected(path)),
+            ),
+            SettingsMessage::DetectBinary => {
+                match crate::backend::process::detect_wstunnel_binary() {
+                    Some(path) => {
+                        state.binary_path_input = path.display().to_string();
+                        state.error_message = None;
+                    }
+                    None => state.error_message = Some(errors::binary::NOT_ON_PATH.to_string()),
+                }
+                iced::Task::none()
+            }
+            SettingsMessage::BinarySelected(path) => {
+                if let Some(path) = path {
+                    state.binary_path_input = path.display().to_string();
+                }
+                iced::Task::none()
+            }
+            SettingsMessage::BrowseLogDirectory => iced::Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select log directory")
+                        .pick_folder()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path| Message::Settings(SettingsMessage::LogDirectorySelected(path)),
+            ),
+            SettingsMessage::LogDirectorySelected(path) => {
+                if let Some(path) = path {
+                    state.log_directory_input = path.display().to_string();
+                }
+                iced::Task::none()
+            }
+            SettingsMessage::Save => {
+                let settings = match state.to_settings() {
+                    Ok(settings) => settings,
+                    Err(error) => {
+                        state.error_message = Some(error);
+                        return iced::Task::none();
+                    }
+                };
+                let backend = Arc::clone(&self.backend);
+                iced::Task::perform(
+                    on_runtime(&self.runtime_handle, async move {
+                        backend
+                            .lock()
+                            .await
+                            .update_global_settings(settings.clone())
+                            .await
+                            .map(|()| settings)
+                            .map_err(|e| format!("{:#}", e))
+                    }),
+                    |result| Message::Settings(SettingsMessage::Saved(result)),
+                )
+            }
+            // The refresh also picks up the version of a newly chosen binary.
+            SettingsMessage::Saved(Ok(settings)) => {
+                self.theme = theme::WstunnelTheme::from_setting(settings.theme.as_deref());
+                self.refresh_interval = settings.ui_refresh_interval();
+                self.screen = Screen::default();
+                with_toast(
+                    Message::TunnelList(TunnelListMessage::Refresh),
+                    Toast::success("Settings saved"),
+                )
+            }
+            SettingsMessage::Saved(Err(error)) => {
+                state.error_message = Some(error);
+                iced::Task::none()
+            }
+            SettingsMessage::Cancel => {
+                self.screen = Screen::default();
+                iced::Task::none()
+            }
+        }
+    }
+
+    // Sizes up the saved log directories.
+    fn measure_logs(&self) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let directories = backend.lock().await.get_config().log_directories();
+                Ok(crate::backend::logs::log_directory_size(&directories))
+            }),
+            |result| match result {
+                Ok(bytes) => Message::Settings(SettingsMessage::LogUsageLoaded(bytes)),
+                Err(error) => Message::Error(error),
+            },
+        )
+    }
+
+    fn handle_about_message(&mut self, message: AboutMessage) -> iced::Task<Message> {
+        if let AboutMessage::Loaded(state) = message {
+            self.screen = Screen::About(state);
+            return iced::Task::none();
+        }
+        let Screen::About(state) = &self.screen else {
+            return iced::Task::none();
+        };
+        match message {
+            AboutMessage::Loaded(_) => iced::Task::none(),
+            AboutMessage::CopyDiagnostics => iced::clipboard::write(state.diagnostics_text()),
+            AboutMessage::OpenLogDirectory => {
+                let directory = &state.log_directory;
+                let result = if directory.is_dir() {
+                    open::that(directory).map_err(|e| errors::logs::failed_to_open(&e.to_string()))
+                } else {
+                    Err(errors::logs::directory_not_found(
+                        &directory.display().to_string(),
+                    ))
+                };
+                match result {
+                    Ok(()) => iced::Task::none(),
+                    Err(error) => iced::Task::done(Message::Notify(Toast::error(error))),
+                }
+            }
+            AboutMessage::Back => {
+                self.screen = Screen::default();
+                iced::Task::none()
+            }
+        }
+    }
+
+    fn handle_combined_logs_message(
+        &mut self,
+        message: CombinedLogsMessage,
+    ) -> iced::Task<Message> {
+        let Screen::CombinedLogs(state) = &mut self.screen else {
+            return iced::Task::none();
+        };
+        match message {
+            CombinedLogsMessage::Back => {
+                self.screen = Screen::default();
+                iced::Task::none()
+            }
+            CombinedLogsMessage::Tick => self.load_combined_logs(),
+            CombinedLogsMessage::Loaded(lines) => {
+                state.lines = lines;
+                iced::Task::none()
+            }
+            CombinedLogsMessage::TunnelSelected(choice) => {
+                state.tunnel_filter = choice;
+                state.history = None;
+                state.current_match = None;
+                iced::Task::none()
+            }
+            CombinedLogsMessage::StreamSelected(filter) => {
+                state.stream_filter = filter;
+                state.current_match = None;
+                iced::Task::none()
+            }
+            CombinedLogsMessage::SearchChanged(pattern) => {
+                let search = LogSearch {
+                    pattern,
+                    ..state.search.clone()
+                };
+                state.set_search(search);
+                iced::Task::none()
+            }
+            CombinedLogsMessage::MatchCaseToggled(case_sensitive) => {
+                let search = LogSearch {
+                    case_sensitive,
+                    ..state.search.clone()
+                };
+                state.set_search(search);
+                iced::Task::none()
+            }
+            CombinedLogsMessage::RegexToggled(regex) => {
+                let search = LogSearch {
+                    regex,
+                    ..state.search.clone()
+                };
+                state.set_search(search);
+                iced::Task::none()
+            }
+            CombinedLogsMessage::NextMatch | CombinedLogsMessage::PreviousMatch => {
+                let forward = matches!(message, CombinedLogsMessage::NextMatch);
+                let line_count = state.visible_lines().len();
+                match state.step_match(forward) {
+                    Some(line) => screens::combined_logs::scroll_to_line(line, line_count),
+                    None => iced::Task::none(),
+                }
+            }
+            CombinedLogsMessage::SearchHistory => {
+                match (state.tunnel_filter.clone(), state.matcher.is_some()) {
+                    (TunnelChoice { id: Some(id), tag }, true) => {
+                        let search = state.search.clone();
+                        self.search_log_history(id, tag, search)
+                    }
+                    _ => iced::Task::none(),
+                }
+            }
+            CombinedLogsMessage::HistoryLoaded(lines) => {
+                state.history = Some(lines);
+                state.current_match = None;
+                iced::Task::none()
+            }
+            CombinedLogsMessage::ClearHistory => {
+                state.history = None;
+                state.current_match = None;
+                iced::Task::none()
+            }
+        }
+    }
+
+    fn search_log_history(
+        &self,
+        id: TunnelId,
+        tag: String,
+        search: LogSearch,
+    ) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                backend
+                    .lock()
+                    .await
+                    .grep_logs(id, &search, LOG_SEARCH_MAX_RESULTS)
+                    .map_err(|e| e.to_string())
+            }),
+            move |result| match result {
+                Ok(lines) => Message::CombinedLogs(CombinedLogsMessage::HistoryLoaded(
+                    lines
+                        .iter()
+                        .map(|line| TaggedLogLine::parse(id, &tag, line))
+                        .collect(),
+                )),
+                Err(error) => Message::Notify(Toast::error(error)),
+            },
+        )
+    }
+
+    fn load_combined_logs(&self) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                Ok(backend
+                    .lock()
+                    .await
+                    .read_combined_logs(COMBINED_LOG_LINES_PER_TUNNEL))
+            }),
+            |result| match result {
+                Ok(lines) => Message::CombinedLogs(CombinedLogsMessage::Loaded(lines)),
+                Err(error) => Message::Error(error),
+            },
+        )
+    }
+
+    // Stats, the last log path and recent stderr are not part of the tunnel list, so the
+    // detail screen fetches them itself on every refresh and tick.
+    fn load_details(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let backend_lock = backend.lock().await;
+                let log_path = backend_lock.get_log_path(id);
+                let log_file = log_path
+                    .as_deref()
+                    .and_then(crate::backend::logs::log_file_info);
+                Ok((
+                    backend_lock.get_tunnel_stats(id),
+                    log_path,
+                    log_file,
+                    backend_lock.get_config().global.sensitive_flags.clone(),
+                    backend_lock.recent_stderr(id),
+                ))
+            }),
+            |result| match result {
+                Ok((stats, log_path, log_file, sensitive_flags, recent_stderr)) => {
+                    Message::TunnelDetail(TunnelDetailMessage::DetailsLoaded {
+                        stats,
+                        log_path,
+                        log_file,
+                        sensitive_flags,
+                        recent_stderr,
+                    })
+                }
+                Err(error) => Message::Error(error),
+            },
+        )
+    }
+
+    fn preview_command(&self, id: TunnelId) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                backend
+                    .lock()
+                    .await
+                    .preview_command(id)
+                    .map_err(|e| format!("{:#}", e))
+            }),
+            |result| match result {
+                Ok(command) => {
+                    Message::TunnelDetail(TunnelDetailMessage::CommandPreviewLoaded(command))
+                }
+                Err(error) => Message::Error(error),
+            },
+        )
+    }
+
+    // Like autostart, a failure doesn't stop the remaining tunnels; the
+    // outcome of each is folded into one summary for the status bar.
+    // Covers every tunnel, or only those of one group.
+    fn bulk_start_stop(&self, start: bool, group: Option<String>) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        iced::Task::future(on_runtime(&self.runtime_handle, async move {
+            let mut backend_lock = backend.lock().await;
+            let tunnels = backend_lock.list_tunnels();
+            let results: Vec<(TunnelId, BackendResult<()>)> = match (&group, start) {
+                (None, true) => (backend_lock.start_all().await)
+                    .into_iter()
+                    .map(|(id, result)| (id, result.map(|_| ())))
+                    .collect(),
+                (None, false) => backend_lock.stop_all().await,
+                (Some(group), _) => {
+                    let ids: Vec<TunnelId> = tunnels
+                        .iter()
+                        .filter(|t| t.group_name() == group)
+                        .filter(|t| backend_lock.is_tunnel_running(t.id) != start)
+                        .map(|t| t.id)
+                        .collect();
+                    let mut results = Vec::new();
+                    for id in ids {
+                        let result = match start {
+                            true => backend_lock.start_tunnel(id).await.map(|_| ()),
+                            false => backend_lock.stop_tunnel(id).await,
+                        };
+                        results.push((id, result));
+                    }
+                    results
+                }
+            };
+
+            let mut succeeded = 0;
+            let mut failures = Vec::new();
+            for (id, result) in results {
+                match result {
+                    Ok(()) => succeeded += 1,
+                    Err(e) => {
+                        let tag = tunnels.iter().find(|t| t.id == id).map(|t| t.tag.clone());
+                        failures.push((tag.unwrap_or_else(|| id.to_string()), e.to_string()));
+                    }
+                }
+            }
+
+            let action = if start { "started" } else { "stopped" };
+            let summary = errors::tunnel::bulk_summary(action, succeeded, &failures);
+            Ok((summary, failures.is_empty()))
+        }))
+        // Failures stay on the error bar so the details can be read.
+        .then(|result| match result {
+            Ok((summary, true)) => with_toast(
+                Message::TunnelList(TunnelListMessage::Refresh),
+                Toast::success(summary),
+            ),
+            Ok((summary, false)) => iced::Task::done(Message::TunnelList(
+                TunnelListMessage::BulkCompleted(summary),
+            )),
+            Err(error) => iced::Task::done(Message::Error(error)),
+        })
+    }
+
     fn handle_edit_tunnel_message(&mut self, message: EditTunnelMessage) -> iced::Task<Message> {
-        match &mut self.screen {
+        let changes_field = !matches!(
+            message,
+            EditTunnelMessage::BrowseLogDirectory
+                | EditTunnelMessage::BrowseWorkingDir
+                | EditTunnelMessage::Save
+                | EditTunnelMessage::Cancel
+                | EditTunnelMessage::DiscardChanges
+                | EditTunnelMessage::KeepEditing
+                | EditTunnelMessage::SaveCompleted(_)
+        );
+        let task = match &mut self.screen {
             Screen::EditTunnel(state) => match message {
                 EditTunnelMessage::TagChanged(new_tag) => {
                     state.tag_input = new_tag;
                     iced::Task::none()
                 }
+                EditTunnelMessage::ModeSelected(tunnel_mode) => {
+                    state.tunnel_mode = tunnel_mode;
+                    iced::Task::none()
+                }
                 EditTunnelMessage::CliArgsChanged(new_args) => {
+                    if let Some(tunnel_mode) = TunnelMode::from_cli_args(&new_args) {
+                        state.tunnel_mode = tunnel_mode;
+                    }
                     state.cli_args_input = new_args;
                     iced::Task::none()
                 }
@@ -231,48 +1796,157 @@ impl WstunnelManagerApp {
                     state.autostart_checkbox = checked;
                     iced::Task::none()
                 }
+                EditTunnelMessage::GroupChanged(group) => {
+                    state.group_input = group;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::EnvKeyChanged(index, key) => {
+                    if let Some(env_var) = state.env_vars.get_mut(index) {
+                        env_var.0 = key;
+                    }
+                    iced::Task::none()
+                }
+                EditTunnelMessage::EnvValueChanged(index, value) => {
+                    if let Some(env_var) = state.env_vars.get_mut(index) {
+                        env_var.1 = value;
+                    }
+                    iced::Task::none()
+                }
+                EditTunnelMessage::AddEnvVar => {
+                    state.env_vars.push((String::new(), String::new()));
+                    iced::Task::none()
+                }
+                EditTunnelMessage::RemoveEnvVar(index) => {
+                    if index < state.env_vars.len() {
+                        state.env_vars.remove(index);
+                    }
+                    iced::Task::none()
+                }
+                EditTunnelMessage::LogDirectoryChanged(path) => {
+                    state.log_directory_input = path;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::BrowseLogDirectory => iced::Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Select log directory")
+                            .pick_folder()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    |path| Message::EditTunnel(EditTunnelMessage::LogDirectorySelected(path)),
+                ),
+                EditTunnelMessage::LogDirectorySelected(path) => {
+                    if let Some(path) = path {
+                        state.log_directory_input = path.display().to_string();
+                    }
+                    iced::Task::none()
+                }
+                EditTunnelMessage::WorkingDirChanged(path) => {
+                    state.working_dir_input = path;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::BrowseWorkingDir => iced::Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Select working directory")
+                            .pick_folder()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    |path| Message::EditTunnel(EditTunnelMessage::WorkingDirSelected(path)),
+                ),
+                EditTunnelMessage::WorkingDirSelected(path) => {
+                    if let Some(path) = path {
+                        state.working_dir_input = path.display().to_string();
+                    }
+                    iced::Task::none()
+                }
+                EditTunnelMessage::PriorityChanged(priority) => {
+                    state.priority_input = priority;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::HealthCheckTargetChanged(target) => {
+                    state.health_check_target_input = target;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::HealthCheckIntervalChanged(interval) => {
+                    state.health_check_interval_input = interval;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::AutostartPriorityChanged(priority) => {
+                    state.autostart_priority_input = priority;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::AutostartDelayChanged(delay) => {
+                    state.autostart_delay_input = delay;
+                    iced::Task::none()
+                }
+                EditTunnelMessage::DependencyToggled(dependency, checked) => {
+                    state.depends_on.retain(|id| *id != dependency);
+                    if checked {
+                        state.depends_on.push(dependency);
+                    }
+                    iced::Task::none()
+                }
                 EditTunnelMessage::Save => {
-                    let entry = TunnelEntry {
-                        id: match state.mode {
-                            state::EditMode::Create => TunnelId::default(),
-                            state::EditMode::Edit { id } => id,
-                        },
-                        tag: state.tag_input.clone(),
-                        mode: TunnelMode::Client,
-                        cli_args: state.cli_args_input.clone(),
-                        autostart: state.autostart_checkbox,
-                        runtime_state: None,
+                    let entry = match state.entry(&self.tunnels) {
+                        Ok(entry) => entry,
+                        Err(error) => {
+                            state.validation_errors = vec![error];
+                            return iced::Task::none();
+                        }
                     };
 
                     let backend = Arc::clone(&self.backend);
                     let mode = state.mode.clone();
 
                     iced::Task::perform(
-                        async move {
-                            let mut backend_lock = backend.lock().unwrap();
+                        on_runtime(&self.runtime_handle, async move {
+                            let mut backend_lock = backend.lock().await;
 
                             match mode {
-                                state::EditMode::Create => {
-                                    backend_lock.add_tunnel(entry).map_err(|e| e.to_string())
-                                }
+                                state::EditMode::Create => backend_lock
+                                    .add_tunnel(entry)
+                                    .await
+                                    .map_err(|e| e.to_string()),
                                 state::EditMode::Edit { id } => backend_lock
                                     .edit_tunnel(id, entry)
+                                    .await
                                     .map(|_| id)
                                     .map_err(|e| e.to_string()),
                             }
-                        },
+                        }),
                         |result| Message::EditTunnel(EditTunnelMessage::SaveCompleted(result)),
                     )
                 }
+                EditTunnelMessage::Cancel if state.has_unsaved_changes() => {
+                    state.leaving = Some(LeaveEdit::Cancel);
+                    iced::Task::none()
+                }
                 EditTunnelMessage::Cancel => {
                     self.screen = Screen::TunnelList(state::TunnelListState::default());
                     iced::Task::none()
                 }
+                EditTunnelMessage::DiscardChanges => {
+                    let leaving = state.leaving.take();
+                    self.screen = Screen::TunnelList(state::TunnelListState::default());
+                    match leaving {
+                        Some(LeaveEdit::Quit) => self.request_quit(),
+                        Some(LeaveEdit::Cancel) | None => iced::Task::none(),
+                    }
+                }
+                EditTunnelMessage::KeepEditing => {
+                    state.leaving = None;
+                    iced::Task::none()
+                }
                 EditTunnelMessage::SaveCompleted(result) => match result {
                     Ok(_tunnel_id) => {
                         self.screen = Screen::TunnelList(state::TunnelListState::default());
-                        self.refresh_tunnels();
-                        iced::Task::none()
+                        iced::Task::batch([
+                            self.refresh_tunnels(),
+                            iced::Task::done(Message::Notify(Toast::success("Config saved"))),
+                        ])
                     }
                     Err(error) => {
                         state.validation_errors = vec![error];
@@ -280,8 +1954,19 @@ impl WstunnelManagerApp {
                     }
                 },
             },
-            Screen::TunnelList(_) | Screen::ConfirmDelete(_) => iced::Task::none(),
+            Screen::TunnelList(_)
+            | Screen::TunnelDetail(_)
+            | Screen::ConfirmDelete(_)
+            | Screen::ConfirmQuit(_)
+            | Screen::CombinedLogs(_)
+            | Screen::Setup(_)
+            | Screen::Settings(_)
+            | Screen::About(_) => iced::Task::none(),
+        };
+        if changes_field && let Screen::EditTunnel(state) = &mut self.screen {
+            state.check_fields(&self.tunnels);
         }
+        task
     }
 
     fn handle_confirm_delete_message(
@@ -292,29 +1977,140 @@ impl WstunnelManagerApp {
             Screen::ConfirmDelete(state) => match message {
                 ConfirmDeleteMessage::Confirm => {
                     let backend = Arc::clone(&self.backend);
-                    let tunnel_id = state.tunnel_id;
+                    let tunnel_ids = state.tunnel_ids();
+                    let deleted = match state.targets.as_slice() {
+                        [target] => target.tunnel_name.clone(),
+                        targets => format!("{} tunnels", targets.len()),
+                    };
 
                     self.screen = Screen::TunnelList(state::TunnelListState::default());
 
-                    iced::Task::perform(
-                        async move {
-                            let mut backend_lock = backend.lock().unwrap();
-                            backend_lock
-                                .delete_tunnel(tunnel_id)
-                                .map_err(|e| e.to_string())
-                        },
-                        |result| match result {
-                            Ok(_) => Message::TunnelList(TunnelListMessage::Refresh),
-                            Err(error) => Message::Error(error),
-                        },
-                    )
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        let mut backend_lock = backend.lock().await;
+                        backend_lock
+                            .delete_tunnels(&tunnel_ids)
+                            .await
+                            .map_err(|e| e.to_string())
+                    }))
+                    .then(move |result| match result {
+                        Ok(()) => with_toast(
+                            Message::TunnelList(TunnelListMessage::Refresh),
+                            Toast::success(format!("Deleted {}", deleted)),
+                        ),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
                 }
                 ConfirmDeleteMessage::Cancel => {
                     self.screen = Screen::TunnelList(state::TunnelListState::default());
                     iced::Task::none()
                 }
             },
-            Screen::TunnelList(_) | Screen::EditTunnel(_) => iced::Task::none(),
+            Screen::TunnelList(_)
+            | Screen::TunnelDetail(_)
+            | Screen::EditTunnel(_)
+            | Screen::ConfirmQuit(_)
+            | Screen::CombinedLogs(_)
+            | Screen::Setup(_)
+            | Screen::Settings(_)
+            | Screen::About(_) => iced::Task::none(),
+        }
+    }
+
+    // Quitting stops every tunnel, so ask first when any are running.
+    fn request_quit(&mut self) -> iced::Task<Message> {
+        if let Screen::ConfirmQuit(state) = &self.screen
+            && state.shutting_down
+        {
+            return iced::Task::none();
+        }
+        // Quitting carries on once the user agrees to drop the edits.
+        if let Screen::EditTunnel(state) = &mut self.screen
+            && state.has_unsaved_changes()
+        {
+            state.leaving = Some(LeaveEdit::Quit);
+            return self.bring_to_front();
+        }
+        self.save_window_state();
+
+        let running_count = self
+            .tunnels
+            .iter()
+            .filter(|t| matches!(t.runtime_state, Some(TunnelRuntimeState::Running { .. })))
+            .count();
+        if running_count == 0 {
+            return iced::exit();
+        }
+
+        self.screen = Screen::ConfirmQuit(ConfirmQuitState::new(running_count));
+        self.bring_to_front()
+    }
+
+    // Quit can come from the tray menu while the window is hidden, and the
+    // question it asks has to be seen.
+    fn bring_to_front(&mut self) -> iced::Task<Message> {
+        #[cfg(feature = "tray")]
+        if self.tray.is_some() {
+            return self.update(Message::ShowWindow);
+        }
+        iced::Task::none()
+    }
+
+    fn handle_confirm_quit_message(&mut self, message: ConfirmQuitMessage) -> iced::Task<Message> {
+        match &mut self.screen {
+            Screen::ConfirmQuit(state) => match message {
+                // The tunnels are stopped before the window closes so the
+                // progress stays visible; main.rs shuts down again afterwards,
+                // which then has nothing left to stop.
+                ConfirmQuitMessage::StopAndQuit => {
+                    if state.shutting_down {
+                        return iced::Task::none();
+                    }
+                    state.shutting_down = true;
+                    let backend = Arc::clone(&self.backend);
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        backend
+                            .lock()
+                            .await
+                            .shutdown()
+                            .await
+                            .map_err(|e| e.to_string())
+                    }))
+                    .then(|result| {
+                        if let Err(error) = result {
+                            tracing::error!("Error during shutdown: {}", error);
+                        }
+                        iced::exit()
+                    })
+                }
+                ConfirmQuitMessage::LeaveRunningAndQuit => {
+                    let backend = Arc::clone(&self.backend);
+                    iced::Task::future(on_runtime(&self.runtime_handle, async move {
+                        backend
+                            .lock()
+                            .await
+                            .detach()
+                            .await
+                            .map_err(|e| e.to_string())
+                    }))
+                    .then(|result| match result {
+                        Ok(()) => iced::exit(),
+                        Err(error) => iced::Task::done(Message::Error(error)),
+                    })
+                }
+                ConfirmQuitMessage::Cancel if state.shutting_down => iced::Task::none(),
+                ConfirmQuitMessage::Cancel => {
+                    self.screen = Screen::default();
+                    iced::Task::none()
+                }
+            },
+            Screen::TunnelList(_)
+            | Screen::TunnelDetail(_)
+            | Screen::EditTunnel(_)
+            | Screen::ConfirmDelete(_)
+            | Screen::CombinedLogs(_)
+            | Screen::Setup(_)
+            | Screen::Settings(_)
+            | Screen::About(_) => iced::Task::none(),
         }
     }
 
@@ -323,16 +2119,69 @@ impl WstunnelManagerApp {
         _id: crate::backend::types::TunnelId,
         _status: crate::backend::types::TunnelRuntimeState,
     ) -> iced::Task<Message> {
-        self.refresh_tunnels();
-        iced::Task::none()
+        self.refresh_tunnels()
+    }
+
+    fn handle_tunnel_event(&mut self, event: TunnelEvent) -> iced::Task<Message> {
+        // Only report exits of tunnels the list still shows as running; a
+        // tunnel that dies while starting is reported by `StartFailed`.
+        let error = match event {
+            TunnelEvent::Crashed { id, exit_code } => self
+                .tunnels
+                .iter()
+                .find(|t| t.id == id)
+                .filter(|t| matches!(t.runtime_state, Some(TunnelRuntimeState::Running { .. })))
+                .map(|t| errors::tunnel::exited_unexpectedly(&t.tag, exit_code)),
+            TunnelEvent::StartFailed { error, .. } => Some(error),
+            _ => None,
+        };
+        if let Some(error) = error {
+            match &mut self.screen {
+                Screen::TunnelList(state) => state.error_message = Some(error),
+                Screen::TunnelDetail(state) => state.error_message = Some(error),
+                Screen::EditTunnel(_)
+                | Screen::ConfirmDelete(_)
+                | Screen::ConfirmQuit(_)
+                | Screen::CombinedLogs(_)
+                | Screen::Setup(_)
+                | Screen::Settings(_)
+                | Screen::About(_) => {}
+            }
+        }
+        self.refresh_tunnels()
+    }
+
+    // Keeps the screens that show a tunnel in step with a reloaded list.
+    fn tunnels_updated(&mut self) -> iced::Task<Message> {
+        #[cfg(feature = "tray")]
+        if let Some(tray) = &mut self.tray {
+            tray.update(&self.tunnels, self.summary);
+        }
+        match &mut self.screen {
+            Screen::ConfirmDelete(state) => state.update(&self.tunnels),
+            Screen::TunnelList(state) => state
+                .selected
+                .retain(|id| self.tunnels.iter().any(|t| t.id == *id)),
+            _ => {}
+        }
+        match &self.screen {
+            Screen::TunnelDetail(state) => {
+                if self.tunnels.iter().any(|t| t.id == state.tunnel_id) {
+                    self.load_details(state.tunnel_id)
+                } else {
+                    self.screen = Screen::default();
+                    iced::Task::none()
+                }
+            }
+            _ => iced::Task::none(),
+        }
     }
 
     fn handle_config_reloaded(
         &mut self,
         _config: Arc<crate::backend::types::Config>,
     ) -> iced::Task<Message> {
-        self.refresh_tunnels();
-        iced::Task::none()
+        self.refresh_tunnels()
     }
 
     fn handle_error(&mut self, error: String) -> iced::Task<Message> {
@@ -340,22 +2189,100 @@ impl WstunnelManagerApp {
             Screen::TunnelList(state) => {
                 state.error_message = Some(error);
             }
+            Screen::TunnelDetail(state) => {
+                state.error_message = Some(error);
+            }
             Screen::EditTunnel(state) => {
                 state.validation_errors = vec![error];
             }
-            Screen::ConfirmDelete(_) => {
+            Screen::Setup(state) => {
+                state.error_message = Some(error);
+            }
+            Screen::Settings(state) => {
+                state.error_message = Some(error);
+            }
+            Screen::ConfirmDelete(_)
+            | Screen::ConfirmQuit(_)
+            | Screen::CombinedLogs(_)
+            | Screen::About(_) => {
                 self.screen = Screen::TunnelList(state::TunnelListState {
-                    scroll_position: 0.0,
                     error_message: Some(error),
+                    ..state::TunnelListState::default()
                 });
             }
         }
         iced::Task::none()
     }
 
-    fn refresh_tunnels(&mut self) {
-        let mut backend_lock = self.backend.lock().unwrap();
-        self.tunnels = backend_lock.list_tunnels();
+    // The version is reloaded too, since starting a tunnel re-checks the
+    // binary when its configured path changed.
+    fn running_tunnel_ids(&self) -> Vec<TunnelId> {
+        self.tunnels
+            .iter()
+            .filter(|t| matches!(t.runtime_state, Some(TunnelRuntimeState::Running { .. })))
+            .map(|t| t.id)
+            .collect()
+    }
+
+    // Looks at the log directories of the running tunnels only; stopped ones
+    // write nothing, and starting one checks its directory anyway.
+    fn check_disk_space(&self) -> iced::Task<Message> {
+        let running = self.running_tunnel_ids();
+        let backend = Arc::clone(&self.backend);
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let config = backend.lock().await.get_config();
+                let mut directories: Vec<PathBuf> = config
+                    .tunnels
+                    .iter()
+                    .filter(|t| running.contains(&t.id))
+                    .map(|t| t.effective_log_directory(&config.global).to_path_buf())
+                    .collect();
+                directories.sort();
+                directories.dedup();
+                Ok(directories.into_iter().find_map(|directory| {
+                    let available_mb =
+                        crate::backend::logs::available_space(&directory)? / (1024 * 1024);
+                    (available_mb < crate::constants::LOW_LOG_SPACE_MB).then(|| {
+                        errors::disk::low_log_space(&directory.display().to_string(), available_mb)
+                    })
+                }))
+            }),
+            |result| match result {
+                Ok(warning) => Message::DiskSpaceChecked(warning),
+                Err(error) => Message::Error(error),
+            },
+        )
+    }
+
+    // Only reloads the entries when the config changed since the last load;
+    // otherwise just their statuses are fetched.
+    fn refresh_tunnels(&self) -> iced::Task<Message> {
+        let backend = Arc::clone(&self.backend);
+        let known_generation = self.config_generation;
+        iced::Task::perform(
+            on_runtime(&self.runtime_handle, async move {
+                let mut backend_lock = backend.lock().await;
+                let wstunnel_version = backend_lock.wstunnel_version();
+                let config_generation = backend_lock.config_generation();
+                Ok(match known_generation == Some(config_generation) {
+                    true => Message::StatusesLoaded {
+                        statuses: backend_lock.tunnel_statuses(),
+                        wstunnel_version,
+                        summary: backend_lock.summary(),
+                        flap_warnings: flap_warnings(&*backend_lock),
+                    },
+                    false => Message::TunnelsLoaded {
+                        tunnels: backend_lock.list_tunnels(),
+                        wstunnel_version,
+                        config_generation,
+                        summary: backend_lock.summary(),
+                        flap_warnings: flap_warnings(&*backend_lock),
+                    },
+                })
+            }),
+            |result| result.unwrap_or_else(Message::Error),
+        )
     }
 
     pub fn theme(&self) -> iced::Theme {
@@ -363,6 +2290,121 @@ impl WstunnelManagerApp {
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        iced::Subscription::none()
+        let mut events = self.tunnel_event_subscription();
+
+        // Keeps the uptime and stats on the detail screen ticking.
+        if let Screen::TunnelDetail(_) = self.screen {
+            events = iced::Subscription::batch([
+                events,
+                iced::time::every(std::time::Duration::from_secs(1))
+                    .map(|_| Message::TunnelDetail(TunnelDetailMessage::Tick)),
+            ]);
+        }
+
+        // New output isn't pushed to the combined view; it re-reads the logs.
+        if let Screen::CombinedLogs(_) = self.screen {
+            events = iced::Subscription::batch([
+                events,
+                iced::time::every(std::time::Duration::from_secs(2))
+                    .map(|_| Message::CombinedLogs(CombinedLogsMessage::Tick)),
+            ]);
+        }
+
+        // The warning is cleared once nothing runs.
+        if !self.running_tunnel_ids().is_empty() || self.disk_space_warning.is_some() {
+            events = iced::Subscription::batch([
+                events,
+                iced::time::every(crate::constants::DISK_SPACE_CHECK_INTERVAL)
+                    .map(|_| Message::CheckDiskSpace),
+            ]);
+        }
+
+        events = iced::Subscription::batch([
+            events,
+            iced::time::every(crate::constants::CONFIG_CHANGE_CHECK_INTERVAL)
+                .map(|_| Message::CheckConfigOnDisk),
+        ]);
+
+        if let Some(interval) = self.refresh_interval {
+            events = iced::Subscription::batch([
+                events,
+                iced::time::every(interval).map(|_| Message::RefreshTick),
+            ]);
+        }
+
+        // Only ticks while a toast is showing.
+        if !self.toasts.is_empty() {
+            events = iced::Subscription::batch([
+                events,
+                iced::time::every(std::time::Duration::from_millis(500))
+                    .map(|_| Message::ExpireToasts),
+            ]);
+        }
+
+        let close_requests = iced::window::close_requests().map(Message::WindowCloseRequested);
+        // Listens whatever has focus, so the keys also work while typing in
+        // the palette's own input.
+        let palette_keys = iced::event::listen_with(|event, _status, _id| {
+            use iced::keyboard::{Event, Key, key::Named};
+            let iced::Event::Keyboard(Event::KeyPressed { key, modifiers, .. }) = event else {
+                return None;
+            };
+            let message = match key.as_ref() {
+                Key::Character("p") if modifiers.command() => CommandPaletteMessage::Toggle,
+                Key::Named(Named::Escape) => CommandPaletteMessage::Close,
+                Key::Named(Named::ArrowDown) => CommandPaletteMessage::SelectNext,
+                Key::Named(Named::ArrowUp) => CommandPaletteMessage::SelectPrevious,
+                _ => return None,
+            };
+            Some(Message::CommandPalette(message))
+        });
+        let geometry = iced::event::listen_with(|event, _status, _id| match event {
+            iced::Event::Window(iced::window::Event::Moved(position)) => {
+                Some(Message::WindowMoved(position))
+            }
+            iced::Event::Window(iced::window::Event::Resized(size)) => {
+                Some(Message::WindowResized(size))
+            }
+            _ => None,
+        });
+
+        #[cfg(feature = "tray")]
+        if self.tray.is_some() {
+            return iced::Subscription::batch([
+                events,
+                close_requests,
+                geometry,
+                palette_keys,
+                tray::subscription(),
+            ]);
+        }
+
+        iced::Subscription::batch([events, close_requests, geometry, palette_keys])
+    }
+
+    fn tunnel_event_subscription(&self) -> iced::Subscription<Message> {
+        let mut events = self.events.resubscribe();
+        iced::Subscription::run_with_id(
+            "tunnel-events",
+            iced::stream::channel(100, move |mut output| async move {
+                use iced::futures::SinkExt;
+                loop {
+                    let event = match events.recv().await {
+                        // Log lines are not shown anywhere yet, so don't wake
+                        // the UI for every line of output.
+                        Ok(TunnelEvent::LogLine { .. }) => continue,
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("UI skipped {} tunnel events", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    if output.send(Message::TunnelEvent(event)).await.is_err() {
+                        break;
+                    }
+                }
+            }),
+        )
     }
 }
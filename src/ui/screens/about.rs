@@ -0,0 +1,32 @@
+use crate::ui::messages::{AboutMessage, Message};
+use crate::ui::state::AboutState;
+use iced::widget::{Column, button, container, row, text};
+use iced::{Element, Length};
+
+// Versions and paths for bug reports, with a button that copies them as the
+// text users paste into issues.
+pub fn about_view(state: AboutState) -> Element<'static, Message> {
+    let mut content = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(text("About").size(24));
+
+    for (name, value) in state.diagnostics() {
+        content = content.push(
+            row![
+                text(format!("{}:", name)).size(14).width(150),
+                text(value).size(14)
+            ]
+            .spacing(10),
+        );
+    }
+
+    let buttons = row![
+        button("Copy diagnostics").on_press(Message::About(AboutMessage::CopyDiagnostics)),
+        button("Open log directory").on_press(Message::About(AboutMessage::OpenLogDirectory)),
+        button("Back").on_press(Message::About(AboutMessage::Back)),
+    ]
+    .spacing(10);
+
+    container(content.push(buttons)).width(Length::Fill).into()
+}
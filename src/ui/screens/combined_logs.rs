@@ -0,0 +1,158 @@
+use crate::backend::logs::{LogLevel, LogStream, TaggedLogLine};
+use crate::ui::messages::{CombinedLogsMessage, Message};
+use crate::ui::state::{CombinedLogsState, StreamFilter, TunnelChoice};
+use crate::ui::theme::search_match_style;
+use iced::widget::{
+    Column, button, checkbox, column, container, pick_list, row, scrollable, text, text_input,
+};
+use iced::{Alignment, Color, Element, Length};
+
+fn log_scrollable_id() -> scrollable::Id {
+    scrollable::Id::new("combined-logs")
+}
+
+// Scrolls so that line `index` of `count` is in view. The list is anchored
+// at the bottom, so offsets count from there.
+pub fn scroll_to_line(index: usize, count: usize) -> iced::Task<Message> {
+    let y = match count {
+        0 | 1 => 0.0,
+        _ => 1.0 - index as f32 / (count - 1) as f32,
+    };
+    scrollable::snap_to(
+        log_scrollable_id(),
+        scrollable::RelativeOffset { x: 0.0, y },
+    )
+}
+
+fn search_bar(state: &CombinedLogsState) -> Element<'static, Message> {
+    let matches = state.search_matches().len();
+    let status = match (&state.search_error, state.matcher.is_some()) {
+        (Some(error), _) => text(error.clone()).color(Color::from_rgb(0.8, 0.2, 0.2)),
+        (None, false) => text(""),
+        (None, true) if matches == 0 => text("No matches"),
+        (None, true) => text(match state.current_match {
+            Some(current) => format!("{} of {} matches", current + 1, matches),
+            None => format!("{} matches", matches),
+        }),
+    };
+    let step = |label: &str, message: CombinedLogsMessage| {
+        button(text(label.to_string()).size(14))
+            .on_press_maybe((matches > 0).then_some(Message::CombinedLogs(message)))
+    };
+    // History search needs a single tunnel to read the files of.
+    let history = match &state.history {
+        Some(_) => button(text("Back to recent lines").size(14))
+            .on_press(Message::CombinedLogs(CombinedLogsMessage::ClearHistory)),
+        None => button(text("Search all logs").size(14)).on_press_maybe(
+            (state.tunnel_filter.id.is_some() && state.matcher.is_some())
+                .then_some(Message::CombinedLogs(CombinedLogsMessage::SearchHistory)),
+        ),
+    };
+
+    row![
+        text_input("Search logs", &state.search.pattern)
+            .on_input(|s| Message::CombinedLogs(CombinedLogsMessage::SearchChanged(s)))
+            .on_submit(Message::CombinedLogs(CombinedLogsMessage::NextMatch))
+            .padding(8)
+            .width(Length::FillPortion(2)),
+        checkbox("Match case", state.search.case_sensitive).on_toggle(|checked| {
+            Message::CombinedLogs(CombinedLogsMessage::MatchCaseToggled(checked))
+        }),
+        checkbox("Regex", state.search.regex)
+            .on_toggle(|checked| Message::CombinedLogs(CombinedLogsMessage::RegexToggled(checked))),
+        step("Previous", CombinedLogsMessage::PreviousMatch),
+        step("Next", CombinedLogsMessage::NextMatch),
+        container(status.size(14)).width(Length::FillPortion(1)),
+        history,
+    ]
+    .spacing(10)
+    .padding([0, 10])
+    .align_y(Alignment::Center)
+    .into()
+}
+
+// wstunnel's level when the line has one, otherwise red for STDERR.
+fn line_color(line: &TaggedLogLine) -> Option<Color> {
+    match (line.level, line.stream) {
+        (Some(LogLevel::Error), _) => Some(Color::from_rgb(0.8, 0.2, 0.2)),
+        (Some(LogLevel::Warn), _) => Some(Color::from_rgb(0.9, 0.6, 0.0)),
+        (Some(LogLevel::Debug | LogLevel::Trace), _) => Some(Color::from_rgb(0.5, 0.5, 0.5)),
+        (Some(LogLevel::Info), _) => None,
+        (None, Some(LogStream::Stderr)) => Some(Color::from_rgb(0.8, 0.2, 0.2)),
+        (None, _) => None,
+    }
+}
+
+// Every tunnel's recent output in one list, each line prefixed with its
+// tunnel's tag and colored by `line_color`. Lines the search matches are
+// highlighted.
+pub fn combined_logs_view(
+    state: CombinedLogsState,
+    tunnel_choices: Vec<TunnelChoice>,
+) -> Element<'static, Message> {
+    let header = row![
+        button("Back").on_press(Message::CombinedLogs(CombinedLogsMessage::Back)),
+        container(text("All Logs").size(24)).width(Length::Fill),
+        pick_list(
+            tunnel_choices,
+            Some(state.tunnel_filter.clone()),
+            |choice| { Message::CombinedLogs(CombinedLogsMessage::TunnelSelected(choice)) }
+        )
+        .padding(8),
+        pick_list(StreamFilter::ALL, Some(state.stream_filter), |filter| {
+            Message::CombinedLogs(CombinedLogsMessage::StreamSelected(filter))
+        })
+        .padding(8),
+    ]
+    .spacing(10)
+    .padding(10)
+    .align_y(Alignment::Center);
+
+    let current_line = state
+        .current_match
+        .and_then(|current| state.search_matches().get(current).copied());
+    let lines: Vec<Element<'static, Message>> = state
+        .visible_lines()
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let entry = text(format!("[{}] {}", line.tag, line.line))
+                .size(12)
+                .font(iced::Font::MONOSPACE);
+            if state.is_match(line) {
+                return container(entry)
+                    .width(Length::Fill)
+                    .style(search_match_style(current_line == Some(index)))
+                    .into();
+            }
+            match line_color(line) {
+                Some(color) => entry.color(color).into(),
+                None => entry.into(),
+            }
+        })
+        .collect();
+
+    let empty_message = match state.history {
+        Some(_) => "No matches in this tunnel's logs",
+        None => "No log output yet",
+    };
+    let body: Element<'static, Message> = if lines.is_empty() {
+        container(text(empty_message).size(16))
+            .width(Length::Fill)
+            .center_x(Length::Fill)
+            .padding(20)
+            .into()
+    } else {
+        scrollable(Column::with_children(lines).spacing(2).padding(10))
+            .id(log_scrollable_id())
+            .anchor_bottom()
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .into()
+    };
+
+    container(column![header, search_bar(&state), body])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
@@ -0,0 +1,66 @@
+use crate::backend::types::TunnelEntry;
+use crate::ui::messages::{CommandPaletteMessage, Message};
+use crate::ui::state::CommandPaletteState;
+use iced::widget::{Column, button, column, container, mouse_area, opaque, text, text_input};
+use iced::{Color, Element, Length};
+
+pub fn palette_input_id() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+// Drawn over the current screen. Clicking outside the box closes it.
+pub fn command_palette_view(
+    state: &CommandPaletteState,
+    tunnels: &[TunnelEntry],
+) -> Element<'static, Message> {
+    let entries = state.entries(tunnels);
+    let list: Element<'static, Message> = if entries.is_empty() {
+        text("No matching tunnel or action")
+            .size(14)
+            .color(Color::from_rgb(0.5, 0.5, 0.5))
+            .into()
+    } else {
+        Column::with_children(entries.into_iter().enumerate().map(|(index, entry)| {
+            let style = match index == state.selected {
+                true => button::primary,
+                false => button::text,
+            };
+            button(text(format!("{}: {}", entry.action, entry.tag)).size(14))
+                .width(Length::Fill)
+                .style(style)
+                .on_press(Message::CommandPalette(CommandPaletteMessage::RunEntry(
+                    index,
+                )))
+                .into()
+        }))
+        .spacing(2)
+        .into()
+    };
+
+    let content = column![
+        text_input("Tunnel and action, e.g. \"web start\"", &state.query)
+            .id(palette_input_id())
+            .on_input(|s| Message::CommandPalette(CommandPaletteMessage::QueryChanged(s)))
+            .on_submit(Message::CommandPalette(CommandPaletteMessage::Run))
+            .padding(8),
+        list,
+        text("Up/Down to choose, Enter to run, Esc to close")
+            .size(12)
+            .color(Color::from_rgb(0.5, 0.5, 0.5)),
+    ]
+    .spacing(10)
+    .padding(15)
+    .width(480);
+
+    let dialog = container(content).style(container::rounded_box);
+    let backdrop = container(opaque(dialog))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(iced::alignment::Horizontal::Center)
+        .padding(80)
+        .style(|_theme: &iced::Theme| {
+            container::Style::default().background(Color::from_rgba(0.0, 0.0, 0.0, 0.4))
+        });
+
+    opaque(mouse_area(backdrop).on_press(Message::CommandPalette(CommandPaletteMessage::Close)))
+}
@@ -1,8 +1,80 @@
+use crate::backend::cli_schema::check_cli_args;
+use crate::backend::process::parse_cli_args;
+use crate::backend::types::{TunnelField, TunnelMode};
 use crate::ui::messages::{EditTunnelMessage, Message};
 use crate::ui::state::{EditMode, EditTunnelState};
-use iced::widget::{Column, button, checkbox, column, container, row, text, text_input};
+use iced::widget::{
+    Column, Row, button, checkbox, column, container, pick_list, row, text, text_input,
+};
 use iced::{Alignment, Color, Element, Length};
 
+// One chip per argument exactly as wstunnel will receive it, so quoting
+// mistakes show up while typing rather than when the tunnel starts.
+fn cli_args_preview(state: &EditTunnelState) -> Element<'static, Message> {
+    if let Some(error) = state.cli_args_error() {
+        return text(error)
+            .size(12)
+            .color(Color::from_rgb(0.8, 0.0, 0.0))
+            .into();
+    }
+
+    let chips = parse_cli_args(&state.cli_args_input)
+        .into_iter()
+        .map(|arg| {
+            let label = if arg.is_empty() {
+                "\"\"".to_string()
+            } else {
+                arg
+            };
+            container(text(label).size(12))
+                .padding([2, 6])
+                .style(container::rounded_box)
+                .into()
+        });
+
+    // Nothing to warn about until something has been typed.
+    let problems = match state.cli_args_input.trim().is_empty() {
+        true => Vec::new(),
+        false => check_cli_args(&state.cli_args_input),
+    };
+    let warnings = problems.into_iter().map(|problem| {
+        text(problem)
+            .size(12)
+            .color(Color::from_rgb(0.9, 0.5, 0.0))
+            .into()
+    });
+
+    column![Row::with_children(chips).spacing(5).wrap()]
+        .extend(warnings)
+        .spacing(5)
+        .into()
+}
+
+// Outlines an input in red while its field has a problem.
+fn field_style(
+    invalid: bool,
+) -> impl Fn(&iced::Theme, text_input::Status) -> text_input::Style + 'static {
+    move |theme, status| {
+        let style = text_input::default(theme, status);
+        match invalid {
+            true => text_input::Style {
+                border: style.border.color(Color::from_rgb(0.8, 0.0, 0.0)),
+                ..style
+            },
+            false => style,
+        }
+    }
+}
+
+fn field_error_text(error: Option<String>) -> Option<Element<'static, Message>> {
+    error.map(|error| {
+        text(error)
+            .size(12)
+            .color(Color::from_rgb(0.8, 0.0, 0.0))
+            .into()
+    })
+}
+
 // T049-T050: edit_tunnel_view with validation error display
 pub fn edit_tunnel_view(state: EditTunnelState) -> Element<'static, Message> {
     let title = match state.mode {
@@ -36,26 +108,51 @@ pub fn edit_tunnel_view(state: EditTunnelState) -> Element<'static, Message> {
         form_content = form_content.push(error_container);
     }
 
+    let error = |field| state.field_error(field);
+
     // Tag input
     let tag_input = column![
         text("Tag/Name:").size(14),
         text_input(
-            "Enter tunnel name (optional - generated ID if empty)",
+            "Enter tunnel name (optional - generated from the server host if empty)",
             &state.tag_input
         )
         .on_input(|s| Message::EditTunnel(EditTunnelMessage::TagChanged(s)))
+        .style(field_style(error(TunnelField::Tag).is_some()))
         .padding(8)
     ]
+    .push_maybe(field_error_text(error(TunnelField::Tag)))
     .spacing(5);
     form_content = form_content.push(tag_input);
 
-    // CLI args input
+    // Mode selector
+    let mode_selector = column![
+        text("Mode:").size(14),
+        pick_list(
+            TunnelMode::all().collect::<Vec<_>>(),
+            Some(state.tunnel_mode),
+            |mode| Message::EditTunnel(EditTunnelMessage::ModeSelected(mode))
+        )
+        .padding(8)
+    ]
+    .push_maybe(field_error_text(error(TunnelField::Mode)))
+    .spacing(5);
+    form_content = form_content.push(mode_selector);
+
+    // CLI args input. The preview already shows why the arguments can't be
+    // split.
+    let cli_args_error = error(TunnelField::CliArgs);
     let cli_args_input = column![
         text("CLI Arguments:").size(14),
         text_input("Enter wstunnel CLI arguments", &state.cli_args_input)
             .on_input(|s| Message::EditTunnel(EditTunnelMessage::CliArgsChanged(s)))
-            .padding(8)
+            .style(field_style(cli_args_error.is_some()))
+            .padding(8),
+        cli_args_preview(&state),
     ]
+    .push_maybe(field_error_text(
+        cli_args_error.filter(|_| state.cli_args_error().is_none()),
+    ))
     .spacing(5);
     form_content = form_content.push(cli_args_input);
 
@@ -67,15 +164,206 @@ pub fn edit_tunnel_view(state: EditTunnelState) -> Element<'static, Message> {
     .on_toggle(|checked| Message::EditTunnel(EditTunnelMessage::AutostartToggled(checked)));
     form_content = form_content.push(autostart_cb);
 
-    // Buttons
-    let buttons = row![
-        button("Save")
-            .on_press(Message::EditTunnel(EditTunnelMessage::Save))
-            .padding(10),
-        button("Cancel")
-            .on_press(Message::EditTunnel(EditTunnelMessage::Cancel))
-            .padding(10)
+    // Autostart order
+    let autostart_error = error(TunnelField::Autostart);
+    let autostart_order_input = row![
+        text("Autostart priority:").size(14),
+        text_input("0", &state.autostart_priority_input)
+            .on_input(|s| Message::EditTunnel(EditTunnelMessage::AutostartPriorityChanged(s)))
+            .style(field_style(autostart_error.is_some()))
+            .padding(8)
+            .width(Length::Fixed(70.0)),
+        text("then wait").size(14),
+        text_input("0", &state.autostart_delay_input)
+            .on_input(|s| Message::EditTunnel(EditTunnelMessage::AutostartDelayChanged(s)))
+            .style(field_style(autostart_error.is_some()))
+            .padding(8)
+            .width(Length::Fixed(90.0)),
+        text("ms (lower priorities start first)").size(14),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    form_content = form_content
+        .push(autostart_order_input)
+        .push_maybe(field_error_text(autostart_error));
+
+    // Group input
+    let group_input = column![
+        text("Group:").size(14),
+        text_input(
+            "e.g. work or home-lab (leave empty for the Default group)",
+            &state.group_input
+        )
+        .on_input(|s| Message::EditTunnel(EditTunnelMessage::GroupChanged(s)))
+        .style(field_style(error(TunnelField::Group).is_some()))
+        .padding(8)
+    ]
+    .push_maybe(field_error_text(error(TunnelField::Group)))
+    .spacing(5);
+    form_content = form_content.push(group_input);
+
+    // Environment variables editor
+    let mut env_editor = Column::new()
+        .spacing(5)
+        .push(text("Environment Variables:").size(14));
+    let env_error = error(TunnelField::Env);
+    for (index, (key, value)) in state.env_vars.iter().enumerate() {
+        let env_row = row![
+            text_input("NAME", key)
+                .on_input(move |s| Message::EditTunnel(EditTunnelMessage::EnvKeyChanged(index, s)))
+                .style(field_style(env_error.is_some()))
+                .padding(8)
+                .width(Length::FillPortion(1)),
+            text_input("value", value)
+                .on_input(move |s| {
+                    Message::EditTunnel(EditTunnelMessage::EnvValueChanged(index, s))
+                })
+                .padding(8)
+                .width(Length::FillPortion(2)),
+            button("Remove")
+                .on_press(Message::EditTunnel(EditTunnelMessage::RemoveEnvVar(index)))
+                .padding(8)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+        env_editor = env_editor.push(env_row);
+    }
+    env_editor = env_editor.push_maybe(field_error_text(env_error)).push(
+        button("Add Variable")
+            .on_press(Message::EditTunnel(EditTunnelMessage::AddEnvVar))
+            .padding(8),
+    );
+    form_content = form_content.push(env_editor);
+
+    // Log directory override
+    let log_directory_input = column![
+        text("Log Directory:").size(14),
+        row![
+            text_input(
+                "Leave empty to use the global log directory",
+                &state.log_directory_input
+            )
+            .on_input(|s| Message::EditTunnel(EditTunnelMessage::LogDirectoryChanged(s)))
+            .style(field_style(error(TunnelField::LogDirectory).is_some()))
+            .padding(8),
+            button("Browse")
+                .on_press(Message::EditTunnel(EditTunnelMessage::BrowseLogDirectory))
+                .padding(8)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+    ]
+    .push_maybe(field_error_text(error(TunnelField::LogDirectory)))
+    .spacing(5);
+    form_content = form_content.push(log_directory_input);
+
+    // Working directory
+    let working_dir_input = column![
+        text("Working Directory:").size(14),
+        row![
+            text_input(
+                "Leave empty to use the config file's directory",
+                &state.working_dir_input
+            )
+            .on_input(|s| Message::EditTunnel(EditTunnelMessage::WorkingDirChanged(s)))
+            .style(field_style(error(TunnelField::WorkingDir).is_some()))
+            .padding(8),
+            button("Browse")
+                .on_press(Message::EditTunnel(EditTunnelMessage::BrowseWorkingDir))
+                .padding(8)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
     ]
+    .push_maybe(field_error_text(error(TunnelField::WorkingDir)))
+    .spacing(5);
+    form_content = form_content.push(working_dir_input);
+
+    // Process priority
+    let priority_input = row![
+        text("Process priority:").size(14),
+        text_input("default", &state.priority_input)
+            .on_input(|s| Message::EditTunnel(EditTunnelMessage::PriorityChanged(s)))
+            .style(field_style(error(TunnelField::Priority).is_some()))
+            .padding(8)
+            .width(Length::Fixed(90.0)),
+        text("nice value from -20 (highest) to 19 (lowest); below 0 usually needs admin rights")
+            .size(14),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    form_content = form_content
+        .push(priority_input)
+        .push_maybe(field_error_text(error(TunnelField::Priority)));
+
+    // Health check
+    let health_check_error = error(TunnelField::HealthCheck);
+    let health_check_input = column![
+        text("Health Check:").size(14),
+        row![
+            text_input(
+                "host:port to connect to, e.g. 127.0.0.1:1080 (leave empty to disable)",
+                &state.health_check_target_input
+            )
+            .on_input(|s| Message::EditTunnel(EditTunnelMessage::HealthCheckTargetChanged(s)))
+            .style(field_style(health_check_error.is_some()))
+            .padding(8)
+            .width(Length::FillPortion(3)),
+            text("every").size(14),
+            text_input("30", &state.health_check_interval_input)
+                .on_input(|s| {
+                    Message::EditTunnel(EditTunnelMessage::HealthCheckIntervalChanged(s))
+                })
+                .style(field_style(health_check_error.is_some()))
+                .padding(8)
+                .width(Length::Fixed(70.0)),
+            text("seconds").size(14),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+    ]
+    .push_maybe(field_error_text(health_check_error))
+    .spacing(5);
+    form_content = form_content.push(health_check_input);
+
+    // Dependencies
+    if !state.dependency_options.is_empty() {
+        let mut dependencies = column![text("Depends on (started first):").size(14)].spacing(5);
+        for (id, tag) in &state.dependency_options {
+            let id = *id;
+            dependencies = dependencies.push(
+                checkbox(tag.clone(), state.depends_on.contains(&id)).on_toggle(move |checked| {
+                    Message::EditTunnel(EditTunnelMessage::DependencyToggled(id, checked))
+                }),
+            );
+        }
+        form_content = form_content
+            .push(dependencies.push_maybe(field_error_text(error(TunnelField::DependsOn))));
+    }
+
+    // Buttons, or the question whether to drop the changes in their place.
+    let buttons = match state.leaving {
+        Some(_) => row![
+            text("Discard your unsaved changes?")
+                .color(Color::from_rgb(0.8, 0.0, 0.0))
+                .width(Length::Fill),
+            button("Discard")
+                .style(button::danger)
+                .on_press(Message::EditTunnel(EditTunnelMessage::DiscardChanges))
+                .padding(10),
+            button("Keep editing")
+                .on_press(Message::EditTunnel(EditTunnelMessage::KeepEditing))
+                .padding(10),
+        ],
+        None => row![
+            button("Save")
+                .on_press(Message::EditTunnel(EditTunnelMessage::Save))
+                .padding(10),
+            button("Cancel")
+                .on_press(Message::EditTunnel(EditTunnelMessage::Cancel))
+                .padding(10)
+        ],
+    }
     .spacing(10)
     .align_y(Alignment::Center);
     form_content = form_content.push(buttons);
@@ -1,2 +1,9 @@
+pub mod about;
+pub mod combined_logs;
+pub mod command_palette;
 pub mod edit_tunnel;
+pub mod settings;
+pub mod setup;
+pub mod toast;
+pub mod tunnel_detail;
 pub mod tunnel_list;
@@ -0,0 +1,257 @@
+use crate::backend::types::{LogMode, format_size};
+use crate::ui::messages::{Message, SettingsMessage};
+use crate::ui::state::{SettingsField, SettingsState, SettingsToggle, ThemeChoice};
+use iced::widget::{
+    Column, button, checkbox, column, container, pick_list, row, scrollable, text, text_input,
+};
+use iced::{Alignment, Color, Element, Length};
+
+fn field(
+    label: &'static str,
+    placeholder: &'static str,
+    value: &str,
+    field: SettingsField,
+) -> Element<'static, Message> {
+    column![
+        text(label).size(14),
+        text_input(placeholder, value)
+            .on_input(move |s| Message::Settings(SettingsMessage::FieldChanged(field, s)))
+            .padding(8),
+    ]
+    .spacing(5)
+    .into()
+}
+
+fn toggle(label: &'static str, checked: bool, toggle: SettingsToggle) -> Element<'static, Message> {
+    checkbox(label, checked)
+        .on_toggle(move |checked| Message::Settings(SettingsMessage::Toggled(toggle, checked)))
+        .into()
+}
+
+fn section(title: &'static str) -> Element<'static, Message> {
+    text(title).size(18).into()
+}
+
+// How much the saved log directories take up, with a button that deletes
+// old logs right away instead of at the next cleanup interval. It works
+// without a retention period as long as a number of days is entered.
+fn log_usage(state: &SettingsState) -> Element<'static, Message> {
+    let usage = match state.log_usage {
+        Some(bytes) => format!("Logs use {}", format_size(bytes)),
+        None => "Measuring log size...".to_string(),
+    };
+    let clean_up = button(match state.cleaning_logs {
+        true => "Cleaning up...",
+        false => "Clean up now",
+    })
+    .on_press_maybe(
+        (!state.cleaning_logs).then_some(Message::Settings(SettingsMessage::CleanUpLogs)),
+    );
+
+    row![
+        text(usage).size(14).width(Length::Fill),
+        text("Delete logs older than").size(14),
+        text_input("days", &state.cleanup_days_input)
+            .on_input(|s| Message::Settings(SettingsMessage::CleanupDaysChanged(s)))
+            .padding(8)
+            .width(Length::Fixed(70.0)),
+        text("days").size(14),
+        clean_up,
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+// The global settings from the config file, saved back through the backend
+// so they are validated the same way as a hand-edited file.
+pub fn settings_view(state: SettingsState) -> Element<'static, Message> {
+    let mut content = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(text("Settings").size(24));
+
+    if let Some(error) = state.error_message.clone() {
+        content = content.push(text(error).color(Color::from_rgb(0.8, 0.0, 0.0)));
+    }
+
+    let binary_path = column![
+        text("wstunnel binary:").size(14),
+        row![
+            text_input(
+                "Path to the wstunnel executable (default: next to the manager)",
+                &state.binary_path_input
+            )
+            .on_input(|s| Message::Settings(SettingsMessage::FieldChanged(
+                SettingsField::BinaryPath,
+                s
+            )))
+            .padding(8),
+            button("Browse...").on_press(Message::Settings(SettingsMessage::BrowseBinary)),
+            button("Detect").on_press(Message::Settings(SettingsMessage::DetectBinary)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(5);
+
+    let log_directory = column![
+        text("Log directory:").size(14),
+        row![
+            text_input("Directory for tunnel logs", &state.log_directory_input)
+                .on_input(|s| Message::Settings(SettingsMessage::FieldChanged(
+                    SettingsField::LogDirectory,
+                    s
+                )))
+                .padding(8),
+            button("Browse...").on_press(Message::Settings(SettingsMessage::BrowseLogDirectory)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(5);
+
+    let log_mode = column![
+        text("Log files:").size(14),
+        pick_list(
+            LogMode::all().collect::<Vec<_>>(),
+            Some(state.log_mode),
+            |mode| Message::Settings(SettingsMessage::LogModeSelected(mode))
+        )
+        .padding(8),
+    ]
+    .spacing(5);
+
+    let theme = column![
+        text("Theme:").size(14),
+        pick_list(ThemeChoice::options(), Some(state.theme.clone()), |theme| {
+            Message::Settings(SettingsMessage::ThemeSelected(theme))
+        })
+        .padding(8),
+    ]
+    .spacing(5);
+
+    content = content
+        .push(section("wstunnel"))
+        .push(binary_path)
+        .push(field(
+            "Start timeout (seconds):",
+            "How long a starting tunnel has to become ready (optional)",
+            &state.start_timeout_seconds_input,
+            SettingsField::StartTimeoutSeconds,
+        ))
+        .push(field(
+            "Ready pattern:",
+            "Regex a starting tunnel's output must match (optional)",
+            &state.ready_pattern_input,
+            SettingsField::ReadyPattern,
+        ))
+        .push(field(
+            "Sensitive flags:",
+            "Comma-separated flags whose values are masked, e.g. --http-upgrade-credentials",
+            &state.sensitive_flags_input,
+            SettingsField::SensitiveFlags,
+        ))
+        .push(toggle(
+            "Refuse CLI args that fail the flag checks",
+            state.strict_validation,
+            SettingsToggle::StrictValidation,
+        ))
+        .push(toggle(
+            "Refuse to start tunnels with missing environment variables",
+            state.fail_on_missing_env_vars,
+            SettingsToggle::FailOnMissingEnvVars,
+        ))
+        .push(toggle(
+            "Stop the tunnels that depend on a tunnel when stopping it",
+            state.stop_dependents,
+            SettingsToggle::StopDependents,
+        ))
+        .push(toggle(
+            "Leave tunnels running when the manager exits",
+            state.detach_on_exit,
+            SettingsToggle::DetachOnExit,
+        ))
+        .push(section("Logs"))
+        .push(log_usage(&state))
+        .push(log_directory)
+        .push(log_mode)
+        .push(field(
+            "Retention (days):",
+            "Delete logs older than this (optional)",
+            &state.log_retention_days_input,
+            SettingsField::LogRetentionDays,
+        ))
+        .push(field(
+            "Compress after (days):",
+            "Gzip logs older than this (optional)",
+            &state.log_compress_days_input,
+            SettingsField::LogCompressDays,
+        ))
+        .push(field(
+            "Cleanup interval (hours):",
+            "How often retention and compression run (default: 24)",
+            &state.cleanup_interval_hours_input,
+            SettingsField::CleanupIntervalHours,
+        ))
+        .push(field(
+            "Maximum log size (MB):",
+            "Rotate a log once it reaches this size (optional)",
+            &state.max_log_size_mb_input,
+            SettingsField::MaxLogSizeMb,
+        ))
+        .push(field(
+            "Rotated log files to keep:",
+            "Number of rotated files",
+            &state.max_log_files_input,
+            SettingsField::MaxLogFiles,
+        ))
+        .push(field(
+            "Line prefix:",
+            "Replaces [{ts}] [{stream}] before each line (optional)",
+            &state.log_line_prefix_input,
+            SettingsField::LogLinePrefix,
+        ))
+        .push(field(
+            "Stderr lines kept in memory:",
+            "Shown on the detail screen and by the API",
+            &state.stderr_buffer_lines_input,
+            SettingsField::StderrBufferLines,
+        ))
+        .push(field(
+            "Maximum log lines per second:",
+            "Lines over the limit are dropped and counted (optional)",
+            &state.log_max_lines_per_second_input,
+            SettingsField::LogMaxLinesPerSecond,
+        ))
+        .push(toggle(
+            "Write log timestamps in UTC",
+            state.log_timestamp_utc,
+            SettingsToggle::LogTimestampUtc,
+        ))
+        .push(section("Interface"))
+        .push(theme)
+        .push(field(
+            "Local port scheme:",
+            "http or https for the Open button (default: http)",
+            &state.local_port_scheme_input,
+            SettingsField::LocalPortScheme,
+        ))
+        .push(field(
+            "Refresh statuses every (seconds):",
+            "Blank or 0 refreshes only after an action (optional)",
+            &state.ui_refresh_seconds_input,
+            SettingsField::UiRefreshSeconds,
+        ));
+
+    let buttons = row![
+        button("Save").on_press(Message::Settings(SettingsMessage::Save)),
+        button("Cancel").on_press(Message::Settings(SettingsMessage::Cancel)),
+    ]
+    .spacing(10);
+    content = content.push(buttons);
+
+    container(scrollable(content).height(Length::Fill))
+        .width(Length::Fill)
+        .into()
+}
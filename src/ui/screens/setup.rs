@@ -0,0 +1,61 @@
+use crate::ui::messages::{Message, SetupMessage};
+use crate::ui::state::SetupState;
+use iced::widget::{Column, button, column, container, row, text, text_input};
+use iced::{Alignment, Color, Element, Length};
+
+// Shown instead of the tunnel list on first run, or when the wstunnel binary
+// can't be found, so the user can point the manager at it without editing
+// the config by hand.
+pub fn setup_view(state: SetupState) -> Element<'static, Message> {
+    let mut content = Column::new()
+        .spacing(15)
+        .padding(20)
+        .max_width(700)
+        .push(text("Welcome to wstunnel Manager").size(24))
+        .push(
+            text("Tell the manager where wstunnel is installed and where tunnel logs should go. Both can be changed later under Settings.")
+                .size(14),
+        );
+
+    if let Some(error) = state.error_message.clone() {
+        content = content.push(text(error).color(Color::from_rgb(0.8, 0.0, 0.0)));
+    }
+
+    let binary_path = column![
+        text("wstunnel binary:").size(14),
+        row![
+            text_input("Path to the wstunnel executable", &state.binary_path_input)
+                .on_input(|s| Message::Setup(SetupMessage::BinaryPathChanged(s)))
+                .padding(8),
+            button("Browse...").on_press(Message::Setup(SetupMessage::BrowseBinary)),
+            button("Detect").on_press(Message::Setup(SetupMessage::DetectBinary)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(5);
+
+    let log_directory = column![
+        text("Log directory:").size(14),
+        row![
+            text_input("Directory for tunnel logs", &state.log_directory_input)
+                .on_input(|s| Message::Setup(SetupMessage::LogDirectoryChanged(s)))
+                .padding(8),
+            button("Browse...").on_press(Message::Setup(SetupMessage::BrowseLogDirectory)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(5);
+
+    let buttons = row![
+        button("Save").on_press(Message::Setup(SetupMessage::Save)),
+        button("Skip").on_press(Message::Setup(SetupMessage::Skip)),
+    ]
+    .spacing(10);
+
+    container(content.push(binary_path).push(log_directory).push(buttons))
+        .width(Length::Fill)
+        .center_x(Length::Fill)
+        .into()
+}
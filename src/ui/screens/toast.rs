@@ -0,0 +1,41 @@
+use crate::ui::messages::Message;
+use crate::ui::state::{Toast, ToastKind};
+use iced::widget::{Column, button, container, text};
+use iced::{Color, Element, Length};
+
+// Toasts stack up in the bottom right corner, newest last. Clicking one
+// dismisses it early.
+pub fn toast_overlay(toasts: &[Toast]) -> Element<'static, Message> {
+    let column = Column::with_children(toasts.iter().enumerate().map(|(index, toast)| {
+        let color = match toast.kind {
+            ToastKind::Success => Color::from_rgb(0.2, 0.6, 0.2),
+            ToastKind::Error => Color::from_rgb(0.8, 0.0, 0.0),
+        };
+        button(text(toast.message.clone()).size(14).color(Color::WHITE))
+            .padding([8, 12])
+            .width(Length::Shrink)
+            .style(move |_theme: &iced::Theme, _status| button::Style {
+                background: Some(iced::Background::Color(color)),
+                text_color: Color::WHITE,
+                border: iced::Border {
+                    color,
+                    width: 1.0,
+                    radius: 5.0.into(),
+                },
+                ..Default::default()
+            })
+            .on_press(Message::DismissToast(index))
+            .into()
+    }))
+    .spacing(8)
+    .align_x(iced::alignment::Horizontal::Right)
+    .max_width(420);
+
+    container(column)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(iced::alignment::Horizontal::Right)
+        .align_y(iced::alignment::Vertical::Bottom)
+        .padding([40, 20])
+        .into()
+}
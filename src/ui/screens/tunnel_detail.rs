@@ -0,0 +1,368 @@
+use crate::backend::logs::LogFileInfo;
+use crate::backend::process::redact_cli_args;
+use crate::backend::types::{
+    TunnelEntry, TunnelId, TunnelRuntimeState, TunnelStats, format_size, format_uptime,
+};
+use crate::ui::messages::{Message, TunnelDetailMessage, TunnelListMessage};
+use crate::ui::screens::tunnel_list::{
+    copy_button, error_bar, lifecycle_buttons, mode_badge, status_indicator,
+};
+use crate::ui::state::TunnelDetailState;
+use crate::ui::theme::panel_style;
+use iced::widget::{Column, button, column, container, row, scrollable, text};
+use iced::{Alignment, Color, Element, Length};
+use std::time::Duration;
+
+fn format_duration(duration: Duration) -> String {
+    humantime::format_duration(Duration::from_secs(duration.as_secs())).to_string()
+}
+
+fn field(label: &str, value: String) -> Element<'static, Message> {
+    row![
+        container(text(label.to_string()).size(14))
+            .width(Length::Fixed(160.0))
+            .padding(2),
+        container(text(value).size(14))
+            .width(Length::Fill)
+            .padding(2),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// A field whose copy button puts `message`'s value on the clipboard, which
+// for the arguments is the full unredacted command line.
+fn copyable_field(label: &str, value: String, message: Message) -> Element<'static, Message> {
+    row![field(label, value), copy_button("Copy", message)]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+}
+
+fn section(title: &str, fields: Vec<Element<'static, Message>>) -> Element<'static, Message> {
+    let content = Column::with_children(fields).spacing(6);
+
+    container(column![text(title.to_string()).size(18), content].spacing(10))
+        .padding(15)
+        .width(Length::Fill)
+        .style(panel_style)
+        .into()
+}
+
+fn configuration_section(
+    tunnel: &TunnelEntry,
+    sensitive_flags: &[String],
+) -> Element<'static, Message> {
+    let mut fields = vec![
+        field("Tag:", tunnel.tag.clone()),
+        field("ID:", tunnel.id.to_string()),
+        field("Mode:", tunnel.mode.to_string()),
+        field("Group:", tunnel.group_name().to_string()),
+        field(
+            "Autostart:",
+            if tunnel.autostart { "Yes" } else { "No" }.to_string(),
+        ),
+        copyable_field(
+            "Arguments:",
+            redact_cli_args(&tunnel.cli_args, sensitive_flags),
+            Message::TunnelList(TunnelListMessage::CopyCliArgs(tunnel.id)),
+        ),
+    ];
+
+    if let Some(log_directory) = &tunnel.log_directory {
+        fields.push(field("Log directory:", log_directory.display().to_string()));
+    }
+
+    if let Some(working_dir) = &tunnel.working_dir {
+        fields.push(field(
+            "Working directory:",
+            working_dir.display().to_string(),
+        ));
+    }
+
+    if let Some(priority) = tunnel.priority {
+        fields.push(field("Process priority:", format!("nice {}", priority)));
+    }
+
+    if let Some(health_check) = &tunnel.health_check {
+        fields.push(field(
+            "Health check:",
+            format!(
+                "{} every {}s",
+                health_check.target, health_check.interval_secs
+            ),
+        ));
+    }
+
+    if let Some(created_at) = &tunnel.created_at {
+        fields.push(field("Created:", created_at.to_string()));
+    }
+
+    if let Some(modified_at) = &tunnel.modified_at {
+        fields.push(field("Modified:", modified_at.to_string()));
+    }
+
+    let mut env_vars: Vec<_> = tunnel.env.iter().collect();
+    env_vars.sort();
+    for (index, (key, value)) in env_vars.into_iter().enumerate() {
+        let label = if index == 0 { "Environment:" } else { "" };
+        fields.push(field(label, format!("{}={}", key, value)));
+    }
+
+    section("Configuration", fields)
+}
+
+fn status_section(
+    tunnel_id: TunnelId,
+    status: &TunnelRuntimeState,
+    log_path: Option<String>,
+    log_file: Option<LogFileInfo>,
+) -> Element<'static, Message> {
+    let mut fields = Vec::new();
+
+    match status {
+        TunnelRuntimeState::Running {
+            pid,
+            started_at,
+            log_path: running_log_path,
+            health,
+            command_line,
+        } => {
+            fields.push(field("State:", "Running".to_string()));
+            fields.push(copyable_field(
+                "PID:",
+                pid.to_string(),
+                Message::TunnelList(TunnelListMessage::CopyPid(tunnel_id)),
+            ));
+            fields.push(field("Started at:", started_at.to_string()));
+            fields.push(field("Uptime:", format_uptime(started_at.elapsed())));
+            fields.push(field("Health:", health.to_string()));
+            fields.push(field("Log file:", running_log_path.display().to_string()));
+            // One argument per row, so how the arguments were split shows.
+            for (index, arg) in command_line.iter().enumerate() {
+                let label = if index == 0 { "Command line:" } else { "" };
+                fields.push(field(label, arg.clone()));
+            }
+        }
+        TunnelRuntimeState::Failed {
+            error,
+            last_attempt,
+            exit_code,
+        } => {
+            fields.push(field("State:", "Failed".to_string()));
+            fields.push(field("Error:", error.clone()));
+            fields.push(field("Last attempt:", last_attempt.to_string()));
+            if let Some(code) = exit_code {
+                fields.push(field("Exit code:", code.to_string()));
+            }
+        }
+        TunnelRuntimeState::Starting => {
+            fields.push(field("State:", "Starting...".to_string()));
+        }
+        TunnelRuntimeState::Stopped => {
+            fields.push(field("State:", "Stopped".to_string()));
+        }
+    }
+
+    if !matches!(status, TunnelRuntimeState::Running { .. })
+        && let Some(log_path) = log_path
+    {
+        fields.push(field("Last log file:", log_path));
+    }
+
+    if let Some(log_file) = log_file {
+        fields.push(field("Log size:", format_size(log_file.size_bytes)));
+        if let Some(created) = log_file.created {
+            fields.push(field("Log created:", created.to_string()));
+        }
+    }
+
+    section("Status", fields)
+}
+
+// Shown after "Preview command": the exact command a start would run, with
+// environment variables expanded. Nothing is spawned to produce it.
+fn command_preview_section(command: String) -> Element<'static, Message> {
+    let actions = row![
+        copy_button(
+            "Copy",
+            Message::TunnelDetail(TunnelDetailMessage::CopyCommandPreview)
+        ),
+        copy_button(
+            "Hide",
+            Message::TunnelDetail(TunnelDetailMessage::HideCommandPreview)
+        ),
+    ]
+    .spacing(10);
+
+    section(
+        "Command Preview",
+        vec![
+            text(command).size(14).font(iced::Font::MONOSPACE).into(),
+            actions.into(),
+        ],
+    )
+}
+
+fn statistics_section(stats: &TunnelStats) -> Element<'static, Message> {
+    let mut fields = vec![
+        field("Starts:", stats.total_starts.to_string()),
+        field("Restarts:", stats.restarts().to_string()),
+        field("Crashes:", stats.total_crashes.to_string()),
+        field("Total uptime:", format_duration(stats.cumulative_uptime)),
+    ];
+
+    if let Some(started_at) = stats.last_started_at {
+        fields.push(field("Last started:", started_at.to_string()));
+    }
+    if let Some(code) = stats.last_exit_code {
+        fields.push(field("Last exit code:", code.to_string()));
+    }
+    if let Some(crashed_at) = stats.last_crashed_at {
+        fields.push(field(
+            "Last crash:",
+            format!("{} ago", format_uptime(crashed_at.elapsed())),
+        ));
+    }
+    if let Some(warning) = stats.flap_warning() {
+        fields.push(
+            text(warning)
+                .size(14)
+                .color(Color::from_rgb(1.0, 0.5, 0.0))
+                .into(),
+        );
+    }
+
+    section("Statistics", fields)
+}
+
+// One row per tunnel this one depends on, so a tunnel that won't start because
+// of a dependency is easy to spot.
+fn dependencies_section(dependencies: &[TunnelEntry]) -> Element<'static, Message> {
+    let rows = dependencies
+        .iter()
+        .map(|dependency| {
+            let status = dependency
+                .runtime_state
+                .clone()
+                .unwrap_or(TunnelRuntimeState::Stopped);
+            let state = match status {
+                TunnelRuntimeState::Running { .. } => "Running",
+                TunnelRuntimeState::Starting => "Starting...",
+                TunnelRuntimeState::Failed { .. } => "Failed",
+                TunnelRuntimeState::Stopped => "Stopped",
+            };
+            row![
+                status_indicator(&status),
+                container(text(dependency.tag.clone()).size(14)).width(Length::Fixed(160.0)),
+                text(state).size(14),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+        })
+        .collect();
+
+    section("Dependencies", rows)
+}
+
+pub fn tunnel_detail_view(
+    state: TunnelDetailState,
+    tunnel: TunnelEntry,
+    dependencies: Vec<TunnelEntry>,
+    read_only: bool,
+) -> Element<'static, Message> {
+    let status = tunnel
+        .runtime_state
+        .clone()
+        .unwrap_or(TunnelRuntimeState::Stopped);
+    let tunnel_id = tunnel.id;
+    let (action_button, edit_button, delete_button) =
+        lifecycle_buttons(tunnel_id, &status, read_only);
+
+    let header = row![
+        button("Back").on_press(Message::TunnelDetail(TunnelDetailMessage::Back)),
+        status_indicator(&status),
+        text(tunnel.tag.clone()).size(24),
+        mode_badge(tunnel.mode),
+        container(action_button)
+            .width(Length::Fill)
+            .align_x(iced::alignment::Horizontal::Right),
+        edit_button,
+        button("Logs").on_press(Message::TunnelList(TunnelListMessage::OpenLogs(tunnel_id))),
+        button("Logs folder").on_press(Message::TunnelList(TunnelListMessage::OpenLogsFolder(
+            tunnel_id
+        ))),
+        button("Copy logs path").on_press(Message::TunnelList(TunnelListMessage::CopyLogsPath(
+            tunnel_id
+        ))),
+        button("Preview command")
+            .on_press(Message::TunnelDetail(TunnelDetailMessage::PreviewCommand)),
+        button("Copy snippet").on_press(Message::TunnelList(TunnelListMessage::ExportTunnel(
+            tunnel_id
+        ))),
+        button("Export").on_press(Message::TunnelList(TunnelListMessage::ExportTunnelToFile(
+            tunnel_id
+        ))),
+        delete_button,
+    ]
+    .spacing(10)
+    .padding(10)
+    .align_y(Alignment::Center);
+
+    let log_path = state
+        .log_path
+        .as_ref()
+        .map(|path| path.display().to_string());
+
+    let mut sections = column![
+        status_section(tunnel_id, &status, log_path, state.log_file),
+        configuration_section(&tunnel, &state.sensitive_flags),
+    ]
+    .spacing(10)
+    .padding(10);
+
+    if !dependencies.is_empty() {
+        sections = sections.push(dependencies_section(&dependencies));
+    }
+
+    if let Some(command) = state.command_preview {
+        sections = sections.push(command_preview_section(command));
+    }
+
+    if let Some(stats) = &state.stats {
+        sections = sections.push(statistics_section(stats));
+    }
+
+    // Usually the reason a tunnel crashed or failed to start.
+    if !state.recent_stderr.is_empty() {
+        sections = sections.push(section(
+            "Recent Stderr",
+            vec![
+                text(state.recent_stderr.trim_end().to_string())
+                    .size(14)
+                    .font(iced::Font::MONOSPACE)
+                    .into(),
+            ],
+        ));
+    }
+
+    let mut main_column = column![
+        header,
+        scrollable(sections)
+            .height(Length::Fill)
+            .width(Length::Fill)
+    ];
+
+    if let Some(error_message) = state.error_message {
+        main_column = main_column.push(error_bar(
+            error_message,
+            Message::TunnelDetail(TunnelDetailMessage::DismissError),
+        ));
+    }
+
+    container(main_column)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
@@ -1,11 +1,27 @@
-use crate::backend::types::{TunnelEntry, TunnelMode, TunnelRuntimeState};
-use crate::ui::messages::{ConfirmDeleteMessage, Message, TunnelListMessage};
-use crate::ui::state::{ConfirmDeleteState, TunnelListState};
-use iced::widget::{Column, Container, button, column, container, row, scrollable, text};
+use crate::backend::cli_schema::Endpoint;
+use crate::backend::types::{
+    TunnelEntry, TunnelHealth, TunnelId, TunnelMode, TunnelRuntimeState, WstunnelVersion,
+    format_uptime,
+};
+use crate::errors;
+use crate::ui::messages::{ConfirmDeleteMessage, ConfirmQuitMessage, Message, TunnelListMessage};
+use crate::ui::state::{
+    ConfirmDeleteState, ConfirmQuitState, QuickFilter, SortOrder, TunnelListState,
+};
+use crate::ui::theme::panel_style;
+use iced::widget::{
+    Column, Container, button, checkbox, column, container, pick_list, row, scrollable, text,
+    text_input, tooltip,
+};
 use iced::{Alignment, Color, Element, Length};
+use std::collections::{BTreeMap, HashMap};
 
 pub fn status_indicator(state: &TunnelRuntimeState) -> Container<'static, Message> {
     let color = match state {
+        TunnelRuntimeState::Running {
+            health: TunnelHealth::Unhealthy { .. },
+            ..
+        } => Color::from_rgb(1.0, 0.5, 0.0), // orange
         TunnelRuntimeState::Running { .. } => Color::from_rgb(0.0, 0.8, 0.0), // green
         TunnelRuntimeState::Stopped => Color::from_rgb(0.8, 0.0, 0.0),        // red
         TunnelRuntimeState::Failed { .. } => Color::from_rgb(0.8, 0.0, 0.0),  // red
@@ -17,7 +33,93 @@ pub fn status_indicator(state: &TunnelRuntimeState) -> Container<'static, Messag
         .center_x(30)
 }
 
-fn mode_badge(mode: TunnelMode) -> Container<'static, Message> {
+pub fn copy_button(label: &str, message: Message) -> Element<'static, Message> {
+    button(text(label.to_string()).size(12))
+        .style(button::text)
+        .padding(2)
+        .on_press(message)
+        .into()
+}
+
+// A button that is greyed out while `disabled_reason` is set, with the reason
+// shown on hover so the user doesn't have to click to find out.
+pub fn guarded_button(
+    label: &str,
+    message: Message,
+    disabled_reason: Option<&'static str>,
+) -> Element<'static, Message> {
+    let button = button(text(label.to_string()));
+    match disabled_reason {
+        None => button.on_press(message).into(),
+        Some(reason) => tooltip(
+            button,
+            container(text(reason).size(12))
+                .padding(5)
+                .style(container::rounded_box),
+            tooltip::Position::Top,
+        )
+        .into(),
+    }
+}
+
+// The Start/Stop, Edit and Delete buttons shared by the list rows and the
+// detail header. Editing and deleting are refused by the backend while the
+// tunnel runs or the manager is read-only, and a starting tunnel can be
+// neither started nor stopped yet.
+pub fn lifecycle_buttons(
+    tunnel_id: TunnelId,
+    status: &TunnelRuntimeState,
+    read_only: bool,
+) -> (
+    Element<'static, Message>,
+    Element<'static, Message>,
+    Element<'static, Message>,
+) {
+    let is_running = matches!(status, TunnelRuntimeState::Running { .. });
+    let is_starting = matches!(status, TunnelRuntimeState::Starting);
+    let starting_reason = is_starting.then_some(errors::tunnel::STILL_STARTING);
+    let (edit_reason, delete_reason) = if read_only {
+        (
+            Some(errors::config::READ_ONLY),
+            Some(errors::config::READ_ONLY),
+        )
+    } else if is_running {
+        (
+            Some(errors::tunnel::CANNOT_EDIT_RUNNING),
+            Some(errors::tunnel::CANNOT_DELETE_RUNNING),
+        )
+    } else {
+        (starting_reason, starting_reason)
+    };
+
+    let action = if is_running {
+        guarded_button(
+            "Stop",
+            Message::TunnelList(TunnelListMessage::StopTunnel(tunnel_id)),
+            None,
+        )
+    } else {
+        guarded_button(
+            "Start",
+            Message::TunnelList(TunnelListMessage::StartTunnel(tunnel_id)),
+            starting_reason,
+        )
+    };
+    let edit = guarded_button(
+        "Edit",
+        Message::TunnelList(TunnelListMessage::EditTunnel(tunnel_id)),
+        edit_reason,
+    );
+    let delete = guarded_button(
+        "Delete",
+        Message::TunnelList(TunnelListMessage::DeleteTunnel(tunnel_id)),
+        delete_reason,
+    );
+
+    (action, edit, delete)
+}
+
+pub fn mode_badge(mode: TunnelMode) -> Container<'static, Message> {
     let (label, color) = match mode {
         TunnelMode::Client => ("CLIENT", Color::from_rgb(0.2, 0.5, 0.8)),
         TunnelMode::Server => ("SERVER", Color::from_rgb(0.5, 0.2, 0.8)),
@@ -37,20 +139,68 @@ fn mode_badge(mode: TunnelMode) -> Container<'static, Message> {
         })
 }
 
-fn tunnel_row(tunnel: TunnelEntry) -> Element<'static, Message> {
+// Outlined rather than filled so it reads as detail next to the mode badge.
+fn endpoint_badge(endpoint: &Endpoint) -> Container<'static, Message> {
+    let color = Color::from_rgb(0.5, 0.5, 0.5);
+    container(text(endpoint.to_string()).size(11))
+        .padding([2, 4])
+        .style(move |_theme: &iced::Theme| container::Style {
+            border: iced::Border {
+                color,
+                width: 1.0,
+                radius: 4.0.into(),
+            },
+            ..Default::default()
+        })
+}
+
+// Orange, like an unhealthy tunnel's status dot.
+fn flap_badge(warning: String) -> Container<'static, Message> {
+    let color = Color::from_rgb(1.0, 0.5, 0.0);
+    container(text(warning).size(11).color(color))
+        .padding([2, 4])
+        .style(move |_theme: &iced::Theme| container::Style {
+            border: iced::Border {
+                color,
+                width: 1.0,
+                radius: 4.0.into(),
+            },
+            ..Default::default()
+        })
+}
+
+fn tunnel_row(
+    tunnel: TunnelEntry,
+    selected: bool,
+    flap_warning: Option<String>,
+    read_only: bool,
+) -> Element<'static, Message> {
     let status = tunnel
         .runtime_state
         .as_ref()
         .unwrap_or(&TunnelRuntimeState::Stopped);
 
     let status_text = match status {
+        TunnelRuntimeState::Running {
+            pid,
+            started_at,
+            health: TunnelHealth::Unhealthy { error },
+            ..
+        } => {
+            format!(
+                "Unhealthy (PID: {}, uptime: {}): {}",
+                pid,
+                format_uptime(started_at.elapsed()),
+                error
+            )
+        }
         TunnelRuntimeState::Running {
             pid, started_at, ..
         } => {
             format!(
-                "Running (PID: {}, uptime: {}s)",
+                "Running (PID: {}, uptime: {})",
                 pid,
-                started_at.elapsed().as_secs()
+                format_uptime(started_at.elapsed())
             )
         }
         TunnelRuntimeState::Stopped => "Stopped".to_string(),
@@ -62,46 +212,199 @@ fn tunnel_row(tunnel: TunnelEntry) -> Element<'static, Message> {
     let tunnel_id = tunnel.id;
     let tunnel_tag = tunnel.tag.clone();
     let tunnel_mode = tunnel.mode;
+    let endpoints = tunnel
+        .parsed_endpoints()
+        .iter()
+        .fold(row![].spacing(4), |badges, endpoint| {
+            badges.push(endpoint_badge(endpoint))
+        });
 
-    let action_button = if is_running {
-        button("Stop").on_press(Message::TunnelList(TunnelListMessage::StopTunnel(
-            tunnel_id,
-        )))
-    } else {
-        button("Start").on_press(Message::TunnelList(TunnelListMessage::StartTunnel(
-            tunnel_id,
-        )))
-    };
+    let (action_button, edit_button, delete_button) =
+        lifecycle_buttons(tunnel_id, status, read_only);
+
+    // Small text buttons next to the status for grabbing the PID (to attach a
+    // debugger) or the full command (to run it by hand).
+    let copy_pid = is_running.then(|| {
+        copy_button(
+            "Copy PID",
+            Message::TunnelList(TunnelListMessage::CopyPid(tunnel_id)),
+        )
+    });
+    let copy_args = copy_button(
+        "Copy args",
+        Message::TunnelList(TunnelListMessage::CopyCliArgs(tunnel_id)),
+    );
+    let open_port = tunnel.local_address().map(|address| {
+        tooltip(
+            button("Open").on_press(Message::TunnelList(TunnelListMessage::OpenLocalPort(
+                tunnel_id,
+            ))),
+            container(text(format!("Open {} in a browser", address)).size(12))
+                .padding(5)
+                .style(container::rounded_box),
+            tooltip::Position::Top,
+        )
+    });
 
-    let row_content = row![
-        status_indicator(status),
-        container(text(tunnel_tag).size(16))
+    // Selecting is only for deleting several tunnels at once.
+    let select = (!read_only).then(|| {
+        checkbox("", selected).on_toggle(move |checked| {
+            Message::TunnelList(TunnelListMessage::SelectTunnel(tunnel_id, checked))
+        })
+    });
+
+    let row_content = row![]
+        .push_maybe(select)
+        .extend([
+            status_indicator(status).into(),
+            container(
+                button(text(tunnel_tag).size(16))
+                    .style(button::text)
+                    .on_press(Message::TunnelList(TunnelListMessage::ShowDetails(
+                        tunnel_id,
+                    ))),
+            )
             .width(Length::Fixed(200.0))
-            .padding(5),
-        mode_badge(tunnel_mode),
-        container(text(status_text).size(14))
+            .padding(5)
+            .into(),
+            mode_badge(tunnel_mode).into(),
+            container(
+                column![
+                    text(status_text).size(14),
+                    endpoints.push_maybe(flap_warning.map(flap_badge))
+                ]
+                .spacing(4),
+            )
             .width(Length::Fill)
-            .padding(5),
-        action_button,
-        button("Edit").on_press(Message::TunnelList(TunnelListMessage::EditTunnel(
-            tunnel_id
-        ))),
-        button("Logs").on_press(Message::TunnelList(TunnelListMessage::OpenLogs(tunnel_id))),
-        button("Delete").on_press(Message::TunnelList(TunnelListMessage::DeleteTunnel(
-            tunnel_id
+            .padding(5)
+            .into(),
+        ])
+        .push_maybe(copy_pid)
+        .push(copy_args)
+        .push(action_button)
+        .push(edit_button)
+        .push(button("Logs").on_press(Message::TunnelList(TunnelListMessage::OpenLogs(tunnel_id))))
+        .push_maybe(open_port)
+        .push(delete_button)
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .padding(10);
+
+    container(row_content)
+        .width(Length::Fill)
+        .style(panel_style)
+        .into()
+}
+
+fn group_header(group: &str, count: usize, collapsed: bool) -> Element<'static, Message> {
+    let arrow = if collapsed { "▶" } else { "▼" };
+
+    row![
+        button(text(format!("{} {} ({})", arrow, group, count)).size(18))
+            .style(button::text)
+            .on_press(Message::TunnelList(TunnelListMessage::ToggleGroup(
+                group.to_string()
+            ))),
+        container(button("Start group").on_press(Message::TunnelList(
+            TunnelListMessage::StartGroup(group.to_string())
+        )))
+        .width(Length::Fill)
+        .align_x(iced::alignment::Horizontal::Right),
+        button("Stop group").on_press(Message::TunnelList(TunnelListMessage::StopGroup(
+            group.to_string()
         ))),
     ]
     .spacing(10)
+    .padding([5, 0])
     .align_y(Alignment::Center)
-    .padding(10);
+    .into()
+}
 
-    container(row_content)
+fn status_bar(wstunnel_version: Option<String>, theme: iced::Theme) -> Element<'static, Message> {
+    let version = match wstunnel_version
+        .as_deref()
+        .map(|v| (v, WstunnelVersion::parse(v)))
+    {
+        Some((_, Some(parsed))) if !parsed.supports_subcommands() => {
+            format!("wstunnel {} (too old, 7.0 or newer is needed)", parsed)
+        }
+        Some((version, _)) => format!("wstunnel {}", version),
+        None => "wstunnel version unknown".to_string(),
+    };
+
+    row![
+        container(text(version).size(12).color(Color::from_rgb(0.4, 0.4, 0.4))).width(Length::Fill),
+        text("Theme").size(12),
+        pick_list(iced::Theme::ALL, Some(theme), Message::ThemeSelected)
+            .text_size(12)
+            .padding(4),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .padding([4, 10])
+    .into()
+}
+
+fn empty_state_view(read_only: bool) -> Element<'static, Message> {
+    let content = match read_only {
+        true => column![
+            text("No tunnels configured").size(24),
+            text(errors::config::READ_ONLY).size(16),
+        ],
+        false => column![
+            text("No tunnels configured").size(24),
+            text("Click 'Add Tunnel' to create your first tunnel").size(16),
+            row![
+                button("Add Tunnel")
+                    .on_press(Message::TunnelList(TunnelListMessage::AddTunnel))
+                    .padding(10),
+                button("Import Config")
+                    .on_press(Message::TunnelList(TunnelListMessage::ImportConfig))
+                    .padding(10)
+            ]
+            .spacing(10)
+        ],
+    };
+    container(content.spacing(20).align_x(Alignment::Center))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into()
+}
+
+pub fn error_bar(error_message: String, on_dismiss: Message) -> Element<'static, Message> {
+    container(
+        row![
+            text(error_message).color(Color::from_rgb(0.8, 0.0, 0.0)),
+            button("Dismiss").on_press(on_dismiss)
+        ]
+        .spacing(10)
+        .padding(10),
+    )
+    .width(Length::Fill)
+    .style(|_theme: &iced::Theme| container::Style {
+        background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.9, 0.9))),
+        border: iced::Border {
+            color: Color::from_rgb(0.8, 0.0, 0.0),
+            width: 2.0,
+            radius: 5.0.into(),
+        },
+        ..Default::default()
+    })
+    .into()
+}
+
+// Like the error bar, but for conditions that clear themselves.
+fn warning_bar(warning: String) -> Element<'static, Message> {
+    container(text(warning).color(Color::from_rgb(0.6, 0.3, 0.0)))
+        .padding(10)
         .width(Length::Fill)
         .style(|_theme: &iced::Theme| container::Style {
-            background: Some(iced::Background::Color(Color::from_rgb(0.95, 0.95, 0.95))),
+            background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.95, 0.8))),
             border: iced::Border {
-                color: Color::from_rgb(0.8, 0.8, 0.8),
-                width: 1.0,
+                color: Color::from_rgb(0.9, 0.5, 0.0),
+                width: 2.0,
                 radius: 5.0.into(),
             },
             ..Default::default()
@@ -109,76 +412,196 @@ fn tunnel_row(tunnel: TunnelEntry) -> Element<'static, Message> {
         .into()
 }
 
-fn empty_state_view() -> Element<'static, Message> {
+// Read-only managers can only take the file's version.
+fn config_changed_bar(read_only: bool) -> Element<'static, Message> {
+    let overwrite = (!read_only).then(|| {
+        button("Overwrite").on_press(Message::TunnelList(TunnelListMessage::OverwriteConfig))
+    });
     container(
-        column![
-            text("No tunnels configured").size(24),
-            text("Click 'Add Tunnel' to create your first tunnel").size(16),
-            button("Add Tunnel")
-                .on_press(Message::TunnelList(TunnelListMessage::AddTunnel))
-                .padding(10)
+        row![
+            text(errors::config::CHANGED_ON_DISK)
+                .color(Color::from_rgb(0.6, 0.3, 0.0))
+                .width(Length::Fill),
+            button("Reload").on_press(Message::TunnelList(TunnelListMessage::ReloadConfig)),
         ]
-        .spacing(20)
-        .align_x(Alignment::Center),
+        .push_maybe(overwrite)
+        .spacing(10)
+        .align_y(Alignment::Center),
     )
+    .padding(10)
     .width(Length::Fill)
-    .height(Length::Fill)
-    .center_x(Length::Fill)
-    .center_y(Length::Fill)
+    .style(|_theme: &iced::Theme| container::Style {
+        background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.95, 0.8))),
+        border: iced::Border {
+            color: Color::from_rgb(0.9, 0.5, 0.0),
+            width: 2.0,
+            radius: 5.0.into(),
+        },
+        ..Default::default()
+    })
+    .into()
+}
+
+// Shown while tunnels are ticked, with the actions that apply to all of them.
+fn selection_bar(count: usize) -> Element<'static, Message> {
+    row![
+        text(format!("{} selected", count)).width(Length::Fill),
+        button("Delete selected")
+            .style(button::danger)
+            .on_press(Message::TunnelList(TunnelListMessage::DeleteSelected)),
+        button("Clear selection")
+            .style(button::secondary)
+            .on_press(Message::TunnelList(TunnelListMessage::ClearSelection)),
+    ]
+    .spacing(10)
+    .padding([0, 10])
+    .align_y(Alignment::Center)
     .into()
 }
 
+fn filter_bar(state: &TunnelListState) -> Element<'static, Message> {
+    let mut filters = row![
+        text_input("Filter by tag...", &state.filter)
+            .on_input(|filter| Message::TunnelList(TunnelListMessage::FilterChanged(filter)))
+            .padding(8)
+            .width(Length::Fill)
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    for quick_filter in QuickFilter::all() {
+        let style = if quick_filter == state.quick_filter {
+            button::primary
+        } else {
+            button::secondary
+        };
+        filters = filters.push(
+            button(text(quick_filter.to_string()))
+                .style(style)
+                .on_press(Message::TunnelList(TunnelListMessage::QuickFilterSelected(
+                    quick_filter,
+                ))),
+        );
+    }
+
+    filters = filters.push(
+        pick_list(SortOrder::ALL, Some(state.sort_order), |sort_order| {
+            Message::TunnelList(TunnelListMessage::SortOrderSelected(sort_order))
+        })
+        .padding(8),
+    );
+
+    filters.padding([0, 10]).into()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn tunnel_list_view(
     state: TunnelListState,
     tunnels: Vec<TunnelEntry>,
+    wstunnel_version: Option<String>,
+    disk_space_warning: Option<String>,
+    mut flap_warnings: HashMap<TunnelId, String>,
+    config_changed_on_disk: bool,
+    read_only: bool,
+    theme: iced::Theme,
 ) -> Element<'static, Message> {
     if tunnels.is_empty() {
-        return empty_state_view();
+        return column![empty_state_view(read_only)]
+            .push_maybe(state.error_message.map(|error_message| {
+                error_bar(
+                    error_message,
+                    Message::TunnelList(TunnelListMessage::DismissError),
+                )
+            }))
+            .push(status_bar(wstunnel_version, theme))
+            .into();
     }
 
     let mut content = Column::new().spacing(10).padding(10);
 
-    for tunnel in tunnels {
-        content = content.push(tunnel_row(tunnel));
+    let grouped = tunnels.iter().any(|tunnel| tunnel.group.is_some());
+    let mut visible: Vec<TunnelEntry> = tunnels
+        .into_iter()
+        .filter(|tunnel| state.shows(tunnel))
+        .collect();
+    state.sort_order.sort(&mut visible);
+
+    if visible.is_empty() {
+        content = content.push(
+            container(text("No tunnels match the current filter").size(16))
+                .width(Length::Fill)
+                .center_x(Length::Fill)
+                .padding(20),
+        );
+    }
+
+    // Section headers only appear once some tunnel has a group.
+    if grouped {
+        let mut groups: BTreeMap<String, Vec<TunnelEntry>> = BTreeMap::new();
+        for tunnel in visible {
+            groups
+                .entry(tunnel.group_name().to_string())
+                .or_default()
+                .push(tunnel);
+        }
+        for (group, members) in groups {
+            let collapsed = state.collapsed_groups.contains(&group);
+            content = content.push(group_header(&group, members.len(), collapsed));
+            if !collapsed {
+                for tunnel in members {
+                    let selected = state.selected.contains(&tunnel.id);
+                    let flap_warning = flap_warnings.remove(&tunnel.id);
+                    content = content.push(tunnel_row(tunnel, selected, flap_warning, read_only));
+                }
+            }
+        }
+    } else {
+        for tunnel in visible {
+            let selected = state.selected.contains(&tunnel.id);
+            let flap_warning = flap_warnings.remove(&tunnel.id);
+            content = content.push(tunnel_row(tunnel, selected, flap_warning, read_only));
+        }
     }
 
     let scrollable_content = scrollable(content).height(Length::Fill).width(Length::Fill);
 
-    let header = row![
-        text(crate::constants::APP_TITLE).size(24),
-        container(button("Add Tunnel").on_press(Message::TunnelList(TunnelListMessage::AddTunnel)))
-            .width(Length::Fill)
-            .align_x(iced::alignment::Horizontal::Right),
-        button("Refresh").on_press(Message::TunnelList(TunnelListMessage::Refresh)),
-    ]
-    .spacing(10)
-    .padding(10)
-    .align_y(Alignment::Center);
+    // Buttons that change the config are left out when it is read-only.
+    let editable = |label: &'static str, message: TunnelListMessage| {
+        (!read_only).then(|| button(label).on_press(Message::TunnelList(message)))
+    };
+    let header = row![container(text(crate::constants::APP_TITLE).size(24)).width(Length::Fill),]
+        .push_maybe(editable("Add Tunnel", TunnelListMessage::AddTunnel))
+        .push(button("Start All").on_press(Message::TunnelList(TunnelListMessage::StartAll)))
+        .push(button("Stop All").on_press(Message::TunnelList(TunnelListMessage::StopAll)))
+        .push_maybe(editable("Import", TunnelListMessage::ImportConfig))
+        .push_maybe(editable("Paste Tunnel", TunnelListMessage::ImportTunnel))
+        .push(button("Export").on_press(Message::TunnelList(TunnelListMessage::ExportConfig)))
+        .push_maybe(editable("Undo Save", TunnelListMessage::RestoreBackup))
+        .push(button("All Logs").on_press(Message::TunnelList(TunnelListMessage::ShowCombinedLogs)))
+        .push_maybe(editable("Settings", TunnelListMessage::ShowSettings))
+        .push(button("About").on_press(Message::TunnelList(TunnelListMessage::ShowAbout)))
+        .push(button("Refresh").on_press(Message::TunnelList(TunnelListMessage::Refresh)))
+        .spacing(10)
+        .padding(10)
+        .align_y(Alignment::Center);
 
-    let mut main_column = column![header, scrollable_content].spacing(0);
+    let mut main_column = column![header]
+        .push_maybe(disk_space_warning.map(warning_bar))
+        .push_maybe(config_changed_on_disk.then(|| config_changed_bar(read_only)))
+        .push(filter_bar(&state))
+        .push_maybe((!state.selected.is_empty()).then(|| selection_bar(state.selected.len())))
+        .push(scrollable_content)
+        .spacing(0);
 
     if let Some(error_message) = state.error_message {
-        let error_bar = container(
-            row![
-                text(error_message).color(Color::from_rgb(0.8, 0.0, 0.0)),
-                button("Dismiss").on_press(Message::TunnelList(TunnelListMessage::DismissError))
-            ]
-            .spacing(10)
-            .padding(10),
-        )
-        .width(Length::Fill)
-        .style(|_theme: &iced::Theme| container::Style {
-            background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.9, 0.9))),
-            border: iced::Border {
-                color: Color::from_rgb(0.8, 0.0, 0.0),
-                width: 2.0,
-                radius: 5.0.into(),
-            },
-            ..Default::default()
-        });
-        main_column = main_column.push(error_bar);
+        main_column = main_column.push(error_bar(
+            error_message,
+            Message::TunnelList(TunnelListMessage::DismissError),
+        ));
     }
 
+    main_column = main_column.push(status_bar(wstunnel_version, theme));
+
     container(main_column)
         .width(Length::Fill)
         .height(Length::Fill)
@@ -186,12 +609,65 @@ pub fn tunnel_list_view(
 }
 
 pub fn confirm_delete_view(state: ConfirmDeleteState) -> Element<'static, Message> {
+    let (title, running_warning) = match state.targets.as_slice() {
+        [target] => (
+            "Delete Tunnel?".to_string(),
+            target.running_pid.map(|pid| {
+                format!(
+                    "This tunnel is currently RUNNING (PID: {}) and will be stopped.",
+                    pid
+                )
+            }),
+        ),
+        targets => (
+            format!("Delete {} Tunnels?", targets.len()),
+            match state.running_count() {
+                0 => None,
+                1 => Some("1 of these tunnels is currently RUNNING and will be stopped.".into()),
+                count => Some(format!(
+                    "{} of these tunnels are currently RUNNING and will be stopped.",
+                    count
+                )),
+            },
+        ),
+    };
+    let running_warning = running_warning.map(|warning| {
+        container(text(warning).size(16).color(Color::WHITE))
+            .padding(10)
+            .style(|_theme: &iced::Theme| {
+                container::Style::default().background(Color::from_rgb(0.8, 0.0, 0.0))
+            })
+    });
+
+    // Running tunnels are marked so it is clear which ones will be stopped.
+    let tags = Column::with_children(state.targets.iter().map(|target| {
+        let tag = text(format!("Tunnel: {}", target.tunnel_name)).size(20);
+        match target.running_pid {
+            Some(pid) if state.targets.len() > 1 => row![
+                tag,
+                text(format!("running, PID {}", pid))
+                    .size(14)
+                    .color(Color::from_rgb(0.8, 0.0, 0.0)),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into(),
+            _ => tag.into(),
+        }
+    }))
+    .spacing(4);
+
     let content = column![
-        text("Delete Tunnel?").size(32),
-        text(format!("Tunnel: {}", state.tunnel_name)).size(20),
-        text("This will stop the tunnel if running and remove the configuration.")
+        text(title).size(32),
+        scrollable(tags).height(Length::Shrink)
+    ]
+    .push_maybe(running_warning)
+    .push(
+        text("This will remove the tunnel configuration.")
             .size(14)
             .color(Color::from_rgb(0.6, 0.0, 0.0)),
+    )
+    .push(
         row![
             button("Cancel")
                 .on_press(Message::ConfirmDelete(ConfirmDeleteMessage::Cancel))
@@ -232,6 +708,71 @@ pub fn confirm_delete_view(state: ConfirmDeleteState) -> Element<'static, Messag
         ]
         .spacing(20)
         .align_y(Alignment::Center),
+    )
+    .spacing(20)
+    .padding(20)
+    .align_x(Alignment::Center);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into()
+}
+
+pub fn confirm_quit_view(state: ConfirmQuitState) -> Element<'static, Message> {
+    if state.shutting_down {
+        let progress = match state.running_count {
+            1 => "Shutting down 1 tunnel...".to_string(),
+            count => format!("Shutting down {} tunnels...", count),
+        };
+        return container(
+            column![
+                text(progress).size(24),
+                text("Tunnels that don't exit within a few seconds are killed.")
+                    .size(14)
+                    .color(Color::from_rgb(0.4, 0.4, 0.4)),
+            ]
+            .spacing(20)
+            .padding(20)
+            .align_x(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into();
+    }
+
+    let question = match state.running_count {
+        1 => "1 tunnel is running. Stop it and quit?".to_string(),
+        count => format!("{} tunnels are running. Stop them and quit?", count),
+    };
+
+    let content = column![
+        text("Quit?").size(32),
+        text(question).size(20),
+        text("Tunnels left running keep working, but their output is no longer logged.")
+            .size(14)
+            .color(Color::from_rgb(0.4, 0.4, 0.4)),
+        row![
+            button("Cancel")
+                .on_press(Message::ConfirmQuit(ConfirmQuitMessage::Cancel))
+                .padding(10),
+            button("Leave running and quit")
+                .on_press(Message::ConfirmQuit(
+                    ConfirmQuitMessage::LeaveRunningAndQuit
+                ))
+                .padding(10)
+                .style(button::secondary),
+            button("Stop and quit")
+                .on_press(Message::ConfirmQuit(ConfirmQuitMessage::StopAndQuit))
+                .padding(10)
+                .style(button::danger),
+        ]
+        .spacing(20)
+        .align_y(Alignment::Center),
     ]
     .spacing(20)
     .padding(20)
@@ -1,10 +1,157 @@
-use crate::backend::types::TunnelId;
+use crate::backend::logs::{LogFileInfo, LogSearch, LogStream, TaggedLogLine};
+use crate::backend::types::{
+    GlobalSettings, HealthCheck, LogMode, ProcessId, ProcessPriority, TunnelEntry, TunnelField,
+    TunnelId, TunnelMode, TunnelRuntimeState, TunnelStats, generate_tag,
+};
+use crate::constants::{DEFAULT_HEALTH_CHECK_INTERVAL_SECS, PALETTE_MAX_RESULTS, TOAST_DURATION};
+use crate::errors;
+use crate::ui::messages::TunnelListMessage;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuickFilter {
+    #[default]
+    All,
+    Running,
+    Stopped,
+    Autostart,
+}
+
+impl QuickFilter {
+    pub fn all() -> impl Iterator<Item = QuickFilter> {
+        [
+            QuickFilter::All,
+            QuickFilter::Running,
+            QuickFilter::Stopped,
+            QuickFilter::Autostart,
+        ]
+        .into_iter()
+    }
+
+    fn matches(self, tunnel: &TunnelEntry) -> bool {
+        let running = matches!(
+            tunnel.runtime_state,
+            Some(TunnelRuntimeState::Running { .. })
+        );
+        match self {
+            QuickFilter::All => true,
+            QuickFilter::Running => running,
+            QuickFilter::Stopped => !running,
+            QuickFilter::Autostart => tunnel.autostart,
+        }
+    }
+}
+
+impl fmt::Display for QuickFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            QuickFilter::All => "All",
+            QuickFilter::Running => "Running",
+            QuickFilter::Stopped => "Stopped",
+            QuickFilter::Autostart => "Autostart",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Success,
+    Error,
+}
+
+// Short-lived notification shown over the current screen. Failures that need
+// attention still go to the screen's error bar instead.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub message: String,
+    pub shown_at: Instant,
+}
+
+impl Toast {
+    pub fn success(message: impl Into<String>) -> Self {
+        Self {
+            kind: ToastKind::Success,
+            message: message.into(),
+            shown_at: Instant::now(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            kind: ToastKind::Error,
+            message: message.into(),
+            shown_at: Instant::now(),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.shown_at.elapsed() >= TOAST_DURATION
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Config,
+    Tag,
+    Created,
+    Modified,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [
+        SortOrder::Config,
+        SortOrder::Tag,
+        SortOrder::Created,
+        SortOrder::Modified,
+    ];
+
+    // Timestamps sort newest first; tunnels without one go last. The sort is
+    // stable so ties keep their config order.
+    pub fn sort(self, tunnels: &mut [TunnelEntry]) {
+        match self {
+            SortOrder::Config => {}
+            SortOrder::Tag => tunnels.sort_by_key(|tunnel| tunnel.tag.to_lowercase()),
+            SortOrder::Created => {
+                tunnels.sort_by_key(|tunnel| std::cmp::Reverse(tunnel.created_at))
+            }
+            SortOrder::Modified => {
+                tunnels.sort_by_key(|tunnel| std::cmp::Reverse(tunnel.modified_at))
+            }
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SortOrder::Config => "Config order",
+            SortOrder::Tag => "Tag",
+            SortOrder::Created => "Newest",
+            SortOrder::Modified => "Recently modified",
+        };
+        write!(f, "{}", label)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct TunnelListState {
     #[allow(dead_code)]
     pub scroll_position: f32,
     pub error_message: Option<String>,
+    pub filter: String,
+    pub quick_filter: QuickFilter,
+    pub sort_order: SortOrder,
+    pub collapsed_groups: HashSet<String>,
+    // Tunnels ticked for a bulk delete.
+    pub selected: HashSet<TunnelId>,
 }
 
 impl Default for TunnelListState {
@@ -12,10 +159,24 @@ impl Default for TunnelListState {
         Self {
             scroll_position: 0.0,
             error_message: None,
+            filter: String::new(),
+            quick_filter: QuickFilter::All,
+            sort_order: SortOrder::Config,
+            collapsed_groups: HashSet::new(),
+            selected: HashSet::new(),
         }
     }
 }
 
+impl TunnelListState {
+    // Case-insensitive tag match combined with the selected quick filter.
+    pub fn shows(&self, tunnel: &TunnelEntry) -> bool {
+        let filter = self.filter.trim().to_lowercase();
+        (filter.is_empty() || tunnel.tag.to_lowercase().contains(&filter))
+            && self.quick_filter.matches(tunnel)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum EditMode {
     Create,
@@ -26,44 +187,1013 @@ pub enum EditMode {
 pub struct EditTunnelState {
     pub mode: EditMode,
     pub tag_input: String,
+    pub tunnel_mode: TunnelMode,
     pub cli_args_input: String,
     pub autostart_checkbox: bool,
+    pub autostart_priority_input: String,
+    pub autostart_delay_input: String,
+    pub group_input: String,
+    pub env_vars: Vec<(String, String)>,
+    pub log_directory_input: String,
+    pub working_dir_input: String,
+    pub priority_input: String,
+    pub health_check_target_input: String,
+    pub health_check_interval_input: String,
+    pub depends_on: Vec<TunnelId>,
+    // The other tunnels, by ID and tag, that can be picked as dependencies.
+    pub dependency_options: Vec<(TunnelId, String)>,
     pub validation_errors: Vec<String>,
+    // Problems with what has been typed so far, shown under each input.
+    // Saving checks everything again.
+    pub field_errors: HashMap<TunnelField, String>,
+    // Set while asking whether to throw away unsaved changes, to what the
+    // user was about to do.
+    pub leaving: Option<LeaveEdit>,
+    // The form as it was opened, to tell whether anything was changed.
+    initial: Option<Box<EditTunnelState>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaveEdit {
+    Cancel,
+    Quit,
 }
 
 impl EditTunnelState {
-    pub fn new_create() -> Self {
+    pub fn new_create(tunnels: &[TunnelEntry]) -> Self {
         Self {
             mode: EditMode::Create,
             tag_input: String::new(),
+            tunnel_mode: TunnelMode::Client,
             cli_args_input: String::new(),
             autostart_checkbox: false,
+            autostart_priority_input: String::new(),
+            autostart_delay_input: String::new(),
+            group_input: String::new(),
+            env_vars: Vec::new(),
+            log_directory_input: String::new(),
+            working_dir_input: String::new(),
+            priority_input: String::new(),
+            health_check_target_input: String::new(),
+            health_check_interval_input: DEFAULT_HEALTH_CHECK_INTERVAL_SECS.to_string(),
+            depends_on: Vec::new(),
+            dependency_options: dependency_options(tunnels, None),
             validation_errors: Vec::new(),
+            field_errors: HashMap::new(),
+            leaving: None,
+            initial: None,
         }
+        .remember_initial()
     }
 
-    pub fn new_edit(id: TunnelId, tag: String, cli_args: String, autostart: bool) -> Self {
+    pub fn new_edit(tunnel: TunnelEntry, tunnels: &[TunnelEntry]) -> Self {
+        let mut env_vars: Vec<(String, String)> = tunnel.env.into_iter().collect();
+        env_vars.sort();
+
         Self {
-            mode: EditMode::Edit { id },
-            tag_input: tag,
-            cli_args_input: cli_args,
-            autostart_checkbox: autostart,
+            mode: EditMode::Edit { id: tunnel.id },
+            tag_input: tunnel.tag,
+            tunnel_mode: tunnel.mode,
+            cli_args_input: tunnel.cli_args,
+            autostart_checkbox: tunnel.autostart,
+            autostart_priority_input: match tunnel.autostart_priority {
+                0 => String::new(),
+                priority => priority.to_string(),
+            },
+            autostart_delay_input: tunnel
+                .autostart_delay_ms
+                .map(|delay| delay.to_string())
+                .unwrap_or_default(),
+            group_input: tunnel.group.unwrap_or_default(),
+            env_vars,
+            log_directory_input: tunnel
+                .log_directory
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            working_dir_input: tunnel
+                .working_dir
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            priority_input: tunnel
+                .priority
+                .map(|priority| priority.to_string())
+                .unwrap_or_default(),
+            health_check_target_input: tunnel
+                .health_check
+                .as_ref()
+                .map(|check| check.target.clone())
+                .unwrap_or_default(),
+            health_check_interval_input: tunnel
+                .health_check
+                .map_or(DEFAULT_HEALTH_CHECK_INTERVAL_SECS, |check| {
+                    check.interval_secs
+                })
+                .to_string(),
+            depends_on: tunnel.depends_on,
+            dependency_options: dependency_options(tunnels, Some(tunnel.id)),
             validation_errors: Vec::new(),
+            field_errors: HashMap::new(),
+            leaving: None,
+            initial: None,
         }
+        .remember_initial()
+    }
+
+    fn remember_initial(mut self) -> Self {
+        self.initial = Some(Box::new(self.clone()));
+        self
+    }
+
+    // Whether any field differs from when the form was opened. Typing
+    // something and deleting it again leaves nothing to lose.
+    pub fn has_unsaved_changes(&self) -> bool {
+        let Some(initial) = &self.initial else {
+            return false;
+        };
+        self.tag_input != initial.tag_input
+            || self.tunnel_mode != initial.tunnel_mode
+            || self.cli_args_input != initial.cli_args_input
+            || self.autostart_checkbox != initial.autostart_checkbox
+            || self.autostart_priority_input != initial.autostart_priority_input
+            || self.autostart_delay_input != initial.autostart_delay_input
+            || self.group_input != initial.group_input
+            || self.env_map() != initial.env_map()
+            || self.log_directory_input != initial.log_directory_input
+            || self.working_dir_input != initial.working_dir_input
+            || self.priority_input != initial.priority_input
+            || self.health_check_target_input != initial.health_check_target_input
+            || self.health_check_interval_input != initial.health_check_interval_input
+            || self.depends_on != initial.depends_on
+    }
+
+    pub fn env_map(&self) -> HashMap<String, String> {
+        self.env_vars
+            .iter()
+            .filter(|(key, value)| !(key.trim().is_empty() && value.is_empty()))
+            .map(|(key, value)| (key.trim().to_string(), value.clone()))
+            .collect()
+    }
+
+    // None when left blank, in which case a tag is generated on save.
+    pub fn tag(&self) -> Option<String> {
+        let trimmed = self.tag_input.trim();
+        (!trimmed.is_empty()).then(|| trimmed.to_string())
+    }
+
+    pub fn cli_args_error(&self) -> Option<String> {
+        crate::backend::process::check_cli_args_quotes(&self.cli_args_input)
+            .err()
+            .map(|e| e.to_string())
+    }
+
+    pub fn group(&self) -> Option<String> {
+        let trimmed = self.group_input.trim();
+        (!trimmed.is_empty()).then(|| trimmed.to_string())
+    }
+
+    pub fn log_directory(&self) -> Option<PathBuf> {
+        let trimmed = self.log_directory_input.trim();
+        (!trimmed.is_empty()).then(|| PathBuf::from(trimmed))
+    }
+
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        let trimmed = self.working_dir_input.trim();
+        (!trimmed.is_empty()).then(|| PathBuf::from(trimmed))
+    }
+
+    // Blank fields mean priority 0 and no delay.
+    pub fn autostart_order(&self) -> Result<(i32, Option<u64>), String> {
+        let priority = self.autostart_priority_input.trim();
+        let priority = if priority.is_empty() {
+            0
+        } else {
+            priority
+                .parse()
+                .map_err(|_| errors::tunnel::validation::autostart_priority_invalid(priority))?
+        };
+        let delay = self.autostart_delay_input.trim();
+        let delay_ms = if delay.is_empty() {
+            None
+        } else {
+            Some(
+                delay
+                    .parse()
+                    .map_err(|_| errors::tunnel::validation::autostart_delay_invalid(delay))?,
+            )
+        };
+        Ok((priority, delay_ms))
+    }
+
+    // The tunnel the form describes, or the first problem with it. A blank
+    // tag is generated the way saving does.
+    pub fn entry(&self, tunnels: &[TunnelEntry]) -> Result<TunnelEntry, String> {
+        let mut first_error = None;
+        let entry = self.build_entry(tunnels, &mut |_, error| {
+            first_error.get_or_insert(error);
+        });
+        first_error.map_or(Ok(entry), Err)
+    }
+
+    // Runs after each edit: every input that doesn't parse, then the same
+    // checks as `TunnelEntry::validate` on the rest.
+    pub fn check_fields(&mut self, tunnels: &[TunnelEntry]) {
+        let mut field_errors = HashMap::new();
+        let entry = self.build_entry(tunnels, &mut |field, error| {
+            field_errors.entry(field).or_insert(error);
+        });
+        for (field, error) in entry.field_errors() {
+            field_errors.entry(field).or_insert(error);
+        }
+        self.field_errors = field_errors;
+    }
+
+    pub fn field_error(&self, field: TunnelField) -> Option<String> {
+        self.field_errors.get(&field).cloned()
+    }
+
+    // Inputs that don't parse are reported and left at their defaults.
+    fn build_entry(
+        &self,
+        tunnels: &[TunnelEntry],
+        report: &mut dyn FnMut(TunnelField, String),
+    ) -> TunnelEntry {
+        if let Some(error) = self.cli_args_error() {
+            report(TunnelField::CliArgs, error);
+        }
+        let health_check = self.health_check().unwrap_or_else(|error| {
+            report(TunnelField::HealthCheck, error);
+            None
+        });
+        let priority = self.priority().unwrap_or_else(|error| {
+            report(TunnelField::Priority, error);
+            None
+        });
+        let (autostart_priority, autostart_delay_ms) =
+            self.autostart_order().unwrap_or_else(|error| {
+                report(TunnelField::Autostart, error);
+                (0, None)
+            });
+        let id = match self.mode {
+            EditMode::Create => TunnelId::default(),
+            EditMode::Edit { id } => id,
+        };
+        let tag = self
+            .tag()
+            .unwrap_or_else(|| generate_tag(id, &self.cli_args_input, tunnels));
+        TunnelEntry {
+            id,
+            autostart: self.autostart_checkbox,
+            autostart_priority,
+            autostart_delay_ms,
+            depends_on: self.depends_on.clone(),
+            group: self.group(),
+            env: self.env_map(),
+            log_directory: self.log_directory(),
+            working_dir: self.working_dir(),
+            priority,
+            health_check,
+            ..TunnelEntry::new(tag, self.tunnel_mode, self.cli_args_input.clone())
+        }
+    }
+
+    // Blank means the OS default; the range is checked with the tunnel entry.
+    pub fn priority(&self) -> Result<Option<ProcessPriority>, String> {
+        let priority = self.priority_input.trim();
+        if priority.is_empty() {
+            return Ok(None);
+        }
+        priority
+            .parse::<i32>()
+            .map(|nice| Some(ProcessPriority::from(nice)))
+            .map_err(|_| errors::tunnel::validation::priority_invalid(priority))
+    }
+
+    // An empty target turns the health check off; the rest is validated with
+    // the tunnel entry.
+    pub fn health_check(&self) -> Result<Option<HealthCheck>, String> {
+        let target = self.health_check_target_input.trim();
+        if target.is_empty() {
+            return Ok(None);
+        }
+        let interval = self.health_check_interval_input.trim();
+        let interval_secs = interval
+            .parse()
+            .map_err(|_| errors::tunnel::validation::health_check_interval_invalid(interval))?;
+        Ok(Some(HealthCheck {
+            target: target.to_string(),
+            interval_secs,
+        }))
     }
 }
 
+fn dependency_options(
+    tunnels: &[TunnelEntry],
+    editing: Option<TunnelId>,
+) -> Vec<(TunnelId, String)> {
+    tunnels
+        .iter()
+        .filter(|t| Some(t.id) != editing)
+        .map(|t| (t.id, t.tag.clone()))
+        .collect()
+}
+
 #[derive(Debug, Clone)]
-pub struct ConfirmDeleteState {
+pub struct DeleteTarget {
     pub tunnel_id: TunnelId,
     pub tunnel_name: String,
+    // Set while the tunnel runs, since deleting it stops the process too.
+    pub running_pid: Option<ProcessId>,
+}
+
+impl DeleteTarget {
+    fn new(tunnel: &TunnelEntry) -> Self {
+        Self {
+            tunnel_id: tunnel.id,
+            tunnel_name: tunnel.tag.clone(),
+            running_pid: running_pid(tunnel),
+        }
+    }
+}
+
+// One tunnel from its Delete button, or the ticked ones from the list.
+#[derive(Debug, Clone)]
+pub struct ConfirmDeleteState {
+    pub targets: Vec<DeleteTarget>,
 }
 
 impl ConfirmDeleteState {
-    pub fn new(tunnel_id: TunnelId, tunnel_name: String) -> Self {
+    pub fn new(tunnel: &TunnelEntry) -> Self {
+        Self::for_tunnels([tunnel])
+    }
+
+    pub fn for_tunnels<'a>(tunnels: impl IntoIterator<Item = &'a TunnelEntry>) -> Self {
+        Self {
+            targets: tunnels.into_iter().map(DeleteTarget::new).collect(),
+        }
+    }
+
+    pub fn tunnel_ids(&self) -> Vec<TunnelId> {
+        self.targets.iter().map(|target| target.tunnel_id).collect()
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.targets
+            .iter()
+            .filter(|target| target.running_pid.is_some())
+            .count()
+    }
+
+    // Keeps the warnings accurate if tunnels start or stop while the dialog
+    // is open.
+    pub fn update(&mut self, tunnels: &[TunnelEntry]) {
+        for target in &mut self.targets {
+            if let Some(tunnel) = tunnels.iter().find(|t| t.id == target.tunnel_id) {
+                target.running_pid = running_pid(tunnel);
+            }
+        }
+    }
+}
+
+fn running_pid(tunnel: &TunnelEntry) -> Option<ProcessId> {
+    match tunnel.runtime_state {
+        Some(TunnelRuntimeState::Running { pid, .. }) => Some(pid),
+        _ => None,
+    }
+}
+
+// The tunnel itself comes from the app's list, which live events keep
+// current; only what the list lacks is loaded here.
+#[derive(Debug, Clone)]
+pub struct TunnelDetailState {
+    pub tunnel_id: TunnelId,
+    pub stats: Option<TunnelStats>,
+    pub log_path: Option<PathBuf>,
+    pub log_file: Option<LogFileInfo>,
+    // Starts out with the defaults so nothing is shown unmasked before the
+    // configured list has loaded.
+    pub sensitive_flags: Vec<String>,
+    // What "Preview command" returned, shown until hidden again.
+    pub command_preview: Option<String>,
+    pub recent_stderr: String,
+    pub error_message: Option<String>,
+}
+
+impl TunnelDetailState {
+    pub fn new(tunnel_id: TunnelId) -> Self {
         Self {
             tunnel_id,
-            tunnel_name,
+            stats: None,
+            log_path: None,
+            log_file: None,
+            sensitive_flags: GlobalSettings::default().sensitive_flags,
+            command_preview: None,
+            recent_stderr: String::new(),
+            error_message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamFilter {
+    #[default]
+    All,
+    Stdout,
+    Stderr,
+    Errors,
+}
+
+impl StreamFilter {
+    pub const ALL: [StreamFilter; 4] = [
+        StreamFilter::All,
+        StreamFilter::Stdout,
+        StreamFilter::Stderr,
+        StreamFilter::Errors,
+    ];
+
+    fn matches(self, line: &TaggedLogLine) -> bool {
+        match self {
+            StreamFilter::All => true,
+            StreamFilter::Stdout => line.stream == Some(LogStream::Stdout),
+            StreamFilter::Stderr => line.stream == Some(LogStream::Stderr),
+            StreamFilter::Errors => line.is_error(),
+        }
+    }
+}
+
+impl fmt::Display for StreamFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            StreamFilter::All => "STDOUT and STDERR",
+            StreamFilter::Stdout => "STDOUT only",
+            StreamFilter::Stderr => "STDERR only",
+            StreamFilter::Errors => "Errors only",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// An entry of the combined log view's tunnel picker; no ID means every
+// tunnel.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TunnelChoice {
+    pub id: Option<TunnelId>,
+    pub tag: String,
+}
+
+impl TunnelChoice {
+    pub fn options(tunnels: &[TunnelEntry]) -> Vec<TunnelChoice> {
+        std::iter::once(TunnelChoice::default())
+            .chain(tunnels.iter().map(|tunnel| TunnelChoice {
+                id: Some(tunnel.id),
+                tag: tunnel.tag.clone(),
+            }))
+            .collect()
+    }
+}
+
+impl fmt::Display for TunnelChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.id {
+            Some(_) => write!(f, "{}", self.tag),
+            None => write!(f, "All tunnels"),
+        }
+    }
+}
+
+// Recent output of every tunnel, merged by time and reloaded while shown.
+#[derive(Debug, Clone, Default)]
+pub struct CombinedLogsState {
+    pub lines: Vec<TaggedLogLine>,
+    pub tunnel_filter: TunnelChoice,
+    pub stream_filter: StreamFilter,
+    pub search: LogSearch,
+    // None while the search is empty or its pattern doesn't compile, in which
+    // case `search_error` says why.
+    pub matcher: Option<regex::Regex>,
+    pub search_error: Option<String>,
+    // Index into `search_matches` of the match last jumped to.
+    pub current_match: Option<usize>,
+    // Matches from the selected tunnel's whole log history, shown instead of
+    // the tail until cleared.
+    pub history: Option<Vec<TaggedLogLine>>,
+}
+
+impl CombinedLogsState {
+    pub fn shows(&self, line: &TaggedLogLine) -> bool {
+        self.tunnel_filter.id.is_none_or(|id| id == line.tunnel_id)
+            && self.stream_filter.matches(line)
+    }
+
+    pub fn visible_lines(&self) -> Vec<&TaggedLogLine> {
+        match &self.history {
+            Some(history) => history.iter().collect(),
+            None => self.lines.iter().filter(|line| self.shows(line)).collect(),
+        }
+    }
+
+    pub fn is_match(&self, line: &TaggedLogLine) -> bool {
+        self.matcher
+            .as_ref()
+            .is_some_and(|matcher| matcher.is_match(&line.line))
+    }
+
+    // Indices into `visible_lines` of the lines the search matches.
+    pub fn search_matches(&self) -> Vec<usize> {
+        self.visible_lines()
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| self.is_match(line))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn set_search(&mut self, search: LogSearch) {
+        (self.matcher, self.search_error) = match search.pattern.is_empty() {
+            true => (None, None),
+            false => match search.matcher() {
+                Ok(matcher) => (Some(matcher), None),
+                Err(e) => (None, Some(e.to_string())),
+            },
+        };
+        self.search = search;
+        self.current_match = None;
+    }
+
+    // Moves to the next match, or the previous one when `forward` is false,
+    // wrapping around; the first step goes to the first or last match.
+    // Returns the line index of the new current match.
+    pub fn step_match(&mut self, forward: bool) -> Option<usize> {
+        let matches = self.search_matches();
+        let count = matches.len();
+        if count == 0 {
+            self.current_match = None;
+            return None;
+        }
+        let current = match (self.current_match, forward) {
+            (Some(current), true) => (current.min(count - 1) + 1) % count,
+            (Some(current), false) => (current.min(count - 1) + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        self.current_match = Some(current);
+        Some(matches[current])
+    }
+}
+
+// First-run setup: where wstunnel is and where its logs go.
+#[derive(Debug, Clone, Default)]
+pub struct SetupState {
+    pub binary_path_input: String,
+    pub log_directory_input: String,
+    pub error_message: Option<String>,
+}
+
+impl SetupState {
+    pub fn new(binary_path: Option<PathBuf>, log_directory: PathBuf) -> Self {
+        Self {
+            binary_path_input: binary_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            log_directory_input: log_directory.display().to_string(),
+            error_message: None,
+        }
+    }
+
+    // Applied over the current settings; blank fields leave them as they are.
+    pub fn apply_to(&self, settings: &mut GlobalSettings) {
+        let binary_path = self.binary_path_input.trim();
+        if !binary_path.is_empty() {
+            settings.wstunnel_binary_path = Some(PathBuf::from(binary_path));
+        }
+        let log_directory = self.log_directory_input.trim();
+        if !log_directory.is_empty() {
+            settings.log_directory = PathBuf::from(log_directory);
+        }
+    }
+}
+
+// A theme for the settings screen's picker; none follows the OS preference.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ThemeChoice(pub Option<iced::Theme>);
+
+impl ThemeChoice {
+    pub fn options() -> Vec<ThemeChoice> {
+        std::iter::once(ThemeChoice(None))
+            .chain(iced::Theme::ALL.iter().cloned().map(Some).map(ThemeChoice))
+            .collect()
+    }
+
+    fn from_setting(name: Option<&str>) -> Self {
+        ThemeChoice(name.and_then(|name| {
+            iced::Theme::ALL
+                .iter()
+                .find(|theme| theme.to_string() == name)
+                .cloned()
+        }))
+    }
+}
+
+impl fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(theme) => write!(f, "{}", theme),
+            None => write!(f, "System default"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsField {
+    BinaryPath,
+    LogDirectory,
+    LogRetentionDays,
+    LogCompressDays,
+    MaxLogSizeMb,
+    MaxLogFiles,
+    LogLinePrefix,
+    SensitiveFlags,
+    StartTimeoutSeconds,
+    ReadyPattern,
+    LocalPortScheme,
+    StderrBufferLines,
+    LogMaxLinesPerSecond,
+    CleanupIntervalHours,
+    UiRefreshSeconds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsToggle {
+    FailOnMissingEnvVars,
+    DetachOnExit,
+    StopDependents,
+    StrictValidation,
+    LogTimestampUtc,
+}
+
+// Every `GlobalSettings` value as the settings screen edits it. Numbers are
+// kept as typed and only parsed on save.
+#[derive(Debug, Clone)]
+pub struct SettingsState {
+    pub binary_path_input: String,
+    pub log_directory_input: String,
+    pub log_retention_days_input: String,
+    pub log_compress_days_input: String,
+    pub max_log_size_mb_input: String,
+    pub max_log_files_input: String,
+    pub log_line_prefix_input: String,
+    pub sensitive_flags_input: String,
+    pub start_timeout_seconds_input: String,
+    pub ready_pattern_input: String,
+    pub local_port_scheme_input: String,
+    pub stderr_buffer_lines_input: String,
+    pub log_max_lines_per_second_input: String,
+    pub cleanup_interval_hours_input: String,
+    pub ui_refresh_seconds_input: String,
+    pub fail_on_missing_env_vars: bool,
+    pub detach_on_exit: bool,
+    pub strict_validation: bool,
+    pub log_timestamp_utc: bool,
+    pub stop_dependents: bool,
+    pub log_mode: LogMode,
+    pub theme: ThemeChoice,
+    pub error_message: Option<String>,
+    // Size of the log directories, None until it has been measured.
+    pub log_usage: Option<u64>,
+    // Age in days past which "Clean up now" deletes logs. Starts at the
+    // retention period; blank uses the saved one.
+    pub cleanup_days_input: String,
+    pub cleaning_logs: bool,
+}
+
+impl SettingsState {
+    pub fn new(settings: &GlobalSettings) -> Self {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        Self {
+            binary_path_input: optional(
+                settings
+                    .wstunnel_binary_path
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+            log_directory_input: settings.log_directory.display().to_string(),
+            log_retention_days_input: optional(settings.log_retention_days.map(|d| d.to_string())),
+            log_compress_days_input: optional(settings.log_compress_days.map(|d| d.to_string())),
+            max_log_size_mb_input: optional(settings.max_log_size_mb.map(|s| s.to_string())),
+            max_log_files_input: settings.max_log_files.to_string(),
+            log_line_prefix_input: optional(settings.log_line_prefix.clone()),
+            sensitive_flags_input: settings.sensitive_flags.join(", "),
+            start_timeout_seconds_input: optional(
+                settings.start_timeout_seconds.map(|s| s.to_string()),
+            ),
+            ready_pattern_input: optional(settings.ready_pattern.clone()),
+            local_port_scheme_input: optional(settings.local_port_scheme.clone()),
+            stderr_buffer_lines_input: settings.stderr_buffer_lines.to_string(),
+            log_max_lines_per_second_input: optional(
+                settings.log_max_lines_per_second.map(|l| l.to_string()),
+            ),
+            cleanup_interval_hours_input: settings.cleanup_interval_hours.to_string(),
+            ui_refresh_seconds_input: optional(settings.ui_refresh_seconds.map(|s| s.to_string())),
+            fail_on_missing_env_vars: settings.fail_on_missing_env_vars,
+            detach_on_exit: settings.detach_on_exit,
+            stop_dependents: settings.stop_dependents,
+            strict_validation: settings.strict_validation,
+            log_timestamp_utc: settings.log_timestamp_utc,
+            log_mode: settings.log_mode,
+            theme: ThemeChoice::from_setting(settings.theme.as_deref()),
+            error_message: None,
+            log_usage: None,
+            cleanup_days_input: optional(settings.log_retention_days.map(|d| d.to_string())),
+            cleaning_logs: false,
+        }
+    }
+
+    pub fn input_mut(&mut self, field: SettingsField) -> &mut String {
+        match field {
+            SettingsField::BinaryPath => &mut self.binary_path_input,
+            SettingsField::LogDirectory => &mut self.log_directory_input,
+            SettingsField::LogRetentionDays => &mut self.log_retention_days_input,
+            SettingsField::LogCompressDays => &mut self.log_compress_days_input,
+            SettingsField::MaxLogSizeMb => &mut self.max_log_size_mb_input,
+            SettingsField::MaxLogFiles => &mut self.max_log_files_input,
+            SettingsField::LogLinePrefix => &mut self.log_line_prefix_input,
+            SettingsField::SensitiveFlags => &mut self.sensitive_flags_input,
+            SettingsField::StartTimeoutSeconds => &mut self.start_timeout_seconds_input,
+            SettingsField::ReadyPattern => &mut self.ready_pattern_input,
+            SettingsField::LocalPortScheme => &mut self.local_port_scheme_input,
+            SettingsField::StderrBufferLines => &mut self.stderr_buffer_lines_input,
+            SettingsField::LogMaxLinesPerSecond => &mut self.log_max_lines_per_second_input,
+            SettingsField::CleanupIntervalHours => &mut self.cleanup_interval_hours_input,
+            SettingsField::UiRefreshSeconds => &mut self.ui_refresh_seconds_input,
+        }
+    }
+
+    pub fn toggle_mut(&mut self, toggle: SettingsToggle) -> &mut bool {
+        match toggle {
+            SettingsToggle::FailOnMissingEnvVars => &mut self.fail_on_missing_env_vars,
+            SettingsToggle::DetachOnExit => &mut self.detach_on_exit,
+            SettingsToggle::StrictValidation => &mut self.strict_validation,
+            SettingsToggle::LogTimestampUtc => &mut self.log_timestamp_utc,
+            SettingsToggle::StopDependents => &mut self.stop_dependents,
+        }
+    }
+
+    pub fn cleanup_days(&self) -> Result<Option<u32>, String> {
+        number("Days of logs to keep", &self.cleanup_days_input)
+    }
+
+    // The edited settings, checked with `GlobalSettings::validate`. Blank
+    // optional fields unset the value.
+    pub fn to_settings(&self) -> Result<GlobalSettings, String> {
+        let text = |input: &str| Some(input.trim().to_string()).filter(|s| !s.is_empty());
+        let log_directory = text(&self.log_directory_input)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| GlobalSettings::default().log_directory);
+
+        let settings = GlobalSettings {
+            wstunnel_binary_path: text(&self.binary_path_input).map(PathBuf::from),
+            log_directory,
+            log_retention_days: number("Log retention days", &self.log_retention_days_input)?,
+            fail_on_missing_env_vars: self.fail_on_missing_env_vars,
+            log_compress_days: number("Log compression days", &self.log_compress_days_input)?,
+            max_log_size_mb: number("Maximum log size", &self.max_log_size_mb_input)?,
+            max_log_files: number("Maximum log files", &self.max_log_files_input)?
+                .unwrap_or(GlobalSettings::default().max_log_files),
+            detach_on_exit: self.detach_on_exit,
+            sensitive_flags: self
+                .sensitive_flags_input
+                .split(',')
+                .map(str::trim)
+                .filter(|flag| !flag.is_empty())
+                .map(str::to_string)
+                .collect(),
+            strict_validation: self.strict_validation,
+            theme: self.theme.0.as_ref().map(|theme| theme.to_string()),
+            log_timestamp_utc: self.log_timestamp_utc,
+            log_line_prefix: text(&self.log_line_prefix_input),
+            log_mode: self.log_mode,
+            start_timeout_seconds: number("Start timeout", &self.start_timeout_seconds_input)?,
+            ready_pattern: text(&self.ready_pattern_input),
+            local_port_scheme: text(&self.local_port_scheme_input),
+            stderr_buffer_lines: number("Stderr buffer size", &self.stderr_buffer_lines_input)?
+                .unwrap_or(GlobalSettings::default().stderr_buffer_lines),
+            log_max_lines_per_second: number(
+                "Log line rate limit",
+                &self.log_max_lines_per_second_input,
+            )?,
+            cleanup_interval_hours: number("Cleanup interval", &self.cleanup_interval_hours_input)?
+                .unwrap_or(GlobalSettings::default().cleanup_interval_hours),
+            stop_dependents: self.stop_dependents,
+            ui_refresh_seconds: number("Refresh interval", &self.ui_refresh_seconds_input)?,
+            // A locked config refuses new settings, so there is nothing to
+            // keep here.
+            locked: false,
+        };
+        settings.validate().map_err(|e| e.to_string())?;
+        Ok(settings)
+    }
+}
+
+fn number<T: std::str::FromStr>(setting: &str, input: &str) -> Result<Option<T>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    input
+        .parse()
+        .map(Some)
+        .map_err(|_| errors::config::setting_not_a_number(setting, input))
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfirmQuitState {
+    pub running_count: usize,
+    // Set once "Stop and quit" is chosen, while the tunnels are stopped.
+    pub shutting_down: bool,
+}
+
+impl ConfirmQuitState {
+    pub fn new(running_count: usize) -> Self {
+        Self {
+            running_count,
+            shutting_down: false,
+        }
+    }
+}
+
+// What the about screen shows and "Copy diagnostics" puts on the clipboard
+// for bug reports.
+#[derive(Debug, Clone)]
+pub struct AboutState {
+    pub wstunnel_version: Option<String>,
+    pub config_path: PathBuf,
+    pub log_directory: PathBuf,
+    pub tunnel_count: usize,
+}
+
+impl AboutState {
+    pub fn from_backend(backend: &dyn crate::backend::Backend) -> Self {
+        let config = backend.get_config();
+        Self {
+            wstunnel_version: backend.wstunnel_version(),
+            config_path: backend.config_path(),
+            log_directory: config.global.log_directory.clone(),
+            tunnel_count: config.tunnels.len(),
+        }
+    }
+
+    pub fn diagnostics(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                crate::constants::APP_TITLE,
+                crate::constants::APP_VERSION.to_string(),
+            ),
+            (
+                "wstunnel",
+                self.wstunnel_version
+                    .clone()
+                    .unwrap_or_else(|| "not found".to_string()),
+            ),
+            ("Config", self.config_path.display().to_string()),
+            ("Log directory", self.log_directory.display().to_string()),
+            ("Tunnels", self.tunnel_count.to_string()),
+            (
+                "OS",
+                format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            ),
+        ]
+    }
+
+    pub fn diagnostics_text(&self) -> String {
+        self.diagnostics()
+            .iter()
+            .map(|(name, value)| format!("{}: {}\n", name, value))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteAction {
+    Start,
+    Stop,
+    Logs,
+    Edit,
+}
+
+impl PaletteAction {
+    pub fn all() -> impl Iterator<Item = PaletteAction> {
+        [
+            PaletteAction::Start,
+            PaletteAction::Stop,
+            PaletteAction::Logs,
+            PaletteAction::Edit,
+        ]
+        .into_iter()
+    }
+
+    // Same rules as the row buttons: a running tunnel can be stopped but not
+    // edited, and nothing but its logs is offered while it starts.
+    pub fn applies_to(self, tunnel: &TunnelEntry) -> bool {
+        let is_running = matches!(
+            tunnel.runtime_state,
+            Some(TunnelRuntimeState::Running { .. })
+        );
+        let is_starting = matches!(tunnel.runtime_state, Some(TunnelRuntimeState::Starting));
+        match self {
+            PaletteAction::Start | PaletteAction::Edit => !is_running && !is_starting,
+            PaletteAction::Stop => is_running,
+            PaletteAction::Logs => true,
+        }
+    }
+
+    pub fn message(self, id: TunnelId) -> TunnelListMessage {
+        match self {
+            PaletteAction::Start => TunnelListMessage::StartTunnel(id),
+            PaletteAction::Stop => TunnelListMessage::StopTunnel(id),
+            PaletteAction::Logs => TunnelListMessage::OpenLogs(id),
+            PaletteAction::Edit => TunnelListMessage::EditTunnel(id),
+        }
+    }
+}
+
+impl fmt::Display for PaletteAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            PaletteAction::Start => "start",
+            PaletteAction::Stop => "stop",
+            PaletteAction::Logs => "logs",
+            PaletteAction::Edit => "edit",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub tunnel_id: TunnelId,
+    pub tag: String,
+    pub action: PaletteAction,
+}
+
+// The Ctrl+P overlay. It lists one entry per tunnel and applicable action.
+#[derive(Debug, Clone, Default)]
+pub struct CommandPaletteState {
+    pub query: String,
+    pub selected: usize,
+}
+
+impl CommandPaletteState {
+    // Every word of the query has to fuzzy-match the tag or the action name,
+    // in any order, so "web st" and "stop web" both find "webserver". Best
+    // matches come first; ties keep the config order.
+    pub fn entries(&self, tunnels: &[TunnelEntry]) -> Vec<PaletteEntry> {
+        let matcher = SkimMatcherV2::default();
+        let words: Vec<&str> = self.query.split_whitespace().collect();
+
+        let mut scored: Vec<(i64, PaletteEntry)> = tunnels
+            .iter()
+            .flat_map(|tunnel| {
+                PaletteAction::all()
+                    .filter(|action| action.applies_to(tunnel))
+                    .map(move |action| (tunnel, action))
+            })
+            .filter_map(|(tunnel, action)| {
+                let action_name = action.to_string();
+                let mut score = 0;
+                for word in &words {
+                    let tag_score = matcher.fuzzy_match(&tunnel.tag, word);
+                    let action_score = matcher.fuzzy_match(&action_name, word);
+                    score += tag_score.max(action_score)?;
+                }
+                let entry = PaletteEntry {
+                    tunnel_id: tunnel.id,
+                    tag: tunnel.tag.clone(),
+                    action,
+                };
+                Some((score, entry))
+            })
+            .collect();
+
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored
+            .into_iter()
+            .take(PALETTE_MAX_RESULTS)
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+
+    // Moves the highlight through `count` entries, wrapping at either end.
+    pub fn step(&mut self, forward: bool, count: usize) {
+        if count == 0 {
+            self.selected = 0;
+        } else if forward {
+            self.selected = (self.selected + 1) % count;
+        } else {
+            self.selected = (self.selected + count - 1) % count;
         }
     }
 }
@@ -71,8 +1201,14 @@ impl ConfirmDeleteState {
 #[derive(Debug, Clone)]
 pub enum Screen {
     TunnelList(TunnelListState),
+    TunnelDetail(TunnelDetailState),
     EditTunnel(EditTunnelState),
     ConfirmDelete(ConfirmDeleteState),
+    ConfirmQuit(ConfirmQuitState),
+    CombinedLogs(CombinedLogsState),
+    Setup(SetupState),
+    Settings(SettingsState),
+    About(AboutState),
 }
 
 impl Default for Screen {
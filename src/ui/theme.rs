@@ -1,19 +1,83 @@
 use iced::Color;
+use iced::widget::container;
 
 pub struct WstunnelTheme {
     #[allow(dead_code)]
     pub colors: ThemeColors,
+    theme: iced::Theme,
 }
 
 impl WstunnelTheme {
     pub fn new() -> Self {
+        Self::from_setting(None)
+    }
+
+    // `name` is the `global.theme` setting. Unset or unknown names follow the
+    // OS dark-mode preference.
+    pub fn from_setting(name: Option<&str>) -> Self {
+        let theme = match name {
+            Some(name) => iced::Theme::ALL
+                .iter()
+                .find(|theme| theme.to_string() == name)
+                .cloned()
+                .unwrap_or_else(|| {
+                    tracing::warn!("Unknown theme '{}', using the system default", name);
+                    system_theme()
+                }),
+            None => system_theme(),
+        };
+
         Self {
             colors: ThemeColors::new(),
+            theme,
         }
     }
 
+    pub fn set(&mut self, theme: iced::Theme) {
+        self.theme = theme;
+    }
+
     pub fn to_iced_theme(&self) -> iced::Theme {
-        iced::Theme::CatppuccinLatte
+        self.theme.clone()
+    }
+}
+
+fn system_theme() -> iced::Theme {
+    match dark_light::detect() {
+        dark_light::Mode::Dark => iced::Theme::CatppuccinMocha,
+        dark_light::Mode::Light | dark_light::Mode::Default => iced::Theme::CatppuccinLatte,
+    }
+}
+
+// Background for tunnel rows and detail sections, taken from the active
+// theme so it stays readable in dark themes.
+pub fn panel_style(theme: &iced::Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    container::Style {
+        background: Some(iced::Background::Color(palette.background.weak.color)),
+        border: iced::Border {
+            color: palette.background.strong.color,
+            width: 1.0,
+            radius: 5.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+// Background for log lines the search matches; `current` marks the one last
+// jumped to.
+pub fn search_match_style(current: bool) -> impl Fn(&iced::Theme) -> container::Style {
+    move |theme| {
+        let palette = theme.extended_palette();
+        let pair = match current {
+            true => palette.primary.strong,
+            false => palette.primary.weak,
+        };
+        container::Style {
+            background: Some(iced::Background::Color(pair.color)),
+            text_color: Some(pair.text),
+            ..Default::default()
+        }
     }
 }
 
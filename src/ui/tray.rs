@@ -0,0 +1,335 @@
+// System tray icon, built with the `tray` feature and enabled with `--tray`.
+//
+// tray-icon needs a platform event loop on the thread that owns the icon. On
+// Linux that is a gtk main loop, so the icon lives on its own thread and is
+// updated over a channel. Windows and macOS need it on the main thread once
+// the event loop is running, so it is created from the first update there.
+
+use super::messages::{Message, TunnelListMessage};
+use super::theme::ThemeColors;
+use crate::backend::types::{TunnelEntry, TunnelId, TunnelRuntimeState, TunnelSummary};
+use crate::constants::APP_TITLE;
+use iced::futures::{SinkExt, StreamExt};
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+const START_PREFIX: &str = "start:";
+const STOP_PREFIX: &str = "stop:";
+const ICON_SIZE: u32 = 32;
+
+#[derive(Debug, Clone)]
+struct TrayTunnel {
+    id: TunnelId,
+    tag: String,
+    running: bool,
+}
+
+impl From<&TunnelEntry> for TrayTunnel {
+    fn from(tunnel: &TunnelEntry) -> Self {
+        Self {
+            id: tunnel.id,
+            tag: tunnel.tag.clone(),
+            running: matches!(
+                tunnel.runtime_state,
+                Some(TunnelRuntimeState::Running { .. })
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrayStatus {
+    Empty,
+    AllRunning(usize),
+    SomeDown {
+        down: usize,
+        total: usize,
+        failed: usize,
+    },
+}
+
+impl TrayStatus {
+    fn of(summary: TunnelSummary) -> Self {
+        let down = summary.total.saturating_sub(summary.running);
+        match (summary.total, down) {
+            (0, _) => TrayStatus::Empty,
+            (total, 0) => TrayStatus::AllRunning(total),
+            (total, down) => TrayStatus::SomeDown {
+                down,
+                total,
+                failed: summary.failed,
+            },
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            TrayStatus::Empty => "No tunnels configured".to_string(),
+            TrayStatus::AllRunning(total) => format!("All {} tunnels running", total),
+            TrayStatus::SomeDown {
+                down,
+                total,
+                failed: 0,
+            } => format!("{} of {} tunnels down", down, total),
+            TrayStatus::SomeDown {
+                down,
+                total,
+                failed,
+            } => format!("{} of {} tunnels down, {} failed", down, total, failed),
+        }
+    }
+
+    fn icon(&self) -> Option<Icon> {
+        let colors = ThemeColors::new();
+        let color = match self {
+            TrayStatus::Empty => colors.border,
+            TrayStatus::AllRunning(_) => colors.success,
+            TrayStatus::SomeDown { failed: 0, .. } => colors.warning,
+            TrayStatus::SomeDown { .. } => colors.error,
+        };
+        let [r, g, b, _] = color.into_rgba8();
+
+        // A filled circle in the status colour on a transparent background.
+        let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+        let radius = ICON_SIZE as f32 / 2.0 - 1.0;
+        let rgba = (0..ICON_SIZE * ICON_SIZE)
+            .flat_map(|i| {
+                let dx = (i % ICON_SIZE) as f32 - center;
+                let dy = (i / ICON_SIZE) as f32 - center;
+                let alpha = if dx * dx + dy * dy <= radius * radius {
+                    255
+                } else {
+                    0
+                };
+                [r, g, b, alpha]
+            })
+            .collect();
+
+        match Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE) {
+            Ok(icon) => Some(icon),
+            Err(e) => {
+                tracing::warn!("Failed to create tray icon image: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn build_menu(tunnels: &[TrayTunnel]) -> Menu {
+    let menu = Menu::new();
+    let mut result = menu.append_items(&[
+        &MenuItem::with_id(SHOW_ID, "Show Window", true, None),
+        &PredefinedMenuItem::separator(),
+    ]);
+
+    for tunnel in tunnels {
+        let status = if tunnel.running { "running" } else { "stopped" };
+        let start = MenuItem::with_id(
+            format!("{}{}", START_PREFIX, tunnel.id),
+            "Start",
+            !tunnel.running,
+            None,
+        );
+        let stop = MenuItem::with_id(
+            format!("{}{}", STOP_PREFIX, tunnel.id),
+            "Stop",
+            tunnel.running,
+            None,
+        );
+        result = result.and_then(|_| {
+            let submenu = Submenu::with_items(
+                format!("{} ({})", tunnel.tag, status),
+                true,
+                &[&start, &stop],
+            )?;
+            menu.append(&submenu)
+        });
+    }
+
+    if !tunnels.is_empty() {
+        result = result.and_then(|_| menu.append(&PredefinedMenuItem::separator()));
+    }
+    result = result.and_then(|_| menu.append(&MenuItem::with_id(QUIT_ID, "Quit", true, None)));
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to build tray menu: {}", e);
+    }
+    menu
+}
+
+fn create_icon() -> Option<TrayIcon> {
+    let status = TrayStatus::Empty;
+    let mut builder = TrayIconBuilder::new()
+        .with_tooltip(format!("{} - {}", APP_TITLE, status.describe()))
+        .with_menu(Box::new(build_menu(&[])))
+        .with_menu_on_left_click(false);
+    if let Some(icon) = status.icon() {
+        builder = builder.with_icon(icon);
+    }
+
+    match builder.build() {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            tracing::error!("Failed to create tray icon: {}", e);
+            None
+        }
+    }
+}
+
+fn apply(icon: &TrayIcon, tunnels: &[TrayTunnel], summary: TunnelSummary) {
+    let status = TrayStatus::of(summary);
+    if let Err(e) = icon.set_icon(status.icon()) {
+        tracing::warn!("Failed to update tray icon: {}", e);
+    }
+    if let Err(e) = icon.set_tooltip(Some(format!("{} - {}", APP_TITLE, status.describe()))) {
+        tracing::warn!("Failed to update tray tooltip: {}", e);
+    }
+    icon.set_menu(Some(Box::new(build_menu(tunnels))));
+}
+
+pub struct Tray {
+    #[cfg(target_os = "linux")]
+    updates: std::sync::mpsc::Sender<(Vec<TrayTunnel>, TunnelSummary)>,
+    #[cfg(not(target_os = "linux"))]
+    icon: Option<TrayIcon>,
+}
+
+impl Tray {
+    #[cfg(target_os = "linux")]
+    pub fn new() -> Self {
+        use gtk::glib::ControlFlow;
+        use std::sync::mpsc::TryRecvError;
+
+        let (updates, receiver) = std::sync::mpsc::channel::<(Vec<TrayTunnel>, TunnelSummary)>();
+        let spawn_result = std::thread::Builder::new()
+            .name("tray".to_string())
+            .spawn(move || {
+                if let Err(e) = gtk::init() {
+                    tracing::error!("Failed to initialize gtk for the tray icon: {}", e);
+                    return;
+                }
+                let Some(icon) = create_icon() else {
+                    return;
+                };
+
+                gtk::glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+                    let mut latest = None;
+                    loop {
+                        match receiver.try_recv() {
+                            Ok(update) => latest = Some(update),
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) => {
+                                gtk::main_quit();
+                                return ControlFlow::Break;
+                            }
+                        }
+                    }
+                    if let Some((tunnels, summary)) = latest {
+                        apply(&icon, &tunnels, summary);
+                    }
+                    ControlFlow::Continue
+                });
+
+                gtk::main();
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to start tray thread: {}", e);
+        }
+
+        Self { updates }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> Self {
+        Self { icon: None }
+    }
+
+    pub fn update(&mut self, tunnels: &[TunnelEntry], summary: TunnelSummary) {
+        let tunnels: Vec<TrayTunnel> = tunnels.iter().map(TrayTunnel::from).collect();
+
+        #[cfg(target_os = "linux")]
+        let _ = self.updates.send((tunnels, summary));
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            if self.icon.is_none() {
+                self.icon = create_icon();
+            }
+            if let Some(icon) = &self.icon {
+                apply(icon, &tunnels, summary);
+            }
+        }
+    }
+}
+
+impl Default for Tray {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn menu_message(id: &str) -> Option<Message> {
+    if id == SHOW_ID {
+        return Some(Message::ShowWindow);
+    }
+    if id == QUIT_ID {
+        return Some(Message::Quit);
+    }
+    if let Some(tunnel_id) = id.strip_prefix(START_PREFIX) {
+        let tunnel_id = tunnel_id.parse().ok()?;
+        return Some(Message::TunnelList(TunnelListMessage::StartTunnel(
+            tunnel_id,
+        )));
+    }
+    if let Some(tunnel_id) = id.strip_prefix(STOP_PREFIX) {
+        let tunnel_id = tunnel_id.parse().ok()?;
+        return Some(Message::TunnelList(TunnelListMessage::StopTunnel(
+            tunnel_id,
+        )));
+    }
+    None
+}
+
+// tray-icon delivers events on global blocking channels, so each one is
+// drained on its own thread and forwarded into the iced stream.
+pub fn subscription() -> iced::Subscription<Message> {
+    iced::Subscription::run_with_id(
+        "tray-events",
+        iced::stream::channel(100, |mut output| async move {
+            let (sender, mut receiver) = iced::futures::channel::mpsc::unbounded();
+
+            let menu_sender = sender.clone();
+            std::thread::spawn(move || {
+                while let Ok(event) = MenuEvent::receiver().recv() {
+                    if let Some(message) = menu_message(event.id().as_ref())
+                        && menu_sender.unbounded_send(message).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+
+            std::thread::spawn(move || {
+                while let Ok(event) = TrayIconEvent::receiver().recv() {
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                        && sender.unbounded_send(Message::ShowWindow).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+
+            while let Some(message) = receiver.next().await {
+                if output.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }),
+    )
+}
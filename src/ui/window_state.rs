@@ -0,0 +1,129 @@
+// Size and position of the main window, kept next to the config file so the
+// GUI reopens where it was closed. It is written once on exit, not on every
+// move or resize.
+
+use crate::constants::{DEFAULT_WINDOW_SIZE, MIN_WINDOW_SIZE};
+use crate::errors;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// No monitor is this far from the origin; Windows reports minimized windows
+// at -32000.
+const MAX_COORDINATE: f32 = 16384.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub width: f32,
+    pub height: f32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<f32>,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WINDOW_SIZE.0,
+            height: DEFAULT_WINDOW_SIZE.1,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+impl WindowGeometry {
+    // Sizes below the minimum are raised to it, and a position that can't be
+    // on any screen is dropped so the window opens centered. The monitor
+    // layout isn't known before the window opens, so negative coordinates
+    // (monitors left of or above the primary one) are kept.
+    pub fn clamped(self) -> Self {
+        let width = sanitize(self.width, DEFAULT_WINDOW_SIZE.0).max(MIN_WINDOW_SIZE.0);
+        let height = sanitize(self.height, DEFAULT_WINDOW_SIZE.1).max(MIN_WINDOW_SIZE.1);
+
+        let (x, y) = match (self.x, self.y) {
+            (Some(x), Some(y)) if on_some_screen(x) && on_some_screen(y) => (Some(x), Some(y)),
+            _ => (None, None),
+        };
+
+        Self {
+            width,
+            height,
+            x,
+            y,
+        }
+    }
+
+    pub fn size(&self) -> iced::Size {
+        iced::Size::new(self.width, self.height)
+    }
+
+    pub fn position(&self) -> iced::window::Position {
+        match (self.x, self.y) {
+            (Some(x), Some(y)) => iced::window::Position::Specific(iced::Point::new(x, y)),
+            _ => iced::window::Position::Centered,
+        }
+    }
+
+    // Minimizing reports a zero size on some platforms; keep the last real
+    // one instead.
+    pub fn resize(&mut self, size: iced::Size) {
+        if size.width >= MIN_WINDOW_SIZE.0 && size.height >= MIN_WINDOW_SIZE.1 {
+            self.width = size.width;
+            self.height = size.height;
+        }
+    }
+
+    // Likewise minimizing moves the window far off screen on Windows.
+    pub fn move_to(&mut self, position: iced::Point) {
+        if on_some_screen(position.x) && on_some_screen(position.y) {
+            self.x = Some(position.x);
+            self.y = Some(position.y);
+        }
+    }
+}
+
+fn on_some_screen(coordinate: f32) -> bool {
+    coordinate.is_finite() && coordinate.abs() <= MAX_COORDINATE
+}
+
+fn sanitize(value: f32, default: f32) -> f32 {
+    if value.is_finite() { value } else { default }
+}
+
+pub fn state_file_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("window.json")
+}
+
+// A missing or unreadable file falls back to the default geometry.
+pub fn load(path: &Path) -> WindowGeometry {
+    let geometry = match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                "{}: {}",
+                errors::window::failed_to_read(&path.display().to_string()),
+                e
+            );
+            WindowGeometry::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => WindowGeometry::default(),
+        Err(e) => {
+            tracing::warn!(
+                "{}: {}",
+                errors::window::failed_to_read(&path.display().to_string()),
+                e
+            );
+            WindowGeometry::default()
+        }
+    };
+    geometry.clamped()
+}
+
+pub fn save(path: &Path, geometry: &WindowGeometry) -> anyhow::Result<()> {
+    let contents = serde_json::to_string_pretty(geometry)?;
+    std::fs::write(path, contents)
+        .with_context(|| errors::window::failed_to_write(&path.display().to_string()))
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use wstunnel_manager::backend::Backend;
 use wstunnel_manager::backend::backend_impl::BackendState;
@@ -22,7 +23,8 @@ fn test_autostart_integration() {
         PathBuf::from("wstunnel")
     };
 
-    let mut backend = BackendState::new(handle.clone(), config_path.clone(), wstunnel_path);
+    let mut backend =
+        BackendState::new(handle.clone(), config_path.clone(), wstunnel_path).unwrap();
 
     let autostart_tunnel = TunnelEntry {
         id: TunnelId::new(),
@@ -30,6 +32,17 @@ fn test_autostart_integration() {
         mode: TunnelMode::Client,
         cli_args: "client ws://example.com".to_string(),
         autostart: true,
+        autostart_priority: 0,
+        autostart_delay_ms: None,
+        depends_on: Vec::new(),
+        group: None,
+        env: HashMap::new(),
+        log_directory: None,
+        working_dir: None,
+        priority: None,
+        health_check: None,
+        created_at: None,
+        modified_at: None,
         runtime_state: None,
     };
 
@@ -39,13 +52,28 @@ fn test_autostart_integration() {
         mode: TunnelMode::Server,
         cli_args: "server ws://0.0.0.0:8080".to_string(),
         autostart: false,
+        autostart_priority: 0,
+        autostart_delay_ms: None,
+        depends_on: Vec::new(),
+        group: None,
+        env: HashMap::new(),
+        log_directory: None,
+        working_dir: None,
+        priority: None,
+        health_check: None,
+        created_at: None,
+        modified_at: None,
         runtime_state: None,
     };
 
-    backend.add_tunnel(autostart_tunnel.clone()).unwrap();
-    backend.add_tunnel(manual_tunnel.clone()).unwrap();
+    runtime
+        .block_on(backend.add_tunnel(autostart_tunnel.clone()))
+        .unwrap();
+    runtime
+        .block_on(backend.add_tunnel(manual_tunnel.clone()))
+        .unwrap();
 
-    let results = backend.start_autostart_tunnels();
+    let results = runtime.block_on(backend.start_autostart_tunnels());
 
     if let Ok(result_list) = results {
         assert_eq!(result_list.len(), 1);
@@ -74,7 +102,7 @@ fn test_config_persistence() {
 
     let tunnel_id = {
         let mut backend =
-            BackendState::new(handle.clone(), config_path.clone(), wstunnel_path.clone());
+            BackendState::new(handle.clone(), config_path.clone(), wstunnel_path.clone()).unwrap();
 
         let tunnel = TunnelEntry {
             id: TunnelId::new(),
@@ -82,10 +110,21 @@ fn test_config_persistence() {
             mode: TunnelMode::Client,
             cli_args: "client ws://example.com".to_string(),
             autostart: false,
+            autostart_priority: 0,
+            autostart_delay_ms: None,
+            depends_on: Vec::new(),
+            group: None,
+            env: HashMap::new(),
+            log_directory: None,
+            working_dir: None,
+            priority: None,
+            health_check: None,
+            created_at: None,
+            modified_at: None,
             runtime_state: None,
         };
 
-        let id = backend.add_tunnel(tunnel).unwrap();
+        let id = runtime.block_on(backend.add_tunnel(tunnel)).unwrap();
 
         let tunnels = backend.list_tunnels();
         assert_eq!(tunnels.len(), 1);
@@ -95,7 +134,8 @@ fn test_config_persistence() {
     };
 
     {
-        let backend2 = BackendState::new(handle.clone(), config_path.clone(), wstunnel_path);
+        let backend2 =
+            BackendState::new(handle.clone(), config_path.clone(), wstunnel_path).unwrap();
 
         let config = backend2.get_config();
         assert_eq!(config.tunnels.len(), 1);
@@ -1,7 +1,17 @@
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use wstunnel_manager::backend::Backend;
 use wstunnel_manager::backend::backend_impl::BackendState;
-use wstunnel_manager::backend::types::{Config, GlobalSettings, TunnelEntry, TunnelId, TunnelMode};
+use wstunnel_manager::backend::types::{
+    CURRENT_CONFIG_VERSION, Config, GlobalSettings, HealthCheck, Timestamp, TunnelEntry,
+    TunnelEvent, TunnelHealth, TunnelId, TunnelMode, TunnelRuntimeState, TunnelStats,
+};
+
+// A client tunnel with every optional setting left unset; tests override the
+// fields they care about with struct update syntax.
+fn tunnel(tag: &str) -> TunnelEntry {
+    TunnelEntry::new(tag, TunnelMode::Client, "client ws://example.com")
+}
 
 mod config_validation {
     use super::*;
@@ -9,18 +19,33 @@ mod config_validation {
     #[test]
     fn valid_config() {
         let config = Config {
-            version: 1,
+            version: CURRENT_CONFIG_VERSION,
             global: GlobalSettings::default(),
-            tunnels: vec![TunnelEntry {
-                id: TunnelId::new(),
-                tag: "test-tunnel".to_string(),
-                mode: TunnelMode::Client,
-                cli_args: "client ws://example.com".to_string(),
-                autostart: false,
-                runtime_state: None,
-            }],
+            tunnels: vec![tunnel("test-tunnel")],
+        };
+
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn strict_validation_rejects_flag_problems() {
+        let mut config = Config {
+            version: CURRENT_CONFIG_VERSION,
+            global: GlobalSettings::default(),
+            tunnels: vec![TunnelEntry::new(
+                "misplaced",
+                TunnelMode::Client,
+                "-L tcp://8080:localhost:80 client wss://example.com",
+            )],
         };
+        assert!(config.validate().is_ok());
 
+        config.global.strict_validation = true;
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains("'-L' must come after the 'client' subcommand"));
+
+        config.tunnels[0].cli_args =
+            "client -L tcp://8080:localhost:80 wss://example.com".to_string();
         assert!(config.validate().is_ok());
     }
 
@@ -28,24 +53,16 @@ mod config_validation {
     fn duplicate_tunnel_ids() {
         let id = TunnelId::new();
         let config = Config {
-            version: 1,
+            version: CURRENT_CONFIG_VERSION,
             global: GlobalSettings::default(),
             tunnels: vec![
                 TunnelEntry {
                     id,
-                    tag: "tunnel-1".to_string(),
-                    mode: TunnelMode::Client,
-                    cli_args: "client ws://example.com".to_string(),
-                    autostart: false,
-                    runtime_state: None,
+                    ..tunnel("tunnel-1")
                 },
                 TunnelEntry {
                     id,
-                    tag: "tunnel-2".to_string(),
-                    mode: TunnelMode::Server,
-                    cli_args: "server ws://0.0.0.0:8080".to_string(),
-                    autostart: false,
-                    runtime_state: None,
+                    ..TunnelEntry::new("tunnel-2", TunnelMode::Server, "server ws://0.0.0.0:8080")
                 },
             ],
         };
@@ -60,6 +77,32 @@ mod config_validation {
         );
     }
 
+    #[test]
+    fn dependency_problems_are_rejected() {
+        let mut config = Config {
+            version: CURRENT_CONFIG_VERSION,
+            global: GlobalSettings::default(),
+            tunnels: vec![tunnel("a"), tunnel("b"), tunnel("c")],
+        };
+        let [a, b, c] = [0, 1, 2].map(|i| config.tunnels[i].id);
+
+        config.tunnels[0].depends_on = vec![b];
+        config.tunnels[1].depends_on = vec![c];
+        assert!(config.validate().is_ok());
+
+        config.tunnels[2].depends_on = vec![a];
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains("a -> b -> c -> a"), "{}", error);
+
+        config.tunnels[2].depends_on = vec![c];
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains("'c' cannot depend on itself"), "{}", error);
+
+        config.tunnels[2].depends_on = vec![TunnelId::new()];
+        let error = format!("{:#}", config.validate().unwrap_err());
+        assert!(error.contains("'c' depends on unknown tunnel"), "{}", error);
+    }
+
     #[test]
     fn invalid_config_version() {
         let config = Config {
@@ -82,15 +125,185 @@ mod config_validation {
 mod tunnel_entry_validation {
     use super::*;
 
+    fn with_health_check(target: &str, interval_secs: u64) -> TunnelEntry {
+        TunnelEntry {
+            health_check: Some(HealthCheck {
+                target: target.to_string(),
+                interval_secs,
+            }),
+            ..TunnelEntry::new(
+                "checked",
+                TunnelMode::Client,
+                "client -L tcp://1080:localhost:80 ws://example.com",
+            )
+        }
+    }
+
+    #[test]
+    fn group_must_not_be_blank() {
+        let mut entry = with_health_check("127.0.0.1:1080", 30);
+        assert_eq!(entry.group_name(), "Default");
+
+        entry.group = Some("home-lab".to_string());
+        assert!(entry.validate().is_ok());
+        assert_eq!(entry.group_name(), "home-lab");
+
+        entry.group = Some("  ".to_string());
+        let error = entry.validate().unwrap_err();
+        assert!(error.to_string().contains("group cannot be empty"));
+
+        entry.group = Some("g".repeat(101));
+        let error = entry.validate().unwrap_err();
+        assert!(error.to_string().contains("group too long"));
+    }
+
+    #[test]
+    fn ungrouped_tunnels_omit_the_field() {
+        let mut entry = with_health_check("127.0.0.1:1080", 30);
+        assert!(!serde_yaml::to_string(&entry).unwrap().contains("group"));
+
+        entry.group = Some("work".to_string());
+        let yaml = serde_yaml::to_string(&entry).unwrap();
+        let parsed: TunnelEntry = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed.group.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn timestamps_round_trip_as_rfc3339() {
+        let mut entry = with_health_check("127.0.0.1:1080", 30);
+        assert!(
+            !serde_yaml::to_string(&entry)
+                .unwrap()
+                .contains("created_at")
+        );
+
+        let created_at: Timestamp = serde_yaml::from_str("2024-05-01T12:00:00Z").unwrap();
+        entry.created_at = Some(created_at);
+        let yaml = serde_yaml::to_string(&entry).unwrap();
+        assert!(yaml.contains("created_at: 2024-05-01T12:00:00Z"));
+
+        let parsed: TunnelEntry = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed.created_at, Some(created_at));
+        assert_eq!(parsed.modified_at, None);
+    }
+
+    #[test]
+    fn working_dir_must_be_a_directory() {
+        let temp_dir = std::env::temp_dir();
+        let mut entry = with_health_check("127.0.0.1:1080", 30);
+
+        entry.working_dir = Some(temp_dir.clone());
+        assert!(entry.path_errors(&temp_dir).is_empty());
+
+        entry.working_dir = Some(temp_dir.join(format!("missing_{}", uuid::Uuid::new_v4())));
+        let errors = entry.path_errors(&temp_dir);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].1.contains("Working directory"));
+        // Only checked on add, edit and start, so the config still loads.
+        assert!(entry.validate().is_ok());
+
+        // Relative directories are resolved against the config file's
+        // directory.
+        entry.working_dir = Some(PathBuf::from("certs"));
+        assert!(
+            !entry
+                .path_errors(std::path::Path::new("/etc/wstunnel"))
+                .is_empty()
+        );
+        assert_eq!(
+            entry.effective_working_dir(std::path::Path::new("/etc/wstunnel")),
+            PathBuf::from("/etc/wstunnel/certs")
+        );
+    }
+
+    #[test]
+    fn absolute_args_files_must_exist() {
+        let temp_dir = std::env::temp_dir();
+        let missing = temp_dir.join(format!("missing_{}.args", uuid::Uuid::new_v4()));
+        let mut entry = with_health_check("127.0.0.1:1080", 30);
+        entry.cli_args = format!("client @{}", missing.display());
+
+        let errors = entry.path_errors(&temp_dir);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].1.contains("Args file not found"));
+        // Only checked on add and edit, so the config still loads.
+        assert!(entry.validate().is_ok());
+
+        entry.cli_args = "client @client.args".to_string();
+        assert!(entry.path_errors(&temp_dir).is_empty());
+    }
+
+    #[test]
+    fn process_priority_is_a_nice_value() {
+        use wstunnel_manager::backend::types::ProcessPriority;
+
+        let mut entry = with_health_check("127.0.0.1:1080", 30);
+        for nice in [-20, 0, 10, 19] {
+            entry.priority = Some(ProcessPriority::from(nice));
+            assert!(entry.validate().is_ok(), "{}", nice);
+        }
+        for nice in [-21, 20] {
+            entry.priority = Some(ProcessPriority::from(nice));
+            let error = entry.validate().unwrap_err();
+            assert!(error.to_string().contains("out of range"), "{}", error);
+        }
+
+        entry.priority = Some(ProcessPriority::from(10));
+        let yaml = serde_yaml::to_string(&entry).unwrap();
+        assert!(yaml.contains("priority: 10"), "{}", yaml);
+        let parsed: TunnelEntry = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed.priority, Some(ProcessPriority::from(10)));
+
+        // Windows priority classes, with normal needing no flag.
+        let class = |nice| ProcessPriority::from(nice).windows_priority_class();
+        assert_eq!(class(19), Some(0x40));
+        assert_eq!(class(10), Some(0x4000));
+        assert_eq!(class(0), None);
+        assert_eq!(class(-10), Some(0x8000));
+        assert_eq!(class(-20), Some(0x80));
+    }
+
+    #[test]
+    fn health_check_target_needs_host_and_port() {
+        assert!(with_health_check("127.0.0.1:1080", 30).validate().is_ok());
+        assert!(with_health_check("localhost:1080", 30).validate().is_ok());
+        assert!(with_health_check("[::1]:1080", 30).validate().is_ok());
+
+        for target in [
+            "1080",
+            ":1080",
+            "localhost:",
+            "localhost:http",
+            "localhost:0",
+        ] {
+            let error = with_health_check(target, 30).validate().unwrap_err();
+            assert!(
+                error.to_string().contains("Invalid health check target"),
+                "{} was accepted",
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn health_check_interval_must_be_positive() {
+        let error = with_health_check("127.0.0.1:1080", 0)
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("Invalid health check interval"));
+    }
+
+    #[test]
+    fn health_check_interval_defaults() {
+        let check: HealthCheck = serde_yaml::from_str("target: 127.0.0.1:1080").unwrap();
+        assert_eq!(check.interval_secs, 30);
+    }
+
     #[test]
     fn valid_tunnel_entry() {
         let entry = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "valid-tunnel".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://example.com".to_string(),
             autostart: true,
-            runtime_state: None,
+            ..tunnel("valid-tunnel")
         };
 
         assert!(entry.validate().is_ok());
@@ -98,30 +311,36 @@ mod tunnel_entry_validation {
 
     #[test]
     fn empty_or_whitespace_tag() {
-        let entry = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "   ".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://example.com".to_string(),
-            autostart: false,
-            runtime_state: None,
-        };
+        let entry = tunnel("   ");
 
         let result = entry.validate();
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("cannot be empty"));
     }
 
+    #[test]
+    fn backend_rejects_blank_tag() {
+        use wstunnel_manager::backend::mock_backend::MockBackend;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let mut backend = MockBackend::new(runtime.handle().clone(), temp_dir.join("config.yaml"));
+
+        let result = runtime.block_on(backend.add_tunnel(tunnel(" \t")));
+        assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+        assert!(backend.list_tunnels().is_empty());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[test]
     fn tag_too_long() {
-        let entry = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "a".repeat(101),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://example.com".to_string(),
-            autostart: false,
-            runtime_state: None,
-        };
+        let entry = TunnelEntry::new(
+            "a".repeat(101),
+            TunnelMode::Client,
+            "client ws://example.com",
+        );
 
         let result = entry.validate();
         assert!(result.is_err());
@@ -130,14 +349,7 @@ mod tunnel_entry_validation {
 
     #[test]
     fn empty_cli_args() {
-        let entry = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "test-tunnel".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "   ".to_string(),
-            autostart: false,
-            runtime_state: None,
-        };
+        let entry = TunnelEntry::new("test-tunnel", TunnelMode::Client, "   ");
 
         let result = entry.validate();
         assert!(result.is_err());
@@ -152,220 +364,2196 @@ mod tunnel_entry_validation {
     #[test]
     fn autostart_flag_behavior() {
         let entry_with_autostart = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "autostart-tunnel".to_string(),
-            mode: TunnelMode::Server,
-            cli_args: "server ws://0.0.0.0:8080".to_string(),
             autostart: true,
-            runtime_state: None,
+            ..TunnelEntry::new(
+                "autostart-tunnel",
+                TunnelMode::Server,
+                "server ws://0.0.0.0:8080",
+            )
         };
 
         assert!(entry_with_autostart.validate().is_ok());
         assert!(entry_with_autostart.autostart);
 
-        let entry_without_autostart = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "manual-tunnel".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://example.com".to_string(),
-            autostart: false,
-            runtime_state: None,
-        };
+        let entry_without_autostart = tunnel("manual-tunnel");
 
         assert!(entry_without_autostart.validate().is_ok());
         assert!(!entry_without_autostart.autostart);
     }
-}
-
-mod log_retention {
-    use super::*;
-
-    #[test]
-    fn defaults_to_none() {
-        let settings = GlobalSettings::default();
-        assert!(settings.log_retention_days.is_none());
-    }
 
     #[test]
-    fn validates_minimum_value() {
-        let settings = GlobalSettings {
-            wstunnel_binary_path: None,
-            log_directory: PathBuf::from("./logs"),
-            log_retention_days: Some(0),
-        };
+    fn mode_must_match_cli_args() {
+        let mut entry = TunnelEntry::new("mismatch", TunnelMode::Server, "client ws://example.com");
 
-        let result = settings.validate();
+        let result = entry.validate();
         assert!(result.is_err());
         assert!(
             result
                 .unwrap_err()
                 .to_string()
-                .contains("must be between 1 and 3650")
+                .contains("Tunnel mode is 'server'")
         );
+
+        entry.cli_args = "--log-lvl DEBUG server ws://0.0.0.0:8080".to_string();
+        assert!(entry.validate().is_ok());
+
+        entry.cli_args = "--help".to_string();
+        assert!(entry.validate().is_ok());
     }
 
     #[test]
-    fn validates_maximum_value() {
-        let settings = GlobalSettings {
-            wstunnel_binary_path: None,
-            log_directory: PathBuf::from("./logs"),
-            log_retention_days: Some(3651),
+    fn mode_inferred_from_cli_args() {
+        assert_eq!(
+            TunnelMode::from_cli_args("client -L tcp://1:localhost:2 ws://example.com"),
+            Some(TunnelMode::Client)
+        );
+        assert_eq!(
+            TunnelMode::from_cli_args("--log-lvl INFO server ws://0.0.0.0:8080"),
+            Some(TunnelMode::Server)
+        );
+        assert_eq!(TunnelMode::from_cli_args("--version"), None);
+    }
+
+    #[test]
+    fn env_vars() {
+        let mut entry = TunnelEntry {
+            env: HashMap::from([("WSTUNNEL_TOKEN".to_string(), "secret".to_string())]),
+            ..tunnel("env-tunnel")
         };
 
-        let result = settings.validate();
+        assert!(entry.validate().is_ok());
+
+        entry
+            .env
+            .insert("BAD=NAME".to_string(), "value".to_string());
+        let result = entry.validate();
         assert!(result.is_err());
         assert!(
             result
                 .unwrap_err()
                 .to_string()
-                .contains("must be between 1 and 3650")
+                .contains("Invalid environment variable name")
         );
+
+        entry.env.clear();
+        entry.env.insert("  ".to_string(), "value".to_string());
+        assert!(entry.validate().is_err());
     }
 
     #[test]
-    fn accepts_valid_values() {
-        let test_cases = vec![
-            (Some(1), true),
-            (Some(7), true),
-            (Some(30), true),
-            (Some(365), true),
-            (Some(3650), true),
-            (None, true),
-        ];
-
-        for (retention_days, should_pass) in test_cases {
-            let settings = GlobalSettings {
-                wstunnel_binary_path: None,
-                log_directory: PathBuf::from("./logs"),
-                log_retention_days: retention_days,
-            };
-
-            let result = settings.validate();
-            assert_eq!(
-                result.is_ok(),
-                should_pass,
-                "Expected retention_days {:?} to {}",
-                retention_days,
-                if should_pass { "pass" } else { "fail" }
-            );
-        }
+    fn env_defaults_to_empty() {
+        let yaml = r#"
+id: "550e8400-e29b-41d4-a716-446655440000"
+tag: "no-env"
+mode: client
+cli_args: "client ws://example.com"
+autostart: false
+"#;
+        let entry: TunnelEntry = serde_yaml::from_str(yaml).unwrap();
+        assert!(entry.env.is_empty());
     }
-}
-
-mod cli_args_parsing {
-    use clap::Parser;
-    use std::path::PathBuf;
 
-    #[derive(Parser, Debug)]
-    #[command(name = "wstunnel_manager")]
-    struct Args {
-        #[arg(long)]
-        headless: bool,
+    #[test]
+    fn field_errors_name_every_invalid_field() {
+        use wstunnel_manager::backend::types::TunnelField;
 
-        #[arg(long)]
-        config: Option<PathBuf>,
+        let mut entry = with_health_check("127.0.0.1:0", 30);
+        entry.group = Some(" ".to_string());
+        let fields: Vec<_> = entry.field_errors().into_iter().map(|(f, _)| f).collect();
+        assert_eq!(fields, vec![TunnelField::Group, TunnelField::HealthCheck]);
 
-        #[arg(long)]
-        wstunnel_path: Option<PathBuf>,
+        // `validate` reports the first of them.
+        let (_, first) = entry.field_errors().remove(0);
+        assert_eq!(entry.validate().unwrap_err().to_string(), first);
     }
+}
+
+mod tunnel_lookup {
+    use super::*;
+    use wstunnel_manager::backend::types::{autostart_plan, find_tunnel_by_tag, generate_tag};
 
     #[test]
-    fn headless_flag() {
-        let args = Args::parse_from(["wstunnel_manager", "--headless"]);
-        assert!(args.headless);
-        assert!(args.config.is_none());
-        assert!(args.wstunnel_path.is_none());
+    fn generated_tag_uses_the_server_host() {
+        let id = TunnelId::new();
+        assert_eq!(
+            generate_tag(
+                id,
+                "client -L tcp://80:localhost:80 wss://user@vpn.example.com:443/path",
+                &[]
+            ),
+            "vpn.example.com"
+        );
+        assert_eq!(generate_tag(id, "client ws://[::1]:8080", &[]), "::1");
     }
 
     #[test]
-    fn config_path_flag() {
-        let args = Args::parse_from(["wstunnel_manager", "--config", "custom_config.yaml"]);
-        assert!(!args.headless);
-        assert_eq!(args.config.unwrap(), PathBuf::from("custom_config.yaml"));
+    fn generated_tag_falls_back_to_the_id() {
+        let id = TunnelId::new();
+        let expected = format!("tunnel-{}", &id.to_string()[..8]);
+        assert_eq!(generate_tag(id, "server wss://0.0.0.0:443", &[]), expected);
+        assert_eq!(generate_tag(id, "client", &[]), expected);
     }
 
     #[test]
-    fn wstunnel_path_flag() {
-        let args = Args::parse_from(["wstunnel_manager", "--wstunnel-path", "/usr/bin/wstunnel"]);
-        assert!(!args.headless);
+    fn generated_tag_is_unique() {
+        let tunnels = vec![tunnel("example.com"), tunnel("example.com-2")];
         assert_eq!(
-            args.wstunnel_path.unwrap(),
-            PathBuf::from("/usr/bin/wstunnel")
+            generate_tag(TunnelId::new(), "client ws://example.com", &tunnels),
+            "example.com-3"
+        );
+        // A tunnel being edited doesn't clash with its own tag.
+        assert_eq!(
+            generate_tag(tunnels[0].id, "client ws://example.com", &tunnels),
+            "example.com"
         );
     }
 
     #[test]
-    fn all_flags_combined() {
-        let args = Args::parse_from([
-            "wstunnel_manager",
-            "--headless",
-            "--config",
-            "test.yaml",
-            "--wstunnel-path",
-            "./wstunnel",
-        ]);
-        assert!(args.headless);
-        assert_eq!(args.config.unwrap(), PathBuf::from("test.yaml"));
-        assert_eq!(args.wstunnel_path.unwrap(), PathBuf::from("./wstunnel"));
+    fn finds_by_tag() {
+        let tunnels = vec![tunnel("alpha"), tunnel("beta")];
+        let found = find_tunnel_by_tag(&tunnels, "beta").unwrap();
+        assert_eq!(found.id, tunnels[1].id);
     }
-}
 
-mod backend_integration {
-    use super::*;
+    #[test]
+    fn finds_by_id() {
+        let tunnels = vec![tunnel("alpha"), tunnel("beta")];
+        let found = find_tunnel_by_tag(&tunnels, &tunnels[0].id.to_string()).unwrap();
+        assert_eq!(found.tag, "alpha");
+    }
 
-    fn create_test_runtime() -> tokio::runtime::Runtime {
-        tokio::runtime::Runtime::new().unwrap()
+    #[test]
+    fn id_takes_precedence_over_tag() {
+        let mut tunnels = vec![tunnel("alpha"), tunnel("beta")];
+        tunnels[1].tag = tunnels[0].id.to_string();
+        let found = find_tunnel_by_tag(&tunnels, &tunnels[0].id.to_string()).unwrap();
+        assert_eq!(found.tag, "alpha");
     }
 
-    fn create_temp_test_dir() -> PathBuf {
-        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
-        std::fs::create_dir_all(&temp_dir).unwrap();
-        temp_dir
+    #[test]
+    fn duplicate_tag_is_ambiguous() {
+        let tunnels = vec![tunnel("same"), tunnel("same")];
+        let error = find_tunnel_by_tag(&tunnels, "same").unwrap_err();
+        assert!(error.to_string().contains("More than one tunnel"));
     }
 
-    fn get_wstunnel_path() -> PathBuf {
-        match cfg!(windows) {
-            true => PathBuf::from("wstunnel.exe"),
-            false => PathBuf::from("wstunnel"),
-        }
+    #[test]
+    fn unknown_tag_is_not_found() {
+        let tunnels = vec![tunnel("alpha")];
+        let error = find_tunnel_by_tag(&tunnels, "missing").unwrap_err();
+        assert!(error.to_string().contains("not found"));
     }
 
     #[test]
-    fn autostart_tunnels() {
-        let runtime = create_test_runtime();
-        let handle = runtime.handle().clone();
-        let temp_dir = create_temp_test_dir();
+    fn autostart_plan_orders_by_priority_and_keeps_config_order_for_ties() {
+        let autostarted = |tag: &str, priority: i32, delay_ms: Option<u64>| TunnelEntry {
+            autostart: true,
+            autostart_priority: priority,
+            autostart_delay_ms: delay_ms,
+            ..tunnel(tag)
+        };
+        let tunnels = vec![
+            autostarted("late", 10, None),
+            tunnel("manual"),
+            autostarted("first", -5, Some(250)),
+            autostarted("tie-a", 0, None),
+            autostarted("tie-b", 0, None),
+        ];
 
-        let config_path = temp_dir.join("test_config.yaml");
-        let wstunnel_path = get_wstunnel_path();
+        let plan = autostart_plan(&tunnels);
+        let tag_of = |id| tunnels.iter().find(|t| t.id == id).unwrap().tag.as_str();
+        let order: Vec<&str> = plan.iter().map(|(id, _)| tag_of(*id)).collect();
+        assert_eq!(order, ["first", "tie-a", "tie-b", "late"]);
+        assert_eq!(plan[0].1, Some(std::time::Duration::from_millis(250)));
+        assert_eq!(plan[1].1, None);
+    }
 
-        let mut backend = BackendState::new(handle.clone(), config_path.clone(), wstunnel_path);
+    #[test]
+    fn default_autostart_order_is_left_out_of_the_config() {
+        let json = serde_json::to_string(&tunnel("plain")).unwrap();
+        assert!(!json.contains("autostart_priority"));
+        assert!(!json.contains("autostart_delay_ms"));
 
-        let autostart_tunnel = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "autostart-test".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://example.com".to_string(),
-            autostart: true,
-            runtime_state: None,
+        let ordered = TunnelEntry {
+            autostart_priority: 2,
+            autostart_delay_ms: Some(500),
+            ..tunnel("ordered")
         };
+        let json = serde_json::to_string(&ordered).unwrap();
+        let parsed: TunnelEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.autostart_priority, 2);
+        assert_eq!(parsed.autostart_delay_ms, Some(500));
+    }
+
+    #[test]
+    fn command_palette_matches_tags_and_actions_in_any_order() {
+        use wstunnel_manager::ui::state::{CommandPaletteState, PaletteAction};
 
-        let manual_tunnel = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "manual-test".to_string(),
-            mode: TunnelMode::Server,
-            cli_args: "server ws://0.0.0.0:8080".to_string(),
-            autostart: false,
-            runtime_state: None,
+        let running = TunnelEntry {
+            runtime_state: Some(TunnelRuntimeState::Running {
+                pid: wstunnel_manager::backend::types::ProcessId::from(4242),
+                started_at: Timestamp::now(),
+                log_path: PathBuf::from("web.log"),
+                health: TunnelHealth::Unchecked,
+                command_line: Vec::new(),
+            }),
+            ..tunnel("webserver")
+        };
+        let tunnels = vec![tunnel("database"), running];
+        let palette = |query: &str| {
+            let mut state = CommandPaletteState::default();
+            state.set_query(query.to_string());
+            state
+                .entries(&tunnels)
+                .into_iter()
+                .map(|entry| format!("{} {}", entry.action, entry.tag))
+                .collect::<Vec<_>>()
         };
 
-        backend.add_tunnel(autostart_tunnel.clone()).unwrap();
-        backend.add_tunnel(manual_tunnel.clone()).unwrap();
+        // A running tunnel offers stop and logs; a stopped one start, logs
+        // and edit.
+        assert_eq!(palette("web"), ["stop webserver", "logs webserver"]);
+        assert_eq!(palette("stop web"), ["stop webserver"]);
+        assert_eq!(palette("dtb st"), ["start database"]);
+        assert_eq!(palette("lg").len(), 2);
+        assert!(palette("nothing").is_empty());
+        assert_eq!(palette("").len(), 5);
+        assert!(PaletteAction::Edit.applies_to(&tunnels[0]));
+        assert!(!PaletteAction::Edit.applies_to(&tunnels[1]));
+    }
 
-        let results = backend.start_autostart_tunnels();
-        if let Ok(result_list) = results {
-            assert_eq!(result_list.len(), 1);
-            let (tunnel_id, _result) = &result_list[0];
+    #[test]
+    fn command_palette_selection_wraps() {
+        use wstunnel_manager::ui::state::CommandPaletteState;
+
+        let mut state = CommandPaletteState::default();
+        state.step(false, 3);
+        assert_eq!(state.selected, 2);
+        state.step(true, 3);
+        assert_eq!(state.selected, 0);
+        state.step(true, 3);
+        state.set_query("db".to_string());
+        assert_eq!(state.selected, 0);
+        state.step(true, 0);
+        assert_eq!(state.selected, 0);
+    }
+
+    #[test]
+    fn edit_form_only_counts_real_changes_as_unsaved() {
+        use wstunnel_manager::ui::state::EditTunnelState;
+
+        let existing = tunnel("web");
+        let mut state =
+            EditTunnelState::new_edit(existing.clone(), std::slice::from_ref(&existing));
+        assert!(!state.has_unsaved_changes());
+
+        state.cli_args_input.push_str(" -L tcp://8080:localhost:80");
+        assert!(state.has_unsaved_changes());
+        state.cli_args_input = existing.cli_args.clone();
+        assert!(!state.has_unsaved_changes());
+
+        // Errors from a failed save are not edits.
+        state.validation_errors.push("Tag is taken".to_string());
+        assert!(!state.has_unsaved_changes());
+
+        let mut state = EditTunnelState::new_create(&[existing]);
+        assert!(!state.has_unsaved_changes());
+        state.env_vars.push(("TOKEN".to_string(), String::new()));
+        assert!(state.has_unsaved_changes());
+    }
+
+    #[test]
+    fn edit_form_marks_each_invalid_field() {
+        use wstunnel_manager::backend::types::TunnelField;
+        use wstunnel_manager::ui::state::EditTunnelState;
+
+        let tunnels = [tunnel("web")];
+        let mut state = EditTunnelState::new_edit(tunnels[0].clone(), &tunnels);
+        state.check_fields(&tunnels);
+        assert!(state.field_errors.is_empty());
+
+        state.tag_input = "x".repeat(101);
+        state.cli_args_input = "client \"ws://example.com".to_string();
+        state.priority_input = "fast".to_string();
+        state.check_fields(&tunnels);
+        assert!(state.field_error(TunnelField::Tag).is_some());
+        assert!(state.field_error(TunnelField::CliArgs).is_some());
+        assert!(state.field_error(TunnelField::Priority).is_some());
+        assert_eq!(state.field_errors.len(), 3);
+
+        // Saving stops at the first problem in form order.
+        assert_eq!(
+            state.entry(&tunnels).unwrap_err(),
+            state.cli_args_error().unwrap()
+        );
+
+        state.tag_input = "web".to_string();
+        state.cli_args_input = String::new();
+        state.priority_input = String::new();
+        state.check_fields(&tunnels);
+        assert_eq!(
+            state.field_errors.keys().collect::<Vec<_>>(),
+            vec![&TunnelField::CliArgs]
+        );
+    }
+
+    #[test]
+    fn bulk_delete_dialog_flags_running_tunnels() {
+        use wstunnel_manager::backend::types::ProcessId;
+        use wstunnel_manager::ui::state::ConfirmDeleteState;
+
+        let idle = tunnel("idle");
+        let mut busy = tunnel("busy");
+        let mut state = ConfirmDeleteState::for_tunnels([&idle, &busy]);
+        assert_eq!(state.tunnel_ids(), vec![idle.id, busy.id]);
+        assert_eq!(state.running_count(), 0);
+
+        // The dialog follows a tunnel that starts while it is open.
+        busy.runtime_state = Some(TunnelRuntimeState::Running {
+            pid: ProcessId::from(4242),
+            started_at: Timestamp::now(),
+            log_path: PathBuf::from("busy.log"),
+            health: TunnelHealth::Unchecked,
+            command_line: Vec::new(),
+        });
+        state.update(&[idle.clone(), busy.clone()]);
+        assert_eq!(state.running_count(), 1);
+        assert_eq!(state.targets[1].running_pid, Some(ProcessId::from(4242)));
+        assert_eq!(state.targets[0].running_pid, None);
+    }
+}
+
+mod status_snapshot {
+    use super::*;
+    use wstunnel_manager::backend::types::{
+        ProcessId, Timestamp, TunnelHealth, TunnelRuntimeState, status_snapshot,
+    };
+
+    fn config_with(tags: &[&str]) -> Config {
+        Config {
+            tunnels: tags.iter().map(|tag| tunnel(tag)).collect(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn running_tunnel_reports_pid_and_uptime() {
+        let config = config_with(&["up", "down"]);
+        let statuses = vec![
+            (
+                config.tunnels[0].id,
+                TunnelRuntimeState::Running {
+                    pid: ProcessId::from(4242),
+                    started_at: Timestamp::now(),
+                    log_path: PathBuf::from("up.log"),
+                    health: TunnelHealth::Unchecked,
+                    command_line: Vec::new(),
+                },
+            ),
+            (config.tunnels[1].id, TunnelRuntimeState::Stopped),
+        ];
+
+        let snapshot = status_snapshot(&config, &statuses);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].tag, "up");
+        assert_eq!(snapshot[0].status, "running");
+        assert_eq!(snapshot[0].pid, Some(ProcessId::from(4242)));
+        assert!(snapshot[0].uptime_secs.is_some());
+        assert_eq!(snapshot[1].status, "stopped");
+        assert_eq!(snapshot[1].pid, None);
+        assert_eq!(snapshot[1].uptime_secs, None);
+    }
+
+    #[test]
+    fn unhealthy_tunnel_is_reported() {
+        let config = config_with(&["sick"]);
+        let statuses = vec![(
+            config.tunnels[0].id,
+            TunnelRuntimeState::Running {
+                pid: ProcessId::from(4242),
+                started_at: Timestamp::now(),
+                log_path: PathBuf::from("sick.log"),
+                health: TunnelHealth::Unhealthy {
+                    error: "connection refused".to_string(),
+                },
+                command_line: Vec::new(),
+            },
+        )];
+
+        let snapshot = status_snapshot(&config, &statuses);
+        assert_eq!(snapshot[0].status, "unhealthy");
+        assert_eq!(snapshot[0].pid, Some(ProcessId::from(4242)));
+    }
+
+    #[test]
+    fn missing_status_is_stopped() {
+        let config = config_with(&["only"]);
+        let snapshot = status_snapshot(&config, &[]);
+        assert_eq!(snapshot[0].status, "stopped");
+    }
+
+    #[test]
+    fn serializes_as_json_array() {
+        let config = config_with(&["json"]);
+        let line = serde_json::to_string(&status_snapshot(&config, &[])).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let entry = &value.as_array().unwrap()[0];
+        assert_eq!(entry["id"], config.tunnels[0].id.to_string());
+        assert_eq!(entry["mode"], "client");
+        assert_eq!(entry["status"], "stopped");
+        assert!(entry["pid"].is_null());
+    }
+}
+
+mod uptime_format {
+    use std::time::Duration;
+    use wstunnel_manager::backend::types::{format_size, format_uptime};
+
+    #[test]
+    fn shows_the_two_largest_units() {
+        assert_eq!(format_uptime(Duration::from_secs(3)), "just now");
+        assert_eq!(format_uptime(Duration::from_secs(42)), "42s");
+        assert_eq!(format_uptime(Duration::from_secs(5 * 60 + 59)), "5m");
+        assert_eq!(format_uptime(Duration::from_secs(9142)), "2h 32m");
+        assert_eq!(
+            format_uptime(Duration::from_secs(3 * 86400 + 4 * 3600 + 59)),
+            "3d 4h"
+        );
+    }
+
+    #[test]
+    fn sizes_use_binary_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 + 400 * 1024), "3.4 MB");
+        assert_eq!(format_size(5 * 1024_u64.pow(5)), "5120.0 TB");
+    }
+}
+
+mod log_retention {
+    use super::*;
+
+    #[test]
+    fn defaults_to_none() {
+        let settings = GlobalSettings::default();
+        assert!(settings.log_retention_days.is_none());
+    }
+
+    #[test]
+    fn validates_minimum_value() {
+        let settings = GlobalSettings {
+            wstunnel_binary_path: None,
+            log_directory: PathBuf::from("./logs"),
+            log_retention_days: Some(0),
+            ..GlobalSettings::default()
+        };
+
+        let result = settings.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("must be between 1 and 3650")
+        );
+    }
+
+    #[test]
+    fn validates_maximum_value() {
+        let settings = GlobalSettings {
+            wstunnel_binary_path: None,
+            log_directory: PathBuf::from("./logs"),
+            log_retention_days: Some(3651),
+            ..GlobalSettings::default()
+        };
+
+        let result = settings.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("must be between 1 and 3650")
+        );
+    }
+
+    #[test]
+    fn compression_has_the_same_maximum() {
+        let settings = GlobalSettings {
+            log_compress_days: Some(3651),
+            ..GlobalSettings::default()
+        };
+        assert!(
+            settings
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("must be between 1 and 3650")
+        );
+    }
+
+    #[test]
+    fn cleanup_interval_defaults_to_a_day_and_is_validated() {
+        let settings = GlobalSettings::default();
+        assert_eq!(settings.cleanup_interval_hours, 24);
+        assert_eq!(
+            settings.cleanup_interval(),
+            std::time::Duration::from_secs(24 * 60 * 60)
+        );
+
+        for hours in [0, 24 * 30 + 1] {
+            let settings = GlobalSettings {
+                cleanup_interval_hours: hours,
+                ..GlobalSettings::default()
+            };
+            assert!(settings.validate().is_err(), "{} hours", hours);
+        }
+        let settings = GlobalSettings {
+            cleanup_interval_hours: 1,
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn accepts_valid_values() {
+        let test_cases = vec![
+            (Some(1), true),
+            (Some(7), true),
+            (Some(30), true),
+            (Some(365), true),
+            (Some(3650), true),
+            (None, true),
+        ];
+
+        for (retention_days, should_pass) in test_cases {
+            let settings = GlobalSettings {
+                wstunnel_binary_path: None,
+                log_directory: PathBuf::from("./logs"),
+                log_retention_days: retention_days,
+                ..GlobalSettings::default()
+            };
+
+            let result = settings.validate();
+            assert_eq!(
+                result.is_ok(),
+                should_pass,
+                "Expected retention_days {:?} to {}",
+                retention_days,
+                if should_pass { "pass" } else { "fail" }
+            );
+        }
+    }
+}
+
+mod cli_args_parsing {
+    use clap::Parser;
+    use std::path::PathBuf;
+    use wstunnel_manager::cli::Command;
+
+    #[derive(Parser, Debug)]
+    #[command(name = "wstunnel_manager")]
+    struct Args {
+        #[arg(long)]
+        headless: bool,
+
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[arg(long)]
+        wstunnel_path: Option<PathBuf>,
+
+        #[arg(long, requires = "headless")]
+        api_port: Option<u16>,
+
+        #[arg(long, requires = "headless")]
+        status_interval: Option<u64>,
+
+        #[arg(long, requires = "headless")]
+        metrics_port: Option<u16>,
+
+        #[command(subcommand)]
+        command: Option<Command>,
+    }
+
+    #[test]
+    fn headless_flag() {
+        let args = Args::parse_from(["wstunnel_manager", "--headless"]);
+        assert!(args.headless);
+        assert!(args.config.is_none());
+        assert!(args.wstunnel_path.is_none());
+    }
+
+    #[test]
+    fn config_path_flag() {
+        let args = Args::parse_from(["wstunnel_manager", "--config", "custom_config.yaml"]);
+        assert!(!args.headless);
+        assert_eq!(args.config.unwrap(), PathBuf::from("custom_config.yaml"));
+    }
+
+    #[test]
+    fn wstunnel_path_flag() {
+        let args = Args::parse_from(["wstunnel_manager", "--wstunnel-path", "/usr/bin/wstunnel"]);
+        assert!(!args.headless);
+        assert_eq!(
+            args.wstunnel_path.unwrap(),
+            PathBuf::from("/usr/bin/wstunnel")
+        );
+    }
+
+    #[test]
+    fn all_flags_combined() {
+        let args = Args::parse_from([
+            "wstunnel_manager",
+            "--headless",
+            "--config",
+            "test.yaml",
+            "--wstunnel-path",
+            "./wstunnel",
+        ]);
+        assert!(args.headless);
+        assert_eq!(args.config.unwrap(), PathBuf::from("test.yaml"));
+        assert_eq!(args.wstunnel_path.unwrap(), PathBuf::from("./wstunnel"));
+    }
+
+    #[test]
+    fn api_port_flag() {
+        let args = Args::parse_from(["wstunnel_manager", "--headless", "--api-port", "8484"]);
+        assert_eq!(args.api_port, Some(8484));
+    }
+
+    #[test]
+    fn api_port_requires_headless() {
+        let result = Args::try_parse_from(["wstunnel_manager", "--api-port", "8484"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn status_interval_flag() {
+        let args = Args::parse_from(["wstunnel_manager", "--headless", "--status-interval", "30"]);
+        assert_eq!(args.status_interval, Some(30));
+        assert!(Args::try_parse_from(["wstunnel_manager", "--status-interval", "30"]).is_err());
+    }
+
+    #[test]
+    fn metrics_port_flag() {
+        let args = Args::parse_from(["wstunnel_manager", "--headless", "--metrics-port", "9184"]);
+        assert_eq!(args.metrics_port, Some(9184));
+        assert!(Args::try_parse_from(["wstunnel_manager", "--metrics-port", "9184"]).is_err());
+    }
+
+    #[test]
+    fn no_subcommand_runs_app() {
+        let args = Args::parse_from(["wstunnel_manager"]);
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn list_subcommand_with_json() {
+        let args = Args::parse_from(["wstunnel_manager", "list", "--json"]);
+        let command = args.command.unwrap();
+        assert!(matches!(command, Command::List { .. }));
+        assert!(command.options().json);
+        assert!(command.options().api_port.is_none());
+    }
+
+    #[test]
+    fn start_subcommand_with_api_port() {
+        let args = Args::parse_from([
+            "wstunnel_manager",
+            "--config",
+            "test.yaml",
+            "start",
+            "my-tunnel",
+            "--api-port",
+            "8484",
+        ]);
+        assert_eq!(args.config.unwrap(), PathBuf::from("test.yaml"));
+        let command = args.command.unwrap();
+        assert_eq!(command.options().api_port, Some(8484));
+        match command {
+            Command::Start { tunnel, .. } => assert_eq!(tunnel, "my-tunnel"),
+            other => panic!("expected start, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn status_tunnel_is_optional() {
+        let args = Args::parse_from(["wstunnel_manager", "status"]);
+        assert!(matches!(
+            args.command,
+            Some(Command::Status { tunnel: None, .. })
+        ));
+    }
+
+    #[test]
+    fn stop_requires_tunnel() {
+        assert!(Args::try_parse_from(["wstunnel_manager", "stop"]).is_err());
+    }
+}
+
+mod backend_integration {
+    use super::*;
+    #[cfg(unix)]
+    use wstunnel_manager::backend::process::is_tunnel_process;
+    #[cfg(unix)]
+    use wstunnel_manager::backend::running;
+    #[cfg(unix)]
+    use wstunnel_manager::backend::types::ProcessId;
+
+    fn create_test_runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    fn create_temp_test_dir() -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        temp_dir
+    }
+
+    fn get_wstunnel_path() -> PathBuf {
+        match cfg!(windows) {
+            true => PathBuf::from("wstunnel.exe"),
+            false => PathBuf::from("wstunnel"),
+        }
+    }
+
+    #[cfg(unix)]
+    fn write_fake_wstunnel(dir: &std::path::Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Answers the version check the backend runs on startup like the real
+        // binary does.
+        let path = dir.join("fake_wstunnel.sh");
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\nif [ \"$1\" = --version ]; then echo 'wstunnel 10.1.8'; exit 0; fi\n{}\n",
+                script
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    fn write_config_with_log_dir(config_path: &std::path::Path, log_dir: &std::path::Path) {
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: log_dir.to_path_buf(),
+                ..GlobalSettings::default()
+            },
+            ..Config::default()
+        };
+        std::fs::write(config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    fn next_event(
+        runtime: &tokio::runtime::Runtime,
+        events: &mut tokio::sync::broadcast::Receiver<TunnelEvent>,
+        matches: impl Fn(&TunnelEvent) -> bool,
+    ) -> TunnelEvent {
+        runtime.block_on(async {
+            tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                loop {
+                    let event = events.recv().await.unwrap();
+                    if matches(&event) {
+                        return event;
+                    }
+                }
+            })
+            .await
+            .expect("timed out waiting for tunnel event")
+        })
+    }
+
+    // Starts `id` and waits for the readiness wait to report how it went.
+    #[cfg(unix)]
+    fn start_and_wait(
+        runtime: &tokio::runtime::Runtime,
+        backend: &mut BackendState,
+        id: TunnelId,
+    ) -> anyhow::Result<ProcessId> {
+        let mut events = backend.subscribe();
+        runtime.block_on(backend.start_tunnel(id))?;
+        match next_event(runtime, &mut events, |e| match e {
+            TunnelEvent::Started { id: started, .. } => *started == id,
+            TunnelEvent::StartFailed { id: failed, .. } => *failed == id,
+            _ => false,
+        }) {
+            TunnelEvent::Started { pid, .. } => Ok(pid),
+            TunnelEvent::StartFailed { error, .. } => Err(anyhow::anyhow!(error)),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lifecycle_events() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("events_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "echo \"listening\"\nexec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("events")))
+            .unwrap();
+
+        let pid = runtime.block_on(backend.start_tunnel(id)).unwrap();
+
+        // Output can arrive before or after the start is announced.
+        let mut started = false;
+        let mut logged = false;
+        while !(started && logged) {
+            match next_event(&runtime, &mut events, |_| true) {
+                TunnelEvent::Started {
+                    id: event_id,
+                    pid: event_pid,
+                } => {
+                    assert_eq!(event_id, id);
+                    assert_eq!(event_pid, pid);
+                    started = true;
+                }
+                TunnelEvent::LogLine { id: event_id, line } => {
+                    assert_eq!(event_id, id);
+                    assert!(line.ends_with("[STDOUT] listening"), "{}", line);
+                    logged = true;
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        match next_event(&runtime, &mut events, |e| {
+            !matches!(e, TunnelEvent::LogLine { .. })
+        }) {
+            TunnelEvent::Stopped { id: event_id } => assert_eq!(event_id, id),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn log_maintenance_skips_running_tunnels_current_log() {
+        use std::time::{Duration, SystemTime};
+        use wstunnel_manager::backend::logs::{compressed_log_path, rotated_log_path};
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("live_log_test.yaml");
+        let log_dir = temp_dir.join("logs");
+        write_config_with_log_dir(&config_path, &log_dir);
+
+        let binary = write_fake_wstunnel(&temp_dir, "echo \"listening\"\nexec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("live")))
+            .unwrap();
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+
+        // A quiet tunnel's log can be older than the cutoff while it is still
+        // open; the file rotated away from it is closed.
+        let ten_days_ago = SystemTime::now() - Duration::from_secs(10 * 24 * 60 * 60);
+        let live_log = backend.get_log_path(id).unwrap();
+        let rotated_log = rotated_log_path(&live_log, 1);
+        std::fs::write(&rotated_log, "rotated\n").unwrap();
+        for path in [&live_log, &rotated_log] {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(ten_days_ago)
+                .unwrap();
+        }
+
+        runtime
+            .block_on(backend.update_global_settings(GlobalSettings {
+                log_directory: log_dir.clone(),
+                log_compress_days: Some(3),
+                log_retention_days: Some(7),
+                ..GlobalSettings::default()
+            }))
+            .unwrap();
+        runtime
+            .block_on(backend.cleanup_old_logs_if_configured())
+            .unwrap();
+
+        assert!(live_log.exists());
+        assert!(!compressed_log_path(&live_log).exists());
+        assert!(!rotated_log.exists());
+        assert!(!compressed_log_path(&rotated_log).exists());
+
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn concurrent_starts_spawn_one_process() {
+        use std::sync::Arc;
+        use wstunnel_manager::backend::SharedBackend;
+        use wstunnel_manager::backend::error::BackendError;
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("race_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let marker = temp_dir.join("spawned");
+        let binary = write_fake_wstunnel(
+            &temp_dir,
+            &format!("echo spawned >> '{}'\nexec sleep 30", marker.display()),
+        );
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("race")))
+            .unwrap();
+        let backend: SharedBackend = Arc::new(tokio::sync::Mutex::new(backend));
+
+        let start =
+            |backend: SharedBackend| async move { backend.lock().await.start_tunnel(id).await };
+        let (first, second) = runtime.block_on(async {
+            tokio::join!(
+                tokio::spawn(start(backend.clone())),
+                tokio::spawn(start(backend.clone()))
+            )
+        });
+        // The second start comes while the first waits out the early-exit
+        // grace without the lock, so only the start guard turns it away.
+        let results = [first.unwrap(), second.unwrap()];
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(
+            results
+                .iter()
+                .any(|r| matches!(r, Err(BackendError::Transitioning(_))))
+        );
+        assert!(matches!(
+            runtime.block_on(async { backend.lock().await.get_tunnel_status(id) }),
+            TunnelRuntimeState::Starting
+        ));
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let spawned = std::fs::read_to_string(&marker).unwrap();
+        assert_eq!(spawned.lines().count(), 1);
+
+        runtime.block_on(async { backend.lock().await.stop_tunnel(id).await.unwrap() });
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_config_change_leaves_everything_as_it_was() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("apply_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend =
+            BackendState::new(runtime.handle().clone(), config_path.clone(), binary).unwrap();
+
+        let entry = tunnel("original");
+        runtime.block_on(backend.add_tunnel(entry.clone())).unwrap();
+        let on_disk = std::fs::read_to_string(&config_path).unwrap();
+
+        // A second tunnel with the same id fails config validation after the
+        // mutation has been applied to the copy.
+        let duplicate = TunnelEntry {
+            tag: "duplicate".to_string(),
+            ..entry
+        };
+        assert!(runtime.block_on(backend.add_tunnel(duplicate)).is_err());
+
+        let error = runtime
+            .block_on(backend.delete_tunnel(TunnelId::new()))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            wstunnel_manager::backend::error::BackendError::TunnelNotFound(_)
+        ));
+
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), on_disk);
+        let tags: Vec<String> = backend.list_tunnels().into_iter().map(|t| t.tag).collect();
+        assert_eq!(tags, vec!["original"]);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tunnels_start_at_their_process_priority() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("priority.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        // `nice` without a command prints the niceness it runs at.
+        let marker = temp_dir.join("niceness");
+        let binary = write_fake_wstunnel(
+            &temp_dir,
+            &format!("nice > '{}'\nexec sleep 30", marker.display()),
+        );
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let id = runtime
+            .block_on(backend.add_tunnel(TunnelEntry {
+                priority: Some(wstunnel_manager::backend::types::ProcessPriority::from(19)),
+                ..tunnel("background")
+            }))
+            .unwrap();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let niceness = loop {
+            match std::fs::read_to_string(&marker) {
+                Ok(contents) if !contents.is_empty() => break contents,
+                _ if std::time::Instant::now() > deadline => panic!("the tunnel never ran"),
+                _ => std::thread::sleep(std::time::Duration::from_millis(20)),
+            }
+        };
+        assert_eq!(niceness.trim(), "19");
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn running_status_reports_the_expanded_command_line() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("command_line.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend =
+            BackendState::new(runtime.handle().clone(), config_path, binary.clone()).unwrap();
+        let id = runtime
+            .block_on(backend.add_tunnel(TunnelEntry {
+                env: HashMap::from([("HOST".to_string(), "tunnel.example.com".to_string())]),
+                ..TunnelEntry::new(
+                    "expanded",
+                    TunnelMode::Client,
+                    "client -P secret \"ws://${HOST}:8080\"",
+                )
+            }))
+            .unwrap();
+
+        start_and_wait(&runtime, &mut backend, id).unwrap();
+        match backend.get_tunnel_status(id) {
+            TunnelRuntimeState::Running { command_line, .. } => assert_eq!(
+                command_line,
+                vec![
+                    std::path::absolute(&binary).unwrap().display().to_string(),
+                    "client".to_string(),
+                    "-P".to_string(),
+                    wstunnel_manager::constants::REDACTED.to_string(),
+                    "ws://tunnel.example.com:8080".to_string(),
+                ]
+            ),
+            other => panic!("expected a running tunnel, got {:?}", other),
+        }
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn second_backend_on_the_same_config_is_refused() {
+        use wstunnel_manager::backend::config_lock::{lock_file_path, read_owner};
+        use wstunnel_manager::backend::error::BackendError;
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("locked.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+
+        let mut first = BackendState::new(
+            runtime.handle().clone(),
+            config_path.clone(),
+            binary.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            read_owner(&lock_file_path(&config_path)),
+            Some(std::process::id().into())
+        );
+
+        let error = BackendState::new(
+            runtime.handle().clone(),
+            config_path.clone(),
+            binary.clone(),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(error, BackendError::ConfigLocked(_)));
+        assert!(error.to_string().contains("already using"));
+
+        runtime.block_on(first.shutdown()).unwrap();
+        assert!(!lock_file_path(&config_path).exists());
+        drop(first);
+        BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn stale_config_locks_are_taken_over() {
+        use wstunnel_manager::backend::config_lock::{lock_file_path, read_owner};
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("stale.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+
+        // A process that has exited, and a file that was never finished.
+        let mut exited = std::process::Command::new(&binary)
+            .arg("--version")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let dead_pid = exited.id();
+        exited.wait().unwrap();
+        for contents in [dead_pid.to_string(), "garbage".to_string()] {
+            std::fs::write(lock_file_path(&config_path), contents).unwrap();
+            let backend = BackendState::new(
+                runtime.handle().clone(),
+                config_path.clone(),
+                binary.clone(),
+            )
+            .unwrap();
+            assert_eq!(
+                read_owner(&lock_file_path(&config_path)),
+                Some(std::process::id().into())
+            );
+            drop(backend);
+            assert!(!lock_file_path(&config_path).exists());
+        }
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn external_config_edits_are_not_overwritten() {
+        use wstunnel_manager::backend::config::{read_config, save_config};
+        use wstunnel_manager::backend::error::BackendError;
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("external_edit.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend =
+            BackendState::new(runtime.handle().clone(), config_path.clone(), binary).unwrap();
+        assert!(!runtime.block_on(backend.config_changed_on_disk()));
+
+        let edit_externally = |tag: &str| {
+            let mut edited = runtime.block_on(read_config(&config_path)).unwrap();
+            edited.tunnels.push(tunnel(tag));
+            runtime
+                .block_on(save_config(&config_path, &edited))
+                .unwrap();
+        };
+
+        edit_externally("from-editor");
+        assert!(runtime.block_on(backend.config_changed_on_disk()));
+        let on_disk = std::fs::read_to_string(&config_path).unwrap();
+        let error = runtime
+            .block_on(backend.add_tunnel(tunnel("from-manager")))
+            .unwrap_err();
+        assert!(matches!(error, BackendError::ConfigChangedOnDisk));
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), on_disk);
+
+        runtime.block_on(backend.reload_config()).unwrap();
+        assert!(!runtime.block_on(backend.config_changed_on_disk()));
+        runtime
+            .block_on(backend.add_tunnel(tunnel("from-manager")))
+            .unwrap();
+        let tags: Vec<String> = backend.list_tunnels().into_iter().map(|t| t.tag).collect();
+        assert_eq!(tags, vec!["from-editor", "from-manager"]);
+
+        // Overwriting keeps the manager's tunnels and drops the new edit.
+        edit_externally("discarded");
+        runtime.block_on(backend.overwrite_config()).unwrap();
+        assert!(!runtime.block_on(backend.config_changed_on_disk()));
+        assert!(
+            !std::fs::read_to_string(&config_path)
+                .unwrap()
+                .contains("discarded")
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn read_only_backend_refuses_changes_but_starts_tunnels() {
+        use wstunnel_manager::backend::error::BackendError;
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("read_only.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend =
+            BackendState::new(runtime.handle().clone(), config_path.clone(), binary).unwrap();
+        let tunnel = tunnel("kiosk");
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel.clone()))
+            .unwrap();
+
+        backend.set_read_only(true);
+        assert!(backend.is_read_only());
+        let on_disk = std::fs::read_to_string(&config_path).unwrap();
+        let settings = backend.get_config().global.clone();
+        let refusals = [
+            runtime.block_on(backend.add_tunnel(tunnel.clone())).err(),
+            runtime.block_on(backend.edit_tunnel(id, tunnel)).err(),
+            runtime.block_on(backend.delete_tunnels(&[id])).err(),
+            runtime
+                .block_on(backend.update_global_settings(settings.clone()))
+                .err(),
+            runtime.block_on(backend.overwrite_config()).err(),
+        ];
+        for refusal in refusals {
+            assert!(matches!(refusal, Some(BackendError::ReadOnly)));
+        }
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), on_disk);
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+
+        // A locked config stays read-only without the flag.
+        backend.set_read_only(false);
+        runtime
+            .block_on(backend.update_global_settings(GlobalSettings {
+                locked: true,
+                ..settings
+            }))
+            .unwrap();
+        assert!(backend.is_read_only());
+        assert!(matches!(
+            runtime.block_on(backend.delete_tunnels(&[id])),
+            Err(BackendError::ReadOnly)
+        ));
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    fn start_with_ready_check(
+        script: &str,
+    ) -> (
+        tokio::runtime::Runtime,
+        BackendState,
+        TunnelId,
+        PathBuf,
+        anyhow::Result<()>,
+    ) {
+        let (runtime, mut backend, id, temp_dir) = ready_check_backend(script);
+        let result = start_and_wait(&runtime, &mut backend, id).map(|_| ());
+        (runtime, backend, id, temp_dir, result)
+    }
+
+    #[cfg(unix)]
+    fn ready_check_backend(
+        script: &str,
+    ) -> (tokio::runtime::Runtime, BackendState, TunnelId, PathBuf) {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("ready_test.yaml");
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: temp_dir.join("logs"),
+                start_timeout_seconds: Some(1),
+                ready_pattern: Some("listening on [0-9]+".to_string()),
+                ..GlobalSettings::default()
+            },
+            ..Config::default()
+        };
+        std::fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+        let binary = write_fake_wstunnel(&temp_dir, script);
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("ready")))
+            .unwrap();
+        (runtime, backend, id, temp_dir)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn start_waits_for_the_ready_line() {
+        let (runtime, mut backend, id, temp_dir, result) =
+            start_with_ready_check("sleep 0.2\necho 'listening on 8080'\nexec sleep 30");
+        result.unwrap();
+        assert!(backend.is_tunnel_running(id));
+
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn start_fails_and_stops_the_process_when_never_ready() {
+        let (_runtime, mut backend, id, temp_dir, result) =
+            start_with_ready_check("echo 'resolving host'\nexec sleep 30");
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("not ready after 1 seconds"), "{}", error);
+        assert!(!backend.is_tunnel_running(id));
+        assert!(backend.get_log_path(id).is_some());
+        assert!(matches!(
+            backend.tunnel_statuses()[0].1,
+            TunnelRuntimeState::Failed { error: ref failed, .. } if *failed == error
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn start_returns_before_the_tunnel_is_ready() {
+        let (runtime, mut backend, id, temp_dir) =
+            ready_check_backend("sleep 0.5\necho 'listening on 8080'\nexec sleep 30");
+        let mut events = backend.subscribe();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        assert!(matches!(
+            backend.tunnel_statuses()[0].1,
+            TunnelRuntimeState::Starting
+        ));
+
+        next_event(
+            &runtime,
+            &mut events,
+            |e| matches!(e, TunnelEvent::Started { id: started, .. } if *started == id),
+        );
+        assert!(matches!(
+            backend.get_tunnel_status(id),
+            TunnelRuntimeState::Running { .. }
+        ));
+
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn start_all_skips_tunnels_still_starting() {
+        let (runtime, mut backend, id, temp_dir) =
+            ready_check_backend("sleep 0.5\necho 'listening on 8080'\nexec sleep 30");
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        assert!(runtime.block_on(backend.start_all()).is_empty());
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+
+        // Exited but not yet reported by its readiness wait: still starting.
+        let (runtime, mut backend, id, temp_dir) = ready_check_backend("exit 1");
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        let results = runtime.block_on(backend.start_all());
+        assert!(
+            results.iter().all(|(_, result)| result.is_ok()),
+            "{:?}",
+            results
+        );
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn split_log_mode_writes_one_file_per_stream() {
+        use wstunnel_manager::backend::logs::{LogStream, stream_log_path};
+        use wstunnel_manager::backend::types::LogMode;
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("split_test.yaml");
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: temp_dir.join("logs"),
+                log_mode: LogMode::Split,
+                ..GlobalSettings::default()
+            },
+            ..Config::default()
+        };
+        std::fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+        let binary =
+            write_fake_wstunnel(&temp_dir, "echo out-line\necho err-line >&2\nexec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("split")))
+            .unwrap();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        for _ in 0..2 {
+            next_event(&runtime, &mut events, |e| {
+                matches!(e, TunnelEvent::LogLine { .. })
+            });
+        }
+        let log_path = backend.get_log_path(id).unwrap();
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+
+        let combined = std::fs::read_to_string(&log_path).unwrap();
+        assert!(combined.contains("[STDOUT] out-line"), "{}", combined);
+        assert!(combined.contains("[STDERR] err-line"), "{}", combined);
+
+        let stdout =
+            std::fs::read_to_string(stream_log_path(&log_path, LogStream::Stdout)).unwrap();
+        assert!(stdout.contains("out-line"), "{}", stdout);
+        assert!(!stdout.contains("err-line"), "{}", stdout);
+
+        let stderr =
+            std::fs::read_to_string(stream_log_path(&log_path, LogStream::Stderr)).unwrap();
+        assert!(stderr.contains("err-line"), "{}", stderr);
+        assert!(!stderr.contains("out-line"), "{}", stderr);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preview_command_does_not_spawn() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("preview_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let marker = temp_dir.join("spawned");
+        let binary = write_fake_wstunnel(
+            &temp_dir,
+            &format!("touch '{}'\nexec sleep 30", marker.display()),
+        );
+        let mut backend =
+            BackendState::new(runtime.handle().clone(), config_path, binary.clone()).unwrap();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(TunnelEntry::new(
+                "preview",
+                TunnelMode::Client,
+                "client -L 'tcp://8080:localhost:80' ws://example.com",
+            )))
+            .unwrap();
+
+        let command = backend.preview_command(id).unwrap();
+        assert!(
+            command.contains(&binary.display().to_string()),
+            "{}",
+            command
+        );
+        assert!(
+            command.ends_with(" client -L tcp://8080:localhost:80 ws://example.com"),
+            "{}",
+            command
+        );
+        assert!(!backend.is_tunnel_running(id));
+        assert!(!marker.exists());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn crash_event_reports_exit_code() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("crash_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "sleep 1\nexit 3");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("crashes")))
+            .unwrap();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        match next_event(&runtime, &mut events, |e| {
+            matches!(e, TunnelEvent::Crashed { .. })
+        }) {
+            TunnelEvent::Crashed {
+                id: event_id,
+                exit_code,
+            } => {
+                assert_eq!(event_id, id);
+                assert_eq!(exit_code, Some(3));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(!backend.is_tunnel_running(id));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn last_run_outcome_survives_a_restart() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("last_run_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "sleep 1\nexit 3");
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            config_path.clone(),
+            binary.clone(),
+        )
+        .unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("remembered")))
+            .unwrap();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        next_event(&runtime, &mut events, |e| {
+            matches!(e, TunnelEvent::Crashed { .. })
+        });
+        // The crash is recorded when the dead process is cleaned up.
+        backend.list_tunnels();
+        assert!(matches!(
+            backend.get_tunnel_status(id),
+            TunnelRuntimeState::Failed {
+                exit_code: Some(3),
+                ..
+            }
+        ));
+        assert!(running::last_run_file_path(&config_path).exists());
+        drop(backend);
+
+        let restarted = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        assert!(matches!(
+            restarted.get_tunnel_status(id),
+            TunnelRuntimeState::Failed {
+                exit_code: Some(3),
+                ..
+            }
+        ));
+        assert!(restarted.get_log_path(id).is_some());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stop_all_stops_tunnels_concurrently() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("stop_all_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        // The sleep keeps the output open after the shell is killed, so each
+        // stop waits out the monitor's drain timeout.
+        let binary = write_fake_wstunnel(&temp_dir, "sleep 60");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        let ids: Vec<TunnelId> = (0..4)
+            .map(|i| {
+                let id = runtime
+                    .block_on(backend.add_tunnel(TunnelEntry::new(
+                        format!("tunnel-{}", i),
+                        TunnelMode::Client,
+                        "client ws://example.com",
+                    )))
+                    .unwrap();
+                runtime.block_on(backend.start_tunnel(id)).unwrap();
+                id
+            })
+            .collect();
+
+        let started = std::time::Instant::now();
+        let results = runtime.block_on(backend.stop_all());
+        let elapsed = started.elapsed();
+
+        assert_eq!(results.len(), ids.len());
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(ids.iter().all(|id| !backend.is_tunnel_running(*id)));
+        assert!(
+            elapsed < std::time::Duration::from_secs(2),
+            "stopping took {:?}",
+            elapsed
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn user_stop_is_not_a_crash() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("user_stop_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("user-stop")))
+            .unwrap();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        assert!(
+            seen.iter()
+                .any(|e| matches!(e, TunnelEvent::Stopped { id: stopped } if *stopped == id))
+        );
+        assert!(
+            !seen
+                .iter()
+                .any(|e| matches!(e, TunnelEvent::Crashed { .. })),
+            "{:?}",
+            seen
+        );
+
+        backend.list_tunnels();
+        let stats = backend.get_tunnel_stats(id).unwrap();
+        assert_eq!(stats.total_starts, 1);
+        assert_eq!(stats.total_crashes, 0);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stats_survive_crash_until_delete() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("stats_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "sleep 1\nexit 3");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("stats")))
+            .unwrap();
+        assert_eq!(backend.get_tunnel_stats(id), Some(TunnelStats::default()));
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        next_event(&runtime, &mut events, |e| {
+            matches!(e, TunnelEvent::Crashed { .. })
+        });
+
+        let stats = backend.get_tunnel_stats(id).unwrap();
+        assert_eq!(stats.total_starts, 1);
+        assert_eq!(stats.total_crashes, 1);
+        assert_eq!(stats.last_exit_code, Some(3));
+        assert!(stats.last_started_at.is_some());
+        assert!(stats.cumulative_uptime >= std::time::Duration::from_millis(500));
+
+        // Cleaning up the dead process must not count the crash twice.
+        backend.list_tunnels();
+        assert_eq!(backend.get_tunnel_stats(id).unwrap().total_crashes, 1);
+
+        runtime.block_on(backend.delete_tunnel(id)).unwrap();
+        assert_eq!(backend.get_tunnel_stats(id), None);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detach_leaves_process_running() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("detach_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("detached")))
+            .unwrap();
+
+        let pid = runtime.block_on(backend.start_tunnel(id)).unwrap();
+        runtime.block_on(backend.detach()).unwrap();
+        assert!(!backend.is_tunnel_running(id));
+
+        // Dropping the backend and its runtime must not take the process down.
+        drop(backend);
+        drop(runtime);
+        // A killed child stays behind as a zombie until reaped, so check the
+        // process state rather than just whether the PID exists.
+        let state = std::process::Command::new("ps")
+            .args(["-o", "stat=", "-p", &pid.to_string()])
+            .output()
+            .unwrap()
+            .stdout;
+        let state = String::from_utf8_lossy(&state);
+        assert!(
+            !state.trim().is_empty() && !state.starts_with('Z'),
+            "detached process {} was killed",
+            pid
+        );
+
+        std::process::Command::new("kill")
+            .arg(pid.to_string())
+            .status()
+            .unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn startup_adopts_running_processes() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("adopt_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        // No exec, so the script itself stays the running process and can be
+        // matched against the binary path.
+        let binary = write_fake_wstunnel(&temp_dir, "while true; do sleep 1; done");
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            config_path.clone(),
+            binary.clone(),
+        )
+        .unwrap();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("adopted")))
+            .unwrap();
+
+        let pid = runtime.block_on(backend.start_tunnel(id)).unwrap();
+        assert!(running::state_file_path(&config_path).exists());
+        runtime.block_on(backend.detach()).unwrap();
+        drop(backend);
+
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            config_path.clone(),
+            binary.clone(),
+        )
+        .unwrap();
+        assert!(matches!(
+            backend.get_tunnel_status(id),
+            TunnelRuntimeState::Running { pid: running_pid, .. } if running_pid == pid
+        ));
+        assert!(runtime.block_on(backend.start_tunnel(id)).is_err());
+
+        // Stopping an adopted tunnel kills it by PID.
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        assert!(!backend.is_tunnel_running(id));
+        assert!(!is_tunnel_process(pid, &binary));
+        assert!(!running::state_file_path(&config_path).exists());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_runs_in_working_dir() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("working_dir_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+        std::fs::create_dir(temp_dir.join("certs")).unwrap();
+
+        let cwd_file = temp_dir.join("cwd.txt");
+        let binary = write_fake_wstunnel(
+            &temp_dir,
+            &format!("pwd >> '{}'\nexec sleep 30", cwd_file.display()),
+        );
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        let mut entry = tunnel("default-dir");
+        let default_id = runtime.block_on(backend.add_tunnel(entry.clone())).unwrap();
+        entry.id = TunnelId::new();
+        entry.tag = "relative-dir".to_string();
+        entry.working_dir = Some(PathBuf::from("certs"));
+        let relative_id = runtime.block_on(backend.add_tunnel(entry)).unwrap();
+
+        start_and_wait(&runtime, &mut backend, default_id).unwrap();
+        start_and_wait(&runtime, &mut backend, relative_id).unwrap();
+        runtime.block_on(backend.shutdown()).unwrap();
+
+        let canonical = temp_dir.canonicalize().unwrap();
+        let dirs: Vec<PathBuf> = std::fs::read_to_string(&cwd_file)
+            .unwrap()
+            .lines()
+            .map(|line| PathBuf::from(line).canonicalize().unwrap())
+            .collect();
+        assert_eq!(dirs, vec![canonical.clone(), canonical.join("certs")]);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timestamps_track_config_changes_only() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("timestamps_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        let entry = tunnel("timestamps");
+        let id = runtime.block_on(backend.add_tunnel(entry)).unwrap();
+        let added = backend.get_tunnel(id).unwrap();
+        assert!(added.created_at.is_some());
+        assert_eq!(added.created_at, added.modified_at);
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        runtime.block_on(backend.stop_tunnel(id)).unwrap();
+        let restarted = backend.get_tunnel(id).unwrap();
+        assert_eq!(restarted.created_at, added.created_at);
+        assert_eq!(restarted.modified_at, added.modified_at);
+
+        // The edit form doesn't carry the timestamps; the backend keeps the
+        // original creation time.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let mut edited = added.clone();
+        edited.tag = "timestamps-edited".to_string();
+        edited.created_at = None;
+        edited.modified_at = None;
+        runtime.block_on(backend.edit_tunnel(id, edited)).unwrap();
+        let edited = backend.get_tunnel(id).unwrap();
+        assert_eq!(edited.created_at, added.created_at);
+        assert!(edited.modified_at > added.modified_at);
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn health_check_follows_target() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("health_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        let mut events = backend.subscribe();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(TunnelEntry {
+                health_check: Some(HealthCheck {
+                    target,
+                    interval_secs: 1,
+                }),
+                ..tunnel("checked")
+            }))
+            .unwrap();
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        next_event(&runtime, &mut events, |event| {
+            matches!(
+                event,
+                TunnelEvent::HealthChanged {
+                    health: TunnelHealth::Healthy,
+                    ..
+                }
+            )
+        });
+
+        // Nothing accepts connections once the listener is gone.
+        drop(listener);
+        next_event(&runtime, &mut events, |event| {
+            matches!(
+                event,
+                TunnelEvent::HealthChanged {
+                    health: TunnelHealth::Unhealthy { .. },
+                    ..
+                }
+            )
+        });
+        assert!(matches!(
+            backend.get_tunnel_status(id),
+            TunnelRuntimeState::Running {
+                health: TunnelHealth::Unhealthy { .. },
+                ..
+            }
+        ));
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detects_wstunnel_version() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("version_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        assert_eq!(backend.wstunnel_version().as_deref(), Some("10.1.8"));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn version_is_checked_once_binary_appears() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("late_binary_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = temp_dir.join("fake_wstunnel.sh");
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        assert_eq!(backend.wstunnel_version(), None);
+
+        write_fake_wstunnel(&temp_dir, "exec sleep 30");
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("late")))
+            .unwrap();
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        assert_eq!(backend.wstunnel_version().as_deref(), Some("10.1.8"));
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unrecognised_binary_still_starts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("wrapper_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        // A wrapper script that doesn't answer `--version` like wstunnel.
+        let binary = temp_dir.join("wrapper.sh");
+        std::fs::write(
+            &binary,
+            "#!/bin/sh\necho 'my wrapper'\n[ \"$1\" = --version ] && exit 0\nexec sleep 30\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+        assert_eq!(backend.wstunnel_version(), None);
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("wrapped")))
+            .unwrap();
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        runtime.block_on(backend.shutdown()).unwrap();
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn start_failure_includes_stderr() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("stderr_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let binary = write_fake_wstunnel(
+            &temp_dir,
+            "echo \"starting\"\necho \"error: unexpected argument '--foo' found\" >&2\nexit 2",
+        );
+        let mut backend = BackendState::new(runtime.handle().clone(), config_path, binary).unwrap();
+
+        let id = runtime
+            .block_on(backend.add_tunnel(TunnelEntry::new(
+                "bad-args",
+                TunnelMode::Client,
+                "client --foo",
+            )))
+            .unwrap();
+
+        let error = start_and_wait(&runtime, &mut backend, id)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("Failed to start tunnel 'bad-args'"),
+            "{}",
+            error
+        );
+        assert!(error.contains("unexpected argument '--foo'"), "{}", error);
+        assert!(!backend.is_tunnel_running(id));
+        assert!(backend.get_log_path(id).is_some());
+        assert_eq!(
+            backend.recent_stderr(id),
+            "error: unexpected argument '--foo' found\n"
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn backend_can_be_created_from_async_code() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("async_new_test.yaml");
+        write_config_with_log_dir(&config_path, &temp_dir.join("logs"));
+
+        let mut backend = runtime.block_on(async {
+            BackendState::load(
+                tokio::runtime::Handle::current(),
+                config_path,
+                get_wstunnel_path(),
+            )
+            .await
+            .unwrap()
+        });
+        assert!(backend.list_tunnels().is_empty());
+
+        runtime.block_on(backend.shutdown()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn config_generation_follows_config_changes() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            temp_dir.join("generation.yaml"),
+            get_wstunnel_path(),
+        )
+        .unwrap();
+
+        let generation = backend.config_generation();
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("generation")))
+            .unwrap();
+        let generation_after_add = backend.config_generation();
+        assert!(generation_after_add > generation);
+
+        // Reading statuses or the list leaves it alone.
+        let statuses = backend.tunnel_statuses();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].0, id);
+        assert!(matches!(statuses[0].1, TunnelRuntimeState::Stopped));
+        backend.list_tunnels();
+        assert_eq!(backend.config_generation(), generation_after_add);
+
+        runtime.block_on(backend.delete_tunnel(id)).unwrap();
+        assert!(backend.config_generation() > generation_after_add);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn import_and_export_config() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            temp_dir.join("active.yaml"),
+            get_wstunnel_path(),
+        )
+        .unwrap();
+        let existing_id = runtime
+            .block_on(backend.add_tunnel(tunnel("existing")))
+            .unwrap();
+
+        let export_path = temp_dir.join("export.json");
+        runtime
+            .block_on(backend.save_config(&backend.get_config(), &export_path))
+            .unwrap();
+        assert!(export_path.exists());
+
+        let imported = runtime
+            .block_on(backend.import_config(&export_path))
+            .unwrap();
+        assert_eq!(imported, 1);
+
+        let tunnels = backend.list_tunnels();
+        assert_eq!(tunnels.len(), 2);
+        assert!(tunnels.iter().all(|t| t.tag == "existing"));
+        assert_eq!(tunnels.iter().filter(|t| t.id == existing_id).count(), 1);
+
+        let invalid_path = temp_dir.join("invalid.yaml");
+        std::fs::write(
+            &invalid_path,
+            "version: 2\ntunnels:\n  - id: \"550e8400-e29b-41d4-a716-446655440000\"\n    tag: \"\"\n    mode: client\n    cli_args: \"client ws://x\"\n    autostart: false\n",
+        )
+        .unwrap();
+        let error = runtime
+            .block_on(backend.import_config(&invalid_path))
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("cannot be empty"));
+        assert_eq!(backend.list_tunnels().len(), 2);
+
+        assert!(
+            runtime
+                .block_on(backend.import_config(&temp_dir.join("missing.yaml")))
+                .is_err()
+        );
+        assert!(!temp_dir.join("missing.yaml").exists());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn autostart_tunnels() {
+        let runtime = create_test_runtime();
+        let handle = runtime.handle().clone();
+        let temp_dir = create_temp_test_dir();
+
+        let config_path = temp_dir.join("test_config.yaml");
+        let wstunnel_path = get_wstunnel_path();
+
+        let mut backend =
+            BackendState::new(handle.clone(), config_path.clone(), wstunnel_path).unwrap();
+
+        let autostart_tunnel = TunnelEntry {
+            autostart: true,
+            ..tunnel("autostart-test")
+        };
+
+        let manual_tunnel = TunnelEntry::new(
+            "manual-test",
+            TunnelMode::Server,
+            "server ws://0.0.0.0:8080",
+        );
+
+        runtime
+            .block_on(backend.add_tunnel(autostart_tunnel.clone()))
+            .unwrap();
+        runtime
+            .block_on(backend.add_tunnel(manual_tunnel.clone()))
+            .unwrap();
+
+        let results = runtime.block_on(backend.start_autostart_tunnels());
+        if let Ok(result_list) = results {
+            assert_eq!(result_list.len(), 1);
+            let (tunnel_id, _result) = &result_list[0];
             assert_eq!(*tunnel_id, autostart_tunnel.id);
         }
 
@@ -373,140 +2561,3170 @@ mod backend_integration {
     }
 
     #[test]
-    fn config_persistence() {
-        let runtime = create_test_runtime();
-        let handle = runtime.handle().clone();
-        let temp_dir = create_temp_test_dir();
+    fn config_persistence() {
+        let runtime = create_test_runtime();
+        let handle = runtime.handle().clone();
+        let temp_dir = create_temp_test_dir();
+
+        let config_path = temp_dir.join("persist_test_config.yaml");
+        let wstunnel_path = get_wstunnel_path();
+
+        let tunnel_id = {
+            let mut backend =
+                BackendState::new(handle.clone(), config_path.clone(), wstunnel_path.clone())
+                    .unwrap();
+
+            let tunnel = tunnel("persist-test");
+
+            let id = runtime.block_on(backend.add_tunnel(tunnel)).unwrap();
+
+            let tunnels = backend.list_tunnels();
+            assert_eq!(tunnels.len(), 1);
+            assert_eq!(tunnels[0].tag, "persist-test");
+
+            id
+        };
+
+        {
+            let backend2 =
+                BackendState::new(handle.clone(), config_path.clone(), wstunnel_path).unwrap();
+
+            let config = backend2.get_config();
+            assert_eq!(config.tunnels.len(), 1);
+            assert_eq!(config.tunnels[0].id, tunnel_id);
+            assert_eq!(config.tunnels[0].tag, "persist-test");
+        }
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn global_settings_update_is_persisted() {
+        let runtime = create_test_runtime();
+        let handle = runtime.handle().clone();
+        let temp_dir = create_temp_test_dir();
+
+        let config_path = temp_dir.join("settings_test.yaml");
+        let wstunnel_path = get_wstunnel_path();
+
+        {
+            let mut backend =
+                BackendState::new(handle.clone(), config_path.clone(), wstunnel_path.clone())
+                    .unwrap();
+            assert_eq!(backend.get_config().global.theme, None);
+
+            let mut settings = backend.get_config().global.clone();
+            settings.theme = Some("Dark".to_string());
+            runtime
+                .block_on(backend.update_global_settings(settings))
+                .unwrap();
+            assert_eq!(backend.get_config().global.theme.as_deref(), Some("Dark"));
+
+            let mut invalid = backend.get_config().global.clone();
+            invalid.max_log_files = 0;
+            assert!(
+                runtime
+                    .block_on(backend.update_global_settings(invalid))
+                    .is_err()
+            );
+        }
+
+        let backend = BackendState::new(handle, config_path, wstunnel_path).unwrap();
+        let global = &backend.get_config().global;
+        assert_eq!(global.theme.as_deref(), Some("Dark"));
+        assert_ne!(global.max_log_files, 0);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn add_and_list_tunnels() {
+        let runtime = create_test_runtime();
+        let handle = runtime.handle().clone();
+        let temp_dir = create_temp_test_dir();
+
+        let config_path = temp_dir.join("add_list_test.yaml");
+        let wstunnel_path = get_wstunnel_path();
+
+        let mut backend = BackendState::new(handle, config_path, wstunnel_path).unwrap();
+
+        assert_eq!(backend.list_tunnels().len(), 0);
+
+        let tunnel1 = TunnelEntry::new("tunnel-1", TunnelMode::Client, "client ws://server1.com");
+
+        let tunnel2 = TunnelEntry {
+            autostart: true,
+            ..TunnelEntry::new("tunnel-2", TunnelMode::Server, "server ws://0.0.0.0:8080")
+        };
+
+        runtime
+            .block_on(backend.add_tunnel(tunnel1.clone()))
+            .unwrap();
+        runtime
+            .block_on(backend.add_tunnel(tunnel2.clone()))
+            .unwrap();
+
+        let tunnels = backend.list_tunnels();
+        assert_eq!(tunnels.len(), 2);
+        assert!(tunnels.iter().any(|t| t.tag == "tunnel-1"));
+        assert!(tunnels.iter().any(|t| t.tag == "tunnel-2"));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn delete_tunnel() {
+        let runtime = create_test_runtime();
+        let handle = runtime.handle().clone();
+        let temp_dir = create_temp_test_dir();
+
+        let config_path = temp_dir.join("delete_test.yaml");
+        let wstunnel_path = get_wstunnel_path();
+
+        let mut backend = BackendState::new(handle, config_path, wstunnel_path).unwrap();
+
+        let tunnel = tunnel("to-delete");
+
+        let id = runtime.block_on(backend.add_tunnel(tunnel)).unwrap();
+        assert_eq!(backend.list_tunnels().len(), 1);
+
+        runtime.block_on(backend.delete_tunnel(id)).unwrap();
+        assert_eq!(backend.list_tunnels().len(), 0);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn delete_several_tunnels_at_once() {
+        let runtime = create_test_runtime();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("bulk_delete.yaml");
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            config_path.clone(),
+            get_wstunnel_path(),
+        )
+        .unwrap();
+
+        let mut add = |tag: &str, depends_on: Vec<TunnelId>| {
+            runtime
+                .block_on(backend.add_tunnel(TunnelEntry {
+                    depends_on,
+                    ..tunnel(tag)
+                }))
+                .unwrap()
+        };
+        let old = add("old", Vec::new());
+        let older = add("older", Vec::new());
+        let keep = add("keep", vec![old]);
+
+        // One unknown ID and nothing is deleted.
+        let error = runtime
+            .block_on(backend.delete_tunnels(&[old, TunnelId::new()]))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            wstunnel_manager::backend::error::BackendError::TunnelNotFound(_)
+        ));
+        assert_eq!(backend.list_tunnels().len(), 3);
+
+        runtime
+            .block_on(backend.delete_tunnels(&[old, older]))
+            .unwrap();
+        let tunnels = backend.list_tunnels();
+        assert_eq!(tunnels.len(), 1);
+        assert_eq!(tunnels[0].id, keep);
+        assert!(tunnels[0].depends_on.is_empty());
+
+        let on_disk = runtime
+            .block_on(wstunnel_manager::backend::config::read_config(&config_path))
+            .unwrap();
+        assert_eq!(on_disk.tunnels.len(), 1);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod global_settings {
+    use super::*;
+
+    #[test]
+    fn settings_screen_round_trips_settings() {
+        use wstunnel_manager::ui::state::{SettingsField, SettingsState};
+
+        let settings = GlobalSettings {
+            log_retention_days: Some(30),
+            log_compress_days: Some(7),
+            theme: Some("Dracula".to_string()),
+            ready_pattern: Some("Starting".to_string()),
+            ..GlobalSettings::default()
+        };
+        let mut state = SettingsState::new(&settings);
+        let saved = state.to_settings().unwrap();
+        assert_eq!(saved.log_retention_days, Some(30));
+        assert_eq!(saved.log_compress_days, Some(7));
+        assert_eq!(saved.theme.as_deref(), Some("Dracula"));
+        assert_eq!(saved.sensitive_flags, settings.sensitive_flags);
+
+        // Blank optional fields unset the value.
+        state.input_mut(SettingsField::ReadyPattern).clear();
+        state.input_mut(SettingsField::LogCompressDays).clear();
+        let saved = state.to_settings().unwrap();
+        assert!(saved.ready_pattern.is_none());
+        assert!(saved.log_compress_days.is_none());
+    }
+
+    #[test]
+    fn settings_screen_rejects_invalid_values() {
+        use wstunnel_manager::ui::state::{SettingsField, SettingsState};
+
+        let mut state = SettingsState::new(&GlobalSettings::default());
+        *state.input_mut(SettingsField::LogRetentionDays) = "a week".to_string();
+        let error = state.to_settings().unwrap_err();
+        assert!(error.contains("a week"), "{}", error);
+
+        // Parsed values still go through GlobalSettings::validate.
+        *state.input_mut(SettingsField::LogRetentionDays) = "0".to_string();
+        let error = state.to_settings().unwrap_err();
+        assert!(error.contains("retention"), "{}", error);
+    }
+
+    #[test]
+    fn setup_screen_fills_in_entered_paths() {
+        use wstunnel_manager::ui::state::SetupState;
+
+        let mut settings = GlobalSettings::default();
+        let mut setup = SetupState::new(None, settings.log_directory.clone());
+        setup.binary_path_input = "/opt/wstunnel/wstunnel".to_string();
+        setup.log_directory_input = "  /var/log/wstunnel ".to_string();
+        setup.apply_to(&mut settings);
+        assert_eq!(
+            settings.wstunnel_binary_path,
+            Some(PathBuf::from("/opt/wstunnel/wstunnel"))
+        );
+        assert_eq!(settings.log_directory, PathBuf::from("/var/log/wstunnel"));
+
+        // Blank fields keep what was there.
+        let setup = SetupState::new(None, PathBuf::new());
+        setup.apply_to(&mut settings);
+        assert_eq!(
+            settings.wstunnel_binary_path,
+            Some(PathBuf::from("/opt/wstunnel/wstunnel"))
+        );
+        assert_eq!(settings.log_directory, PathBuf::from("/var/log/wstunnel"));
+    }
+
+    #[test]
+    fn default_values() {
+        let settings = GlobalSettings::default();
+        assert!(settings.wstunnel_binary_path.is_none());
+        assert_eq!(settings.log_directory, PathBuf::from(".").join("logs"));
+        assert!(settings.log_retention_days.is_none());
+    }
+
+    #[test]
+    fn custom_log_directory() {
+        let settings = GlobalSettings {
+            wstunnel_binary_path: None,
+            log_directory: PathBuf::from("/var/log/wstunnel"),
+            log_retention_days: None,
+            ..GlobalSettings::default()
+        };
+
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.log_directory, PathBuf::from("/var/log/wstunnel"));
+    }
+
+    #[test]
+    fn start_check_settings() {
+        use wstunnel_manager::constants::{DEFAULT_START_TIMEOUT_SECS, EARLY_EXIT_GRACE};
+
+        let settings = GlobalSettings::default();
+        let check = settings.start_check();
+        assert_eq!(check.timeout, EARLY_EXIT_GRACE);
+        assert!(check.ready_pattern.is_none());
+
+        let settings = GlobalSettings {
+            ready_pattern: Some("ready".to_string()),
+            ..GlobalSettings::default()
+        };
+        let check = settings.start_check();
+        assert_eq!(
+            check.timeout,
+            std::time::Duration::from_secs(DEFAULT_START_TIMEOUT_SECS)
+        );
+        assert!(check.ready_pattern.is_some());
+
+        let settings = GlobalSettings {
+            start_timeout_seconds: Some(0),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = GlobalSettings {
+            ready_pattern: Some("(unclosed".to_string()),
+            ..GlobalSettings::default()
+        };
+        let error = settings.validate().unwrap_err().to_string();
+        assert!(
+            error.contains("not a valid regular expression"),
+            "{}",
+            error
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn binary_path_must_be_executable_to_save() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let binary = temp_dir.join("wstunnel");
+        std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let settings = GlobalSettings {
+            wstunnel_binary_path: Some(binary.clone()),
+            ..GlobalSettings::default()
+        };
+        // A config that points at it must still load.
+        assert!(settings.validate().is_ok());
+        let error = settings.check_binary_executable().unwrap_err().to_string();
+        assert!(error.contains("Permission denied"), "{}", error);
+
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(settings.check_binary_executable().is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn local_port_scheme_must_be_http_or_https() {
+        for (scheme, valid) in [("https", true), ("http", true), ("ftp", false)] {
+            let settings = GlobalSettings {
+                local_port_scheme: Some(scheme.to_string()),
+                ..GlobalSettings::default()
+            };
+            assert_eq!(settings.validate().is_ok(), valid, "{}", scheme);
+        }
+    }
+
+    #[test]
+    fn ui_refresh_is_off_by_default_and_clamped_to_the_minimum() {
+        let refresh = |seconds: Option<u32>| {
+            GlobalSettings {
+                ui_refresh_seconds: seconds,
+                ..GlobalSettings::default()
+            }
+            .ui_refresh_interval()
+        };
+        assert_eq!(GlobalSettings::default().ui_refresh_interval(), None);
+        assert_eq!(refresh(Some(0)), None);
+        assert_eq!(refresh(Some(5)), Some(std::time::Duration::from_secs(5)));
+        assert!(
+            refresh(Some(1)).unwrap()
+                >= std::time::Duration::from_secs(
+                    wstunnel_manager::constants::MIN_UI_REFRESH_SECS.into()
+                )
+        );
+    }
+}
+
+mod cli_args_env_expansion {
+    use std::collections::HashMap;
+    use wstunnel_manager::backend::process::expand_env_vars;
+
+    fn lookup(name: &str) -> Option<String> {
+        let vars = HashMap::from([
+            ("TUNNEL_HOST", "example.com"),
+            ("TUNNEL_PORT", "8080"),
+            ("_UNDERSCORE", "ok"),
+        ]);
+        vars.get(name).map(|v| v.to_string())
+    }
+
+    #[test]
+    fn expands_known_variables() {
+        let expanded =
+            expand_env_vars("ws://${TUNNEL_HOST}:${TUNNEL_PORT}", lookup, false).unwrap();
+        assert_eq!(expanded, "ws://example.com:8080");
+
+        let expanded = expand_env_vars("${_UNDERSCORE}", lookup, false).unwrap();
+        assert_eq!(expanded, "ok");
+    }
+
+    #[test]
+    fn leaves_missing_variables_as_is() {
+        let expanded = expand_env_vars("ws://${MISSING_HOST}:8080", lookup, false).unwrap();
+        assert_eq!(expanded, "ws://${MISSING_HOST}:8080");
+    }
+
+    #[test]
+    fn errors_on_missing_variables_when_strict() {
+        let result = expand_env_vars("ws://${MISSING_HOST}:8080", lookup, true);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("MISSING_HOST"));
+    }
+
+    #[test]
+    fn literal_dollar_signs_are_preserved() {
+        let cases = [
+            "price$5",
+            "$TUNNEL_HOST",
+            "trailing$",
+            "${unterminated",
+            "${}",
+            "${1INVALID}",
+            "${WITH-DASH}",
+        ];
+
+        for case in cases {
+            assert_eq!(expand_env_vars(case, lookup, true).unwrap(), case);
+        }
+    }
+
+    #[test]
+    fn mixed_literal_and_variables() {
+        let expanded = expand_env_vars("$${TUNNEL_PORT}$", lookup, false).unwrap();
+        assert_eq!(expanded, "$8080$");
+    }
+}
+
+mod cli_args_tokenizer {
+    use wstunnel_manager::backend::process::{check_cli_args_quotes, parse_cli_args};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            parse_cli_args("  client \t -L  tcp://8080:localhost:80\nws://example.com "),
+            args(&[
+                "client",
+                "-L",
+                "tcp://8080:localhost:80",
+                "ws://example.com"
+            ])
+        );
+        assert!(parse_cli_args("   ").is_empty());
+    }
+
+    #[test]
+    fn double_and_single_quotes() {
+        assert_eq!(
+            parse_cli_args(r#"client --header "X-Foo: bar" --header 'X-Bar: "baz"'"#),
+            args(&[
+                "client",
+                "--header",
+                "X-Foo: bar",
+                "--header",
+                r#"X-Bar: "baz""#
+            ])
+        );
+    }
+
+    #[test]
+    fn backslash_escapes() {
+        assert_eq!(
+            parse_cli_args(r#"a\ b \"quoted\" \'single\' back\\slash"#),
+            args(&["a b", "\"quoted\"", "'single'", r"back\slash"])
+        );
+        assert_eq!(
+            parse_cli_args(r#""inner \"escaped\" quote""#),
+            args(&[r#"inner "escaped" quote"#])
+        );
+        assert_eq!(parse_cli_args(r"'no \ escapes'"), args(&[r"no \ escapes"]));
+    }
+
+    #[test]
+    fn windows_paths() {
+        assert_eq!(
+            parse_cli_args(r#"C:\wstunnel\certs "C:\Program Files\x""#),
+            args(&[r"C:\wstunnel\certs", r"C:\Program Files\x"])
+        );
+    }
+
+    #[test]
+    fn adjacent_segments_are_joined() {
+        assert_eq!(parse_cli_args(r#"foo"bar baz""#), args(&["foobar baz"]));
+        assert_eq!(parse_cli_args(r#"'a'"b"c"#), args(&["abc"]));
+    }
+
+    #[test]
+    fn empty_quotes_produce_empty_arguments() {
+        assert_eq!(parse_cli_args(r#"a "" b ''"#), args(&["a", "", "b", ""]));
+    }
+
+    #[test]
+    fn unterminated_quotes_are_reported() {
+        assert!(check_cli_args_quotes(r#"client --header "X-Foo: bar" 'baz'"#).is_ok());
+        assert!(check_cli_args_quotes(r#"say \"hi"#).is_ok());
+
+        let error = check_cli_args_quotes(r#"client --header "X-Foo: bar"#).unwrap_err();
+        assert!(error.to_string().contains("unterminated \" quote"));
+        let error = check_cli_args_quotes("client 'ws://example.com").unwrap_err();
+        assert!(error.to_string().contains("unterminated ' quote"));
+        assert!(check_cli_args_quotes("it's fine").is_err());
+    }
+}
+
+mod cli_args_schema {
+    use wstunnel_manager::backend::cli_schema::{Endpoint, check_cli_args, parse_endpoints};
+
+    #[test]
+    fn well_formed_commands_pass() {
+        assert!(
+            check_cli_args(
+                "--log-lvl INFO client -L socks5://127.0.0.1:1080 -R=tcp://9000:localhost:22 --tls-sni-override example.com wss://example.com:443"
+            )
+            .is_empty()
+        );
+        assert!(
+            check_cli_args(
+                "server --restrict-to localhost:22 --tls-client-ca-certs ca.pem wss://0.0.0.0:443"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn unknown_flags_are_allowed() {
+        assert!(check_cli_args("client --some-future-flag 5 wss://example.com").is_empty());
+    }
+
+    #[test]
+    fn args_files_may_hold_the_subcommand_and_url() {
+        assert!(check_cli_args("@client.args").is_empty());
+        assert!(check_cli_args("client @remote.args").is_empty());
+    }
+
+    #[test]
+    fn missing_subcommand_and_url() {
+        assert_eq!(
+            check_cli_args("-L tcp://1:a:2 wss://example.com"),
+            vec!["CLI arguments do not contain a 'client' or 'server' subcommand"]
+        );
+        let problems = check_cli_args("client -L tcp://8080:localhost:80");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("server URL"));
+        let problems = check_cli_args("server --restrict-to localhost:22");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("bind address"));
+    }
+
+    #[test]
+    fn misplaced_and_wrong_side_flags() {
+        let problems = check_cli_args("--tls-verify-certificate client wss://example.com");
+        assert_eq!(
+            problems,
+            vec!["'--tls-verify-certificate' must come after the 'client' subcommand"]
+        );
+
+        let problems = check_cli_args("server -L tcp://8080:localhost:80 wss://0.0.0.0:443");
+        assert_eq!(
+            problems,
+            vec!["'-L' is not a 'server' flag; check the subcommand"]
+        );
+
+        let problems = check_cli_args("client --restrict-to=localhost:22 wss://example.com");
+        assert_eq!(
+            problems,
+            vec!["'--restrict-to' is not a 'client' flag; check the subcommand"]
+        );
+    }
+
+    #[test]
+    fn malformed_tunnel_specs() {
+        let problems = check_cli_args("client -L 8080:localhost:80 wss://example.com");
+        assert_eq!(
+            problems,
+            vec!["'-L' expects a tunnel like tcp://8080:localhost:80, got '8080:localhost:80'"]
+        );
+
+        let problems = check_cli_args("client wss://example.com -R");
+        assert_eq!(
+            problems,
+            vec!["'-R' expects a tunnel like tcp://8080:localhost:80, got ''"]
+        );
+    }
+
+    #[test]
+    fn endpoints_are_parsed_from_tunnel_specs_and_url() {
+        let endpoints = parse_endpoints(
+            "client -L tcp://127.0.0.1:8888:google.com:443 -L socks5://[::1]:1080 \
+             --remote-to-local=udp://53:[2001:db8::1]:53?timeout_sec=10 -L stdio://host:22 wss://server:443",
+        );
+        let shown: Vec<String> = endpoints.iter().map(Endpoint::to_string).collect();
+        assert_eq!(
+            shown,
+            vec![
+                "L:127.0.0.1:8888 → google.com:443",
+                "L:[::1]:1080 → socks5",
+                "R:53 → [2001:db8::1]:53",
+                "L:stdio → host:22",
+                "wss://server:443",
+            ]
+        );
+
+        assert_eq!(
+            parse_endpoints("server wss://0.0.0.0:8080"),
+            vec![Endpoint::Url("wss://0.0.0.0:8080".to_string())]
+        );
+    }
+
+    #[test]
+    fn local_address_of_browsable_forwards() {
+        let address = |cli_args: &str| {
+            parse_endpoints(cli_args)
+                .iter()
+                .find_map(Endpoint::local_address)
+        };
+        assert_eq!(
+            address("client -L tcp://8080:localhost:80 wss://s").as_deref(),
+            Some("127.0.0.1:8080")
+        );
+        assert_eq!(
+            address("client -L tcp://[::]:8080:localhost:80 wss://s").as_deref(),
+            Some("127.0.0.1:8080")
+        );
+        assert_eq!(
+            address("client -L udp://53:1.1.1.1:53 -L http://127.0.0.2:3128 wss://s").as_deref(),
+            Some("127.0.0.2:3128")
+        );
+        assert_eq!(
+            address("client -L socks5://1080 -R tcp://80:localhost:80 wss://s"),
+            None
+        );
+    }
+
+    #[test]
+    fn unrecognized_endpoints_are_left_out() {
+        assert!(parse_endpoints("--help").is_empty());
+        assert!(
+            parse_endpoints("client -L 8080:localhost:80 -L quic://1:a:2 -R tcp://x").is_empty()
+        );
+        assert_eq!(
+            parse_endpoints("client -L http://8080 http://proxy:3128"),
+            vec![
+                Endpoint::Local {
+                    protocol: "http".to_string(),
+                    listen: "8080".to_string(),
+                    target: "http".to_string(),
+                },
+                Endpoint::Url("http://proxy:3128".to_string()),
+            ]
+        );
+    }
+}
+
+mod command_preview {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use wstunnel_manager::backend::process::{format_command, resolve_cli_args};
+    use wstunnel_manager::backend::types::GlobalSettings;
+
+    #[test]
+    fn resolves_quotes_and_env_vars() {
+        let env = HashMap::from([("PORT".to_string(), "8080".to_string())]);
+        let args = resolve_cli_args(
+            r#"client -L "tcp://${PORT}:localhost:80" wss://example.com"#,
+            &env,
+            true,
+            Path::new("."),
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "client",
+                "-L",
+                "tcp://8080:localhost:80",
+                "wss://example.com"
+            ]
+        );
+        assert!(
+            resolve_cli_args(
+                "client ${WSTM_PREVIEW_UNSET_VAR}",
+                &env,
+                true,
+                Path::new(".")
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn expands_args_files() {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("tls.args"),
+            "# client certificate\n--tls-certificate \"${CERT_DIR}/client.pem\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("client.args"),
+            "-L tcp://8080:localhost:80\n@tls.args\nwss://example.com\n",
+        )
+        .unwrap();
+
+        let env = HashMap::from([("CERT_DIR".to_string(), "/certs".to_string())]);
+        let args = resolve_cli_args("client @client.args", &env, true, &temp_dir).unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "client",
+                "-L",
+                "tcp://8080:localhost:80",
+                "--tls-certificate",
+                "/certs/client.pem",
+                "wss://example.com"
+            ]
+        );
+        assert!(resolve_cli_args("client @missing.args", &env, true, &temp_dir).is_err());
+
+        std::fs::write(temp_dir.join("loop.args"), "-L tcp://1:a:1\n@loop.args\n").unwrap();
+        let error = resolve_cli_args("client @loop.args", &env, true, &temp_dir).unwrap_err();
+        assert!(error.to_string().contains("includes itself"));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn formats_a_pasteable_command() {
+        let env = HashMap::from([
+            ("RUST_LOG".to_string(), "debug".to_string()),
+            ("A_TOKEN".to_string(), "two words".to_string()),
+        ]);
+        let args = vec![
+            "client".to_string(),
+            "-P".to_string(),
+            "secret".to_string(),
+            "--http-headers-file".to_string(),
+            "/etc/my headers".to_string(),
+            "wss://example.com".to_string(),
+        ];
+        assert_eq!(
+            format_command(
+                Path::new("/usr/bin/wstunnel"),
+                &args,
+                &env,
+                Path::new("/srv/tunnels"),
+                None,
+                &GlobalSettings::default().sensitive_flags,
+            ),
+            "cd /srv/tunnels && A_TOKEN=*** RUST_LOG=*** /usr/bin/wstunnel \
+             client -P *** --http-headers-file '/etc/my headers' wss://example.com"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn priority_runs_the_binary_under_nice() {
+        assert_eq!(
+            format_command(
+                Path::new("/usr/bin/wstunnel"),
+                &["client".to_string(), "ws://example.com".to_string()],
+                &HashMap::new(),
+                Path::new("/srv"),
+                Some(wstunnel_manager::backend::types::ProcessPriority::from(10)),
+                &[],
+            ),
+            "cd /srv && nice -n 10 /usr/bin/wstunnel client ws://example.com"
+        );
+    }
+}
+
+mod cli_args_redaction {
+    use wstunnel_manager::backend::process::{parse_cli_args, redact_cli_args};
+    use wstunnel_manager::backend::types::GlobalSettings;
+
+    fn redact(cli_args: &str) -> String {
+        redact_cli_args(cli_args, &GlobalSettings::default().sensitive_flags)
+    }
+
+    #[test]
+    fn masks_values_after_sensitive_flags() {
+        assert_eq!(
+            redact("client -P my-secret -L tcp://8080:localhost:80 wss://example.com"),
+            "client -P *** -L tcp://8080:localhost:80 wss://example.com"
+        );
+        assert_eq!(
+            redact("client --http-upgrade-credentials user:pass wss://example.com"),
+            "client --http-upgrade-credentials *** wss://example.com"
+        );
+        assert_eq!(
+            redact("server --restrict-http-upgrade-path-prefix abc wss://0.0.0.0:443"),
+            "server --restrict-http-upgrade-path-prefix *** wss://0.0.0.0:443"
+        );
+    }
+
+    #[test]
+    fn masks_inline_values() {
+        assert_eq!(
+            redact("client --http-upgrade-path-prefix=my-secret wss://example.com"),
+            "client --http-upgrade-path-prefix=*** wss://example.com"
+        );
+    }
+
+    #[test]
+    fn masks_quoted_header_values() {
+        assert_eq!(
+            redact(r#"client -H "Authorization: Bearer abc" wss://example.com"#),
+            "client -H *** wss://example.com"
+        );
+    }
+
+    #[test]
+    fn leaves_other_arguments_alone() {
+        let cli_args = "client -L socks5://127.0.0.1:1080 wss://example.com";
+        assert_eq!(redact(cli_args), cli_args);
+        // A flag that only starts like a sensitive one is not masked.
+        assert_eq!(redact("client --Pfoo bar"), "client --Pfoo bar");
+    }
+
+    #[test]
+    fn flag_list_is_configurable() {
+        let flags = vec!["--tls-sni-override".to_string()];
+        assert_eq!(
+            redact_cli_args("client -P secret --tls-sni-override example.org", &flags),
+            "client -P secret --tls-sni-override ***"
+        );
+        assert_eq!(redact_cli_args("client -P secret", &[]), "client -P secret");
+    }
+
+    #[test]
+    fn requoted_arguments_parse_back() {
+        let redacted = redact(r#"client -L "tcp://8080:local host:80" 'it'"'"'s' C:\certs"#);
+        assert_eq!(
+            parse_cli_args(&redacted),
+            vec![
+                "client",
+                "-L",
+                "tcp://8080:local host:80",
+                "it's",
+                r"C:\certs"
+            ]
+        );
+    }
+}
+
+mod log_rotation {
+    use super::*;
+    use wstunnel_manager::backend::logs::{
+        LogRotation, RotatingLogWriter, is_log_file, rotated_log_path,
+    };
+
+    #[test]
+    fn recognizes_rotated_log_files() {
+        assert!(is_log_file(&PathBuf::from("logs/tunnel-1-20240101.log")));
+        assert!(is_log_file(&PathBuf::from("logs/tunnel-1-20240101.log.1")));
+        assert!(is_log_file(&PathBuf::from("logs/tunnel-1-20240101.log.12")));
+        assert!(!is_log_file(&PathBuf::from("logs/tunnel.txt")));
+        assert!(!is_log_file(&PathBuf::from("logs/tunnel.log.bak")));
+        assert!(!is_log_file(&PathBuf::from("logs/.log")));
+    }
+
+    #[test]
+    fn settings_validation() {
+        let settings = GlobalSettings {
+            max_log_size_mb: Some(0),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = GlobalSettings {
+            max_log_size_mb: Some(10),
+            max_log_files: 0,
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = GlobalSettings {
+            max_log_size_mb: Some(10),
+            max_log_files: 3,
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.log_rotation(),
+            Some(LogRotation {
+                max_bytes: 10 * 1024 * 1024,
+                max_files: 3,
+            })
+        );
+        assert!(GlobalSettings::default().log_rotation().is_none());
+    }
+
+    #[test]
+    fn rotates_and_keeps_max_files() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let log_path = temp_dir.join("tunnel.log");
+
+        runtime.block_on(async {
+            let rotation = LogRotation {
+                max_bytes: 10,
+                max_files: 2,
+            };
+            let mut writer = RotatingLogWriter::open(log_path.clone(), Some(rotation))
+                .await
+                .unwrap();
+            for line in ["first-1\n", "second-2\n", "third-3\n", "fourth-4\n"] {
+                writer.write_line(line).await.unwrap();
+            }
+            writer.flush().await.unwrap();
+        });
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(log_path.clone()), "fourth-4\n");
+        assert_eq!(read(rotated_log_path(&log_path, 1)), "third-3\n");
+        assert_eq!(read(rotated_log_path(&log_path, 2)), "second-2\n");
+        assert!(!rotated_log_path(&log_path, 3).exists());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod log_compression {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use wstunnel_manager::backend::config::{cleanup_logs_now, maintain_logs};
+    use wstunnel_manager::backend::logs::{
+        compressed_log_path, is_compressed_log_file, read_log_file, resolve_log_path,
+    };
+
+    fn create_temp_test_dir() -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        temp_dir
+    }
+
+    fn write_log_with_age(path: &PathBuf, contents: &str, age_days: u64) {
+        std::fs::write(path, contents).unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60))
+            .unwrap();
+    }
+
+    #[test]
+    fn validates_compress_days() {
+        let settings = GlobalSettings {
+            log_compress_days: Some(0),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = GlobalSettings {
+            log_compress_days: Some(7),
+            log_retention_days: Some(7),
+            ..GlobalSettings::default()
+        };
+        let result = settings.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("must be less than")
+        );
+
+        let settings = GlobalSettings {
+            log_compress_days: Some(3),
+            log_retention_days: Some(30),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn compresses_then_deletes_by_age() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = create_temp_test_dir();
+
+        let fresh_log = temp_dir.join("fresh-1-20240101.log");
+        let old_log = temp_dir.join("old-2-20240101.log");
+        let ancient_log = temp_dir.join("ancient-3-20240101.log");
+        write_log_with_age(&fresh_log, "fresh\n", 0);
+        write_log_with_age(&old_log, "old line\n", 5);
+        write_log_with_age(&ancient_log, "ancient\n", 40);
+
+        let settings = GlobalSettings {
+            log_directory: temp_dir.clone(),
+            log_compress_days: Some(3),
+            log_retention_days: Some(30),
+            ..GlobalSettings::default()
+        };
+        let config = Config {
+            global: settings,
+            ..Config::default()
+        };
+        runtime
+            .block_on(maintain_logs(&config, &HashSet::new()))
+            .unwrap();
+
+        assert!(fresh_log.exists());
+        assert!(!old_log.exists());
+        assert!(!ancient_log.exists());
+        assert!(!compressed_log_path(&ancient_log).exists());
+
+        let compressed = compressed_log_path(&old_log);
+        assert!(is_compressed_log_file(&compressed));
+        assert_eq!(read_log_file(&compressed).unwrap(), "old line\n");
+        assert_eq!(resolve_log_path(&old_log), Some(compressed));
+        assert_eq!(resolve_log_path(&fresh_log), Some(fresh_log.clone()));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn cleans_up_now_without_a_retention_period() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = create_temp_test_dir();
+
+        let fresh_log = temp_dir.join("fresh-1-20240101.log");
+        let old_log = temp_dir.join("old-2-20240101.log");
+        let ancient_log = temp_dir.join("ancient-3-20240101.log");
+        write_log_with_age(&fresh_log, "fresh\n", 0);
+        write_log_with_age(&old_log, "old\n", 5);
+        write_log_with_age(&ancient_log, "ancient\n", 40);
+
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: temp_dir.clone(),
+                ..GlobalSettings::default()
+            },
+            ..Config::default()
+        };
+        assert!(
+            runtime
+                .block_on(cleanup_logs_now(&config, 0, &HashSet::new()))
+                .is_err()
+        );
+        assert!(ancient_log.exists());
+
+        assert_eq!(
+            runtime
+                .block_on(cleanup_logs_now(&config, 3, &HashSet::new()))
+                .unwrap(),
+            2
+        );
+        assert!(fresh_log.exists());
+        assert!(!old_log.exists());
+        assert!(!ancient_log.exists());
+        assert_eq!(
+            runtime
+                .block_on(cleanup_logs_now(&config, 3, &HashSet::new()))
+                .unwrap(),
+            0
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod tunnel_log_directory {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use wstunnel_manager::backend::config::maintain_logs;
+
+    fn create_temp_test_dir() -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        temp_dir
+    }
+
+    fn tunnel_with_log_directory(log_directory: Option<PathBuf>) -> TunnelEntry {
+        TunnelEntry {
+            log_directory,
+            ..tunnel("noisy")
+        }
+    }
+
+    #[test]
+    fn override_takes_precedence() {
+        let global = GlobalSettings {
+            log_directory: PathBuf::from("./logs"),
+            ..GlobalSettings::default()
+        };
+
+        let tunnel = tunnel_with_log_directory(None);
+        assert_eq!(
+            tunnel.effective_log_directory(&global),
+            PathBuf::from("./logs")
+        );
+
+        let tunnel = tunnel_with_log_directory(Some(PathBuf::from("/data/logs")));
+        assert_eq!(
+            tunnel.effective_log_directory(&global),
+            PathBuf::from("/data/logs")
+        );
+    }
+
+    #[test]
+    fn accepts_missing_directory_under_writable_parent() {
+        let temp_dir = create_temp_test_dir();
+
+        let tunnel = tunnel_with_log_directory(Some(temp_dir.join("not").join("yet")));
+        assert!(tunnel.path_errors(&temp_dir).is_empty());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn rejects_file_as_directory() {
+        let temp_dir = create_temp_test_dir();
+        let file_path = temp_dir.join("not_a_dir");
+        std::fs::write(&file_path, "").unwrap();
+
+        let tunnel = tunnel_with_log_directory(Some(file_path));
+        let errors = tunnel.path_errors(&temp_dir);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].1.contains("not writable"));
+        // Left to add, edit and start: an unwritable directory must not stop
+        // the config from loading.
+        assert!(tunnel.validate().is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn log_directories_are_deduplicated() {
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: PathBuf::from("./logs"),
+                ..GlobalSettings::default()
+            },
+            tunnels: vec![
+                tunnel_with_log_directory(Some(PathBuf::from("/data/logs"))),
+                tunnel_with_log_directory(Some(PathBuf::from("/data/logs"))),
+                tunnel_with_log_directory(Some(PathBuf::from("./logs"))),
+                tunnel_with_log_directory(None),
+            ],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.log_directories(),
+            vec![PathBuf::from("./logs"), PathBuf::from("/data/logs")]
+        );
+    }
+
+    #[test]
+    fn retention_cleans_override_directories() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let global_dir = create_temp_test_dir();
+        let tunnel_dir = create_temp_test_dir();
+
+        let old_log = tunnel_dir.join("noisy-1-20240101.log");
+        std::fs::write(&old_log, "old\n").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&old_log)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(10 * 24 * 60 * 60))
+            .unwrap();
+
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: global_dir.clone(),
+                log_retention_days: Some(7),
+                ..GlobalSettings::default()
+            },
+            tunnels: vec![tunnel_with_log_directory(Some(tunnel_dir.clone()))],
+            ..Config::default()
+        };
+        runtime
+            .block_on(maintain_logs(&config, &HashSet::new()))
+            .unwrap();
+
+        assert!(!old_log.exists());
+
+        std::fs::remove_dir_all(&global_dir).ok();
+        std::fs::remove_dir_all(&tunnel_dir).ok();
+    }
+
+    #[test]
+    fn free_space_is_read_for_directories_not_created_yet() {
+        use wstunnel_manager::backend::logs::available_space;
+
+        let temp_dir = create_temp_test_dir();
+        let existing = available_space(&temp_dir);
+        assert!(existing.is_some_and(|bytes| bytes > 0));
+        // Falls back to the nearest existing parent.
+        assert!(available_space(&temp_dir.join("not").join("yet")).is_some());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod log_tail {
+    use super::*;
+    use wstunnel_manager::backend::logs::{
+        LineRateLimiter, StderrBuffer, compress_log_file, tail_lines,
+    };
+
+    #[test]
+    fn stderr_buffer_keeps_the_latest_lines() {
+        let mut buffer = StderrBuffer::new(2);
+        assert_eq!(buffer.contents(), "");
+        for line in ["first", "second", "third"] {
+            buffer.push(line.to_string());
+        }
+        assert_eq!(buffer.contents(), "second\nthird\n");
+    }
+
+    #[test]
+    fn stderr_buffer_size_is_validated() {
+        let settings = GlobalSettings {
+            stderr_buffer_lines: 0,
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn rate_limiter_drops_and_counts_lines_over_the_limit() {
+        let mut limiter = LineRateLimiter::new(Some(2));
+        let start = std::time::Instant::now();
+        assert!(limiter.admit(start));
+        assert!(limiter.admit(start));
+        assert!(!limiter.admit(start));
+        assert!(!limiter.admit(start + std::time::Duration::from_millis(500)));
+
+        let next_window = start + std::time::Duration::from_secs(1);
+        assert!(limiter.admit(next_window));
+        assert_eq!(limiter.take_dropped(), 2);
+        assert_eq!(limiter.take_dropped(), 0);
+
+        let mut unlimited = LineRateLimiter::new(None);
+        assert!((0..10_000).all(|_| unlimited.admit(start)));
+        assert_eq!(unlimited.take_dropped(), 0);
+
+        let settings = GlobalSettings {
+            log_max_lines_per_second: Some(0),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    fn create_temp_test_dir() -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn returns_last_lines_across_chunks() {
+        let temp_dir = create_temp_test_dir();
+        let log_path = temp_dir.join("tail-1-20240101.log");
+        let contents: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&log_path, &contents).unwrap();
+
+        let lines = tail_lines(&log_path, 3).unwrap();
+        assert_eq!(lines, vec!["line 4997", "line 4998", "line 4999"]);
+
+        let lines = tail_lines(&log_path, 2000).unwrap();
+        assert_eq!(lines.len(), 2000);
+        assert_eq!(lines[0], "line 3000");
+
+        assert!(tail_lines(&log_path, 0).unwrap().is_empty());
+        assert_eq!(tail_lines(&log_path, 10_000).unwrap().len(), 5000);
+
+        compress_log_file(&log_path).unwrap();
+        let compressed = temp_dir.join("tail-1-20240101.log.gz");
+        let lines = tail_lines(&compressed, 2).unwrap();
+        assert_eq!(lines, vec!["line 4998", "line 4999"]);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn handles_missing_trailing_newline_and_empty_files() {
+        let temp_dir = create_temp_test_dir();
+        let log_path = temp_dir.join("partial.log");
+
+        std::fs::write(&log_path, "").unwrap();
+        assert!(tail_lines(&log_path, 5).unwrap().is_empty());
+
+        std::fs::write(&log_path, "a\nb\nc").unwrap();
+        assert_eq!(tail_lines(&log_path, 2).unwrap(), vec!["b", "c"]);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn never_started_tunnel_has_no_logs() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = create_temp_test_dir();
+
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            temp_dir.join("tail_test.yaml"),
+            PathBuf::from("wstunnel"),
+        )
+        .unwrap();
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("never-started")))
+            .unwrap();
+
+        assert!(backend.read_recent_logs(id, 10).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod combined_logs {
+    use super::*;
+    use wstunnel_manager::backend::logs::{LogLevel, LogStream, TaggedLogLine, merge_logs};
+
+    fn tail(id: TunnelId, tag: &str, lines: &[&str]) -> Vec<TaggedLogLine> {
+        lines
+            .iter()
+            .map(|line| TaggedLogLine::parse(id, tag, line))
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_monitor_line_format() {
+        let id = TunnelId::new();
+        let line = TaggedLogLine::parse(id, "web", "[2024-01-01T12:00:00.000+02:00] [STDERR] oops");
+        assert_eq!(line.stream, Some(LogStream::Stderr));
+        assert_eq!(
+            line.timestamp.unwrap().to_rfc3339(),
+            "2024-01-01T12:00:00+02:00"
+        );
+
+        let plain = TaggedLogLine::parse(id, "web", "continuation of a long message");
+        assert_eq!(plain.timestamp, None);
+        assert_eq!(plain.stream, None);
+        assert_eq!(plain.line, "continuation of a long message");
+    }
+
+    #[test]
+    fn merges_by_time_across_offsets() {
+        let (a, b) = (TunnelId::new(), TunnelId::new());
+        let merged = merge_logs(vec![
+            tail(
+                a,
+                "a",
+                &[
+                    "[2024-01-01T10:00:00.000Z] [STDOUT] a1",
+                    "a1 continued",
+                    "[2024-01-01T10:00:02.000Z] [STDOUT] a2",
+                ],
+            ),
+            // 11:00:01+01:00 is 10:00:01 UTC.
+            tail(b, "b", &["[2024-01-01T11:00:01.000+01:00] [STDERR] b1"]),
+        ]);
+
+        let order: Vec<&str> = merged
+            .iter()
+            .map(|line| line.line.rsplit(' ').next().unwrap())
+            .collect();
+        assert_eq!(order, vec!["a1", "continued", "b1", "a2"]);
+        assert_eq!(merged[2].tag, "b");
+        assert_eq!(merged[2].tunnel_id, b);
+    }
+
+    #[test]
+    fn mock_backend_combines_running_tunnels() {
+        use wstunnel_manager::backend::mock_backend::MockBackend;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let mut backend = MockBackend::new(runtime.handle().clone(), temp_dir.join("config.yaml"));
+
+        let mut ids = Vec::new();
+        for tag in ["first", "second", "idle"] {
+            let id = runtime.block_on(backend.add_tunnel(tunnel(tag))).unwrap();
+            ids.push(id);
+        }
+        runtime.block_on(backend.start_tunnel(ids[0])).unwrap();
+        runtime.block_on(backend.start_tunnel(ids[1])).unwrap();
+
+        let lines = backend.read_combined_logs(3);
+        assert_eq!(lines.len(), 6);
+        assert!(
+            lines
+                .iter()
+                .all(|line| line.stream == Some(LogStream::Stdout))
+        );
+        assert!(!lines.iter().any(|line| line.tag == "idle"));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn detects_wstunnel_log_levels() {
+        let id = TunnelId::new();
+        let parse = |line: &str| TaggedLogLine::parse(id, "web", line);
+
+        let warn = parse(
+            "[2024-01-01T12:00:00.000+02:00] [STDOUT] 2024-01-01T10:00:00.123Z  WARN wstunnel::tunnel: retrying",
+        );
+        assert_eq!(warn.level, Some(LogLevel::Warn));
+        assert!(!warn.is_error());
+
+        let error = parse(
+            "[2024-01-01T12:00:00.000+02:00] [STDOUT] 2024-01-01T10:00:00.123Z ERROR wstunnel: connection refused",
+        );
+        assert_eq!(error.level, Some(LogLevel::Error));
+        assert!(error.is_error());
+
+        // A message that only mentions a level further in isn't one.
+        let info = parse("[2024-01-01T12:00:00.000+02:00] [STDOUT] listening, log level is ERROR");
+        assert_eq!(info.level, None);
+        assert!(!info.is_error());
+
+        // Unlevelled stderr output counts as an error, levelled output by its level.
+        let panic = parse("[2024-01-01T12:00:00.000+02:00] [STDERR] thread 'main' panicked");
+        assert_eq!(panic.level, None);
+        assert!(panic.is_error());
+        let stderr_info =
+            parse("[2024-01-01T12:00:00.000+02:00] [STDERR] 2024-01-01T10:00:00Z  INFO started");
+        assert_eq!(stderr_info.level, Some(LogLevel::Info));
+        assert!(!stderr_info.is_error());
+
+        assert_eq!(
+            parse("DEBUG without the monitor prefix").level,
+            Some(LogLevel::Debug)
+        );
+    }
+
+    #[test]
+    fn errors_only_filter_keeps_error_lines() {
+        use wstunnel_manager::ui::state::{CombinedLogsState, StreamFilter};
+
+        let id = TunnelId::new();
+        let state = CombinedLogsState {
+            lines: tail(
+                id,
+                "web",
+                &[
+                    "[2024-01-01T12:00:00.000+02:00] [STDOUT] 2024-01-01T10:00:00Z  INFO up",
+                    "[2024-01-01T12:00:01.000+02:00] [STDOUT] 2024-01-01T10:00:01Z ERROR down",
+                    "[2024-01-01T12:00:02.000+02:00] [STDERR] panicked",
+                ],
+            ),
+            stream_filter: StreamFilter::Errors,
+            ..CombinedLogsState::default()
+        };
+        let shown: Vec<&str> = state
+            .visible_lines()
+            .iter()
+            .map(|line| line.line.as_str())
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                "[2024-01-01T12:00:01.000+02:00] [STDOUT] 2024-01-01T10:00:01Z ERROR down",
+                "[2024-01-01T12:00:02.000+02:00] [STDERR] panicked",
+            ]
+        );
+    }
+
+    #[test]
+    fn search_highlights_and_steps_through_matches() {
+        use wstunnel_manager::backend::logs::LogSearch;
+        use wstunnel_manager::ui::state::CombinedLogsState;
+
+        let id = TunnelId::new();
+        let mut state = CombinedLogsState {
+            lines: tail(
+                id,
+                "web",
+                &[
+                    "connection refused",
+                    "listening on 127.0.0.1:8080",
+                    "Connection reset by peer",
+                ],
+            ),
+            ..CombinedLogsState::default()
+        };
+
+        state.set_search(LogSearch {
+            pattern: "connection".to_string(),
+            ..LogSearch::default()
+        });
+        assert_eq!(state.search_matches(), [0, 2]);
+        assert_eq!(state.step_match(true), Some(0));
+        assert_eq!(state.step_match(true), Some(2));
+        assert_eq!(state.step_match(true), Some(0));
+        assert_eq!(state.step_match(false), Some(2));
+
+        state.set_search(LogSearch {
+            case_sensitive: true,
+            ..state.search.clone()
+        });
+        assert_eq!(state.search_matches(), [0]);
+
+        state.set_search(LogSearch {
+            pattern: r"\d+\.\d+".to_string(),
+            ..state.search.clone()
+        });
+        assert!(state.search_matches().is_empty());
+        state.set_search(LogSearch {
+            regex: true,
+            ..state.search.clone()
+        });
+        assert_eq!(state.search_matches(), [1]);
+
+        state.set_search(LogSearch {
+            pattern: "(".to_string(),
+            ..state.search.clone()
+        });
+        assert!(state.matcher.is_none());
+        assert!(
+            state
+                .search_error
+                .unwrap()
+                .contains("Invalid search pattern")
+        );
+    }
+}
+
+mod log_line_format {
+    use super::*;
+    use wstunnel_manager::backend::logs::{LogLineFormat, LogStream, TaggedLogLine};
+
+    fn noon() -> chrono::DateTime<chrono::Utc> {
+        "2024-01-01T12:00:00.250Z".parse().unwrap()
+    }
+
+    #[test]
+    fn default_matches_the_original_format() {
+        let line = GlobalSettings::default().log_line_format().format_at(
+            noon(),
+            "web",
+            LogStream::Stdout,
+            "hello",
+        );
+        let expected_ts = noon()
+            .with_timezone(&chrono::Local)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        assert_eq!(line, format!("[{}] [STDOUT] hello", expected_ts));
+
+        // The combined log view still understands it.
+        let parsed = TaggedLogLine::parse(TunnelId::new(), "web", &line);
+        assert_eq!(parsed.timestamp.unwrap(), noon());
+        assert_eq!(parsed.stream, Some(LogStream::Stdout));
+    }
+
+    #[test]
+    fn utc_and_custom_prefix() {
+        let settings = GlobalSettings {
+            log_timestamp_utc: true,
+            log_line_prefix: Some("{ts} {tag}/{stream}: ".to_string()),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings
+                .log_line_format()
+                .format_at(noon(), "{odd} tag", LogStream::Stderr, "oops"),
+            "2024-01-01T12:00:00.250Z {odd} tag/STDERR: oops"
+        );
+
+        let format = LogLineFormat {
+            utc: true,
+            prefix: None,
+        };
+        assert_eq!(
+            format.format_at(noon(), "web", LogStream::Stdout, "x"),
+            "[2024-01-01T12:00:00.250Z] [STDOUT] x"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let settings = GlobalSettings {
+            log_line_prefix: Some("{ts} {level} ".to_string()),
+            ..GlobalSettings::default()
+        };
+        let error = settings.validate().unwrap_err().to_string();
+        assert!(error.contains("{level}"), "{}", error);
+
+        let settings = GlobalSettings {
+            log_line_prefix: Some("{ts} { unclosed".to_string()),
+            ..GlobalSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+    }
+}
+
+mod log_lookup {
+    use super::*;
+    use wstunnel_manager::backend::logs::{
+        LogSearch, compress_log_file, grep_files, latest_log_for, log_history_for,
+    };
+    use wstunnel_manager::backend::process::log_name;
+
+    fn create_temp_test_dir() -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn finds_the_newest_log_of_a_tunnel() {
+        let temp_dir = create_temp_test_dir();
+        for name in [
+            "web-100-20240101_120000.log",
+            "web-101-20240301_120000.log.gz",
+            "web-102-20240201_120000.log",
+            "web-api-103-20250101_120000.log",
+            "web-104-20260101_120000.txt",
+            "web-notes.log",
+        ] {
+            std::fs::write(temp_dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            latest_log_for(&temp_dir, "web"),
+            Some(temp_dir.join("web-101-20240301_120000.log.gz"))
+        );
+        assert_eq!(
+            latest_log_for(&temp_dir, "web-api"),
+            Some(temp_dir.join("web-api-103-20250101_120000.log"))
+        );
+        assert_eq!(latest_log_for(&temp_dir, "db"), None);
+        assert_eq!(latest_log_for(&temp_dir.join("missing"), "web"), None);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn backend_falls_back_to_logs_from_earlier_runs() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = create_temp_test_dir();
+        let config_path = temp_dir.join("lookup_test.yaml");
+        let log_dir = temp_dir.join("logs");
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: log_dir.clone(),
+                ..GlobalSettings::default()
+            },
+            ..Config::default()
+        };
+        std::fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+        let mut backend = BackendState::new(
+            runtime.handle().clone(),
+            config_path,
+            PathBuf::from("wstunnel"),
+        )
+        .unwrap();
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("old: run")))
+            .unwrap();
+        assert_eq!(backend.get_log_path(id), None);
+
+        let earlier = log_dir.join(format!(
+            "{}-42-20240101_000000.log",
+            log_name(id, "old: run")
+        ));
+        std::fs::write(&earlier, "").unwrap();
+        assert_eq!(backend.get_log_path(id), Some(earlier));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn history_includes_rotated_and_compressed_logs_oldest_first() {
+        let temp_dir = create_temp_test_dir();
+        let write = |name: &str, contents: &str| {
+            let path = temp_dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let newest = write("web-2-20240201_120000.log", "timeout: newest\n");
+        write("web-2-20240201_120000.log.1", "Timeout: rotated once\n");
+        write("web-2-20240201_120000.log.2", "timeout: rotated twice\n");
+        let first_run = write("web-1-20240101_120000.log", "timeout: first run\nok\n");
+        compress_log_file(&first_run).unwrap();
+        write("web-api-3-20250101_120000.log", "timeout: other tunnel\n");
+
+        assert_eq!(
+            log_history_for(&temp_dir, "web"),
+            [
+                temp_dir.join("web-1-20240101_120000.log.gz"),
+                temp_dir.join("web-2-20240201_120000.log.2"),
+                temp_dir.join("web-2-20240201_120000.log.1"),
+                newest.clone(),
+            ]
+        );
+        assert_eq!(latest_log_for(&temp_dir, "web"), Some(newest));
+
+        let files = log_history_for(&temp_dir, "web");
+        let search = LogSearch {
+            pattern: "timeout".to_string(),
+            ..LogSearch::default()
+        };
+        assert_eq!(
+            grep_files(&files, &search, 100).unwrap(),
+            [
+                "timeout: first run",
+                "timeout: rotated twice",
+                "Timeout: rotated once",
+                "timeout: newest"
+            ]
+        );
+        // Only the newest matches are kept.
+        assert_eq!(
+            grep_files(&files, &search, 2).unwrap(),
+            ["Timeout: rotated once", "timeout: newest"]
+        );
+        let search = LogSearch {
+            pattern: "^timeout: (first|newest)".to_string(),
+            case_sensitive: true,
+            regex: true,
+        };
+        assert_eq!(
+            grep_files(&files, &search, 100).unwrap(),
+            ["timeout: first run", "timeout: newest"]
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn measures_log_files_and_directories() {
+        use wstunnel_manager::backend::logs::{log_directory_size, log_file_info};
+
+        let temp_dir = create_temp_test_dir();
+        let other_dir = temp_dir.join("other");
+        std::fs::create_dir_all(&other_dir).unwrap();
+        let log = temp_dir.join("web_20240101.log");
+        std::fs::write(&log, "x".repeat(100)).unwrap();
+        std::fs::write(temp_dir.join("web_20231231.log.1"), "x".repeat(20)).unwrap();
+        std::fs::write(other_dir.join("db_20240101.log.gz"), "x".repeat(5)).unwrap();
+        std::fs::write(temp_dir.join("notes.txt"), "x".repeat(1000)).unwrap();
+
+        let info = log_file_info(&log).unwrap();
+        assert_eq!(info.size_bytes, 100);
+        assert!(log_file_info(&temp_dir.join("missing.log")).is_none());
+
+        let directories = vec![temp_dir.clone(), other_dir, temp_dir.join("not-created")];
+        assert_eq!(log_directory_size(&directories), 125);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod config_formats {
+    use super::*;
+    use wstunnel_manager::backend::config::{ConfigFormat, load_config, save_config};
+
+    fn sample_config() -> Config {
+        Config {
+            version: CURRENT_CONFIG_VERSION,
+            global: GlobalSettings {
+                log_directory: PathBuf::from("/var/log/wstunnel"),
+                log_retention_days: Some(14),
+                max_log_size_mb: Some(20),
+                ..GlobalSettings::default()
+            },
+            tunnels: vec![
+                TunnelEntry {
+                    autostart: true,
+                    env: HashMap::from([("TOKEN".to_string(), "secret".to_string())]),
+                    ..TunnelEntry::new(
+                        "client-tunnel",
+                        TunnelMode::Client,
+                        "client -L tcp://8080:localhost:80 ws://example.com",
+                    )
+                },
+                TunnelEntry::new(
+                    "server-tunnel",
+                    TunnelMode::Server,
+                    "server ws://0.0.0.0:8080",
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn schema_covers_every_serialized_field() {
+        let schema = wstunnel_manager::backend::config::config_schema();
+        let definition = |name: &str| schema["$defs"][name]["properties"].clone();
+        let keys = |value: &serde_json::Value| {
+            value
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let mut config = sample_config();
+        config.global.theme = Some("Dark".to_string());
+        config.tunnels[0].group = Some("Web".to_string());
+        config.tunnels[0].health_check = Some(HealthCheck {
+            target: "localhost:8080".to_string(),
+            interval_secs: 10,
+        });
+        config.tunnels[0].created_at = Some(Timestamp::now());
+        let value = serde_json::to_value(&config).unwrap();
+
+        for key in keys(&value) {
+            assert!(schema["properties"].get(&key).is_some(), "config.{}", key);
+        }
+        let global = definition("GlobalSettings");
+        for key in keys(&value["global"]) {
+            assert!(global.get(&key).is_some(), "global.{}", key);
+        }
+        let tunnel = definition("TunnelEntry");
+        for key in keys(&value["tunnels"][0]) {
+            assert!(tunnel.get(&key).is_some(), "tunnels.{}", key);
+        }
+        assert!(tunnel.get("runtime_state").is_none());
+
+        let required = schema["$defs"]["TunnelEntry"]["required"]
+            .as_array()
+            .unwrap();
+        assert!(required.contains(&"tag".into()));
+        assert!(!required.contains(&"group".into()));
+        assert_eq!(
+            schema["$defs"]["TunnelMode"]["enum"],
+            serde_json::json!(["client", "server"])
+        );
+        assert_eq!(schema["$defs"]["Timestamp"]["format"], "date-time");
+    }
+
+    fn assert_same_config(a: &Config, b: &Config) {
+        assert_eq!(a.version, b.version);
+        assert_eq!(a.global.log_directory, b.global.log_directory);
+        assert_eq!(a.global.log_retention_days, b.global.log_retention_days);
+        assert_eq!(a.global.max_log_size_mb, b.global.max_log_size_mb);
+        assert_eq!(a.tunnels.len(), b.tunnels.len());
+        for (left, right) in a.tunnels.iter().zip(&b.tunnels) {
+            assert_eq!(left.id, right.id);
+            assert_eq!(left.tag, right.tag);
+            assert_eq!(left.mode, right.mode);
+            assert_eq!(left.cli_args, right.cli_args);
+            assert_eq!(left.autostart, right.autostart);
+            assert_eq!(left.env, right.env);
+        }
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(&PathBuf::from("a.yaml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(&PathBuf::from("a.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(&PathBuf::from("a.JSON")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(&PathBuf::from("a.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(&PathBuf::from("config")),
+            ConfigFormat::Yaml
+        );
+    }
+
+    #[test]
+    fn round_trip_each_format() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        let config = sample_config();
+
+        for file_name in ["config.yaml", "config.yml", "config.json", "config.toml"] {
+            let path = temp_dir.join(file_name);
+            let loaded = runtime.block_on(async {
+                save_config(&path, &config).await.unwrap();
+                load_config(&path).await.unwrap()
+            });
+            assert_same_config(&config, &loaded);
+
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let value = ConfigFormat::from_path(&path)
+                .parse_value(&contents)
+                .unwrap();
+            let parsed: Config = serde_yaml::from_value(value).unwrap();
+            assert_same_config(&config, &parsed);
+        }
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn corrupted_file_is_backed_up() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let path = temp_dir.join("config.json");
+        std::fs::write(&path, "{ not valid json").unwrap();
+
+        let result = runtime.block_on(load_config(&path));
+        assert!(result.is_err());
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.join("config.json.bak")).unwrap(),
+            "{ not valid json"
+        );
+
+        let replaced = std::fs::read_to_string(&path).unwrap();
+        assert!(ConfigFormat::Json.parse_value(&replaced).is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn relative_paths_follow_the_config_file() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(temp_dir.join("bin")).unwrap();
+        let binary = temp_dir.join("bin").join("wstunnel");
+        std::fs::write(&binary, "").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let path = temp_dir.join("config.yaml");
+        let on_disk =
+            "global:\n  log_directory: ./logs\n  wstunnel_binary_path: bin/wstunnel\ntunnels: []\n";
+        std::fs::write(&path, on_disk).unwrap();
+
+        let mut config = runtime.block_on(load_config(&path)).unwrap();
+        assert_eq!(config.global.log_directory, temp_dir.join("logs"));
+        assert_eq!(config.global.wstunnel_binary_path, Some(binary));
+
+        config.global.log_retention_days = Some(7);
+        runtime.block_on(save_config(&path, &config)).unwrap();
+        let value = ConfigFormat::Yaml
+            .parse_value(&std::fs::read_to_string(&path).unwrap())
+            .unwrap();
+        let saved: Config = serde_yaml::from_value(value).unwrap();
+        assert_eq!(saved.global.log_directory, PathBuf::from("./logs"));
+        assert_eq!(
+            saved.global.wstunnel_binary_path,
+            Some(PathBuf::from("bin/wstunnel"))
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn relative_tunnel_log_directories_follow_the_config_file() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("config.yaml");
+        let config = Config {
+            tunnels: vec![TunnelEntry {
+                log_directory: Some(PathBuf::from("noisy-logs")),
+                ..tunnel("noisy")
+            }],
+            ..Config::default()
+        };
+        std::fs::write(&path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+        let config = runtime.block_on(load_config(&path)).unwrap();
+        assert_eq!(
+            config.tunnels[0].effective_log_directory(&config.global),
+            temp_dir.join("noisy-logs")
+        );
+
+        runtime.block_on(save_config(&path, &config)).unwrap();
+        let value = ConfigFormat::Yaml
+            .parse_value(&std::fs::read_to_string(&path).unwrap())
+            .unwrap();
+        let saved: Config = serde_yaml::from_value(value).unwrap();
+        assert_eq!(
+            saved.tunnels[0].log_directory,
+            Some(PathBuf::from("noisy-logs"))
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn tunnel_snippets_round_trip_with_a_fresh_id_and_redactions() {
+        use wstunnel_manager::backend::config::{
+            is_tunnel_snippet, parse_tunnel_snippet, tunnel_snippet,
+        };
+
+        let mut tunnel = sample_config().tunnels.remove(0);
+        tunnel.cli_args =
+            "client -L tcp://8080:localhost:80 --http-upgrade-credentials user:pass ws://example.com"
+                .to_string();
+        tunnel.depends_on = vec![TunnelId::new()];
+        let sensitive_flags = GlobalSettings::default().sensitive_flags;
+
+        for format in [ConfigFormat::Yaml, ConfigFormat::Json, ConfigFormat::Toml] {
+            let snippet = tunnel_snippet(&tunnel, &sensitive_flags, format).unwrap();
+            assert!(!snippet.contains("user:pass"), "{}", snippet);
+            assert!(!snippet.contains("secret"), "{}", snippet);
+            assert!(!snippet.contains(&tunnel.id.to_string()), "{}", snippet);
+            assert!(is_tunnel_snippet(&snippet, format));
+            assert_eq!(ConfigFormat::guess(&snippet), format, "{}", snippet);
+
+            let imported = parse_tunnel_snippet(&snippet, format).unwrap();
+            assert_ne!(imported.id, tunnel.id);
+            assert_eq!(imported.tag, tunnel.tag);
+            assert!(imported.autostart);
+            assert!(imported.depends_on.is_empty());
+            assert!(imported.cli_args.contains("--http-upgrade-credentials ***"));
+            assert_eq!(imported.env["TOKEN"], "***");
+        }
+
+        let full_config = ConfigFormat::Yaml.serialize(&sample_config()).unwrap();
+        assert!(!is_tunnel_snippet(&full_config, ConfigFormat::Yaml));
+        let error = parse_tunnel_snippet("tag: only a tag", ConfigFormat::Yaml).unwrap_err();
+        assert!(format!("{:#}", error).contains("Not a tunnel snippet"));
+    }
+}
+
+mod yaml_comments {
+    use super::*;
+    use wstunnel_manager::backend::config::{load_config, save_config};
+    use wstunnel_manager::backend::yaml_comments::carry_over;
+    use wstunnel_manager::constants::CONFIG_HEADER;
+
+    #[test]
+    fn comments_follow_their_entries() {
+        let (web, db) = (TunnelId::new(), TunnelId::new());
+        let previous = format!(
+            "# My tunnels
+version: 2
+# Settings shared by every tunnel
+global:
+  log_directory: ./logs  # next to the config
+  sensitive_flags:
+    # the path prefix is a secret
+    - -P
+tunnels:
+  # Old database tunnel
+  - id: {db}
+    tag: db
+    mode: client
+    cli_args: client ws://db
+  # Production web tunnel
+  - id: {web}
+    tag: web  # do not rename
+    mode: client
+    cli_args: |-
+      client
+      # not a comment
+# end of file
+"
+        );
+
+        let config = Config {
+            global: GlobalSettings {
+                log_directory: PathBuf::from("./logs"),
+                sensitive_flags: vec!["-P".to_string(), "-H".to_string()],
+                ..GlobalSettings::default()
+            },
+            tunnels: vec![TunnelEntry {
+                id: web,
+                ..tunnel("web")
+            }],
+            ..Config::default()
+        };
+        let serialized = serde_yaml::to_string(&config).unwrap();
+        let saved = carry_over(&previous, &serialized);
+
+        assert!(saved.starts_with(&format!("{}\n# My tunnels\nversion: 2\n", CONFIG_HEADER)));
+        assert!(saved.contains("# Settings shared by every tunnel\nglobal:\n"));
+        assert!(saved.contains("  log_directory: ./logs  # next to the config\n"));
+        assert!(saved.contains("  # the path prefix is a secret\n  - -P\n  - -H\n"));
+        assert!(saved.contains(&format!("# Production web tunnel\n- id: {}\n", web)));
+        assert!(saved.contains("  tag: web  # do not rename\n"));
+        assert!(saved.ends_with("# end of file\n"));
+        assert!(!saved.contains("Old database tunnel"));
+        assert!(!saved.contains("not a comment"));
+
+        let reloaded: Config = serde_yaml::from_str(&saved).unwrap();
+        assert_eq!(
+            reloaded.global.sensitive_flags,
+            config.global.sensitive_flags
+        );
+        assert_eq!(reloaded.tunnels[0].tag, "web");
+        assert_eq!(carry_over(&saved, &serialized), saved);
+    }
+
+    #[test]
+    fn saving_keeps_comments_and_adds_the_header_once() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        let path = temp_dir.join("config.yaml");
+        let config = Config {
+            tunnels: vec![tunnel("web")],
+            ..Config::default()
+        };
+        runtime.block_on(save_config(&path, &config)).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with(CONFIG_HEADER));
+
+        let annotated = written.replace("tunnels:", "# Everything we run\ntunnels:");
+        std::fs::write(&path, annotated).unwrap();
+        let mut config = runtime.block_on(load_config(&path)).unwrap();
+        config.tunnels[0].tag = "website".to_string();
+        runtime.block_on(save_config(&path, &config)).unwrap();
+
+        let rewritten = std::fs::read_to_string(&path).unwrap();
+        assert!(rewritten.contains("# Everything we run\ntunnels:\n"));
+        assert!(rewritten.contains("tag: website"));
+        assert_eq!(rewritten.matches(CONFIG_HEADER).count(), 1);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn exports_leave_the_comments_of_the_file_they_replace() {
+        use wstunnel_manager::backend::mock_backend::MockBackend;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let backend = MockBackend::new(runtime.handle().clone(), temp_dir.join("config.yaml"));
+
+        let export_path = temp_dir.join("shared.yaml");
+        std::fs::write(
+            &export_path,
+            "# Notes about something else
+version: 2
+",
+        )
+        .unwrap();
+        runtime
+            .block_on(backend.save_config(&backend.get_config(), &export_path))
+            .unwrap();
+
+        let exported = std::fs::read_to_string(&export_path).unwrap();
+        assert!(!exported.contains("Notes about something else"));
+        assert!(!exported.contains(CONFIG_HEADER));
+        assert!(runtime.block_on(load_config(&export_path)).is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod config_save_retry {
+    use std::cell::Cell;
+    use std::io::{Error, ErrorKind};
+    use wstunnel_manager::backend::config::{is_transient, retry_transient};
+    use wstunnel_manager::constants::CONFIG_SAVE_ATTEMPTS;
+
+    fn run<T>(future: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn transient_errors_are_retried_until_success() {
+        let attempts = Cell::new(0);
+        let result = run(retry_transient("test", || {
+            attempts.set(attempts.get() + 1);
+            let result = match attempts.get() {
+                1 => Err(Error::from(ErrorKind::PermissionDenied)),
+                _ => Ok(attempts.get()),
+            };
+            async move { result }
+        }));
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        let attempts = Cell::new(0);
+        let result: std::io::Result<()> = run(retry_transient("test", || {
+            attempts.set(attempts.get() + 1);
+            async { Err(Error::from(ErrorKind::PermissionDenied)) }
+        }));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(attempts.get(), CONFIG_SAVE_ATTEMPTS);
+    }
+
+    #[test]
+    fn permanent_errors_fail_immediately() {
+        assert!(!is_transient(&Error::from(ErrorKind::StorageFull)));
+        assert!(!is_transient(&Error::from(ErrorKind::NotFound)));
+
+        let attempts = Cell::new(0);
+        let result: std::io::Result<()> = run(retry_transient("test", || {
+            attempts.set(attempts.get() + 1);
+            async { Err(Error::from(ErrorKind::StorageFull)) }
+        }));
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+}
+
+mod config_backups {
+    use super::*;
+    use wstunnel_manager::backend::config::{config_backup_path, save_config};
+    use wstunnel_manager::backend::mock_backend::MockBackend;
+    use wstunnel_manager::constants::CONFIG_BACKUP_COUNT;
+
+    fn tags(backend: &mut MockBackend) -> Vec<String> {
+        backend.list_tunnels().into_iter().map(|t| t.tag).collect()
+    }
+
+    #[test]
+    fn restoring_walks_back_through_saves() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let config_path = temp_dir.join("config.yaml");
+        let mut backend = MockBackend::new(runtime.handle().clone(), config_path.clone());
+
+        runtime
+            .block_on(backend.add_tunnel(tunnel("first")))
+            .unwrap();
+        runtime
+            .block_on(backend.add_tunnel(tunnel("second")))
+            .unwrap();
+        assert!(config_backup_path(&config_path, 2).exists());
+
+        runtime.block_on(backend.restore_config_backup()).unwrap();
+        assert_eq!(tags(&mut backend), vec!["first"]);
+        assert!(!config_backup_path(&config_path, 2).exists());
+
+        // The restored config is what is on disk now, too.
+        let on_disk = MockBackend::new(runtime.handle().clone(), config_path.clone())
+            .get_config()
+            .tunnels
+            .len();
+        assert_eq!(on_disk, 1);
+
+        runtime.block_on(backend.restore_config_backup()).unwrap();
+        assert!(tags(&mut backend).is_empty());
+
+        let error = runtime
+            .block_on(backend.restore_config_backup())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            wstunnel_manager::errors::config::NO_BACKUP
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn ring_is_capped() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let config_path = temp_dir.join("config.yaml");
+
+        for _ in 0..CONFIG_BACKUP_COUNT + 3 {
+            runtime
+                .block_on(save_config(&config_path, &Config::default()))
+                .unwrap();
+        }
+        assert!(config_backup_path(&config_path, CONFIG_BACKUP_COUNT).exists());
+        assert!(!config_backup_path(&config_path, CONFIG_BACKUP_COUNT + 1).exists());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn unparseable_files_are_not_backed_up() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let config_path = temp_dir.join("config.yaml");
+        std::fs::write(&config_path, "tunnels: [not: valid: yaml").unwrap();
+
+        runtime
+            .block_on(save_config(&config_path, &Config::default()))
+            .unwrap();
+        assert!(!config_backup_path(&config_path, 1).exists());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod config_migrations {
+    use super::*;
+    use wstunnel_manager::backend::config::load_config;
+    use wstunnel_manager::backend::migrations::{config_version, migrate};
+
+    const V1_CONFIG: &str = r#"
+version: 1
+global:
+  log_directory: "./logs"
+tunnels:
+  - id: "550e8400-e29b-41d4-a716-446655440000"
+    tag: "client without mode"
+    cli_args: "client -L socks5://127.0.0.1:1080 wss://example.com:443"
+    autostart: true
+  - id: "550e8400-e29b-41d4-a716-446655440001"
+    tag: "server without mode"
+    cli_args: "server wss://0.0.0.0:443"
+    autostart: false
+  - id: "550e8400-e29b-41d4-a716-446655440002"
+    tag: "explicit mode"
+    mode: server
+    cli_args: "server ws://0.0.0.0:8080"
+    autostart: false
+"#;
+
+    #[test]
+    fn missing_version_is_treated_as_v1() {
+        let value: serde_yaml::Value = serde_yaml::from_str("tunnels: []").unwrap();
+        assert_eq!(config_version(&value), 1);
+    }
+
+    #[test]
+    fn v1_to_v2_infers_mode() {
+        let mut value: serde_yaml::Value = serde_yaml::from_str(V1_CONFIG).unwrap();
+        assert_eq!(migrate(&mut value).unwrap(), Some(1));
+
+        let config: Config = serde_yaml::from_value(value).unwrap();
+        assert_eq!(config.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(config.tunnels[0].mode, TunnelMode::Client);
+        assert_eq!(config.tunnels[1].mode, TunnelMode::Server);
+        assert_eq!(config.tunnels[2].mode, TunnelMode::Server);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn current_version_is_not_migrated() {
+        let yaml = format!("version: {}\ntunnels: []\n", CURRENT_CONFIG_VERSION);
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(migrate(&mut value).unwrap(), None);
+    }
+
+    #[test]
+    fn load_config_migrates_backs_up_and_resaves() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("config.yaml");
+        std::fs::write(&path, V1_CONFIG).unwrap();
+
+        let config = runtime.block_on(load_config(&path)).unwrap();
+        assert_eq!(config.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(config.tunnels.len(), 3);
+
+        let backup = std::fs::read_to_string(temp_dir.join("config.yaml.v1.bak")).unwrap();
+        assert_eq!(backup, V1_CONFIG);
+
+        let saved: Config = serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(saved.tunnels[1].mode, TunnelMode::Server);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod backend_errors {
+    use super::*;
+    use wstunnel_manager::backend::error::BackendError;
+    use wstunnel_manager::backend::mock_backend::MockBackend;
+    use wstunnel_manager::errors;
+
+    #[test]
+    fn lifecycle_errors_have_variants_and_unchanged_messages() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let mut backend = MockBackend::new(runtime.handle().clone(), temp_dir.join("config.yaml"));
+
+        let missing = TunnelId::new();
+        let error = runtime.block_on(backend.start_tunnel(missing)).unwrap_err();
+        assert!(matches!(error, BackendError::TunnelNotFound(id) if id == missing));
+        assert_eq!(
+            error.to_string(),
+            errors::tunnel::not_found(&format!("{:?}", missing))
+        );
+
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("errors")))
+            .unwrap();
+
+        let error = runtime.block_on(backend.stop_tunnel(id)).unwrap_err();
+        assert!(matches!(error, BackendError::NotRunning));
+        assert_eq!(error.to_string(), errors::tunnel::NOT_RUNNING);
+
+        runtime.block_on(backend.start_tunnel(id)).unwrap();
+        let error = runtime.block_on(backend.start_tunnel(id)).unwrap_err();
+        assert!(matches!(&error, BackendError::AlreadyRunning(tag) if tag == "errors"));
+        assert_eq!(error.to_string(), errors::tunnel::already_running("errors"));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn variants_survive_a_trip_through_anyhow() {
+        let error: BackendError = anyhow::Error::from(BackendError::PortInUse).into();
+        assert!(matches!(error, BackendError::PortInUse));
+
+        // Context added on top means the caller only gets `Other`, with the
+        // full message kept.
+        let wrapped = anyhow::Error::from(BackendError::DiskFull).context("Saving");
+        let error: BackendError = wrapped.into();
+        assert!(matches!(error, BackendError::Other(_)));
+        assert_eq!(
+            format!("{:#}", error),
+            format!("Saving: {}", errors::disk::FULL)
+        );
+    }
+
+    #[test]
+    fn corrupted_config_is_reported_as_such() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("config.yaml");
+        std::fs::write(&path, "tunnels: [not: valid: yaml").unwrap();
+
+        let error = runtime
+            .block_on(wstunnel_manager::backend::config::load_config(&path))
+            .unwrap_err();
+        assert!(matches!(
+            BackendError::from(error),
+            BackendError::ConfigCorrupted(_)
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod mock_scenarios {
+    use super::*;
+    use std::collections::HashSet;
+    use std::time::Duration;
+    use wstunnel_manager::backend::error::BackendError;
+    use wstunnel_manager::backend::mock_backend::{MockBackend, MockCrash, MockScenario};
+
+    fn mock_with(
+        runtime: &tokio::runtime::Runtime,
+        scenario: MockScenario,
+    ) -> (MockBackend, TunnelId, TunnelId, PathBuf) {
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let mut backend = MockBackend::with_scenario(
+            runtime.handle().clone(),
+            temp_dir.join("config.yaml"),
+            scenario,
+        );
+        let mut add = |tag: &str| runtime.block_on(backend.add_tunnel(tunnel(tag))).unwrap();
+        let (web, db) = (add("web"), add("db"));
+        (backend, web, db, temp_dir)
+    }
+
+    #[test]
+    fn scenario_is_parsed_from_its_env_value() {
+        let scenario = MockScenario::parse("fail-start=web, crash=2.5,unhealthy,bogus");
+        assert!(!scenario.fail_all_starts);
+        assert_eq!(scenario.fail_start_tags, HashSet::from(["web".to_string()]));
+        assert_eq!(
+            scenario.crash,
+            Some(MockCrash::After(Duration::from_millis(2500)))
+        );
+        assert!(scenario.unhealthy);
+
+        let scenario = MockScenario::parse("fail-start,crash");
+        assert!(scenario.fail_all_starts);
+        assert_eq!(scenario.crash, Some(MockCrash::Random));
+        assert!(!scenario.unhealthy);
+    }
+
+    #[test]
+    fn tagged_starts_fail_and_show_as_failed() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, db, temp_dir) =
+            mock_with(&runtime, MockScenario::parse("fail-start=web"));
+
+        let error = runtime.block_on(backend.start_tunnel(web)).unwrap_err();
+        assert!(error.to_string().contains("simulated failure"), "{}", error);
+        assert!(matches!(
+            backend.get_tunnel_status(web),
+            TunnelRuntimeState::Failed { .. }
+        ));
+        runtime.block_on(backend.start_tunnel(db)).unwrap();
+        assert!(backend.is_tunnel_running(db));
+        // db may still be starting, which doesn't count as running yet.
+        let summary = backend.summary();
+        assert_eq!((summary.total, summary.failed), (2, 1));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn running_tunnels_crash_after_the_delay() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, db, temp_dir) = mock_with(
+            &runtime,
+            MockScenario {
+                crash: Some(MockCrash::After(Duration::from_millis(200))),
+                ..MockScenario::default()
+            },
+        );
+        let mut events = backend.subscribe();
+
+        runtime.block_on(backend.start_tunnel(web)).unwrap();
+        runtime.block_on(backend.start_tunnel(db)).unwrap();
+        runtime.block_on(backend.stop_tunnel(db)).unwrap();
+
+        let crashed = runtime.block_on(async {
+            loop {
+                match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                    Ok(Ok(TunnelEvent::Crashed { id, exit_code })) => break (id, exit_code),
+                    Ok(Ok(_)) => continue,
+                    other => panic!("no crash event: {:?}", other),
+                }
+            }
+        });
+        assert_eq!(crashed, (web, Some(1)));
+
+        let statuses: HashMap<TunnelId, TunnelRuntimeState> = backend
+            .list_tunnels()
+            .into_iter()
+            .map(|t| (t.id, t.runtime_state.unwrap()))
+            .collect();
+        assert!(matches!(
+            &statuses[&web],
+            TunnelRuntimeState::Failed {
+                exit_code: Some(1),
+                ..
+            }
+        ));
+        assert!(matches!(statuses[&db], TunnelRuntimeState::Stopped));
+        assert_eq!(backend.get_tunnel_stats(web).unwrap().total_crashes, 1);
+        assert_eq!(backend.get_tunnel_stats(db).unwrap().total_crashes, 0);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn flapping_tunnels_fail_and_are_not_started_as_dependencies() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, db, temp_dir) = mock_with(
+            &runtime,
+            MockScenario {
+                crash: Some(MockCrash::After(Duration::ZERO)),
+                ..MockScenario::default()
+            },
+        );
+        let mut entry = backend.get_tunnel(db).unwrap();
+        entry.depends_on = vec![web];
+        runtime.block_on(backend.edit_tunnel(db, entry)).unwrap();
+
+        let restart = |backend: &mut MockBackend| {
+            runtime.block_on(backend.start_tunnel(web)).unwrap();
+            backend.get_tunnel_stats(web).unwrap()
+        };
+
+        let stats = restart(&mut backend);
+        assert_eq!(stats.restarts_in_window(), 0);
+        assert_eq!(stats.flap_warning(), None);
+        for _ in 0..3 {
+            restart(&mut backend);
+        }
+        let stats = restart(&mut backend);
+        assert_eq!(stats.restarts_in_window(), 4);
+        assert!(stats.flap_warning().unwrap().contains("Restarted 4 times"));
+        assert!(stats.last_crashed_at.is_some());
+        assert!(!stats.is_flapping());
+        assert!(matches!(
+            backend.get_tunnel_status(web),
+            TunnelRuntimeState::Failed { error, .. } if error.contains("exited unexpectedly")
+        ));
+
+        let stats = restart(&mut backend);
+        assert!(stats.is_flapping());
+        assert!(matches!(
+            backend.get_tunnel_status(web),
+            TunnelRuntimeState::Failed { error, .. } if error.contains("too many restarts")
+        ));
+
+        let error = runtime.block_on(backend.start_tunnel(db)).unwrap_err();
+        assert!(error.to_string().contains("too many restarts"));
+        assert!(!backend.is_tunnel_running(db));
+        assert_eq!(backend.get_tunnel_stats(web).unwrap().total_starts, 6);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn tunnels_show_as_starting_before_running() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, _, temp_dir) = mock_with(
+            &runtime,
+            MockScenario {
+                starting: Duration::from_millis(300),
+                ..MockScenario::default()
+            },
+        );
+        let mut events = backend.subscribe();
 
-        let config_path = temp_dir.join("persist_test_config.yaml");
-        let wstunnel_path = get_wstunnel_path();
+        let pid = runtime.block_on(backend.start_tunnel(web)).unwrap();
+        assert!(matches!(
+            backend.get_tunnel_status(web),
+            TunnelRuntimeState::Starting
+        ));
+        let error = runtime.block_on(backend.start_tunnel(web)).unwrap_err();
+        assert!(matches!(error, BackendError::Transitioning(_)));
 
-        let tunnel_id = {
-            let mut backend =
-                BackendState::new(handle.clone(), config_path.clone(), wstunnel_path.clone());
-
-            let tunnel = TunnelEntry {
-                id: TunnelId::new(),
-                tag: "persist-test".to_string(),
-                mode: TunnelMode::Client,
-                cli_args: "client ws://example.com".to_string(),
-                autostart: false,
-                runtime_state: None,
-            };
+        let started = runtime
+            .block_on(async { tokio::time::timeout(Duration::from_secs(5), events.recv()).await });
+        assert!(matches!(
+            started,
+            Ok(Ok(TunnelEvent::Started { id, pid: started_pid })) if id == web && started_pid == pid
+        ));
+        assert!(matches!(
+            backend.get_tunnel_status(web),
+            TunnelRuntimeState::Running { pid: running_pid, .. } if running_pid == pid
+        ));
 
-            let id = backend.add_tunnel(tunnel).unwrap();
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
 
-            let tunnels = backend.list_tunnels();
-            assert_eq!(tunnels.len(), 1);
-            assert_eq!(tunnels[0].tag, "persist-test");
+    fn depend(
+        runtime: &tokio::runtime::Runtime,
+        backend: &mut MockBackend,
+        id: TunnelId,
+        on: TunnelId,
+    ) {
+        let mut entry = backend.get_tunnel(id).unwrap();
+        entry.depends_on = vec![on];
+        runtime.block_on(backend.edit_tunnel(id, entry)).unwrap();
+    }
 
-            id
+    #[test]
+    fn starting_a_tunnel_starts_its_dependencies() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, db, temp_dir) =
+            mock_with(&runtime, MockScenario::parse("starting=0"));
+        depend(&runtime, &mut backend, web, db);
+
+        runtime.block_on(backend.start_tunnel(web)).unwrap();
+        assert!(backend.is_tunnel_running(db));
+        assert!(backend.is_tunnel_running(web));
+
+        // Dependents keep running unless the setting says otherwise.
+        runtime.block_on(backend.stop_tunnel(db)).unwrap();
+        assert!(backend.is_tunnel_running(web));
+
+        let settings = GlobalSettings {
+            stop_dependents: true,
+            ..backend.get_config().global.clone()
         };
+        runtime
+            .block_on(backend.update_global_settings(settings))
+            .unwrap();
+        runtime.block_on(backend.start_tunnel(db)).unwrap();
+        runtime.block_on(backend.stop_tunnel(db)).unwrap();
+        assert!(!backend.is_tunnel_running(web));
 
-        {
-            let backend2 = BackendState::new(handle.clone(), config_path.clone(), wstunnel_path);
+        // Deleting a dependency drops it from the tunnels that had it.
+        runtime.block_on(backend.delete_tunnel(db)).unwrap();
+        assert!(backend.get_tunnel(web).unwrap().depends_on.is_empty());
 
-            let config = backend2.get_config();
-            assert_eq!(config.tunnels.len(), 1);
-            assert_eq!(config.tunnels[0].id, tunnel_id);
-            assert_eq!(config.tunnels[0].tag, "persist-test");
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn failed_dependency_fails_the_dependent() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, db, temp_dir) =
+            mock_with(&runtime, MockScenario::parse("fail-start=db"));
+        depend(&runtime, &mut backend, web, db);
+
+        let error = runtime.block_on(backend.start_tunnel(web)).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("dependency 'db' failed to start"),
+            "{}",
+            error
+        );
+        assert!(!backend.is_tunnel_running(web));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn seeded_crash_delays_repeat() {
+        let scenario = MockScenario::parse("crash,seed=42");
+        let delays: Vec<Duration> = scenario.crash_delays().take(10).collect();
+        assert_eq!(delays, scenario.crash_delays().take(10).collect::<Vec<_>>());
+        assert!(delays.iter().all(|d| (5..=30).contains(&d.as_secs())));
+        assert_ne!(
+            delays,
+            MockScenario::parse("crash,seed=7")
+                .crash_delays()
+                .take(10)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(MockScenario::default().crash_delays().count(), 0);
+    }
+
+    #[test]
+    fn unhealthy_scenario_reports_failing_health() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut backend, web, _, temp_dir) =
+            mock_with(&runtime, MockScenario::parse("unhealthy,starting=0"));
+
+        runtime.block_on(backend.start_tunnel(web)).unwrap();
+        assert!(matches!(
+            backend.get_tunnel_status(web),
+            TunnelRuntimeState::Running {
+                health: TunnelHealth::Unhealthy { .. },
+                ..
+            }
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
+
+mod wstunnel_version {
+    use super::*;
+    use wstunnel_manager::backend::mock_backend::MockBackend;
+    use wstunnel_manager::backend::types::WstunnelVersion;
+
+    fn version(major: u64, minor: u64, patch: u64) -> WstunnelVersion {
+        WstunnelVersion {
+            major,
+            minor,
+            patch,
         }
+    }
+
+    #[test]
+    fn parses_release_versions() {
+        assert_eq!(WstunnelVersion::parse("10.1.8"), Some(version(10, 1, 8)));
+        assert_eq!(WstunnelVersion::parse("v7.2"), Some(version(7, 2, 0)));
+        assert_eq!(
+            WstunnelVersion::parse("10.2.0-rc1"),
+            Some(version(10, 2, 0))
+        );
+        assert_eq!(WstunnelVersion::parse("unknown"), None);
+        assert_eq!(WstunnelVersion::parse("10.x"), None);
+    }
+
+    #[test]
+    fn subcommands_need_version_7() {
+        assert!(version(10, 1, 8).supports_subcommands());
+        assert!(version(7, 0, 0).supports_subcommands());
+        assert!(!version(6, 0, 0).supports_subcommands());
+        assert!(version(6, 9, 9) < version(7, 0, 0));
+    }
+
+    #[test]
+    fn mock_reports_a_fixed_version() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let backend = MockBackend::new(runtime.handle().clone(), temp_dir.join("config.yaml"));
+        let version = backend.wstunnel_version().unwrap();
+        assert!(WstunnelVersion::parse(&version).is_some());
 
         std::fs::remove_dir_all(&temp_dir).ok();
     }
 
     #[test]
-    fn add_and_list_tunnels() {
-        let runtime = create_test_runtime();
-        let handle = runtime.handle().clone();
-        let temp_dir = create_temp_test_dir();
+    fn about_screen_diagnostics_include_versions_and_paths() {
+        use wstunnel_manager::ui::state::AboutState;
 
-        let config_path = temp_dir.join("add_list_test.yaml");
-        let wstunnel_path = get_wstunnel_path();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let config_path = temp_dir.join("config.yaml");
 
-        let mut backend = BackendState::new(handle, config_path, wstunnel_path);
+        let backend = MockBackend::new(runtime.handle().clone(), config_path.clone());
+        let about = AboutState::from_backend(&backend);
+        let text = about.diagnostics_text();
+        assert!(text.contains(wstunnel_manager::constants::APP_VERSION));
+        assert!(text.contains(&backend.wstunnel_version().unwrap()));
+        assert!(text.contains(&config_path.display().to_string()));
+        assert!(text.contains(std::env::consts::OS));
+        assert_eq!(about.tunnel_count, backend.get_config().tunnels.len());
 
-        assert_eq!(backend.list_tunnels().len(), 0);
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
 
-        let tunnel1 = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "tunnel-1".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://server1.com".to_string(),
-            autostart: false,
-            runtime_state: None,
-        };
+mod window_state {
+    use wstunnel_manager::ui::window_state::{self, WindowGeometry};
 
-        let tunnel2 = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "tunnel-2".to_string(),
-            mode: TunnelMode::Server,
-            cli_args: "server ws://0.0.0.0:8080".to_string(),
-            autostart: true,
-            runtime_state: None,
-        };
+    fn geometry(width: f32, height: f32, x: f32, y: f32) -> WindowGeometry {
+        WindowGeometry {
+            width,
+            height,
+            x: Some(x),
+            y: Some(y),
+        }
+    }
 
-        backend.add_tunnel(tunnel1.clone()).unwrap();
-        backend.add_tunnel(tunnel2.clone()).unwrap();
+    #[test]
+    fn visible_geometry_is_kept() {
+        let saved = geometry(900.0, 700.0, 1920.0, 40.0);
+        assert_eq!(saved.clamped(), saved);
 
-        let tunnels = backend.list_tunnels();
-        assert_eq!(tunnels.len(), 2);
-        assert!(tunnels.iter().any(|t| t.tag == "tunnel-1"));
-        assert!(tunnels.iter().any(|t| t.tag == "tunnel-2"));
+        // Monitors left of the primary one have negative coordinates.
+        let saved = geometry(900.0, 700.0, -1500.0, 40.0);
+        assert_eq!(saved.clamped(), saved);
+    }
+
+    #[test]
+    fn off_screen_positions_are_dropped() {
+        for (x, y) in [(-32000.0, -32000.0), (50000.0, 0.0), (0.0, f32::INFINITY)] {
+            let clamped = geometry(900.0, 700.0, x, y).clamped();
+            assert_eq!((clamped.x, clamped.y), (None, None), "({x}, {y})");
+            assert_eq!((clamped.width, clamped.height), (900.0, 700.0));
+        }
+    }
+
+    #[test]
+    fn tiny_sizes_are_raised() {
+        let clamped = geometry(10.0, f32::NAN, 0.0, 0.0).clamped();
+        assert_eq!((clamped.width, clamped.height), (400.0, 800.0));
+
+        // Minimizing must not overwrite the last real geometry.
+        let mut tracked = WindowGeometry::default();
+        tracked.resize(iced::Size::new(0.0, 0.0));
+        tracked.move_to(iced::Point::new(-32000.0, -32000.0));
+        assert_eq!(tracked, WindowGeometry::default());
+    }
+
+    #[test]
+    fn round_trips_through_the_state_file() {
+        let dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = window_state::state_file_path(&dir.join("config.yaml"));
+        assert_eq!(path, dir.join("config.window.json"));
+
+        assert_eq!(window_state::load(&path), WindowGeometry::default());
+
+        let saved = geometry(1000.0, 600.0, 200.0, 100.0);
+        window_state::save(&path, &saved).unwrap();
+        assert_eq!(window_state::load(&path), saved);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(window_state::load(&path), WindowGeometry::default());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+mod http_api {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use wstunnel_manager::api::router;
+    use wstunnel_manager::backend::SharedBackend;
+    use wstunnel_manager::backend::mock_backend::MockBackend;
+    use wstunnel_manager::backend::types::RuntimeStateView;
+
+    fn setup() -> (tokio::runtime::Runtime, SharedBackend, TunnelId, PathBuf) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = std::env::temp_dir().join(format!("wstunnel_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let mut backend =
+            MockBackend::new(runtime.handle().clone(), temp_dir.join("test_config.yaml"));
+        let id = runtime
+            .block_on(backend.add_tunnel(tunnel("api-test")))
+            .unwrap();
+
+        let backend: SharedBackend = Arc::new(tokio::sync::Mutex::new(backend));
+        (runtime, backend, id, temp_dir)
+    }
+
+    fn request(
+        runtime: &tokio::runtime::Runtime,
+        backend: &SharedBackend,
+        method: &str,
+        uri: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        runtime.block_on(async {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = router(backend.clone()).oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, serde_json::from_slice(&body).unwrap())
+        })
+    }
+
+    #[test]
+    fn list_tunnels_includes_status() {
+        let (runtime, backend, id, temp_dir) = setup();
+
+        let (status, body) = request(&runtime, &backend, "GET", "/tunnels");
+        assert_eq!(status, StatusCode::OK);
+        let tunnels = body.as_array().unwrap();
+        assert_eq!(tunnels.len(), 1);
+        assert_eq!(tunnels[0]["id"], id.to_string());
+        assert_eq!(tunnels[0]["tag"], "api-test");
+        assert_eq!(tunnels[0]["status"]["state"], "stopped");
 
         std::fs::remove_dir_all(&temp_dir).ok();
     }
 
     #[test]
-    fn delete_tunnel() {
-        let runtime = create_test_runtime();
-        let handle = runtime.handle().clone();
-        let temp_dir = create_temp_test_dir();
+    fn start_status_and_stop() {
+        let (runtime, backend, id, temp_dir) = setup();
 
-        let config_path = temp_dir.join("delete_test.yaml");
-        let wstunnel_path = get_wstunnel_path();
+        let (status, body) = request(
+            &runtime,
+            &backend,
+            "POST",
+            &format!("/tunnels/{}/start", id),
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"]["state"], "running");
 
-        let mut backend = BackendState::new(handle, config_path, wstunnel_path);
+        let (status, body) = request(
+            &runtime,
+            &backend,
+            "GET",
+            &format!("/tunnels/{}/status", id),
+        );
+        assert_eq!(status, StatusCode::OK);
+        let view: RuntimeStateView = serde_json::from_value(body).unwrap();
+        assert!(matches!(view, RuntimeStateView::Running { .. }));
 
-        let tunnel = TunnelEntry {
-            id: TunnelId::new(),
-            tag: "to-delete".to_string(),
-            mode: TunnelMode::Client,
-            cli_args: "client ws://example.com".to_string(),
-            autostart: false,
-            runtime_state: None,
-        };
+        let (status, _) = request(
+            &runtime,
+            &backend,
+            "POST",
+            &format!("/tunnels/{}/start", id),
+        );
+        assert_eq!(status, StatusCode::CONFLICT);
 
-        let id = backend.add_tunnel(tunnel).unwrap();
-        assert_eq!(backend.list_tunnels().len(), 1);
+        let (status, body) = request(&runtime, &backend, "POST", &format!("/tunnels/{}/stop", id));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"]["state"], "stopped");
 
-        backend.delete_tunnel(id).unwrap();
-        assert_eq!(backend.list_tunnels().len(), 0);
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn start_all_and_stop_all_report_each_tunnel() {
+        let (runtime, backend, id, temp_dir) = setup();
+
+        let (status, body) = request(&runtime, &backend, "POST", "/tunnels/start");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["id"], id.to_string());
+        assert!(body[0]["error"].is_null());
+
+        // Tunnels that are already running are skipped rather than reported
+        // as failures.
+        let (_, body) = request(&runtime, &backend, "POST", "/tunnels/start");
+        assert!(body.as_array().unwrap().is_empty());
+
+        let (status, body) = request(&runtime, &backend, "POST", "/tunnels/stop");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["id"], id.to_string());
+        assert!(!runtime.block_on(backend.lock()).is_tunnel_running(id));
+
+        let (_, body) = request(&runtime, &backend, "POST", "/tunnels/stop");
+        assert!(body.as_array().unwrap().is_empty());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn client_round_trip() {
+        use wstunnel_manager::api::{ApiClient, bind, serve};
+
+        let (runtime, backend, id, temp_dir) = setup();
+
+        runtime.block_on(async {
+            let listener = bind(0).await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(serve(listener, backend.clone()));
+
+            let client = ApiClient::new(port);
+            let tunnels = client.list_tunnels().await.unwrap();
+            assert_eq!(tunnels.len(), 1);
+            assert_eq!(tunnels[0].tunnel.tag, "api-test");
+            assert_eq!(tunnels[0].status, RuntimeStateView::Stopped);
+
+            let view = client.start_tunnel(id).await.unwrap();
+            assert!(matches!(view.status, RuntimeStateView::Running { .. }));
+
+            let error = client.start_tunnel(id).await.unwrap_err();
+            assert!(error.to_string().contains("already running"));
+
+            let view = client.stop_tunnel(id).await.unwrap();
+            assert_eq!(view.status, RuntimeStateView::Stopped);
+        });
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn unknown_and_invalid_ids() {
+        let (runtime, backend, _, temp_dir) = setup();
+
+        let (status, body) = request(
+            &runtime,
+            &backend,
+            "GET",
+            &format!("/tunnels/{}/status", TunnelId::new()),
+        );
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].is_string());
+
+        let (status, _) = request(&runtime, &backend, "POST", "/tunnels/not-a-uuid/start");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn stderr_of_a_tunnel() {
+        let (runtime, backend, id, temp_dir) = setup();
+
+        let (status, body) = request(
+            &runtime,
+            &backend,
+            "GET",
+            &format!("/tunnels/{}/stderr", id),
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["stderr"], "");
+
+        let (status, _) = request(
+            &runtime,
+            &backend,
+            "GET",
+            &format!("/tunnels/{}/stderr", TunnelId::new()),
+        );
+        assert_eq!(status, StatusCode::NOT_FOUND);
 
         std::fs::remove_dir_all(&temp_dir).ok();
     }
 }
 
-mod global_settings {
+mod prometheus_metrics {
     use super::*;
+    use wstunnel_manager::backend::types::{ProcessId, Timestamp, TunnelRuntimeState};
+    use wstunnel_manager::metrics::Metrics;
+
+    fn series_line<'a>(output: &'a str, metric: &str, tag: &str) -> Option<&'a str> {
+        output
+            .lines()
+            .find(|line| line.starts_with(metric) && line.contains(&format!("tag=\"{}\"", tag)))
+    }
 
     #[test]
-    fn default_values() {
-        let settings = GlobalSettings::default();
-        assert!(settings.wstunnel_binary_path.is_none());
-        assert_eq!(settings.log_directory, PathBuf::from(".").join("logs"));
-        assert!(settings.log_retention_days.is_none());
+    fn reports_up_and_uptime() {
+        let metrics = Metrics::new().unwrap();
+        let config = Config {
+            tunnels: vec![tunnel("running"), tunnel("stopped")],
+            ..Config::default()
+        };
+        let statuses = vec![
+            (
+                config.tunnels[0].id,
+                TunnelRuntimeState::Running {
+                    pid: ProcessId::from(1234),
+                    started_at: Timestamp::now(),
+                    log_path: PathBuf::from("running.log"),
+                    health: TunnelHealth::Unchecked,
+                    command_line: Vec::new(),
+                },
+            ),
+            (config.tunnels[1].id, TunnelRuntimeState::Stopped),
+        ];
+
+        metrics.update(&config, &statuses, &HashMap::new());
+        let output = metrics.render().unwrap();
+
+        assert!(
+            series_line(&output, "wstunnel_tunnel_up", "running")
+                .unwrap()
+                .ends_with(" 1")
+        );
+        assert!(
+            series_line(&output, "wstunnel_tunnel_up", "stopped")
+                .unwrap()
+                .ends_with(" 0")
+        );
+        assert!(
+            series_line(&output, "wstunnel_tunnel_uptime_seconds", "stopped")
+                .unwrap()
+                .ends_with(" 0")
+        );
+        assert!(
+            series_line(&output, "wstunnel_tunnel_restarts_total", "running")
+                .unwrap()
+                .ends_with(" 0")
+        );
     }
 
     #[test]
-    fn custom_log_directory() {
-        let settings = GlobalSettings {
-            wstunnel_binary_path: None,
-            log_directory: PathBuf::from("/var/log/wstunnel"),
-            log_retention_days: None,
+    fn restarts_follow_stats() {
+        let metrics = Metrics::new().unwrap();
+        let entry = tunnel("flaky");
+        let config = Config {
+            tunnels: vec![entry.clone()],
+            ..Config::default()
         };
 
-        assert!(settings.validate().is_ok());
-        assert_eq!(settings.log_directory, PathBuf::from("/var/log/wstunnel"));
+        let mut stats = TunnelStats::default();
+        for _ in 0..3 {
+            stats.record_start(Timestamp::now());
+        }
+        metrics.update(&config, &[], &HashMap::from([(entry.id, stats.clone())]));
+        let output = metrics.render().unwrap();
+        assert!(
+            series_line(&output, "wstunnel_tunnel_restarts_total", "flaky")
+                .unwrap()
+                .ends_with(" 2")
+        );
+
+        stats.record_start(Timestamp::now());
+        metrics.update(&config, &[], &HashMap::from([(entry.id, stats)]));
+        let output = metrics.render().unwrap();
+        assert!(
+            series_line(&output, "wstunnel_tunnel_restarts_total", "flaky")
+                .unwrap()
+                .ends_with(" 3")
+        );
+    }
+
+    #[test]
+    fn deleted_tunnels_are_dropped() {
+        let metrics = Metrics::new().unwrap();
+        let mut config = Config {
+            tunnels: vec![tunnel("keep"), tunnel("gone")],
+            ..Config::default()
+        };
+        metrics.update(&config, &[], &HashMap::new());
+        assert!(series_line(&metrics.render().unwrap(), "wstunnel_tunnel_up", "gone").is_some());
+
+        config.tunnels.pop();
+        metrics.update(&config, &[], &HashMap::new());
+        let output = metrics.render().unwrap();
+        assert!(series_line(&output, "wstunnel_tunnel_up", "gone").is_none());
+        assert!(series_line(&output, "wstunnel_tunnel_up", "keep").is_some());
     }
 }